@@ -0,0 +1,25 @@
+// The built in shader, shown until another is given with --shader or dropped on the window.
+// Shaders define main_image, which returns the color of the pixel at frag_coord,
+// measured in pixels from the bottom left corner like Shadertoy's fragCoord.
+// `globals` holds the time, delta_time, frame, resolution and mouse position.
+// A `params` uniform at group 0 gets a control for each member, annotated in trailing comments.
+
+struct Params {
+    ring_radius: f32, // @range(0.0, 200.0) @default(40.0)
+    ring_color: vec3<f32>, // @color
+};
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+fn main_image(frag_coord: vec2<f32>) -> vec4<f32> {
+    let uv = frag_coord / globals.resolution;
+    var color = 0.5 + 0.5 * cos(globals.time + uv.xyx + vec3(0.0, 2.0, 4.0));
+
+    // A ring around the cursor, whose position is measured from the top left
+    let mouse = vec2(globals.mouse.x, globals.resolution.y - globals.mouse.y);
+    let ring = abs(length(frag_coord - mouse) - params.ring_radius);
+    color = mix(params.ring_color, color, smoothstep(1.0, 3.0, ring));
+
+    return vec4(color, 1.0);
+}
@@ -0,0 +1,481 @@
+use anyhow::{Context, Result};
+use image::{Rgba, RgbaImage};
+use nalgebra_glm as glm;
+use std::{borrow::Cow, mem};
+use support::{
+    impl_vertex, run, AppConfig, Application, AtlasBuilder, AtlasRegion, BindingBuilder, Input,
+    Renderer, System, TextureAtlas, VertexDescription,
+};
+use wgpu::{
+    util::DeviceExt, BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
+};
+
+const MAX_SPRITES: usize = 10_000;
+
+/// Size in pixels of the atlas preview drawn in the corner
+const PREVIEW_SIZE: f32 = 256.0;
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.08,
+    g: 0.08,
+    b: 0.1,
+    a: 1.0,
+};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    screen_size: glm::Vec2,
+    _padding: glm::Vec2,
+}
+
+/// A rectangle in pixels, measured from the top left of the screen, showing one atlas region
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+    center: [f32; 2],
+    half_size: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+}
+
+impl_vertex!(
+    SpriteInstance,
+    Instance,
+    [0 => Float32x2, 1 => Float32x2, 2 => Float32x2, 3 => Float32x2]
+);
+
+struct Sprite {
+    region: usize,
+    position: glm::Vec2,
+    velocity: glm::Vec2,
+}
+
+/// Small deterministic generator so every run packs and scatters the same sprites
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+}
+
+fn hue(hue: f32) -> [u8; 3] {
+    let channel = |offset: f32| {
+        let value = ((hue + offset).fract() * 6.0 - 3.0).abs() - 1.0;
+        (value.clamp(0.0, 1.0) * 255.0) as u8
+    };
+    [channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0)]
+}
+
+/// Procedural images of assorted shapes and sizes to fill the atlas with
+fn generate_image(index: usize, random: &mut Random) -> RgbaImage {
+    let [r, g, b] = hue(random.next());
+    let color = Rgba([r, g, b, 255]);
+    let clear = Rgba([r, g, b, 0]);
+    let width = random.range(16.0, 96.0) as u32;
+    match index % 4 {
+        0 => RgbaImage::from_fn(width, width, |x, y| {
+            let center = width as f32 * 0.5;
+            let distance = glm::distance(
+                &glm::vec2(x as f32 + 0.5, y as f32 + 0.5),
+                &glm::vec2(center, center),
+            );
+            if distance < center - 1.0 {
+                color
+            } else {
+                clear
+            }
+        }),
+        1 => RgbaImage::from_fn(width, width, |x, y| {
+            let center = width as f32 * 0.5;
+            let distance = glm::distance(
+                &glm::vec2(x as f32 + 0.5, y as f32 + 0.5),
+                &glm::vec2(center, center),
+            );
+            if distance < center - 1.0 && distance > center * 0.6 {
+                color
+            } else {
+                clear
+            }
+        }),
+        2 => {
+            let height = random.range(16.0, 96.0) as u32;
+            RgbaImage::from_fn(width, height, |x, y| {
+                if (x / 8 + y / 8) % 2 == 0 {
+                    color
+                } else {
+                    Rgba([r / 3, g / 3, b / 3, 255])
+                }
+            })
+        }
+        _ => {
+            let height = random.range(16.0, 96.0) as u32;
+            RgbaImage::from_fn(width, height, |x, y| {
+                if (x + y) / 6 % 2 == 0 {
+                    color
+                } else {
+                    clear
+                }
+            })
+        }
+    }
+}
+
+struct Scene {
+    pub sprite_count: usize,
+    pub speed: f32,
+    pub scale: f32,
+    pub show_atlas: bool,
+    atlas: TextureAtlas,
+    backdrop: AtlasRegion,
+    regions: Vec<AtlasRegion>,
+    sprites: Vec<Sprite>,
+    uniform: Buffer,
+    instances: Buffer,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+    instance_count: u32,
+}
+
+impl Scene {
+    pub fn new(device: &Device, queue: &Queue, surface_format: TextureFormat) -> Result<Self> {
+        let mut random = Random(0x9e37_79b9);
+
+        let mut builder = AtlasBuilder::new(2);
+        let planks = image::load_from_memory(include_bytes!("../../assets/textures/planks.jpg"))?;
+        builder.add(
+            "planks",
+            image::imageops::resize(
+                &planks.to_rgba8(),
+                128,
+                128,
+                image::imageops::FilterType::Triangle,
+            ),
+        );
+        // A solid patch drawn behind the atlas preview, since most of the atlas is transparent
+        builder.add(
+            "backdrop",
+            RgbaImage::from_pixel(4, 4, Rgba([24, 24, 30, 255])),
+        );
+        for index in 0..48 {
+            builder.add(format!("shape {index}"), generate_image(index, &mut random));
+        }
+        let atlas = builder.build(device, queue, device.limits().max_texture_dimension_2d)?;
+        let backdrop = *atlas
+            .region("backdrop")
+            .context("The atlas is missing its backdrop")?;
+        let mut regions = atlas
+            .regions
+            .iter()
+            .filter(|(name, _)| name.as_str() != "backdrop")
+            .collect::<Vec<_>>();
+        regions.sort_by_key(|(name, _)| name.as_str());
+        let regions = regions
+            .into_iter()
+            .map(|(_, region)| *region)
+            .collect::<Vec<_>>();
+
+        let sprites = (0..MAX_SPRITES)
+            .map(|_| {
+                let angle = random.range(0.0, std::f32::consts::TAU);
+                Sprite {
+                    region: (random.next() * regions.len() as f32) as usize % regions.len(),
+                    position: glm::vec2(random.next(), random.next()),
+                    velocity: glm::vec2(angle.cos(), angle.sin()) * random.range(0.5, 1.0),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        // Two extra slots for the atlas preview and its backdrop
+        let instances = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Instance Buffer"),
+            size: ((MAX_SPRITES + 2) * mem::size_of::<SpriteInstance>()) as _,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = BindingBuilder::new()
+            .label("Sprite")
+            .uniform(0, wgpu::ShaderStages::VERTEX)
+            .texture(1, wgpu::ShaderStages::FRAGMENT)
+            .sampler(2, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        // Every sprite samples the same atlas, so a single bind group serves the whole batch
+        let bind_group = bind_group_layout.bind_group(
+            device,
+            &[
+                uniform.as_entire_binding(),
+                wgpu::BindingResource::TextureView(&atlas.texture.view),
+                wgpu::BindingResource::Sampler(&atlas.texture.sampler),
+            ],
+        );
+        let pipeline = Self::create_pipeline(device, surface_format, &bind_group_layout.layout);
+
+        Ok(Self {
+            sprite_count: 150,
+            speed: 120.0,
+            scale: 1.0,
+            show_atlas: true,
+            atlas,
+            backdrop,
+            regions,
+            sprites,
+            uniform,
+            instances,
+            bind_group,
+            pipeline,
+            instance_count: 0,
+        })
+    }
+
+    pub fn atlas(&self) -> &TextureAtlas {
+        &self.atlas
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        let screen_size = glm::vec2(renderer.config.width as f32, renderer.config.height as f32);
+        let uniform = UniformBuffer {
+            screen_size,
+            _padding: glm::Vec2::zeros(),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+
+        // Positions are kept in 0..1 so sprites stay on screen through resizes
+        let step = self.speed * delta_time;
+        let mut instances = Vec::with_capacity(self.sprite_count + 2);
+        for sprite in self.sprites.iter_mut().take(self.sprite_count) {
+            sprite.position += sprite.velocity.component_div(&screen_size) * step;
+            for axis in 0..2 {
+                if !(0.0..=1.0).contains(&sprite.position[axis]) {
+                    sprite.velocity[axis] = -sprite.velocity[axis];
+                    sprite.position[axis] = sprite.position[axis].clamp(0.0, 1.0);
+                }
+            }
+            let region = &self.regions[sprite.region];
+            let center = sprite.position.component_mul(&screen_size);
+            instances.push(SpriteInstance {
+                center: center.into(),
+                half_size: [
+                    region.width as f32 * 0.5 * self.scale,
+                    region.height as f32 * 0.5 * self.scale,
+                ],
+                uv_min: region.uv_min,
+                uv_max: region.uv_max,
+            });
+        }
+        if self.show_atlas {
+            let half_size = PREVIEW_SIZE * 0.5;
+            let center = [
+                screen_size.x - half_size - 10.0,
+                screen_size.y - half_size - 10.0,
+            ];
+            instances.push(SpriteInstance {
+                center,
+                half_size: [half_size + 4.0, half_size + 4.0],
+                uv_min: self.backdrop.uv_min,
+                uv_max: self.backdrop.uv_max,
+            });
+            instances.push(SpriteInstance {
+                center,
+                half_size: [half_size, half_size],
+                uv_min: [0.0, 0.0],
+                uv_max: [1.0, 1.0],
+            });
+        }
+        self.instance_count = instances.len() as _;
+        renderer.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.set_vertex_buffer(0, self.instances.slice(..));
+        renderpass.draw(0..6, 0..self.instance_count);
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sprite Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[SpriteInstance::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    screen_size: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var atlas: texture_2d<f32>;
+
+@group(0) @binding(2)
+var atlas_sampler: sampler;
+
+struct SpriteInput {
+    @location(0) center: vec2<f32>,
+    @location(1) half_size: vec2<f32>,
+    @location(2) uv_min: vec2<f32>,
+    @location(3) uv_max: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(@builtin(vertex_index) vertex_index: u32, sprite: SpriteInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+
+    // Pixels and texture coordinates both run downwards
+    let pixel = sprite.center + corner * sprite.half_size;
+    var out: VertexOutput;
+    out.position = vec4<f32>(
+        pixel.x / ubo.screen_size.x * 2.0 - 1.0,
+        1.0 - pixel.y / ubo.screen_size.y * 2.0,
+        0.0,
+        1.0,
+    );
+    out.uv = mix(sprite.uv_min, sprite.uv_max, corner * 0.5 + 0.5);
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(atlas, atlas_sampler, in.uv);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(
+            &renderer.device,
+            &renderer.queue,
+            renderer.config.format,
+        )?);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Texture Atlas");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                let atlas = scene.atlas();
+                ui.label(format!(
+                    "{} images in a {}x{} atlas, {:.0}% occupied",
+                    atlas.regions.len(),
+                    atlas.width,
+                    atlas.height,
+                    atlas.occupancy * 100.0
+                ));
+                ui.label("All sprites share one bind group and one draw call");
+                ui.add(egui::Slider::new(&mut scene.sprite_count, 0..=MAX_SPRITES).text("Sprites"));
+                ui.add(egui::Slider::new(&mut scene.speed, 0.0..=500.0).text("Speed"));
+                ui.add(egui::Slider::new(&mut scene.scale, 0.25..=2.0).text("Scale"));
+                ui.checkbox(&mut scene.show_atlas, "Show atlas");
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, Some(CLEAR_COLOR), None);
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Texture Atlas".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
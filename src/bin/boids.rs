@@ -0,0 +1,677 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::{borrow::Cow, mem};
+use support::{
+    impl_vertex, run, AppConfig, Application, BindingBuilder, BindingLayout, ComputeKernel, Input,
+    PingPongBuffer, Renderer, System, VertexDescription,
+};
+use wgpu::{
+    util::DeviceExt, vertex_attr_array, BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device,
+    RenderPass, RenderPipeline, TextureFormat,
+};
+
+/// Cells per side of the grid the boids are bucketed into, each at least as wide as the neighborhood radius
+const GRID_SIZE: u32 = 128;
+
+const BOID_COUNTS: [u32; 4] = [1 << 14, 1 << 16, 1 << 18, 1 << 19];
+const WORKGROUP_SIZES: [u32; 4] = [32, 64, 128, 256];
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Boid {
+    position: [f32; 2],
+    velocity: [f32; 2],
+}
+
+impl_vertex!(Boid, Instance, [0 => Float32x2, 1 => Float32x2]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimulationUniform {
+    /// Delta time, neighborhood radius, separation radius and maximum speed
+    params: glm::Vec4,
+    /// Cohesion, alignment and separation weights
+    weights: glm::Vec4,
+    /// Boid count and grid size
+    counts: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RenderUniform {
+    /// Scale from simulation space to clip space, with the boid size in z
+    scale: glm::Vec4,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Rules {
+    neighborhood: f32,
+    separation_distance: f32,
+    cohesion: f32,
+    alignment: f32,
+    separation: f32,
+    max_speed: f32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            neighborhood: 2.0 / GRID_SIZE as f32,
+            separation_distance: 0.005,
+            cohesion: 1.0,
+            alignment: 3.0,
+            separation: 60.0,
+            max_speed: 0.25,
+        }
+    }
+}
+
+/// Random positions and headings from a small xorshift generator, so every run starts the same
+fn initial_boids(count: u32) -> Vec<Boid> {
+    let mut state = 0x9e37_79b9_u32;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f32 / u32::MAX as f32
+    };
+    (0..count)
+        .map(|_| {
+            let angle = random() * std::f32::consts::TAU;
+            let speed = 0.02 + random() * 0.03;
+            Boid {
+                position: [random() * 2.0 - 1.0, random() * 2.0 - 1.0],
+                velocity: [angle.cos() * speed, angle.sin() * speed],
+            }
+        })
+        .collect()
+}
+
+/// The per frame passes, rebuilt together whenever the workgroup size changes
+struct Kernels {
+    count: ComputeKernel,
+    scan: ComputeKernel,
+    scatter: ComputeKernel,
+    simulate: ComputeKernel,
+}
+
+impl Kernels {
+    fn new(device: &Device, layout: &BindGroupLayout, workgroup_size: u32) -> Self {
+        let kernel = |entry_point| {
+            ComputeKernel::new(
+                device,
+                COMPUTE_SHADER_SOURCE,
+                entry_point,
+                &[layout],
+                workgroup_size,
+            )
+        };
+        Self {
+            count: kernel("count"),
+            scan: kernel("scan"),
+            scatter: kernel("scatter"),
+            simulate: kernel("simulate"),
+        }
+    }
+}
+
+struct Scene {
+    boid_count: u32,
+    rules: Rules,
+    workgroup_size: u32,
+    boids: PingPongBuffer,
+    cell_counts: Buffer,
+    simulation_uniform: Buffer,
+    render_uniform: Buffer,
+    compute_layout: BindingLayout,
+    /// Indexed by the ping pong buffer currently holding the latest state
+    compute_bind_groups: [BindGroup; 2],
+    render_bind_group: BindGroup,
+    kernels: Kernels,
+    triangle: Buffer,
+    pipeline: RenderPipeline,
+}
+
+impl Scene {
+    pub fn new(device: &Device, surface_format: TextureFormat, boid_count: u32) -> Self {
+        let workgroup_size = 64;
+        let boids = PingPongBuffer::new(
+            device,
+            "Boids",
+            bytemuck::cast_slice(&initial_boids(boid_count)),
+            wgpu::BufferUsages::VERTEX,
+        );
+        let cell_count = (GRID_SIZE * GRID_SIZE) as u64;
+        let cell_counts = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cell Counts"),
+            size: cell_count * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let cell_starts = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cell Starts"),
+            size: cell_count * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        // The cell each boid falls in and its slot within that cell
+        let boid_cells = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Boid Cells"),
+            size: boid_count as u64 * 8,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let simulation_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Simulation Uniform"),
+            contents: bytemuck::cast_slice(&[SimulationUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let render_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Render Uniform"),
+            contents: bytemuck::cast_slice(&[RenderUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let compute_layout = BindingBuilder::new()
+            .label("Compute")
+            .uniform(0, wgpu::ShaderStages::COMPUTE)
+            .storage(1, wgpu::ShaderStages::COMPUTE, true)
+            .storage(2, wgpu::ShaderStages::COMPUTE, false)
+            .storage(3, wgpu::ShaderStages::COMPUTE, false)
+            .storage(4, wgpu::ShaderStages::COMPUTE, false)
+            .storage(5, wgpu::ShaderStages::COMPUTE, false)
+            .build(device);
+        let compute_bind_groups = [0, 1].map(|source| {
+            compute_layout.bind_group(
+                device,
+                &[
+                    simulation_uniform.as_entire_binding(),
+                    boids.buffers[source].as_entire_binding(),
+                    boids.buffers[1 - source].as_entire_binding(),
+                    cell_counts.as_entire_binding(),
+                    cell_starts.as_entire_binding(),
+                    boid_cells.as_entire_binding(),
+                ],
+            )
+        });
+        let kernels = Kernels::new(device, &compute_layout.layout, workgroup_size);
+
+        let render_layout = BindingBuilder::new()
+            .label("Render")
+            .uniform(0, wgpu::ShaderStages::VERTEX)
+            .build(device);
+        let render_bind_group =
+            render_layout.bind_group(device, &[render_uniform.as_entire_binding()]);
+
+        let triangle = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Boid Triangle"),
+            contents: bytemuck::cast_slice(&[[1.0_f32, 0.0], [-0.6, 0.5], [-0.6, -0.5]]),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let pipeline = Self::create_pipeline(device, surface_format, &render_layout.layout);
+
+        Self {
+            boid_count,
+            rules: Rules::default(),
+            workgroup_size,
+            boids,
+            cell_counts,
+            simulation_uniform,
+            render_uniform,
+            compute_layout,
+            compute_bind_groups,
+            render_bind_group,
+            kernels,
+            triangle,
+            pipeline,
+        }
+    }
+
+    pub fn set_workgroup_size(&mut self, device: &Device, workgroup_size: u32) {
+        self.kernels = Kernels::new(device, &self.compute_layout.layout, workgroup_size);
+        self.workgroup_size = self.kernels.count.workgroup_size;
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        let rules = &self.rules;
+        let simulation = SimulationUniform {
+            // Large steps from a stalled frame would scatter the flocks
+            params: glm::vec4(
+                delta_time.min(1.0 / 30.0),
+                rules.neighborhood,
+                rules.separation_distance,
+                rules.max_speed,
+            ),
+            weights: glm::vec4(rules.cohesion, rules.alignment, rules.separation, 0.0),
+            counts: [self.boid_count, GRID_SIZE, 0, 0],
+        };
+        renderer.write_buffer(
+            &self.simulation_uniform,
+            0,
+            bytemuck::cast_slice(&[simulation]),
+        );
+
+        // Keep the simulation square in the middle of the window
+        let aspect_ratio = renderer.aspect_ratio();
+        let scale = if aspect_ratio > 1.0 {
+            glm::vec2(1.0 / aspect_ratio, 1.0)
+        } else {
+            glm::vec2(1.0, aspect_ratio)
+        };
+        let render = RenderUniform {
+            scale: glm::vec4(scale.x, scale.y, 0.004, 0.0),
+        };
+        renderer.write_buffer(&self.render_uniform, 0, bytemuck::cast_slice(&[render]));
+    }
+
+    /// Buckets the boids into grid cells so each one only visits nearby flockmates, then steps them.
+    /// The boids are sorted by cell into the other buffer, and the simulation reads the
+    /// sorted copy while writing the new state back, so the latest state stays in the same buffer.
+    pub fn compute(&mut self, encoder: &mut CommandEncoder) {
+        encoder.clear_buffer(&self.cell_counts, 0, None);
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Boids Compute Pass"),
+        });
+
+        pass.set_bind_group(0, &self.compute_bind_groups[self.boids.index()], &[]);
+        self.kernels.count.dispatch(&mut pass, self.boid_count);
+        // A single workgroup scans every cell
+        self.kernels
+            .scan
+            .dispatch(&mut pass, self.kernels.scan.workgroup_size);
+        self.kernels.scatter.dispatch(&mut pass, self.boid_count);
+        self.boids.swap();
+
+        pass.set_bind_group(0, &self.compute_bind_groups[self.boids.index()], &[]);
+        self.kernels.simulate.dispatch(&mut pass, self.boid_count);
+        self.boids.swap();
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.render_bind_group, &[]);
+        renderpass.set_vertex_buffer(0, self.boids.current().slice(..));
+        renderpass.set_vertex_buffer(1, self.triangle.slice(..));
+        renderpass.draw(0..3, 0..self.boid_count);
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(RENDER_SHADER_SOURCE)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[
+                    Boid::layout().buffer_layout(),
+                    wgpu::VertexBufferLayout {
+                        array_stride: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &vertex_attr_array![2 => Float32x2],
+                    },
+                ],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const COMPUTE_SHADER_SOURCE: &str = "
+struct Boid {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+};
+
+struct Simulation {
+    params: vec4<f32>,
+    weights: vec4<f32>,
+    counts: vec4<u32>,
+};
+
+@group(0) @binding(0)
+var<uniform> simulation: Simulation;
+
+@group(0) @binding(1)
+var<storage, read> source: array<Boid>;
+
+@group(0) @binding(2)
+var<storage, read_write> destination: array<Boid>;
+
+@group(0) @binding(3)
+var<storage, read_write> cell_counts: array<atomic<u32>>;
+
+@group(0) @binding(4)
+var<storage, read_write> cell_starts: array<u32>;
+
+@group(0) @binding(5)
+var<storage, read_write> boid_cells: array<vec2<u32>>;
+
+var<workgroup> partial_sums: array<u32, WORKGROUP_SIZE>;
+
+fn cell_coordinates(position: vec2<f32>) -> vec2<i32> {
+    let grid = i32(simulation.counts.y);
+    let cell = vec2<i32>(floor((position * 0.5 + 0.5) * f32(grid)));
+    return clamp(cell, vec2(0), vec2(grid - 1));
+}
+
+fn cell_index(cell: vec2<i32>) -> u32 {
+    let grid = i32(simulation.counts.y);
+    let wrapped = (cell + vec2(grid)) % vec2(grid);
+    return u32(wrapped.y * grid + wrapped.x);
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn count(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= simulation.counts.x {
+        return;
+    }
+    let cell = cell_index(cell_coordinates(source[index].position));
+    boid_cells[index] = vec2(cell, atomicAdd(&cell_counts[cell], 1u));
+}
+
+// Exclusive prefix sum of the cell counts, each invocation handling a contiguous run of cells
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn scan(@builtin(local_invocation_id) local_id: vec3<u32>) {
+    let lane = local_id.x;
+    let cell_count = simulation.counts.y * simulation.counts.y;
+    let run = (cell_count + WORKGROUP_SIZEu - 1u) / WORKGROUP_SIZEu;
+    let first = lane * run;
+    let last = min(first + run, cell_count);
+
+    var total = 0u;
+    for (var cell = first; cell < last; cell++) {
+        total += atomicLoad(&cell_counts[cell]);
+    }
+    partial_sums[lane] = total;
+    workgroupBarrier();
+
+    for (var offset = 1u; offset < WORKGROUP_SIZEu; offset *= 2u) {
+        var value = 0u;
+        if lane >= offset {
+            value = partial_sums[lane - offset];
+        }
+        workgroupBarrier();
+        partial_sums[lane] += value;
+        workgroupBarrier();
+    }
+
+    var start = partial_sums[lane] - total;
+    for (var cell = first; cell < last; cell++) {
+        cell_starts[cell] = start;
+        start += atomicLoad(&cell_counts[cell]);
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= simulation.counts.x {
+        return;
+    }
+    let cell = boid_cells[index];
+    destination[cell_starts[cell.x] + cell.y] = source[index];
+}
+
+// Reads the boids sorted by cell and visits the 3x3 block of cells around each one
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn simulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= simulation.counts.x {
+        return;
+    }
+    let delta_time = simulation.params.x;
+    let neighborhood = simulation.params.y;
+    let separation_distance = simulation.params.z;
+    let max_speed = simulation.params.w;
+
+    var boid = source[index];
+    let cell = cell_coordinates(boid.position);
+    var center = vec2<f32>(0.0);
+    var heading = vec2<f32>(0.0);
+    var separation = vec2<f32>(0.0);
+    var neighbors = 0u;
+
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbor_cell = cell_index(cell + vec2(x, y));
+            let start = cell_starts[neighbor_cell];
+            // Dense clumps are sampled rather than visited exhaustively
+            let count = min(atomicLoad(&cell_counts[neighbor_cell]), 32u);
+            for (var other = start; other < start + count; other++) {
+                if other == index {
+                    continue;
+                }
+                let neighbor = source[other];
+                var offset = neighbor.position - boid.position;
+                // The space wraps around, so measure across the edges
+                offset -= 2.0 * round(offset * 0.5);
+                let distance = length(offset);
+                if distance < neighborhood {
+                    center += offset;
+                    heading += neighbor.velocity;
+                    neighbors++;
+                }
+                if distance < separation_distance {
+                    separation -= offset;
+                }
+            }
+        }
+    }
+
+    var steering = separation * simulation.weights.z;
+    if neighbors > 0u {
+        steering += center / f32(neighbors) * simulation.weights.x;
+        steering += (heading / f32(neighbors) - boid.velocity) * simulation.weights.y;
+    }
+    var velocity = boid.velocity + steering * delta_time;
+    // Boids never stop, they only turn and vary their speed within a range
+    let speed = max(length(velocity), 0.0001);
+    velocity *= clamp(speed, 0.5 * max_speed, max_speed) / speed;
+
+    var position = boid.position + velocity * delta_time;
+    position -= 2.0 * floor(position * 0.5 + 0.5);
+    destination[index] = Boid(position, velocity);
+}
+";
+
+const RENDER_SHADER_SOURCE: &str = "
+struct Uniform {
+    scale: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) velocity: vec2<f32>,
+    @location(2) corner: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    let angle = atan2(vert.velocity.y, vert.velocity.x);
+    let rotated = vec2(
+        vert.corner.x * cos(angle) - vert.corner.y * sin(angle),
+        vert.corner.x * sin(angle) + vert.corner.y * cos(angle),
+    );
+    let position = vert.position + rotated * ubo.scale.z;
+
+    var out: VertexOutput;
+    out.position = vec4(position * ubo.scale.xy, 0.0, 1.0);
+    // Hue by heading
+    let hue = angle / 6.2831853 + 0.5;
+    out.color = clamp(abs(fract(hue + vec3(0.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0, vec3(0.0), vec3(1.0)) * 0.8 + 0.2;
+    return out;
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    boid_count: u32,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.boid_count = BOID_COUNTS[2];
+        let mut scene = Scene::new(&renderer.device, renderer.config.format, self.boid_count);
+        // Let the flocks form before the first frame
+        for _ in 0..120 {
+            scene.update(renderer, 1.0 / 60.0);
+            let mut encoder =
+                renderer
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Warmup Encoder"),
+                    });
+            scene.compute(&mut encoder);
+            renderer.submit(encoder);
+        }
+        self.scene = Some(scene);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if self
+            .scene
+            .as_ref()
+            .is_some_and(|scene| scene.boid_count != self.boid_count)
+        {
+            let mut scene = Scene::new(&renderer.device, renderer.config.format, self.boid_count);
+            if let Some(previous) = self.scene.take() {
+                scene.rules = previous.rules;
+                scene.set_workgroup_size(&renderer.device, previous.workgroup_size);
+            }
+            self.scene = Some(scene);
+        }
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Boids");
+                egui::ComboBox::from_label("Boids")
+                    .selected_text(self.boid_count.to_string())
+                    .show_ui(ui, |ui| {
+                        for count in BOID_COUNTS {
+                            ui.selectable_value(&mut self.boid_count, count, count.to_string());
+                        }
+                    });
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                let mut workgroup_size = scene.workgroup_size;
+                egui::ComboBox::from_label("Workgroup size")
+                    .selected_text(workgroup_size.to_string())
+                    .show_ui(ui, |ui| {
+                        for size in WORKGROUP_SIZES {
+                            ui.selectable_value(&mut workgroup_size, size, size.to_string());
+                        }
+                    });
+                if workgroup_size != scene.workgroup_size {
+                    scene.set_workgroup_size(&renderer.device, workgroup_size);
+                }
+                let rules = &mut scene.rules;
+                ui.add(
+                    egui::Slider::new(&mut rules.neighborhood, 0.001..=2.0 / GRID_SIZE as f32)
+                        .text("Neighborhood"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut rules.separation_distance, 0.0..=0.01)
+                        .text("Separation distance"),
+                );
+                ui.add(egui::Slider::new(&mut rules.cohesion, 0.0..=20.0).text("Cohesion"));
+                ui.add(egui::Slider::new(&mut rules.alignment, 0.0..=10.0).text("Alignment"));
+                ui.add(egui::Slider::new(&mut rules.separation, 0.0..=200.0).text("Separation"));
+                ui.add(egui::Slider::new(&mut rules.max_speed, 0.01..=1.0).text("Max speed"));
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(None);
+        };
+        // The compute pass is recorded first, so the draw sees this frame's positions
+        scene.compute(encoder);
+
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(wgpu::Color {
+                r: 0.02,
+                g: 0.02,
+                b: 0.05,
+                a: 1.0,
+            }),
+            None,
+        );
+        scene.render(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Boids".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
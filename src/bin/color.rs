@@ -21,25 +21,8 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
-
-        let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
 
         Ok(Some(render_pass))
     }
@@ -52,6 +35,7 @@ fn main() -> Result<()> {
             title: "Solid Color".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
@@ -0,0 +1,509 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::{borrow::Cow, time::Instant};
+use support::{
+    camera::MouseOrbit, run, Aabb, AppConfig, Application, BindingBuilder, Geometry, Indices,
+    Input, MeshData, PrimitiveVertex, RenderStats, Renderer, System, Texture, VertexDescription,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    view_projection: glm::Mat4,
+    camera_position: glm::Vec4,
+    /// The surface's index
+    params: glm::Vec4,
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) world_position: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = ubo.view_projection * vec4<f32>(vert.position, 1.0);
+    out.normal = vert.normal;
+    out.uv = vert.uv;
+    out.world_position = vert.position;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    var normal = normalize(in.normal);
+    if !front_facing {
+        normal = -normal;
+    }
+    let light = normalize(vec3<f32>(0.4, 1.0, 0.6));
+    let view = normalize(ubo.camera_position.xyz - in.world_position);
+    let diffuse = max(dot(normal, light), 0.0);
+    let specular = pow(max(dot(normal, normalize(light + view)), 0.0), 64.0);
+
+    var albedo = vec3<f32>(0.9, 0.9, 0.9);
+    if ubo.params.x > 0.5 {
+        if u32(in.uv.y * 7.0) % 2u == 0u {
+            albedo = vec3<f32>(0.75, 0.1, 0.1);
+        }
+        return vec4<f32>(albedo * (0.3 + 0.7 * diffuse), 1.0);
+    }
+    albedo = mix(vec3<f32>(0.02, 0.1, 0.25), vec3<f32>(0.1, 0.45, 0.55), clamp(in.world_position.y * 1.5 + 0.4, 0.0, 1.0));
+    return vec4<f32>(albedo * (0.3 + 0.7 * diffuse) + vec3<f32>(specular), 1.0);
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum Surface {
+    /// A horizontal grid rolling with noise
+    #[default]
+    Ocean,
+    /// A vertical grid pinned along its left edge
+    Flag,
+}
+
+impl Surface {
+    const ALL: [Self; 2] = [Self::Ocean, Self::Flag];
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum UploadPath {
+    /// `Geometry::stream_vertices`, copied through the renderer's staging belt
+    #[default]
+    StagingBelt,
+    /// `Geometry::update_vertices`, written with `Queue::write_buffer`
+    QueueWrite,
+}
+
+impl UploadPath {
+    const ALL: [Self; 2] = [Self::StagingBelt, Self::QueueWrite];
+}
+
+#[derive(Debug, Copy, Clone)]
+struct DeformSettings {
+    surface: Surface,
+    upload_path: UploadPath,
+    /// Quads along each side of the grid
+    resolution: u32,
+    amplitude: f32,
+    /// Lattice cells per world unit of the first noise octave
+    frequency: f32,
+    speed: f32,
+    octaves: u32,
+}
+
+impl Default for DeformSettings {
+    fn default() -> Self {
+        Self {
+            surface: Surface::default(),
+            upload_path: UploadPath::default(),
+            resolution: 128,
+            amplitude: 0.5,
+            frequency: 0.5,
+            speed: 0.6,
+            octaves: 3,
+        }
+    }
+}
+
+impl DeformSettings {
+    /// Returns true if the grid needs rebuilding
+    fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let resolution = self.resolution;
+        egui::ComboBox::from_label("Surface")
+            .selected_text(format!("{:?}", self.surface))
+            .show_ui(ui, |ui| {
+                for surface in Surface::ALL {
+                    ui.selectable_value(&mut self.surface, surface, format!("{surface:?}"));
+                }
+            });
+        egui::ComboBox::from_label("Upload")
+            .selected_text(format!("{:?}", self.upload_path))
+            .show_ui(ui, |ui| {
+                for path in UploadPath::ALL {
+                    ui.selectable_value(&mut self.upload_path, path, format!("{path:?}"));
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.resolution, 8..=512).text("Resolution"));
+        ui.add(egui::Slider::new(&mut self.amplitude, 0.0..=2.0).text("Amplitude"));
+        ui.add(egui::Slider::new(&mut self.frequency, 0.05..=4.0).text("Frequency"));
+        ui.add(egui::Slider::new(&mut self.speed, 0.0..=4.0).text("Speed"));
+        ui.add(egui::Slider::new(&mut self.octaves, 1..=6).text("Octaves"));
+        resolution != self.resolution
+    }
+}
+
+/// Random values in 0..1 at integer lattice points
+fn lattice_value(x: i32, y: i32, z: i32) -> f32 {
+    let mut state = (x as u32).wrapping_mul(0x27d4eb2d)
+        ^ (y as u32).wrapping_mul(0x165667b1)
+        ^ (z as u32).wrapping_mul(0x9e3779b9);
+    state = (state ^ (state >> 15)).wrapping_mul(0x2c1b3c6d);
+    state = (state ^ (state >> 12)).wrapping_mul(0x297a2d39);
+    state ^= state >> 15;
+    (state & 0xffff) as f32 / 65535.0
+}
+
+/// Lattice values blended with a smoothstep, in 0..1
+fn value_noise(point: glm::Vec3) -> f32 {
+    let cell = point.map(f32::floor);
+    let local = point - cell;
+    let fade = local.map(|t| t * t * (3.0 - 2.0 * t));
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+    let corner = |dx, dy, dz| lattice_value(x + dx, y + dy, z + dz);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let face = |dz| {
+        lerp(
+            lerp(corner(0, 0, dz), corner(1, 0, dz), fade.x),
+            lerp(corner(0, 1, dz), corner(1, 1, dz), fade.x),
+            fade.y,
+        )
+    };
+    lerp(face(0), face(1), fade.z)
+}
+
+/// Octaves of value noise doubling in frequency and halving in amplitude, centered on zero
+fn fractal_noise(point: glm::Vec3, octaves: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    for _ in 0..octaves.max(1) {
+        value += (value_noise(point * frequency) - 0.5) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    value / total * 2.0
+}
+
+/// A grid whose vertices are recomputed on the CPU and uploaded every frame
+struct Scene {
+    settings: DeformSettings,
+    /// The undeformed grid, its uvs place each deformed vertex
+    grid: MeshData,
+    vertices: Vec<PrimitiveVertex>,
+    geometry: Geometry,
+    time: f32,
+    /// Seconds the last deformation took on the CPU
+    deform_time: f64,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl Scene {
+    const SIZE: f32 = 8.0;
+
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = &renderer.device;
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = BindingBuilder::new()
+            .label("Uniform")
+            .uniform(0, wgpu::ShaderStages::VERTEX_FRAGMENT)
+            .build(device);
+        let uniform_bind_group = uniform_layout.bind_group(device, &[uniform.as_entire_binding()]);
+
+        let settings = DeformSettings::default();
+        let grid = MeshData::plane(Self::SIZE, settings.resolution - 1);
+        let geometry =
+            Geometry::new_dynamic(device, &renderer.queue, &grid.vertices, &grid.indices);
+        Self {
+            settings,
+            vertices: grid.vertices.clone(),
+            grid,
+            geometry,
+            time: 0.0,
+            deform_time: 0.0,
+            pipeline: Self::create_pipeline(device, renderer.config.format, &uniform_layout.layout),
+            uniform,
+            uniform_bind_group,
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        uniform_layout: &wgpu::BindGroupLayout,
+    ) -> RenderPipeline {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Deform Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Deform Pipeline Layout"),
+            bind_group_layouts: &[uniform_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Deform Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vertex_main",
+                buffers: &[PrimitiveVertex::layout().buffer_layout()],
+            },
+            // The flag is seen from both sides
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+
+    /// Rebuilds the undeformed grid at the current resolution, uploading its new indices
+    fn rebuild_grid(&mut self, renderer: &Renderer) {
+        self.grid = MeshData::plane(Self::SIZE, self.settings.resolution.max(2) - 1);
+        self.vertices = self.grid.vertices.clone();
+        match Indices::narrow(&self.grid.indices) {
+            Some(indices) => {
+                self.geometry
+                    .update_indices(&renderer.device, &renderer.queue, &indices)
+            }
+            None => {
+                self.geometry
+                    .update_indices(&renderer.device, &renderer.queue, &self.grid.indices)
+            }
+        }
+    }
+
+    /// Where a point of the undeformed grid ends up at the current time
+    fn displace(settings: &DeformSettings, time: f32, uv: glm::Vec2) -> glm::Vec3 {
+        let time = time * settings.speed;
+        match settings.surface {
+            Surface::Ocean => {
+                let position = glm::vec2(uv.x - 0.5, uv.y - 0.5) * Self::SIZE;
+                let sample = glm::vec3(position.x, position.y, time) * settings.frequency;
+                let height = fractal_noise(sample, settings.octaves) * settings.amplitude;
+                glm::vec3(position.x, height, position.y)
+            }
+            Surface::Flag => {
+                let size = glm::vec2(Self::SIZE, Self::SIZE * 0.6);
+                let position = glm::vec2(uv.x * size.x - size.x * 0.5, (0.5 - uv.y) * size.y);
+                // Ripples travel away from the pole and grow toward the free edge
+                let sample =
+                    glm::vec3(position.x - time * 2.0, position.y, time) * settings.frequency;
+                let ripple = fractal_noise(sample, settings.octaves)
+                    + (position.x * 1.5 - time * 4.0).sin() * 0.3;
+                let depth = ripple * settings.amplitude * uv.x;
+                glm::vec3(position.x, position.y, depth)
+            }
+        }
+    }
+
+    /// Recomputes every vertex, then normals from the neighboring vertices along each axis
+    fn deform(&mut self) {
+        let start = Instant::now();
+        for (vertex, base) in self.vertices.iter_mut().zip(&self.grid.vertices) {
+            let position = Self::displace(&self.settings, self.time, glm::Vec2::from(base.uv));
+            vertex.position = position.into();
+        }
+
+        let side = self.settings.resolution.max(2) as usize + 1;
+        let position = |column: usize, row: usize| {
+            glm::Vec3::from(self.vertices[row.min(side - 1) * side + column.min(side - 1)].position)
+        };
+        let normals = (0..side * side)
+            .map(|index| {
+                let (column, row) = (index % side, index / side);
+                let along_row = position(column, row + 1) - position(column, row.saturating_sub(1));
+                let along_column =
+                    position(column + 1, row) - position(column.saturating_sub(1), row);
+                along_row.cross(&along_column).normalize()
+            })
+            .collect::<Vec<_>>();
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.into();
+        }
+        self.deform_time = start.elapsed().as_secs_f64();
+    }
+
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        camera: &MouseOrbit,
+        stats: &mut RenderStats,
+        delta_time: f32,
+    ) {
+        self.time += delta_time;
+        self.deform();
+        match self.settings.upload_path {
+            UploadPath::StagingBelt => self.geometry.stream_vertices(renderer, &self.vertices),
+            UploadPath::QueueWrite => {
+                self.geometry
+                    .update_vertices(&renderer.device, &renderer.queue, &self.vertices)
+            }
+        }
+        stats.record_upload(std::mem::size_of_val(self.vertices.as_slice()) as u64);
+
+        let uniform = UniformBuffer {
+            view_projection: camera.projection_view_matrix(renderer.aspect_ratio()),
+            camera_position: camera.transform.translation.push(1.0),
+            params: glm::vec4(self.settings.surface as u32 as f32, 0.0, 0.0, 0.0),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        stats: &mut RenderStats,
+    ) {
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.geometry.slices();
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        renderpass.draw_indexed(0..self.geometry.index_count, 0, 0..1);
+        stats.record_draw(self.geometry.index_count, 1);
+        stats.record_geometry(&self.geometry);
+    }
+}
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+    depth_texture: Option<Texture>,
+    stats: RenderStats,
+}
+
+impl App {
+    fn resize_depth(&mut self, renderer: &Renderer) {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(renderer));
+        self.resize_depth(renderer);
+        self.camera.orientation.direction = glm::vec2(20_f32.to_radians(), 60_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-4.0, -1.0, -4.0),
+            glm::vec3(4.0, 1.0, 4.0),
+        ));
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        self.stats.reset();
+        self.stats.frame_time = system.delta_time;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(
+                renderer,
+                &self.camera,
+                &mut self.stats,
+                system.delta_time as f32,
+            );
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("CPU Deformation");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                if scene.settings.show(ui) {
+                    scene.rebuild_grid(renderer);
+                }
+                ui.separator();
+                ui.label(format!("Vertices: {}", scene.vertices.len()));
+                ui.label(format!(
+                    "CPU deformation: {:.2} ms",
+                    scene.deform_time * 1000.0
+                ));
+                self.stats.show(ui);
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let (Some(scene), Some(depth_texture)) = (self.scene.as_ref(), self.depth_texture.as_ref())
+        else {
+            return Ok(None);
+        };
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(Renderer::DEFAULT_CLEAR_COLOR),
+            Some(&depth_texture.view),
+        );
+        scene.render(&mut render_pass, &mut self.stats);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "CPU Deformation".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,399 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use support::{
+    run, AppConfig, Application, BindingBuilder, BindingLayout, ComputeKernel, Input, Renderer,
+    System, Texture, TexturedQuad, Uniform,
+};
+use wgpu::{BindGroup, CommandEncoder, Device, RenderPass};
+
+const IMAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Width of the complex plane shown across the window when the view is reset
+const DEFAULT_SPAN: f32 = 3.5;
+
+/// Zoom factor per unit of mouse wheel movement
+const ZOOM_SPEED: f32 = 1.15;
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum Fractal {
+    #[default]
+    Mandelbrot,
+    Julia,
+}
+
+impl Fractal {
+    const ALL: [Self; 2] = [Self::Mandelbrot, Self::Julia];
+
+    fn center(&self) -> glm::Vec2 {
+        match self {
+            Self::Mandelbrot => glm::vec2(-0.75, 0.0),
+            Self::Julia => glm::Vec2::zeros(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct FractalUniform {
+    /// Point of the complex plane at the center of the image
+    center: glm::Vec2,
+    /// The constant added each iteration of the Julia set
+    julia: glm::Vec2,
+    /// Units of the complex plane per pixel
+    scale: f32,
+    max_iterations: u32,
+    /// 0 for the Mandelbrot set, 1 for the Julia set
+    fractal: u32,
+    _padding: u32,
+}
+
+/// The pan, zoom and fractal settings, compared each frame to decide whether to redraw
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct View {
+    fractal: Fractal,
+    center: glm::Vec2,
+    /// Width of the complex plane shown across the window
+    span: f32,
+    max_iterations: u32,
+    julia: glm::Vec2,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            fractal: Fractal::default(),
+            center: Fractal::default().center(),
+            span: DEFAULT_SPAN,
+            max_iterations: 256,
+            julia: glm::vec2(-0.8, 0.156),
+        }
+    }
+}
+
+impl View {
+    fn reset(&mut self) {
+        self.center = self.fractal.center();
+        self.span = DEFAULT_SPAN;
+    }
+
+    /// The point of the complex plane under a pixel, with rows counted from the bottom
+    fn point(&self, pixel: glm::Vec2, width: u32, height: u32) -> glm::Vec2 {
+        let scale = self.span / width.max(1) as f32;
+        self.center + (pixel - glm::vec2(width as f32, height as f32) * 0.5) * scale
+    }
+
+    fn uniform(&self, width: u32) -> FractalUniform {
+        FractalUniform {
+            center: self.center,
+            julia: self.julia,
+            scale: self.span / width.max(1) as f32,
+            max_iterations: self.max_iterations,
+            fractal: self.fractal as u32,
+            _padding: 0,
+        }
+    }
+}
+
+/// The image the compute shader writes, sized to the window
+struct Image {
+    texture: Texture,
+    /// Binds the texture for writing in the compute pass
+    storage_bind_group: BindGroup,
+    /// Binds the texture for sampling on the quad
+    quad_bind_group: BindGroup,
+}
+
+struct Scene {
+    view: View,
+    /// The view last drawn into the image, none when the image needs redrawing
+    drawn: Option<View>,
+    uniform: Uniform<FractalUniform>,
+    storage_layout: BindingLayout,
+    kernel: ComputeKernel,
+    quad: TexturedQuad,
+    image: Image,
+}
+
+impl Scene {
+    pub fn new(renderer: &Renderer) -> Result<Self> {
+        let device = &renderer.device;
+        let uniform = Uniform::with_visibility(device, wgpu::ShaderStages::COMPUTE);
+        let storage_layout = BindingBuilder::new()
+            .label("Fractal Image")
+            .storage_texture(0, wgpu::ShaderStages::COMPUTE, IMAGE_FORMAT)
+            .build(device);
+        let kernel = ComputeKernel::new(
+            device,
+            SHADER_SOURCE,
+            "draw_fractal",
+            &[uniform.layout(), &storage_layout.layout],
+            64,
+        );
+        let quad = TexturedQuad::new(device, renderer.config.format, 1.0)?;
+        let image = Self::create_image(
+            device,
+            &storage_layout,
+            &quad,
+            renderer.config.width,
+            renderer.config.height,
+        );
+        Ok(Self {
+            view: View::default(),
+            drawn: None,
+            uniform,
+            storage_layout,
+            kernel,
+            quad,
+            image,
+        })
+    }
+
+    fn create_image(
+        device: &Device,
+        storage_layout: &BindingLayout,
+        quad: &TexturedQuad,
+        width: u32,
+        height: u32,
+    ) -> Image {
+        let (width, height) = (width.max(1), height.max(1));
+        let texture = Texture::create_storage_texture(
+            device,
+            width,
+            height,
+            IMAGE_FORMAT,
+            wgpu::FilterMode::Linear,
+            "Fractal",
+        );
+        let storage_bind_group =
+            storage_layout.bind_group(device, &[wgpu::BindingResource::TextureView(&texture.view)]);
+        let quad_bind_group = quad.create_bind_group(device, &texture);
+        Image {
+            texture,
+            storage_bind_group,
+            quad_bind_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        self.image = Self::create_image(device, &self.storage_layout, &self.quad, width, height);
+        self.drawn = None;
+    }
+
+    /// Drags pan the view and the wheel zooms around the cursor
+    pub fn handle_input(&mut self, input: &Input, system: &System) {
+        let window = system.window_dimensions;
+        let (width, height) = (window.width.max(1), window.height.max(1));
+        let scale = self.view.span / width as f32;
+        // The cursor is measured from the top down, while image rows run from the bottom up
+        let flip = |vector: glm::Vec2| glm::vec2(vector.x, -vector.y);
+        if input.mouse.is_left_clicked {
+            self.view.center -= flip(input.mouse.position_delta) * scale;
+        }
+        if input.mouse.scrolled && input.mouse.wheel_delta.y != 0.0 {
+            let cursor = glm::vec2(
+                input.mouse.position.x,
+                height as f32 - input.mouse.position.y,
+            );
+            let anchor = self.view.point(cursor, width, height);
+            let zoom = ZOOM_SPEED.powf(-input.mouse.wheel_delta.y);
+            // Keep the point under the cursor in place while the span changes
+            self.view.span *= zoom;
+            self.view.center = anchor + (self.view.center - anchor) * zoom;
+        }
+    }
+
+    /// Redraws the image when the view changed since the last draw
+    pub fn update(&mut self, renderer: &mut Renderer) {
+        if self.drawn == Some(self.view) {
+            return;
+        }
+        self.uniform.write(
+            renderer,
+            &self.view.uniform(self.image.texture.texture.width()),
+        );
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Fractal Encoder"),
+            });
+        self.draw(&mut encoder);
+        renderer.submit(encoder);
+        self.drawn = Some(self.view);
+    }
+
+    fn draw(&self, encoder: &mut CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Fractal Compute Pass"),
+        });
+        pass.set_bind_group(0, self.uniform.bind_group(), &[]);
+        pass.set_bind_group(1, &self.image.storage_bind_group, &[]);
+        let texture = &self.image.texture.texture;
+        self.kernel
+            .dispatch_2d(&mut pass, texture.width(), texture.height());
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        self.quad.render(renderpass, &self.image.quad_bind_group);
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let view = &mut self.view;
+        let fractal = view.fractal;
+        egui::ComboBox::from_label("Fractal")
+            .selected_text(format!("{:?}", view.fractal))
+            .show_ui(ui, |ui| {
+                for option in Fractal::ALL {
+                    ui.selectable_value(&mut view.fractal, option, format!("{option:?}"));
+                }
+            });
+        if view.fractal != fractal {
+            view.reset();
+        }
+        ui.add(egui::Slider::new(&mut view.max_iterations, 16..=2048).text("Iterations"));
+        if view.fractal == Fractal::Julia {
+            ui.add(egui::Slider::new(&mut view.julia.x, -1.5..=1.5).text("c real"));
+            ui.add(egui::Slider::new(&mut view.julia.y, -1.5..=1.5).text("c imaginary"));
+        }
+        ui.label(format!(
+            "Center {:.6} {:+.6}i, zoom {:.1}x",
+            view.center.x,
+            view.center.y,
+            DEFAULT_SPAN / view.span
+        ));
+        if ui.button("Reset view").clicked() {
+            view.reset();
+        }
+        ui.label("Drag to pan, scroll to zoom");
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    center: vec2<f32>,
+    julia: vec2<f32>,
+    scale: f32,
+    max_iterations: u32,
+    fractal: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(1) @binding(0)
+var image: texture_storage_2d<rgba8unorm, write>;
+
+// Cosine gradient cycling through blues and golds
+fn palette(t: f32) -> vec3<f32> {
+    let phase = vec3<f32>(0.5, 0.6, 0.7);
+    return 0.5 + 0.5 * cos(6.28318 * (t + phase));
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn draw_fractal(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(image);
+    if id.x >= size.x || id.y >= size.y {
+        return;
+    }
+    let pixel = vec2<f32>(id.xy) + 0.5;
+    let point = ubo.center + (pixel - vec2<f32>(size) * 0.5) * ubo.scale;
+
+    var z = point;
+    var c = point;
+    if ubo.fractal == 1u {
+        c = ubo.julia;
+    } else {
+        z = vec2<f32>(0.0);
+    }
+
+    var iteration = 0u;
+    loop {
+        if iteration >= ubo.max_iterations || dot(z, z) > 256.0 {
+            break;
+        }
+        z = vec2<f32>(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        iteration++;
+    }
+
+    var color = vec3<f32>(0.0);
+    if iteration < ubo.max_iterations {
+        // The fractional escape count smooths the bands between iterations
+        let smooth_iteration = f32(iteration) + 1.0 - log2(log2(dot(z, z)) * 0.5);
+        color = palette(smooth_iteration * 0.02);
+    }
+    textureStore(image, vec2<i32>(id.xy), vec4<f32>(color, 1.0));
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(renderer)?);
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.resize(
+                &renderer.device,
+                renderer.config.width,
+                renderer.config.height,
+            );
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.handle_input(input, system);
+            scene.update(renderer);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Fractal");
+                if let Some(scene) = self.scene.as_mut() {
+                    scene.show(ui);
+                }
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Fractal".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
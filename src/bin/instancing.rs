@@ -1,21 +1,36 @@
 use anyhow::Result;
 use nalgebra_glm as glm;
-use std::{borrow::Cow, mem};
+use std::{borrow::Cow, mem, time::Instant};
 use support::{
-    camera::MouseOrbit, run, AppConfig, Application, Geometry, Input, Renderer, System, Texture,
+    camera::{Arcball, CameraPath, FirstPersonCamera, MouseOrbit, PerspectiveCamera},
+    impl_vertex, run, show_adapters, Aabb, AntiAliasing, AppConfig, Application, BindingBuilder,
+    Bvh, ComputeKernel, DepthOfField, FocusSettings, Frustum, Fxaa, Geometry, Input,
+    InstanceBuffer, MotionBlur, MotionBlurSettings, Palette, Ray, RenderSettings, RenderStats,
+    RenderTarget, Renderer, System, Texture, Transform, Tutorial, Uniform, VertexDescription,
+    ViewerState,
 };
 use wgpu::{
-    util::DeviceExt, vertex_attr_array, BindGroup, BindGroupLayout, Buffer, BufferAddress, Device,
-    Queue, RenderPass, RenderPipeline, TextureFormat, VertexAttribute,
+    BindGroup, Buffer, BufferAddress, Device, Queue, RenderPass, RenderPipeline, TextureFormat,
 };
+use winit::event::{ElementState, VirtualKeyCode};
 
-struct InstanceBinding {
-    pub instances: Vec<Instance>,
-    pub buffer: Buffer,
+/// The grid of instances, with a bvh for culling and picking
+struct InstanceGrid {
+    /// Data of the instances that survived frustum culling
+    pub visible: InstanceBuffer<InstanceData>,
+    pub bounds: Aabb,
+    /// Per instance bounds
+    pub boxes: Vec<Aabb>,
+    pub bvh: Bvh,
+    /// Every instance, indexed the same as the boxes
+    instances: Vec<Instance>,
+    /// Data of every instance, recolored when the palette changes
+    data: Vec<InstanceData>,
+    culled_matrix: Option<glm::Mat4>,
 }
 
-impl InstanceBinding {
-    pub fn new(device: &Device) -> Self {
+impl InstanceGrid {
+    pub fn new(device: &Device, palette: Palette) -> Self {
         let num_instances_per_row: u32 = 1000;
         let instance_displacement: glm::Vec3 = glm::vec3(
             num_instances_per_row as f32,
@@ -35,31 +50,184 @@ impl InstanceBinding {
                         glm::quat_angle_axis(45_f32.to_degrees(), &position.normalize())
                     };
 
-                    Instance { position, rotation }
+                    Instance {
+                        position,
+                        rotation,
+                        // Blocks of 100 by 100 instances share a color
+                        block: (x / 100 + z / 100 * 10) as usize,
+                    }
                 })
             })
             .collect::<Vec<_>>();
+        let data = Self::instance_data(&instances, palette);
 
-        let instance_data = instances
+        // Instances are rotated arbitrarily, so pad their positions by the mesh's bounding radius,
+        // and they bob up and down on the GPU
+        let mesh_bounds = Aabb::from_points(
+            VERTICES
+                .iter()
+                .map(|vertex| glm::make_vec3(&vertex.position[..3])),
+        );
+        let padding = mesh_bounds.bounding_radius() + mesh_bounds.center().magnitude();
+        let boxes = instances
             .iter()
-            .map(Instance::model_matrix)
+            .map(|instance| {
+                let bob = glm::vec3(0.0, BOB_HEIGHT, 0.0);
+                Aabb::new(instance.position - bob, instance.position + bob).inflate(padding)
+            })
             .collect::<Vec<_>>();
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let bvh = Bvh::new(&boxes);
 
         Self {
+            // Read as storage when a compute shader animates the instances
+            visible: InstanceBuffer::with_usage(device, data.clone(), wgpu::BufferUsages::STORAGE),
+            bounds: bvh.bounds(),
+            boxes,
+            bvh,
             instances,
-            buffer: instance_buffer,
+            data,
+            culled_matrix: None,
+        }
+    }
+
+    fn instance_data(instances: &[Instance], palette: Palette) -> Vec<InstanceData> {
+        instances
+            .iter()
+            .map(|instance| InstanceData {
+                model_matrix: instance.model_matrix(),
+                // Skip the colors used by the triangle's corners
+                color: palette.linear_color(instance.block + 3).push(1.0),
+                // Waves travel outward from the center of the grid
+                phase: instance.position.magnitude() * 0.05,
+                _padding: [0.0; 3],
+            })
+            .collect()
+    }
+
+    /// Recolors every instance, uploading the visible ones on the next cull
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.data = Self::instance_data(&self.instances, palette);
+        self.culled_matrix = None;
+    }
+
+    /// Packs the instances inside the view frustum at the front of the instance buffer,
+    /// only rewriting the ones that changed since the last cull
+    pub fn cull(&mut self, renderer: &mut Renderer, projection_view_matrix: &glm::Mat4) {
+        if self.culled_matrix.as_ref() == Some(projection_view_matrix) {
+            return;
+        }
+        self.culled_matrix = Some(*projection_view_matrix);
+        let frustum = Frustum::from_matrix(projection_view_matrix);
+        let visible = self
+            .bvh
+            .query_frustum(&frustum, &self.boxes)
+            .into_iter()
+            .map(|index| self.data[index])
+            .collect::<Vec<_>>();
+        self.visible.replace(&visible);
+        self.visible.upload(renderer);
+    }
+
+    /// The closest point where the ray enters an instance's bounds
+    pub fn pick(&self, ray: &Ray) -> Option<glm::Vec3> {
+        self.bvh
+            .raycast(ray, |index| ray.intersect_aabb(&self.boxes[index]))
+            .map(|(_, distance)| ray.at(distance))
+    }
+}
+
+/// Holds animated copies of the visible instances for the draw to read,
+/// written by the CPU or by a compute shader
+struct InstanceAnimator {
+    buffer: Buffer,
+    kernel: ComputeKernel,
+    params: Uniform<AnimationParams>,
+    bind_group: BindGroup,
+    /// Instances the compute shader animates
+    count: u32,
+}
+
+impl InstanceAnimator {
+    pub fn new(device: &Device, visible: &InstanceBuffer<InstanceData>, capacity: usize) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Animated Instance Buffer"),
+            size: (capacity * mem::size_of::<InstanceData>()) as BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let params = Uniform::with_visibility(device, wgpu::ShaderStages::COMPUTE);
+        let layout = BindingBuilder::new()
+            .label("Instance Animation")
+            .storage(0, wgpu::ShaderStages::COMPUTE, true)
+            .storage(1, wgpu::ShaderStages::COMPUTE, false)
+            .build(device);
+        let bind_group = layout.bind_group(
+            device,
+            &[
+                visible.buffer().as_entire_binding(),
+                buffer.as_entire_binding(),
+            ],
+        );
+        let kernel = ComputeKernel::new(
+            device,
+            &format!("{ANIMATE_SOURCE}{ANIMATE_KERNEL_SOURCE}"),
+            "animate_main",
+            &[params.layout(), &layout.layout],
+            64,
+        );
+        Self {
+            buffer,
+            kernel,
+            params,
+            bind_group,
+            count: 0,
+        }
+    }
+
+    /// Animates the instances on the CPU and uploads every one of them
+    pub fn upload(&mut self, renderer: &mut Renderer, instances: &[InstanceData], time: f32) {
+        let animated = instances
+            .iter()
+            .map(|instance| instance.animated(time))
+            .collect::<Vec<_>>();
+        if !animated.is_empty() {
+            renderer.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&animated));
+        }
+    }
+
+    /// Sets up the compute shader to animate the first `count` visible instances
+    pub fn prepare(&mut self, renderer: &mut Renderer, count: u32, time: f32) {
+        self.params.write(
+            renderer,
+            &AnimationParams {
+                time,
+                count,
+                ..Default::default()
+            },
+        );
+        self.count = count;
+    }
+
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.count == 0 {
+            return;
         }
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Animate Instances"),
+        });
+        pass.set_bind_group(0, self.params.bind_group(), &[]);
+        pass.set_bind_group(1, &self.bind_group, &[]);
+        self.kernel.dispatch(&mut pass, self.count);
     }
 }
 
 struct Instance {
     position: glm::Vec3,
     rotation: glm::Quat,
+    /// Picks the instance's palette color
+    block: usize,
 }
 
 impl Instance {
@@ -68,20 +236,6 @@ impl Instance {
     }
 }
 
-impl Instance {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![2 => Float32x4, 3 => Float32x4, 4 => Float32x4, 5 => Float32x4].to_vec()
-    }
-
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<glm::Mat4>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes,
-        }
-    }
-}
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Vertex {
@@ -89,82 +243,76 @@ struct Vertex {
     color: [f32; 4],
 }
 
-impl Vertex {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![0 => Float32x4, 1 => Float32x4].to_vec()
-    }
+impl_vertex!(Vertex, [0 => Float32x4, 1 => Float32x4]);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct InstanceData {
+    model_matrix: glm::Mat4,
+    color: glm::Vec4,
+    /// Offsets the instance's animation so neighbors move out of step
+    phase: f32,
+    /// Pads the struct to its size in a WGSL storage array
+    _padding: [f32; 3],
+}
 
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes,
+impl InstanceData {
+    /// Bobs and spins the instance, matching `animate` in the shaders
+    fn animated(&self, time: f32) -> Self {
+        let wave = (time * 2.0 - self.phase).sin();
+        let model_matrix = glm::translation(&glm::vec3(0.0, wave * BOB_HEIGHT, 0.0))
+            * self.model_matrix
+            * glm::rotation(time + self.phase, &glm::Vec3::y());
+        Self {
+            model_matrix,
+            ..*self
         }
     }
 }
 
+impl_vertex!(
+    InstanceData,
+    Instance,
+    [
+        2 => Float32x4,
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32,
+    ]
+);
+
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct UniformBuffer {
     mvp: glm::Mat4,
+    /// Seconds since the scene was created in x, and 1 in y when the vertex shader animates
+    time: glm::Vec4,
 }
 
-struct UniformBinding {
-    pub buffer: Buffer,
-    pub bind_group: BindGroup,
-    pub bind_group_layout: BindGroupLayout,
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct AnimationParams {
+    time: f32,
+    count: u32,
+    _padding: [u32; 2],
 }
 
-impl UniformBinding {
-    pub fn new(device: &Device) -> Self {
-        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("uniform_bind_group_layout"),
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("uniform_bind_group"),
-        });
-
-        Self {
-            buffer,
-            bind_group,
-            bind_group_layout,
-        }
-    }
+/// Where the instances are bobbed and spun each frame, to compare the costs
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum Animation {
+    /// The vertex shader animates the unchanged instance data
+    #[default]
+    VertexShader,
+    /// The CPU animates the visible instances and uploads all of them every frame
+    CpuUpload,
+    /// A compute shader animates the visible instances into a storage buffer
+    Compute,
+}
 
-    pub fn update_buffer(
-        &mut self,
-        queue: &Queue,
-        offset: BufferAddress,
-        uniform_buffer: UniformBuffer,
-    ) {
-        queue.write_buffer(
-            &self.buffer,
-            offset,
-            bytemuck::cast_slice(&[uniform_buffer]),
-        )
-    }
+impl Animation {
+    const ALL: [Self; 3] = [Self::VertexShader, Self::CpuUpload, Self::Compute];
 }
 
 const VERTICES: [Vertex; 3] = [
@@ -184,16 +332,80 @@ const VERTICES: [Vertex; 3] = [
 
 const INDICES: [u32; 3] = [0, 1, 2]; // Clockwise winding order
 
+/// How far instances bob above and below their resting height
+const BOB_HEIGHT: f32 = 0.5;
+
+const RENDER_SETTINGS_PATH: &str = "render_settings.ron";
+
+/// Shared by the render and compute shaders
+const ANIMATE_SOURCE: &str = "
+const BOB_HEIGHT: f32 = 0.5;
+
+fn wave(time: f32, phase: f32) -> f32 {
+    return sin(time * 2.0 - phase);
+}
+
+// Bobs and spins an instance, matching InstanceData::animated
+fn animate(model_matrix: mat4x4<f32>, time: f32, phase: f32) -> mat4x4<f32> {
+    let angle = time + phase;
+    let spin = mat4x4<f32>(
+        vec4<f32>(cos(angle), 0.0, -sin(angle), 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(sin(angle), 0.0, cos(angle), 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+    var animated = model_matrix * spin;
+    animated[3].y += wave(time, phase) * BOB_HEIGHT;
+    return animated;
+}
+";
+
+const ANIMATE_KERNEL_SOURCE: &str = "
+struct Instance {
+    model_matrix: mat4x4<f32>,
+    color: vec4<f32>,
+    phase: f32,
+};
+
+struct Params {
+    time: f32,
+    count: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(1) @binding(0)
+var<storage, read> instances: array<Instance>;
+
+@group(1) @binding(1)
+var<storage, read_write> animated: array<Instance>;
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn animate_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= params.count {
+        return;
+    }
+    var instance = instances[index];
+    instance.model_matrix = animate(instance.model_matrix, params.time, instance.phase);
+    animated[index] = instance;
+}
+";
+
 const SHADER_SOURCE: &str = "
 struct InstanceInput {
     @location(2) model_matrix_0: vec4<f32>,
     @location(3) model_matrix_1: vec4<f32>,
     @location(4) model_matrix_2: vec4<f32>,
     @location(5) model_matrix_3: vec4<f32>,
+    @location(6) color: vec4<f32>,
+    @location(7) phase: f32,
 };
 
 struct Uniform {
     mvp: mat4x4<f32>,
+    time: vec4<f32>,
 };
 
 @group(0) @binding(0)
@@ -210,18 +422,25 @@ struct VertexOutput {
 
 @vertex
 fn vertex_main(vert: VertexInput, instance: InstanceInput) -> VertexOutput {
-    let model_matrix = mat4x4<f32>(
+    var model_matrix = mat4x4<f32>(
         instance.model_matrix_0,
         instance.model_matrix_1,
         instance.model_matrix_2,
         instance.model_matrix_3,
     );
+    if ubo.time.y > 0.0 {
+        model_matrix = animate(model_matrix, ubo.time.x, instance.phase);
+    }
 
     var position = vert.position;
     position.y *= -1.0;
 
+    // Per vertex colors are tinted by the instance's color and pulse with its height
+    let height = wave(ubo.time.x, instance.phase);
+
     var out: VertexOutput;
-    out.color = vert.color;
+    out.color = mix(vert.color, instance.color, 0.75) * (0.75 + 0.25 * height);
+    out.color.a = 1.0;
     out.position = ubo.mvp * model_matrix * position;
 
     return out;
@@ -235,81 +454,177 @@ fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
 struct Scene {
     pub geometry: Geometry,
-    pub instance: InstanceBinding,
-    pub uniform: UniformBinding,
+    pub instance: InstanceGrid,
+    pub uniform: Uniform<UniformBuffer>,
     pub pipeline: RenderPipeline,
+    pub palette: Palette,
+    /// Seconds since the scene was created, animating the instances
+    time: f32,
+    pub animation: Animation,
+    /// Created the first time the instances are animated outside the vertex shader
+    animator: Option<InstanceAnimator>,
+    /// Seconds the CPU spent animating the instances last update
+    update_time: f64,
+    uploaded_bytes: u64,
 }
 
 impl Scene {
-    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
-        let geometry = Geometry::new(device, &VERTICES, &INDICES);
-        let uniform = UniformBinding::new(device);
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_format: TextureFormat,
+        palette: Palette,
+    ) -> Self {
+        let geometry = Geometry::new_dynamic(device, queue, &Self::vertices(palette), &INDICES);
+        let uniform = Uniform::new(device);
         let pipeline = Self::create_pipeline(device, surface_format, &uniform);
-        let instance = InstanceBinding::new(device);
+        let instance = InstanceGrid::new(device, palette);
         Self {
             geometry,
             instance,
             uniform,
             pipeline,
+            palette,
+            time: 0.0,
+            animation: Animation::default(),
+            animator: None,
+            update_time: 0.0,
+            uploaded_bytes: 0,
+        }
+    }
+
+    /// Colors the triangle's corners with the first colors of the debug palette
+    fn vertices(palette: Palette) -> [Vertex; 3] {
+        let mut vertices = VERTICES;
+        for (index, vertex) in vertices.iter_mut().enumerate() {
+            vertex.color = palette.linear_color(index).push(1.0).into();
         }
+        vertices
+    }
+
+    pub fn set_palette(&mut self, device: &Device, queue: &Queue, palette: Palette) {
+        self.geometry
+            .update_vertices(device, queue, &Self::vertices(palette));
+        self.instance.set_palette(palette);
+        self.palette = palette;
     }
 
-    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+    pub fn render<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        stats: &mut RenderStats,
+    ) {
+        renderpass.push_debug_group("Instanced boxes");
         renderpass.set_pipeline(&self.pipeline);
-        renderpass.set_bind_group(0, &self.uniform.bind_group, &[]);
+        self.uniform.set(renderpass, 0);
 
-        let (vertex_buffer_slice, index_buffer_slice) = self.geometry.slices();
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.geometry.slices();
         renderpass.set_vertex_buffer(0, vertex_buffer_slice);
-        renderpass.set_vertex_buffer(1, self.instance.buffer.slice(..));
-        renderpass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32);
+        match self.animator.as_ref() {
+            Some(animator) if self.animation != Animation::VertexShader => {
+                renderpass.set_vertex_buffer(1, animator.buffer.slice(..));
+                stats.record_buffer(&animator.buffer);
+            }
+            _ => renderpass.set_vertex_buffer(1, self.instance.visible.slice(0)),
+        }
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
 
-        renderpass.draw_indexed(
-            0..(INDICES.len() as _),
-            0,
-            0..self.instance.instances.len() as _,
-        );
+        let instance_count = self.instance.visible.len() as u32;
+        renderpass.draw_indexed(0..self.geometry.index_count, 0, 0..instance_count);
+        renderpass.pop_debug_group();
+        stats.record_draw(self.geometry.index_count, instance_count);
+        stats.record_geometry(&self.geometry);
+        stats.record_buffer(self.instance.visible.buffer());
+        stats.record_buffer(self.uniform.buffer());
+        stats.record_upload(self.uploaded_bytes);
+        stats.update_time = self.update_time;
     }
 
-    pub fn update(&mut self, view_projection_matrix: glm::Mat4, queue: &Queue) {
-        self.uniform.update_buffer(
-            queue,
-            0,
-            UniformBuffer {
+    /// Runs the compute shader that animates the instances, before the scene's render pass
+    pub fn animate(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.animation != Animation::Compute {
+            return;
+        }
+        if let Some(animator) = self.animator.as_ref() {
+            animator.dispatch(encoder);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        view_projection_matrix: glm::Mat4,
+        delta_time: f32,
+        renderer: &mut Renderer,
+    ) {
+        self.time += delta_time;
+        self.instance.cull(renderer, &view_projection_matrix);
+        self.animate_instances(renderer);
+        let animate_in_shader = self.animation == Animation::VertexShader;
+        self.uniform.write(
+            renderer,
+            &UniformBuffer {
                 mvp: view_projection_matrix,
+                time: glm::vec4(self.time, f32::from(u8::from(animate_in_shader)), 0.0, 0.0),
             },
         )
     }
 
+    fn animate_instances(&mut self, renderer: &mut Renderer) {
+        let start = Instant::now();
+        self.uploaded_bytes = 0;
+        if self.animation != Animation::VertexShader && self.animator.is_none() {
+            self.animator = Some(InstanceAnimator::new(
+                &renderer.device,
+                &self.instance.visible,
+                self.instance.instances.len(),
+            ));
+        }
+        let visible = &self.instance.visible;
+        match (self.animation, self.animator.as_mut()) {
+            (Animation::CpuUpload, Some(animator)) => {
+                animator.upload(renderer, visible.instances(), self.time);
+                self.uploaded_bytes = mem::size_of_val(visible.instances()) as u64;
+            }
+            (Animation::Compute, Some(animator)) => {
+                animator.prepare(renderer, visible.len() as u32, self.time);
+            }
+            _ => {}
+        }
+        self.update_time = start.elapsed().as_secs_f64();
+    }
+
     fn create_pipeline(
         device: &Device,
         surface_format: TextureFormat,
-        uniform: &UniformBinding,
+        uniform: &Uniform<UniformBuffer>,
     ) -> RenderPipeline {
         let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+            label: Some("Instancing Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!(
+                "{ANIMATE_SOURCE}{SHADER_SOURCE}"
+            ))),
         });
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&uniform.bind_group_layout],
+            label: Some("Instancing Pipeline Layout"),
+            bind_group_layouts: &[uniform.layout()],
             push_constant_ranges: &[],
         });
 
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
+            label: Some("Instancing Pipeline"),
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader_module,
                 entry_point: "vertex_main",
                 buffers: &[
-                    Vertex::description(&Vertex::vertex_attributes()),
-                    Instance::description(&Instance::vertex_attributes()),
+                    Vertex::layout().buffer_layout(),
+                    InstanceData::layout().buffer_layout(),
                 ],
             },
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
+                strip_index_format: None,
                 front_face: wgpu::FrontFace::Cw,
                 cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
@@ -342,23 +657,244 @@ impl Scene {
     }
 }
 
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum CameraMode {
+    #[default]
+    Orbit,
+    FirstPerson,
+    Arcball,
+}
+
+/// Effects that run over the scene after it renders, listed in the order they run
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PostPass {
+    DepthOfField,
+    MotionBlur,
+    Fxaa,
+}
+
 #[derive(Default)]
 struct App {
     scene: Option<Scene>,
     camera: MouseOrbit,
+    first_person_camera: FirstPersonCamera,
+    arcball: Arcball,
+    camera_mode: CameraMode,
+    was_left_clicked: bool,
     depth_texture: Option<Texture>,
+    /// The scene renders here when any post effect is in use
+    scene_target: Option<RenderTarget>,
+    /// Effects between the first and last write to these in turn
+    post_targets: [Option<RenderTarget>; 2],
+    /// The post effects in use, in the order they run
+    post_passes: Vec<PostPass>,
+    depth_of_field: Option<DepthOfField>,
+    motion_blur: Option<MotionBlur>,
+    fxaa: Option<Fxaa>,
+    focus_settings: FocusSettings,
+    motion_blur_settings: MotionBlurSettings,
+    /// The next click on an instance sets the focus distance
+    picking_focus: bool,
+    tutorial: Tutorial,
+    quick_save: bool,
+    quick_load: bool,
+    focus: bool,
+    camera_path: CameraPath,
+    stats: RenderStats,
+    /// Seconds the last frame took, shown with the stats
+    frame_time: f64,
+    #[cfg(feature = "audio")]
+    audio: Option<support::Audio>,
+}
+
+impl App {
+    fn handle_quick_save(&mut self, renderer: &mut Renderer) -> Result<()> {
+        if mem::take(&mut self.quick_save) {
+            ViewerState {
+                camera: self.camera.orientation.clone(),
+                render_settings: renderer.settings,
+            }
+            .save_state(ViewerState::QUICK_SAVE_PATH)?;
+        }
+        if mem::take(&mut self.quick_load) {
+            let state = ViewerState::load_state(ViewerState::QUICK_SAVE_PATH)?;
+            self.camera.orientation = state.camera;
+            self.camera_mode = CameraMode::Orbit;
+            renderer.apply_settings(state.render_settings);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "audio")]
+    fn initialize_audio(&mut self) {
+        match support::Audio::new() {
+            Ok(mut audio) => {
+                audio.load_tone("click", 660.0, 0.08);
+                audio.bind("pick", "click");
+                self.audio = Some(audio);
+            }
+            Err(error) => log::warn!("Audio disabled: {error:#}"),
+        }
+    }
+
+    /// Creates, resizes or drops the offscreen targets and passes to follow the post effects in use.
+    /// The first effect reads the scene, each one after reads the previous effect's output.
+    fn update_post_processing(&mut self, renderer: &Renderer) {
+        let passes = [
+            (
+                PostPass::DepthOfField,
+                renderer.settings.post_effects.depth_of_field,
+            ),
+            (
+                PostPass::MotionBlur,
+                renderer.settings.post_effects.motion_blur,
+            ),
+            (
+                PostPass::Fxaa,
+                renderer.settings.anti_aliasing == AntiAliasing::Fxaa,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(effect, enabled)| enabled.then_some(effect))
+        .collect::<Vec<_>>();
+
+        let mut changed = passes != self.post_passes;
+        changed |= sync_target(&mut self.scene_target, !passes.is_empty(), renderer);
+        for (index, target) in self.post_targets.iter_mut().enumerate() {
+            changed |= sync_target(target, passes.len() > index + 1, renderer);
+        }
+        self.post_passes = passes;
+
+        if !self.post_passes.contains(&PostPass::DepthOfField) {
+            self.depth_of_field = None;
+        }
+        if !self.post_passes.contains(&PostPass::MotionBlur) {
+            self.motion_blur = None;
+        }
+        if !self.post_passes.contains(&PostPass::Fxaa) {
+            self.fxaa = None;
+        }
+        let Some(scene_target) = self.scene_target.as_ref() else {
+            return;
+        };
+        let (device, format) = (&renderer.device, renderer.config.format);
+        let depth = &scene_target.depth;
+        for (index, effect) in self.post_passes.iter().enumerate() {
+            let source = match index {
+                0 => Some(scene_target),
+                _ => self.post_targets[(index - 1) % 2].as_ref(),
+            };
+            let Some(color) = source.map(|target| &target.color) else {
+                continue;
+            };
+            match effect {
+                PostPass::DepthOfField => match self.depth_of_field.as_mut() {
+                    Some(pass) if changed => pass.set_source(device, color, depth),
+                    Some(_) => {}
+                    None => {
+                        self.depth_of_field = Some(DepthOfField::new(device, format, color, depth))
+                    }
+                },
+                PostPass::MotionBlur => match self.motion_blur.as_mut() {
+                    Some(pass) if changed => pass.set_source(device, color, depth),
+                    Some(_) => {}
+                    None => self.motion_blur = Some(MotionBlur::new(device, format, color, depth)),
+                },
+                PostPass::Fxaa => match self.fxaa.as_mut() {
+                    Some(pass) if changed => pass.set_source(device, color),
+                    Some(_) => {}
+                    None => self.fxaa = Some(Fxaa::new(device, format, color)),
+                },
+            }
+        }
+    }
+
+    fn pick_focus(&mut self, renderer: &Renderer, input: &Input) {
+        self.picking_focus = false;
+        let Some(scene) = self.scene.as_ref() else {
+            return;
+        };
+        let viewport = glm::vec2(renderer.config.width as f32, renderer.config.height as f32);
+        let transform = self.active_transform();
+        let ray = self
+            .active_camera()
+            .screen_ray(&transform, input.mouse.position, viewport);
+        if let Some(point) = scene.instance.pick(&ray) {
+            let view_position = transform.as_view_matrix() * point.push(1.0);
+            self.focus_settings.focus_distance = -view_position.z;
+        }
+    }
+
+    /// Moves the arcball pivot to the point on the instance grid under the cursor
+    fn pick_arcball_pivot(&mut self, renderer: &Renderer, input: &Input) {
+        let Some(scene) = self.scene.as_ref() else {
+            return;
+        };
+        let viewport = glm::vec2(renderer.config.width as f32, renderer.config.height as f32);
+        let ray =
+            self.arcball
+                .camera
+                .screen_ray(&self.arcball.transform, input.mouse.position, viewport);
+        if let Some(point) = scene.instance.pick(&ray) {
+            self.arcball.set_pivot(point);
+            #[cfg(feature = "audio")]
+            if let Some(audio) = self.audio.as_mut() {
+                if let Err(error) = audio.trigger("pick", Some(point)) {
+                    log::warn!("{error:#}");
+                }
+            }
+        }
+    }
+
+    fn active_transform(&self) -> Transform {
+        match self.camera_mode {
+            CameraMode::Orbit => self.camera.transform,
+            CameraMode::FirstPerson => self.first_person_camera.transform,
+            CameraMode::Arcball => self.arcball.transform,
+        }
+    }
+
+    fn active_camera(&self) -> &PerspectiveCamera {
+        match self.camera_mode {
+            CameraMode::Orbit => &self.camera.camera,
+            CameraMode::FirstPerson => &self.first_person_camera.camera,
+            CameraMode::Arcball => &self.arcball.camera,
+        }
+    }
+
+    fn set_camera_mode(&mut self, camera_mode: CameraMode) {
+        let transform = self.active_transform();
+        let target = self.camera.orientation.offset;
+        match camera_mode {
+            CameraMode::Orbit => {}
+            CameraMode::FirstPerson => {
+                self.first_person_camera.transform.translation = transform.translation;
+                self.first_person_camera.look_at(&target);
+            }
+            CameraMode::Arcball => self.arcball.look_at_pivot(transform.translation, target),
+        }
+        self.camera_mode = camera_mode;
+    }
 }
 
 impl Application for App {
     fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.tutorial = Tutorial::from_ron(include_str!("../../assets/tutorials/instancing.ron"))?;
         self.camera.transform.translation = glm::vec3(4.0, 0.0, 4.0);
         self.camera.orientation.sensitivity = glm::vec2(0.1, 0.1);
-        self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
+        self.scene = Some(Scene::new(
+            &renderer.device,
+            &renderer.queue,
+            renderer.config.format,
+            renderer.settings.debug_palette,
+        ));
         self.depth_texture = Some(Texture::create_depth_texture(
             &renderer.device,
             renderer.config.width,
             renderer.config.height,
         ));
+        #[cfg(feature = "audio")]
+        self.initialize_audio();
         Ok(())
     }
 
@@ -366,22 +902,209 @@ impl Application for App {
         Some(Texture::DEPTH_FORMAT)
     }
 
-    fn update(&mut self, renderer: &mut Renderer, input: &Input, system: &System) -> Result<()> {
-        self.camera.update(input, system)?;
-        let projection_view_matrix = self.camera.projection_view_matrix(renderer.aspect_ratio());
+    /// Circles the grid once over the run
+    fn benchmark_step(&mut self, _renderer: &mut Renderer, progress: f32) -> Result<()> {
+        self.camera_path.active = false;
+        self.camera_mode = CameraMode::Orbit;
+        self.camera.orientation.direction.x = progress * std::f32::consts::TAU;
+        Ok(())
+    }
+
+    fn on_key(&mut self, keycode: &VirtualKeyCode, keystate: &ElementState) -> Result<()> {
+        if *keystate == ElementState::Pressed {
+            match keycode {
+                VirtualKeyCode::F5 => self.quick_save = true,
+                VirtualKeyCode::F9 => self.quick_load = true,
+                VirtualKeyCode::F => self.focus = true,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Err(error) = self.handle_quick_save(renderer) {
+            log::error!("{error:#}");
+        }
+        self.update_post_processing(renderer);
+        self.frame_time = system.delta_time;
+        if mem::take(&mut self.focus) {
+            if let Some(scene) = self.scene.as_ref() {
+                self.camera_mode = CameraMode::Orbit;
+                self.camera.frame_bounds(&scene.instance.bounds);
+            }
+        }
+        let path_transform = self.camera_path.update(system.delta_time as f32);
+        let projection_view_matrix = if let Some(transform) = path_transform {
+            input.set_relative_mode(false);
+            self.camera
+                .camera
+                .projection_matrix(renderer.aspect_ratio())
+                * transform.as_view_matrix()
+        } else {
+            match self.camera_mode {
+                CameraMode::Orbit => {
+                    input.set_relative_mode(false);
+                    self.camera.update(input, system)?;
+                    self.camera.projection_view_matrix(renderer.aspect_ratio())
+                }
+                CameraMode::FirstPerson => {
+                    input.set_relative_mode(input.mouse.is_right_clicked);
+                    self.first_person_camera.update(input, system)?;
+                    self.first_person_camera
+                        .projection_view_matrix(renderer.aspect_ratio())
+                }
+                CameraMode::Arcball => {
+                    input.set_relative_mode(false);
+                    if input.mouse.is_left_clicked && !self.was_left_clicked {
+                        self.pick_arcball_pivot(renderer, input);
+                    }
+                    self.arcball.update(input, system)?;
+                    self.arcball.projection_view_matrix(renderer.aspect_ratio())
+                }
+            }
+        };
+        if self.picking_focus && input.mouse.is_left_clicked && !self.was_left_clicked {
+            self.pick_focus(renderer, input);
+        }
+        self.was_left_clicked = input.mouse.is_left_clicked;
+        if let Some(depth_of_field) = self.depth_of_field.as_ref() {
+            let projection = self
+                .active_camera()
+                .projection_matrix(renderer.aspect_ratio());
+            depth_of_field.update(renderer, &self.focus_settings, projection);
+        }
+        if let Some(motion_blur) = self.motion_blur.as_mut() {
+            motion_blur.update(renderer, &self.motion_blur_settings, projection_view_matrix);
+        }
+        #[cfg(feature = "audio")]
+        {
+            let listener = self.active_transform();
+            if let Some(audio) = self.audio.as_mut() {
+                audio.set_listener(&listener);
+            }
+        }
         if let Some(scene) = self.scene.as_mut() {
-            scene.update(projection_view_matrix, &renderer.queue);
+            if scene.palette != renderer.settings.debug_palette {
+                scene.set_palette(
+                    &renderer.device,
+                    &renderer.queue,
+                    renderer.settings.debug_palette,
+                );
+            }
+            scene.update(projection_view_matrix, system.delta_time as f32, renderer);
         }
         Ok(())
     }
 
-    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
-        egui::Window::new("wgpu")
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        let window = egui::Window::new("wgpu")
             .resizable(false)
             .fixed_pos((10.0, 10.0))
             .show(context, |ui| {
                 ui.heading("Instancing");
+                ui.checkbox(&mut self.tutorial.visible, "Tutorial");
+                let mut camera_mode = self.camera_mode;
+                egui::ComboBox::from_label("Camera")
+                    .selected_text(format!("{camera_mode:?}"))
+                    .show_ui(ui, |ui| {
+                        for mode in [
+                            CameraMode::Orbit,
+                            CameraMode::FirstPerson,
+                            CameraMode::Arcball,
+                        ] {
+                            ui.selectable_value(&mut camera_mode, mode, format!("{mode:?}"));
+                        }
+                    });
+                if camera_mode != self.camera_mode {
+                    self.set_camera_mode(camera_mode);
+                }
+                ui.label("F to focus on the instances");
+                ui.label("F5 to quick save the view, F9 to restore it");
+                match self.camera_mode {
+                    CameraMode::Orbit => {}
+                    CameraMode::FirstPerson => {
+                        ui.label("Hold the right mouse button to look around, WASD to move");
+                    }
+                    CameraMode::Arcball => {
+                        ui.label("Left drag rotates around the point under the cursor");
+                    }
+                }
+                ui.collapsing("Camera path", |ui| {
+                    let transform = self.active_transform();
+                    self.camera_path.show(ui, &transform);
+                });
+                let post_effects = &mut renderer.settings.post_effects;
+                ui.collapsing("Motion blur", |ui| {
+                    ui.checkbox(&mut post_effects.motion_blur, "Enabled");
+                    self.motion_blur_settings.show(ui);
+                });
+                ui.collapsing("Depth of field", |ui| {
+                    ui.checkbox(&mut post_effects.depth_of_field, "Enabled");
+                    self.focus_settings.show(ui);
+                    ui.horizontal(|ui| {
+                        if ui.button("Pick focus").clicked() {
+                            self.picking_focus = true;
+                        }
+                        if self.picking_focus {
+                            ui.label("Click an instance to focus on it");
+                        }
+                    });
+                });
+                ui.collapsing("Animation", |ui| {
+                    if let Some(scene) = self.scene.as_mut() {
+                        egui::ComboBox::from_label("Instances animated by")
+                            .selected_text(format!("{:?}", scene.animation))
+                            .show_ui(ui, |ui| {
+                                for animation in Animation::ALL {
+                                    ui.selectable_value(
+                                        &mut scene.animation,
+                                        animation,
+                                        format!("{animation:?}"),
+                                    );
+                                }
+                            });
+                    }
+                    ui.label("Compare the frame and CPU update times under Statistics");
+                });
+                ui.collapsing("Statistics", |ui| {
+                    self.stats.show(ui);
+                });
+                ui.collapsing("Adapter", |ui| show_adapters(ui, renderer));
+                ui.collapsing("Render settings", |ui| {
+                    let mut settings = renderer.settings;
+                    let mut changed = settings.show(ui);
+                    ui.horizontal(|ui| {
+                        if ui.button("Save").clicked() {
+                            if let Err(error) = settings.save(RENDER_SETTINGS_PATH) {
+                                log::error!("{error}");
+                            }
+                        }
+                        if ui.button("Load").clicked() {
+                            match RenderSettings::load(RENDER_SETTINGS_PATH) {
+                                Ok(loaded) => {
+                                    settings = loaded;
+                                    changed = true;
+                                }
+                                Err(error) => log::error!("{error}"),
+                            }
+                        }
+                    });
+                    if changed {
+                        renderer.apply_settings(settings);
+                    }
+                });
             });
+        if let Some(window) = window {
+            self.tutorial
+                .highlight(context, "window", window.response.rect);
+        }
+        self.tutorial.show(context);
         Ok(())
     }
 
@@ -391,6 +1114,7 @@ impl Application for App {
             renderer.config.width,
             renderer.config.height,
         ));
+        self.update_post_processing(renderer);
         Ok(())
     }
 
@@ -399,39 +1123,73 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
-
-        let depth_stencil_attachment = self.depth_texture.as_ref().map(|depth_texture| {
-            wgpu::RenderPassDepthStencilAttachment {
-                view: &depth_texture.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
-                }),
-                stencil_ops: None,
+        let depth_view = self
+            .depth_texture
+            .as_ref()
+            .map(|depth_texture| &depth_texture.view);
+
+        self.stats.reset();
+        self.stats.frame_time = self.frame_time;
+        if let Some(scene) = self.scene.as_ref() {
+            scene.animate(encoder);
+        }
+        if let Some(depth_texture) = self.depth_texture.as_ref() {
+            self.stats.record_texture(&depth_texture.texture);
+        }
+
+        if let Some(scene_target) = self.scene_target.as_ref() {
+            {
+                let mut scene_pass =
+                    scene_target.begin_pass(encoder, Renderer::DEFAULT_CLEAR_COLOR);
+                if let Some(scene) = self.scene.as_ref() {
+                    scene.render(&mut scene_pass, &mut self.stats);
+                }
+            }
+            for target in std::iter::once(scene_target).chain(self.post_targets.iter().flatten()) {
+                self.stats.record_texture(&target.color.texture);
+                self.stats.record_texture(&target.depth.texture);
             }
-        });
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment,
-        });
+            let last = self.post_passes.len() - 1;
+            for (index, effect) in self.post_passes.iter().enumerate() {
+                let output = match self.post_targets[index % 2].as_ref() {
+                    Some(target) if index < last => &target.color.view,
+                    _ => view,
+                };
+                let mut post_pass = Renderer::begin_default_pass(encoder, output, None, None);
+                match effect {
+                    PostPass::DepthOfField => {
+                        if let Some(depth_of_field) = self.depth_of_field.as_ref() {
+                            depth_of_field.render(&mut post_pass);
+                        }
+                    }
+                    PostPass::MotionBlur => {
+                        if let Some(motion_blur) = self.motion_blur.as_ref() {
+                            motion_blur.render(&mut post_pass);
+                        }
+                    }
+                    PostPass::Fxaa => {
+                        if let Some(fxaa) = self.fxaa.as_ref() {
+                            fxaa.render(&mut post_pass);
+                        }
+                    }
+                }
+            }
+
+            // The gui pipeline expects the depth attachment
+            return Ok(Some(Renderer::begin_default_pass(
+                encoder, view, None, depth_view,
+            )));
+        }
 
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(Renderer::DEFAULT_CLEAR_COLOR),
+            depth_view,
+        );
         if let Some(scene) = self.scene.as_ref() {
-            scene.render(&mut render_pass);
+            scene.render(&mut render_pass, &mut self.stats);
         }
 
         Ok(Some(render_pass))
@@ -445,6 +1203,22 @@ fn main() -> Result<()> {
             title: "Instancing".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
+
+/// Creates, resizes or drops an offscreen target, returning true if it was replaced
+fn sync_target(target: &mut Option<RenderTarget>, enabled: bool, renderer: &Renderer) -> bool {
+    match target.as_mut() {
+        _ if !enabled => target.take().is_some(),
+        Some(target) => target.fit_render_resolution(renderer),
+        None => {
+            *target = Some(RenderTarget::for_render_resolution(
+                renderer,
+                renderer.config.format,
+            ));
+            true
+        }
+    }
+}
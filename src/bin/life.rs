@@ -0,0 +1,418 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use support::{
+    run, AppConfig, Application, BindingBuilder, ComputeKernel, Input, Renderer, System, Texture,
+    TexturedQuad,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, CommandEncoder, Device, Queue, RenderPass};
+
+const GRID_WIDTH: u32 = 200;
+const GRID_HEIGHT: u32 = 150;
+
+/// Generations run in a single frame at most, so a slow frame doesn't snowball
+const MAX_GENERATIONS_PER_FRAME: u32 = 8;
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    /// Grid width and height, and whether to apply the rules or only paint
+    grid: [u32; 4],
+    /// Brush center in cells, radius in cells, and 1 to draw or 2 to erase
+    brush: glm::Vec4,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Brush {
+    Draw,
+    Erase,
+}
+
+impl Brush {
+    fn mode(brush: Option<Self>) -> f32 {
+        match brush {
+            None => 0.0,
+            Some(Self::Draw) => 1.0,
+            Some(Self::Erase) => 2.0,
+        }
+    }
+}
+
+const ALIVE_COLOR: [u8; 4] = [242, 242, 217, 255];
+const DEAD_COLOR: [u8; 4] = [0, 0, 0, 255];
+
+/// Cell colors for a random soup, roughly a quarter of the cells alive
+fn random_cells(seed: u32) -> Vec<u8> {
+    let mut state = seed.max(1);
+    (0..GRID_WIDTH * GRID_HEIGHT)
+        .flat_map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if state % 4 == 0 {
+                ALIVE_COLOR
+            } else {
+                DEAD_COLOR
+            }
+        })
+        .collect()
+}
+
+struct Scene {
+    running: bool,
+    generations_per_second: f32,
+    brush_radius: f32,
+    generation: u64,
+    seed: u32,
+    accumulator: f32,
+    /// The two generations, the latest one indexed by `current`
+    cells: [Texture; 2],
+    current: usize,
+    uniform: Buffer,
+    /// Reads `cells[index]` and writes the other
+    compute_bind_groups: [BindGroup; 2],
+    /// Displays `cells[index]`
+    quad_bind_groups: [BindGroup; 2],
+    kernel: ComputeKernel,
+    quad: TexturedQuad,
+}
+
+impl Scene {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        surface_format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        let cells = [0, 1].map(|index| Self::create_cell_texture(device, index));
+        let seed = 0x2545_f491;
+        cells[0].write_pixels(queue, &random_cells(seed));
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let compute_layout = BindingBuilder::new()
+            .label("Compute")
+            .uniform(0, wgpu::ShaderStages::COMPUTE)
+            .unfilterable_texture(1, wgpu::ShaderStages::COMPUTE)
+            .storage_texture(
+                2,
+                wgpu::ShaderStages::COMPUTE,
+                wgpu::TextureFormat::Rgba8Unorm,
+            )
+            .build(device);
+        let compute_bind_groups = [0, 1].map(|source| {
+            compute_layout.bind_group(
+                device,
+                &[
+                    uniform.as_entire_binding(),
+                    wgpu::BindingResource::TextureView(&cells[source].view),
+                    wgpu::BindingResource::TextureView(&cells[1 - source].view),
+                ],
+            )
+        });
+        let kernel = ComputeKernel::new(
+            device,
+            SHADER_SOURCE,
+            "generation",
+            &[&compute_layout.layout],
+            64,
+        );
+
+        let quad = TexturedQuad::new(device, surface_format, 1.0)?;
+        let quad_bind_groups = [0, 1].map(|index| quad.create_bind_group(device, &cells[index]));
+
+        Ok(Self {
+            running: true,
+            generations_per_second: 20.0,
+            brush_radius: 3.0,
+            generation: 0,
+            seed,
+            accumulator: 0.0,
+            cells,
+            current: 0,
+            uniform,
+            compute_bind_groups,
+            quad_bind_groups,
+            kernel,
+            quad,
+        })
+    }
+
+    pub fn randomize(&mut self, queue: &Queue) {
+        self.seed = self
+            .seed
+            .wrapping_mul(747_796_405)
+            .wrapping_add(2_891_336_453);
+        self.cells[self.current].write_pixels(queue, &random_cells(self.seed));
+        self.generation = 0;
+    }
+
+    pub fn clear(&mut self, queue: &Queue) {
+        let cells = DEAD_COLOR.repeat((GRID_WIDTH * GRID_HEIGHT) as usize);
+        self.cells[self.current].write_pixels(queue, &cells);
+        self.generation = 0;
+    }
+
+    /// Runs the generations due this frame, painting with the brush at a cell position if given
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        delta_time: f32,
+        brush: Option<(Brush, glm::Vec2)>,
+    ) {
+        let mut generations = 0;
+        if self.running {
+            self.accumulator += delta_time * self.generations_per_second;
+            generations = (self.accumulator as u32).min(MAX_GENERATIONS_PER_FRAME);
+            self.accumulator = self.accumulator.fract();
+        }
+        if generations == 0 && brush.is_none() {
+            return;
+        }
+
+        let (mode, position) = brush.map_or((None, glm::Vec2::zeros()), |(mode, position)| {
+            (Some(mode), position)
+        });
+        let uniform = UniformBuffer {
+            grid: [GRID_WIDTH, GRID_HEIGHT, u32::from(generations > 0), 0],
+            brush: glm::vec4(position.x, position.y, self.brush_radius, Brush::mode(mode)),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Life Encoder"),
+            });
+        // Painting while paused still needs one pass to write the brush into the next texture
+        for _ in 0..generations.max(1) {
+            self.step(&mut encoder);
+        }
+        renderer.submit(encoder);
+        self.generation += generations as u64;
+    }
+
+    /// Runs generations immediately, regardless of whether the simulation is running
+    pub fn advance(&mut self, renderer: &mut Renderer, generations: u32) {
+        let uniform = UniformBuffer {
+            grid: [GRID_WIDTH, GRID_HEIGHT, 1, 0],
+            brush: glm::Vec4::zeros(),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Life Encoder"),
+            });
+        for _ in 0..generations {
+            self.step(&mut encoder);
+        }
+        renderer.submit(encoder);
+        self.generation += generations as u64;
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        self.quad
+            .render(renderpass, &self.quad_bind_groups[self.current]);
+    }
+
+    fn step(&mut self, encoder: &mut CommandEncoder) {
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Life Compute Pass"),
+            });
+            pass.set_bind_group(0, &self.compute_bind_groups[self.current], &[]);
+            self.kernel.dispatch(&mut pass, GRID_WIDTH * GRID_HEIGHT);
+        }
+        self.current = 1 - self.current;
+    }
+
+    fn create_cell_texture(device: &Device, index: usize) -> Texture {
+        // Nearest filtering keeps the cells crisp when magnified
+        Texture::create_storage_texture(
+            device,
+            GRID_WIDTH,
+            GRID_HEIGHT,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::FilterMode::Nearest,
+            &format!("Cells {index}"),
+        )
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    grid: vec4<u32>,
+    brush: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var current: texture_2d<f32>;
+
+@group(0) @binding(2)
+var next: texture_storage_2d<rgba8unorm, write>;
+
+// Dead cells keep a fading trail in their color, live cells are brighter than any trail
+const TRAIL_COLOR: vec3<f32> = vec3<f32>(0.1, 0.35, 0.6);
+const ALIVE_COLOR: vec3<f32> = vec3<f32>(0.95, 0.95, 0.85);
+
+fn is_alive(cell: vec2<i32>) -> bool {
+    let size = vec2<i32>(ubo.grid.xy);
+    // The grid wraps around at the edges
+    let wrapped = (cell + size) % size;
+    return textureLoad(current, wrapped, 0).r > 0.5;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn generation(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= ubo.grid.x * ubo.grid.y {
+        return;
+    }
+    let cell = vec2<i32>(i32(index % ubo.grid.x), i32(index / ubo.grid.x));
+    let previous = textureLoad(current, cell, 0);
+    var alive = previous.r > 0.5;
+
+    if ubo.grid.z == 1u {
+        var neighbors = 0u;
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                if (x != 0 || y != 0) && is_alive(cell + vec2(x, y)) {
+                    neighbors++;
+                }
+            }
+        }
+        alive = neighbors == 3u || (alive && neighbors == 2u);
+    }
+
+    let brush = ubo.brush;
+    if brush.w > 0.0 && distance(vec2<f32>(cell) + 0.5, brush.xy) <= brush.z {
+        alive = brush.w < 1.5;
+    }
+
+    var color = ALIVE_COLOR;
+    if !alive {
+        let trail = min(previous.b / TRAIL_COLOR.b, 1.0);
+        color = TRAIL_COLOR * trail * 0.92;
+    }
+    textureStore(next, cell, vec4(color, 1.0));
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        let mut scene = Scene::new(&renderer.device, &renderer.queue, renderer.config.format)?;
+        // Let the initial soup settle into recognizable patterns
+        scene.advance(renderer, 60);
+        self.scene = Some(scene);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        let mode = if input.mouse.is_left_clicked {
+            Some(Brush::Draw)
+        } else if input.mouse.is_right_clicked {
+            Some(Brush::Erase)
+        } else {
+            None
+        };
+        let brush = mode.map(|mode| {
+            let window = system.window_dimensions;
+            let position = input.mouse.position;
+            // Texture rows run from the bottom of the quad up, while the cursor is measured from the top down
+            let cell = glm::vec2(
+                position.x / window.width.max(1) as f32 * GRID_WIDTH as f32,
+                (1.0 - position.y / window.height.max(1) as f32) * GRID_HEIGHT as f32,
+            );
+            (mode, cell)
+        });
+        scene.update(renderer, system.delta_time as f32, brush);
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Game of Life");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                ui.label(format!("Generation {}", scene.generation));
+                ui.horizontal(|ui| {
+                    let label = if scene.running { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        scene.running = !scene.running;
+                    }
+                    if ui
+                        .add_enabled(!scene.running, egui::Button::new("Step"))
+                        .clicked()
+                    {
+                        scene.advance(renderer, 1);
+                    }
+                    if ui.button("Randomize").clicked() {
+                        scene.randomize(&renderer.queue);
+                    }
+                    if ui.button("Clear").clicked() {
+                        scene.clear(&renderer.queue);
+                    }
+                });
+                ui.add(
+                    egui::Slider::new(&mut scene.generations_per_second, 1.0..=240.0)
+                        .text("Generations per second"),
+                );
+                ui.add(egui::Slider::new(&mut scene.brush_radius, 0.5..=20.0).text("Brush radius"));
+                ui.label("Left click to draw, right click to erase");
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, Some(CLEAR_COLOR), None);
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Game of Life".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -1,158 +1,314 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use bytemuck::Zeroable;
 use egui::color_picker::color_edit_button_rgb;
+use nalgebra::UnitQuaternion;
 use nalgebra_glm as glm;
-use std::{borrow::Cow, mem};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, time::Instant};
 use support::{
-    camera::MouseOrbit, run, AppConfig, Application, Geometry, Input, Renderer, System, Texture,
-};
-use wgpu::{
-    util::DeviceExt, vertex_attr_array, BindGroup, BindGroupLayout, Buffer, BufferAddress, Device,
-    Queue, RenderPass, RenderPipeline, TextureFormat, VertexAttribute,
+    camera::MouseOrbit, impl_vertex, paint_axis_gizmo, run, screen_coverage, Aabb, AppConfig,
+    Application, ColorAttachment, ConsolePanel, DepthAttachment, EditorLayout, EditorPanels,
+    EditorTab, Frustum, Geometry, Grid, GridSettings, Indices, Input, InstanceBuffer, LodSelector,
+    MeshData, MeshOptimization, Palette, PerDrawData, PipelineCache, PipelineKey, Renderer,
+    SceneView, StencilMode, System, Texture, TimeOfDay, Uniform, VertexDescription, VertexLayout,
 };
+use wgpu::{vertex_attr_array, Device, RenderPass, TextureFormat};
 
-#[repr(C)]
-#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct LightUniformBuffer {
-    position: glm::Vec4,
-    color: glm::Vec4,
-}
+/// Lights beyond this many are not drawn
+const MAX_LIGHTS: usize = 16;
 
-struct LightBinding {
-    pub light_uniform: LightUniformBuffer,
-    pub buffer: Buffer,
-    pub bind_group: BindGroup,
-    pub bind_group_layout: BindGroupLayout,
-}
+/// Bind group of the per draw data when push constants aren't available
+const PER_DRAW_GROUP: u32 = 2;
 
-impl LightBinding {
-    pub fn new(device: &Device) -> Self {
-        let light_uniform = LightUniformBuffer {
-            position: glm::vec4(2.0, 2.0, 2.0, 1.0),
-            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
-        };
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum LightKind {
+    /// Parallel rays from infinitely far away, such as the sun
+    Directional,
+    #[default]
+    Point,
+    Spot,
+}
 
-        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Light Buffer"),
-            contents: bytemuck::cast_slice(&[light_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
+impl LightKind {
+    const ALL: [Self; 3] = [Self::Directional, Self::Point, Self::Spot];
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("light_bind_group_layout"),
-        });
+    /// Directional lights are measured in illuminance, the others in luminous intensity
+    fn intensity_unit(&self) -> &'static str {
+        match self {
+            Self::Directional => "lx",
+            Self::Point | Self::Spot => "cd",
+        }
+    }
+}
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("light_bind_group"),
-        });
+/// A light placed in the scene, with intensities in photometric units
+/// that the exposure scales into the shader's range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Light {
+    kind: LightKind,
+    /// Where the gizmo is drawn, directional lights aren't affected by it
+    position: glm::Vec3,
+    direction: glm::Vec3,
+    color: [f32; 3],
+    intensity: f32,
+    /// Distance at which point and spot lights fade out completely
+    range: f32,
+    /// Spot cone half angles in degrees, full intensity inside the inner angle
+    inner_angle: f32,
+    outer_angle: f32,
+}
 
+impl Light {
+    fn new(kind: LightKind) -> Self {
         Self {
-            light_uniform,
-            buffer,
-            bind_group,
-            bind_group_layout,
+            kind,
+            position: glm::vec3(2.0, 2.0, 2.0),
+            direction: glm::vec3(-1.0, -1.0, -1.0).normalize(),
+            color: [1.0, 1.0, 1.0],
+            intensity: match kind {
+                LightKind::Directional => 1.0,
+                LightKind::Point | LightKind::Spot => 10.0,
+            },
+            range: 20.0,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
         }
     }
 
-    pub fn update_buffer(
-        &mut self,
-        queue: &Queue,
-        offset: BufferAddress,
-        uniform_buffer: LightUniformBuffer,
-    ) {
-        self.light_uniform = uniform_buffer;
-        queue.write_buffer(
-            &self.buffer,
-            offset,
-            bytemuck::cast_slice(&[self.light_uniform]),
+    fn gpu_light(&self, exposure: f32) -> GpuLight {
+        let kind = match self.kind {
+            LightKind::Directional => 0.0,
+            LightKind::Point => 1.0,
+            LightKind::Spot => 2.0,
+        };
+        let radiance = glm::Vec3::from(self.color) * self.intensity * exposure;
+        GpuLight {
+            position: self.position.push(kind),
+            direction: self.direction.normalize().push(self.range),
+            color: radiance.push(1.0),
+            cone: glm::vec4(
+                self.inner_angle.min(self.outer_angle).to_radians().cos(),
+                self.outer_angle.to_radians().cos(),
+                0.0,
+                0.0,
+            ),
+        }
+    }
+
+    fn show_inspector(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Kind")
+            .selected_text(format!("{:?}", self.kind))
+            .show_ui(ui, |ui| {
+                for kind in LightKind::ALL {
+                    ui.selectable_value(&mut self.kind, kind, format!("{kind:?}"));
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            color_edit_button_rgb(ui, &mut self.color);
+        });
+        ui.add(
+            egui::DragValue::new(&mut self.intensity)
+                .speed(0.1)
+                .clamp_range(0.0..=f32::MAX)
+                .suffix(format!(" {}", self.kind.intensity_unit())),
         );
+
+        ui.label("Position");
+        ui.horizontal(|ui| {
+            for axis in 0..3 {
+                ui.add(egui::DragValue::new(&mut self.position[axis]).speed(0.05));
+            }
+        });
+        if self.kind != LightKind::Point {
+            ui.label("Direction");
+            ui.horizontal(|ui| {
+                for axis in 0..3 {
+                    ui.add(egui::DragValue::new(&mut self.direction[axis]).speed(0.01));
+                }
+            });
+            if self.direction.magnitude() < f32::EPSILON {
+                self.direction = -glm::Vec3::y();
+            }
+        }
+        if self.kind != LightKind::Directional {
+            ui.add(
+                egui::Slider::new(&mut self.range, 0.1..=100.0)
+                    .logarithmic(true)
+                    .text("Range"),
+            );
+        }
+        if self.kind == LightKind::Spot {
+            ui.add(
+                egui::Slider::new(&mut self.inner_angle, 0.0..=89.0)
+                    .suffix("°")
+                    .text("Inner angle"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.outer_angle, 0.0..=89.0)
+                    .suffix("°")
+                    .text("Outer angle"),
+            );
+        }
     }
 }
 
-struct InstanceBinding {
-    pub instances: Vec<Instance>,
-    pub buffer: Buffer,
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuLight {
+    /// Kind in w
+    position: glm::Vec4,
+    /// Range in w
+    direction: glm::Vec4,
+    /// Color scaled by the intensity and exposure
+    color: glm::Vec4,
+    /// Cosines of the inner and outer spot angles
+    cone: glm::Vec4,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct LightsUniformBuffer {
+    lights: [GpuLight; MAX_LIGHTS],
+    /// Number of lights in x
+    count: [u32; 4],
+}
+
+struct LightBinding {
+    pub lights: Vec<Light>,
+    /// Exposure value at ISO 100, higher values darken the scene for brighter lights
+    pub ev100: f32,
+    pub uniform: Uniform<LightsUniformBuffer>,
 }
 
-impl InstanceBinding {
+impl LightBinding {
     pub fn new(device: &Device) -> Self {
-        let num_instances_per_row: u32 = 10;
-        let instance_displacement: glm::Vec3 = glm::vec3(
-            num_instances_per_row as f32,
-            0.0,
-            num_instances_per_row as f32,
-        );
-        let instances = (0..num_instances_per_row)
-            .flat_map(|z| {
-                (0..num_instances_per_row).map(move |x| {
-                    let position = glm::vec3(x as f32, 0.0, z as f32) - instance_displacement;
-
-                    let rotation = if position.is_empty() {
-                        // this is needed so an object at (0, 0, 0) won't get scaled to zero
-                        // as Quaternions can effect scale if they're not created correctly
-                        glm::quat_angle_axis(0.0, &glm::Vec3::z())
-                    } else {
-                        glm::quat_angle_axis(45_f32.to_degrees(), &position.normalize())
-                    };
+        Self {
+            lights: vec![
+                Light::new(LightKind::Directional),
+                Light::new(LightKind::Point),
+            ],
+            ev100: 0.0,
+            uniform: Uniform::new(device),
+        }
+    }
 
-                    Instance { position, rotation }
-                })
-            })
-            .collect::<Vec<_>>();
+    /// Scales photometric intensities into the shader's range, like a camera's exposure
+    pub fn exposure(&self) -> f32 {
+        1.0 / (1.2 * 2_f32.powf(self.ev100))
+    }
 
-        let instance_data = instances
-            .iter()
-            .map(Instance::model_matrix)
-            .collect::<Vec<_>>();
-        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Instance Buffer"),
-            contents: bytemuck::cast_slice(&instance_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    /// Uploads the edited lights
+    pub fn update_buffer(&self, renderer: &mut Renderer) {
+        self.write_lights(renderer, &self.lights, self.exposure());
+    }
 
-        Self {
-            instances,
-            buffer: instance_buffer,
+    /// Uploads lights that aren't the edited ones, such as the day/night cycle's sun
+    pub fn write_lights(&self, renderer: &mut Renderer, lights: &[Light], exposure: f32) {
+        let mut uniform = LightsUniformBuffer::zeroed();
+        for (gpu_light, light) in uniform.lights.iter_mut().zip(lights.iter()) {
+            *gpu_light = light.gpu_light(exposure);
         }
+        uniform.count[0] = lights.len().min(MAX_LIGHTS) as u32;
+        self.uniform.write(renderer, &uniform);
     }
 }
 
+/// A grid of unscaled instances rotated away from the center
+fn grid_instances() -> Vec<Instance> {
+    let num_instances_per_row: u32 = 10;
+    let instance_displacement: glm::Vec3 = glm::vec3(
+        num_instances_per_row as f32,
+        0.0,
+        num_instances_per_row as f32,
+    );
+    (0..num_instances_per_row)
+        .flat_map(|z| {
+            (0..num_instances_per_row).map(move |x| {
+                let position = glm::vec3(x as f32, 0.0, z as f32) - instance_displacement;
+
+                let rotation = if position.is_empty() {
+                    // this is needed so an object at (0, 0, 0) won't get scaled to zero
+                    // as Quaternions can effect scale if they're not created correctly
+                    glm::quat_angle_axis(0.0, &glm::Vec3::z())
+                } else {
+                    glm::quat_angle_axis(45_f32.to_degrees(), &position.normalize())
+                };
+
+                Instance {
+                    position,
+                    rotation,
+                    scale: glm::vec3(1.0, 1.0, 1.0),
+                }
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Instance {
     position: glm::Vec3,
     rotation: glm::Quat,
+    scale: glm::Vec3,
 }
 
 impl Instance {
     fn model_matrix(&self) -> glm::Mat4 {
-        glm::translation(&self.position) * glm::quat_to_mat4(&self.rotation)
+        glm::translation(&self.position)
+            * glm::quat_to_mat4(&self.rotation)
+            * glm::scaling(&self.scale)
     }
-}
 
-impl Instance {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4].to_vec()
-    }
+    /// Drag values for the transform, with the rotation shown as Euler angles in degrees
+    fn show_inspector(&mut self, ui: &mut egui::Ui) {
+        ui.label("Translation");
+        ui.horizontal(|ui| {
+            for axis in 0..3 {
+                ui.add(egui::DragValue::new(&mut self.position[axis]).speed(0.05));
+            }
+        });
 
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<glm::Mat4>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Instance,
-            attributes,
+        let rotation = UnitQuaternion::from_quaternion(self.rotation);
+        let (roll, pitch, yaw) = rotation.euler_angles();
+        let mut angles = [roll, pitch, yaw].map(f32::to_degrees);
+        let mut changed = false;
+        ui.label("Rotation");
+        ui.horizontal(|ui| {
+            for angle in angles.iter_mut() {
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(angle)
+                            .speed(0.5)
+                            .max_decimals(1)
+                            .suffix("°"),
+                    )
+                    .changed();
+            }
+        });
+        if changed {
+            let [roll, pitch, yaw] = angles.map(f32::to_radians);
+            self.rotation = UnitQuaternion::from_euler_angles(roll, pitch, yaw).into_inner();
         }
+
+        ui.label("Scale");
+        ui.horizontal(|ui| {
+            for axis in 0..3 {
+                ui.add(
+                    egui::DragValue::new(&mut self.scale[axis])
+                        .speed(0.01)
+                        .clamp_range(0.01..=10.0),
+                );
+            }
+        });
+    }
+}
+
+impl Instance {
+    /// Instances are uploaded as their model matrices
+    pub fn layout() -> VertexLayout {
+        InstanceBuffer::<glm::Mat4>::layout(
+            &vertex_attr_array![3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4],
+        )
     }
 }
 
@@ -162,83 +318,96 @@ struct Vertex {
     position: [f32; 4],
     normal: [f32; 4],
     color: [f32; 4],
+    uv: [f32; 2],
+}
+
+// Locations 3 to 6 are taken by the instance's model matrix
+impl_vertex!(Vertex, [0 => Float32x4, 1 => Float32x4, 2 => Float32x4, 7 => Float32x2]);
+
+/// What the fragment shader outputs, for diagnosing meshes
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum DebugView {
+    #[default]
+    Lit,
+    /// Vertex colors without lighting
+    Albedo,
+    Normals,
+    UvChecker,
+    /// Lit triangle edges, needs `wgpu::Features::POLYGON_MODE_LINE`
+    Wireframe,
 }
 
-impl Vertex {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x4].to_vec()
+impl DebugView {
+    const ALL: [Self; 5] = [
+        Self::Lit,
+        Self::Albedo,
+        Self::Normals,
+        Self::UvChecker,
+        Self::Wireframe,
+    ];
+
+    fn fragment_entry_point(&self) -> &'static str {
+        match self {
+            Self::Lit | Self::Wireframe => "fragment_main",
+            Self::Albedo => "fragment_albedo",
+            Self::Normals => "fragment_normals",
+            Self::UvChecker => "fragment_uv_checker",
+        }
     }
 
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes,
+    fn polygon_mode(&self) -> wgpu::PolygonMode {
+        match self {
+            Self::Wireframe => wgpu::PolygonMode::Line,
+            _ => wgpu::PolygonMode::Fill,
         }
     }
 }
 
+/// Per level of detail, the tint shows which level each instance is drawn with
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DrawData {
+    tint: glm::Vec4,
+}
+
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 struct UniformBuffer {
     mvp: glm::Mat4,
+    /// Outline color and how far the outline grows the outlined mesh
+    outline: glm::Vec4,
 }
 
-struct UniformBinding {
-    pub buffer: Buffer,
-    pub bind_group: BindGroup,
-    pub bind_group_layout: BindGroupLayout,
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct OutlineSettings {
+    enabled: bool,
+    color: [f32; 3],
+    /// Fraction of the mesh's size the outline extends past it
+    width: f32,
 }
 
-impl UniformBinding {
-    pub fn new(device: &Device) -> Self {
-        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("uniform_bind_group_layout"),
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("uniform_bind_group"),
-        });
-
+impl Default for OutlineSettings {
+    fn default() -> Self {
         Self {
-            buffer,
-            bind_group,
-            bind_group_layout,
+            enabled: true,
+            color: [1.0, 0.6, 0.1],
+            width: 0.05,
         }
     }
+}
 
-    pub fn update_buffer(
-        &mut self,
-        queue: &Queue,
-        offset: BufferAddress,
-        uniform_buffer: UniformBuffer,
-    ) {
-        queue.write_buffer(
-            &self.buffer,
-            offset,
-            bytemuck::cast_slice(&[uniform_buffer]),
-        )
+impl OutlineSettings {
+    fn show(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Outline selection");
+        ui.horizontal(|ui| {
+            color_edit_button_rgb(ui, &mut self.color);
+            ui.label("Outline color");
+        });
+        ui.add(egui::Slider::new(&mut self.width, 0.01..=0.25).text("Outline width"));
+    }
+
+    fn uniform(&self) -> glm::Vec4 {
+        glm::vec4(self.color[0], self.color[1], self.color[2], self.width)
     }
 }
 
@@ -248,16 +417,19 @@ const VERTICES: [Vertex; 3] = [
         position: [ 1.0, -1.0, 0.0, 1.0],
         normal:   [ 0.0, -1.0, 0.0, 1.0],
         color:    [ 1.0,  0.0, 0.0, 1.0],
+        uv:       [ 1.0,  0.0],
     },
     Vertex {
         position: [-1.0, -1.0, 0.0, 1.0],
         normal:   [ 0.0, -1.0, 0.0, 1.0],
         color:    [ 0.0,  1.0, 0.0, 1.0],
+        uv:       [ 0.0,  0.0],
     },
     Vertex {
         position: [ 0.0,  1.0, 0.0, 1.0],
         normal:   [ 0.0, -1.0, 0.0, 1.0],
         color:    [ 0.0,  0.0, 1.0, 1.0],
+        uv:       [ 0.5,  1.0],
     },
 ];
 
@@ -273,32 +445,48 @@ struct InstanceInput {
 
 struct Uniform {
     mvp: mat4x4<f32>,
+    outline: vec4<f32>,
 };
 
 struct Light {
     position: vec4<f32>,
+    direction: vec4<f32>,
     color: vec4<f32>,
-}
+    cone: vec4<f32>,
+};
+
+struct Lights {
+    lights: array<Light, 16>,
+    count: vec4<u32>,
+};
+
+// Replaces the vertex color with the tint by its w
+struct DrawData {
+    tint: vec4<f32>,
+};
 
 @group(0) @binding(0)
 var<uniform> ubo: Uniform;
 
 @group(1) @binding(0)
-var<uniform> light: Light;
+var<uniform> lights: Lights;
 
 struct VertexInput {
     @location(0) position: vec4<f32>,
     @location(1) normal: vec4<f32>,
     @location(2) color: vec4<f32>,
+    @location(7) uv: vec2<f32>,
 };
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) world_normal: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) world_position: vec3<f32>,
 };
 
-@vertex
-fn vertex_main(vert: VertexInput, instance: InstanceInput) -> VertexOutput {
+// Scaling about the mesh's origin lets the outline reuse the same transform
+fn transform_vertex(vert: VertexInput, instance: InstanceInput, scale: f32) -> VertexOutput {
     let model_matrix = mat4x4<f32>(
         instance.model_matrix_0,
         instance.model_matrix_1,
@@ -306,220 +494,1084 @@ fn vertex_main(vert: VertexInput, instance: InstanceInput) -> VertexOutput {
         instance.model_matrix_3,
     );
 
-    var position = vert.position;
+    var position = vec4<f32>(vert.position.xyz * scale, 1.0);
     position.y *= -1.0;
 
+    let normal = vec4<f32>(vert.normal.x, -vert.normal.y, vert.normal.z, 0.0);
+    let world_position = model_matrix * position;
+
     var out: VertexOutput;
-    out.color = vert.color;
-    out.world_normal = vert.normal;
-    out.position = ubo.mvp * model_matrix * position;
+    out.color = mix(vert.color, vec4<f32>(draw.tint.rgb, 1.0), draw.tint.w);
+    out.world_normal = vec4<f32>(normalize((model_matrix * normal).xyz), 0.0);
+    out.uv = vert.uv;
+    out.world_position = world_position.xyz;
+    out.position = ubo.mvp * world_position;
     return out;
-};
+}
+
+@vertex
+fn vertex_main(vert: VertexInput, instance: InstanceInput) -> VertexOutput {
+    return transform_vertex(vert, instance, 1.0);
+}
+
+// Smoothly reaches zero at the light's range instead of only approaching it
+fn range_falloff(distance: f32, range: f32) -> f32 {
+    let ratio = distance / max(range, 0.0001);
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / max(distance * distance, 0.01);
+}
 
 @fragment
 fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let ambient_strength = 0.1;
-    let ambient_color = light.color * ambient_strength;
-    let light_dir = normalize(light.position - in.position);
-    let diffuse_strength =  max(dot(in.world_normal, light_dir), 0.0);
-    let diffuse_color = light.color * diffuse_strength;
-    let result = (ambient_color + diffuse_color) * in.color;
+    let normal = normalize(in.world_normal.xyz);
+    var result = vec3<f32>(0.0);
+    for (var index = 0u; index < min(lights.count.x, 16u); index++) {
+        let light = lights.lights[index];
+        var light_dir = -normalize(light.direction.xyz);
+        var attenuation = 1.0;
+        if light.position.w > 0.5 {
+            let to_light = light.position.xyz - in.world_position;
+            light_dir = normalize(to_light);
+            attenuation = range_falloff(length(to_light), light.direction.w);
+            result += light.color.rgb * ambient_strength * attenuation;
+        } else {
+            result += light.color.rgb * ambient_strength;
+        }
+        if light.position.w > 1.5 {
+            let cos_angle = dot(-light_dir, normalize(light.direction.xyz));
+            attenuation *= smoothstep(light.cone.y, light.cone.x, cos_angle);
+        }
+        let diffuse_strength = max(dot(normal, light_dir), 0.0);
+        result += light.color.rgb * diffuse_strength * attenuation;
+    }
+    return vec4<f32>(result * in.color.rgb, 1.0);
+}
 
-    return vec4<f32>(result.xyz, 1.0);
+@fragment
+fn fragment_albedo(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color.rgb, 1.0);
+}
+
+@fragment
+fn fragment_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.world_normal.xyz) * 0.5 + 0.5, 1.0);
+}
+
+// Grows the mesh about its origin rather than along its normals,
+// which would split apart at hard edges and can't widen a flat triangle
+@vertex
+fn vertex_outline(vert: VertexInput, instance: InstanceInput) -> VertexOutput {
+    return transform_vertex(vert, instance, 1.0 + ubo.outline.w);
+}
+
+@fragment
+fn fragment_outline(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(ubo.outline.rgb, 1.0);
+}
+
+@fragment
+fn fragment_uv_checker(in: VertexOutput) -> @location(0) vec4<f32> {
+    let cells = floor(in.uv * 8.0);
+    let checker = 0.6 + 0.4 * (cells.x + cells.y - 2.0 * floor((cells.x + cells.y) * 0.5));
+    return vec4<f32>(vec3<f32>(in.uv, 1.0 - in.uv.x) * checker, 1.0);
 }
 ";
 
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Shape {
+    #[default]
+    Triangle,
+    Cube,
+    UvSphere,
+    Icosphere,
+    Plane,
+    Cylinder,
+    Cone,
+    Torus,
+}
+
+impl Shape {
+    const ALL: [Self; 8] = [
+        Self::Triangle,
+        Self::Cube,
+        Self::UvSphere,
+        Self::Icosphere,
+        Self::Plane,
+        Self::Cylinder,
+        Self::Cone,
+        Self::Torus,
+    ];
+
+    /// Each level of detail halves the tessellation of the previous one
+    fn mesh(&self, level: usize) -> Option<MeshData> {
+        let detail = |segments: u32| (segments >> level).max(3);
+        Some(match self {
+            Self::Triangle => return None,
+            Self::Cube => MeshData::cube(0.6),
+            Self::UvSphere => MeshData::uv_sphere(0.4, detail(24), detail(16)),
+            Self::Icosphere => MeshData::icosphere(0.4, 2_u32.saturating_sub(level as u32)),
+            Self::Plane => MeshData::plane(0.8, 3),
+            Self::Cylinder => MeshData::cylinder(0.3, 0.8, detail(24)),
+            Self::Cone => MeshData::cone(0.3, 0.8, detail(24)),
+            Self::Torus => MeshData::torus(0.3, 0.1, detail(32), detail(16)),
+        })
+    }
+
+    /// The triangle is wound clockwise while the generated meshes are counter-clockwise
+    fn front_face(&self) -> wgpu::FrontFace {
+        match self {
+            Self::Triangle => wgpu::FrontFace::Cw,
+            _ => wgpu::FrontFace::Ccw,
+        }
+    }
+
+    /// Bounds of the most detailed mesh in its local space
+    fn bounds(&self) -> Aabb {
+        match self.mesh(0) {
+            Some(mesh) => mesh.aabb(),
+            None => Aabb::from_points(
+                VERTICES
+                    .iter()
+                    .map(|vertex| glm::make_vec3(&vertex.position[..3])),
+            ),
+        }
+    }
+
+    /// Optimizes the generated meshes on the way, returning their before and after counts
+    fn geometry(&self, device: &Device, level: usize) -> (Geometry, Option<MeshOptimization>) {
+        let Some(mut mesh) = self.mesh(level) else {
+            return (Geometry::new(device, &VERTICES, &INDICES), None);
+        };
+        let optimization = mesh.optimize();
+        let vertices = mesh
+            .vertices
+            .iter()
+            .map(|vertex| Vertex {
+                position: glm::Vec3::from(vertex.position).push(1.0).into(),
+                normal: glm::Vec3::from(vertex.normal).push(0.0).into(),
+                color: [vertex.uv[0], vertex.uv[1], 1.0, 1.0],
+                uv: vertex.uv,
+            })
+            .collect::<Vec<_>>();
+        let geometry = match Indices::narrow(&mesh.indices) {
+            Some(indices) => Geometry::new(device, &vertices, &indices),
+            None => Geometry::new(device, &vertices, &mesh.indices),
+        };
+        (geometry, Some(optimization))
+    }
+}
+
 struct Scene {
-    pub geometry: Geometry,
-    pub instance: InstanceBinding,
-    pub uniform: UniformBinding,
+    /// One geometry per level of detail, from most to least detailed
+    pub lods: Vec<Geometry>,
+    /// Before and after counts for each generated level
+    pub optimizations: Vec<MeshOptimization>,
+    pub lod: LodSelector,
+    /// Number of instances drawn with each level, packed in level order in the instance buffer
+    pub level_counts: Vec<u32>,
+    /// Skips instances outside the view frustum before they are packed
+    pub frustum_culling: bool,
+    pub culled_count: u32,
+    /// Seconds the CPU spent culling and packing the instances last update
+    pub select_time: f64,
+    pub shape: Shape,
+    pub instances: Vec<Instance>,
+    /// Model matrices of the instances, packed by level of detail
+    instance_buffer: InstanceBuffer<glm::Mat4>,
+    pub uniform: Uniform<UniformBuffer>,
     pub light: LightBinding,
-    pub pipeline: RenderPipeline,
+    /// One tint per level of detail, set before each level's draw
+    pub per_draw: PerDrawData<DrawData>,
+    pub pipelines: PipelineCache,
+    pub pipeline_key: PipelineKey,
+    pub double_sided: bool,
+    pub blending: bool,
+    pub debug_view: DebugView,
+    pub outline: OutlineSettings,
+    /// Index of the instance to outline, drawn again on its own over the instanced draw
+    pub outlined: Option<usize>,
+    outline_instance: InstanceBuffer<glm::Mat4>,
+    /// Marks the outlined instance in the stencil, then draws its grown copy outside the mark
+    outline_keys: [PipelineKey; 2],
+    shader: u64,
+    surface_format: TextureFormat,
+}
+
+/// The parts of the scene that can be edited, saved from the File menu
+#[derive(Serialize, Deserialize)]
+struct SavedScene {
+    shape: Shape,
+    instances: Vec<Instance>,
+    lights: Vec<Light>,
+    #[serde(default)]
+    ev100: f32,
+}
+
+impl SavedScene {
+    fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scene from {}", path.display()))?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write scene to {}", path.display()))?;
+        Ok(())
+    }
 }
 
 impl Scene {
-    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
-        let geometry = Geometry::new(device, &VERTICES, &INDICES);
-        let uniform = UniformBinding::new(device);
+    pub fn new(renderer: &Renderer, surface_format: TextureFormat) -> Self {
+        let device = &renderer.device;
+        let shape = Shape::default();
+        let lod = LodSelector::default();
+        let (lods, optimizations) = Self::create_lods(device, shape, &lod);
+        let uniform = Uniform::new(device);
         let light = LightBinding::new(device);
-        let pipeline = Self::create_pipeline(device, surface_format, &uniform, &light);
-        let instance = InstanceBinding::new(device);
-        Self {
-            geometry,
-            instance,
+        let per_draw = PerDrawData::new(renderer, wgpu::ShaderStages::VERTEX);
+        let mut bind_group_layouts = vec![uniform.layout(), light.uniform.layout()];
+        bind_group_layouts.extend(per_draw.bind_group_layout());
+        let mut pipelines = PipelineCache::with_push_constants(
+            device,
+            &bind_group_layouts,
+            &per_draw.push_constant_ranges(),
+        );
+        let source = format!(
+            "{}{SHADER_SOURCE}",
+            per_draw.declaration("DrawData", PER_DRAW_GROUP)
+        );
+        let shader = pipelines.shader(device, &source);
+        let instances = grid_instances();
+        let instance_buffer = InstanceBuffer::new(
+            device,
+            instances.iter().map(Instance::model_matrix).collect(),
+        );
+        let outline_instance = InstanceBuffer::new(device, vec![glm::Mat4::identity()]);
+        let pipeline_key = Self::pipeline_key(
+            shader,
+            surface_format,
+            shape,
+            true,
+            true,
+            DebugView::default(),
+        );
+        let mut scene = Self {
+            level_counts: vec![instances.len() as _],
+            frustum_culling: true,
+            culled_count: 0,
+            select_time: 0.0,
+            lods,
+            optimizations,
+            lod,
+            shape,
+            instances,
+            instance_buffer,
             uniform,
             light,
-            pipeline,
+            per_draw,
+            pipelines,
+            outline_keys: Self::outline_keys(&pipeline_key),
+            pipeline_key,
+            double_sided: true,
+            blending: true,
+            debug_view: DebugView::default(),
+            outline: OutlineSettings::default(),
+            outlined: None,
+            outline_instance,
+            shader,
+            surface_format,
+        };
+        scene.update_pipeline(device);
+        scene
+    }
+
+    pub fn to_saved(&self) -> SavedScene {
+        SavedScene {
+            shape: self.shape,
+            instances: self.instances.clone(),
+            lights: self.light.lights.clone(),
+            ev100: self.light.ev100,
         }
     }
 
-    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
-        renderpass.set_pipeline(&self.pipeline);
-        renderpass.set_bind_group(0, &self.uniform.bind_group, &[]);
-        renderpass.set_bind_group(1, &self.light.bind_group, &[]);
+    pub fn apply_saved(&mut self, renderer: &mut Renderer, saved: SavedScene) {
+        self.set_shape(&renderer.device, saved.shape);
+        self.instances = saved.instances;
+        self.light.lights = saved.lights;
+        self.light.ev100 = saved.ev100;
+        self.light.update_buffer(renderer);
+    }
 
-        let (vertex_buffer_slice, index_buffer_slice) = self.geometry.slices();
-        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
-        renderpass.set_vertex_buffer(1, self.instance.buffer.slice(..));
-        renderpass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32);
+    pub fn set_shape(&mut self, device: &Device, shape: Shape) {
+        self.shape = shape;
+        (self.lods, self.optimizations) = Self::create_lods(device, shape, &self.lod);
+        self.update_pipeline(device);
+    }
 
-        renderpass.draw_indexed(
-            0..(INDICES.len() as _),
-            0,
-            0..self.instance.instances.len() as _,
+    /// Selects the pipeline variant for the current shape and render state, building it on first use
+    pub fn update_pipeline(&mut self, device: &Device) {
+        self.pipeline_key = Self::pipeline_key(
+            self.shader,
+            self.surface_format,
+            self.shape,
+            self.double_sided,
+            self.blending,
+            self.debug_view,
         );
+        self.outline_keys = Self::outline_keys(&self.pipeline_key);
+        // Failures are logged by the cache the first time, and drawing skips missing variants
+        for key in std::iter::once(&self.pipeline_key).chain(self.outline_keys.iter()) {
+            let _ = self.pipelines.get_or_create(device, key);
+        }
     }
 
-    pub fn update(&mut self, view_projection_matrix: glm::Mat4, queue: &Queue) {
-        self.uniform.update_buffer(
-            queue,
-            0,
-            UniformBuffer {
-                mvp: view_projection_matrix,
+    /// Both draw filled and unculled over everything, the mark writes only to the stencil
+    fn outline_keys(pipeline_key: &PipelineKey) -> [PipelineKey; 2] {
+        let key = |vertex_entry_point, color_write_mask, stencil: StencilMode| PipelineKey {
+            vertex_entry_point,
+            fragment_entry_point: "fragment_outline",
+            color_targets: pipeline_key
+                .color_targets
+                .iter()
+                .map(|target| {
+                    target.clone().map(|target| wgpu::ColorTargetState {
+                        blend: None,
+                        write_mask: color_write_mask,
+                        ..target
+                    })
+                })
+                .collect(),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                ..pipeline_key.primitive
             },
-        );
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_STENCIL_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: stencil.state(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            ..pipeline_key.clone()
+        };
+        [
+            key(
+                "vertex_main",
+                wgpu::ColorWrites::empty(),
+                StencilMode::Write,
+            ),
+            key(
+                "vertex_outline",
+                wgpu::ColorWrites::ALL,
+                StencilMode::NotEqual,
+            ),
+        ]
     }
 
-    fn create_pipeline(
-        device: &Device,
+    fn pipeline_key(
+        shader: u64,
         surface_format: TextureFormat,
-        uniform: &UniformBinding,
-        light: &LightBinding,
-    ) -> RenderPipeline {
-        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&uniform.bind_group_layout, &light.bind_group_layout],
-            push_constant_ranges: &[],
-        });
-
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vertex_main",
-                buffers: &[
-                    Vertex::description(&Vertex::vertex_attributes()),
-                    Instance::description(&Instance::vertex_attributes()),
-                ],
-            },
+        shape: Shape,
+        double_sided: bool,
+        blending: bool,
+        debug_view: DebugView,
+    ) -> PipelineKey {
+        PipelineKey {
+            shader,
+            fragment_shader: None,
+            vertex_entry_point: "vertex_main",
+            fragment_entry_point: debug_view.fragment_entry_point(),
+            vertex_layouts: vec![Vertex::layout(), Instance::layout()],
+            color_targets: vec![Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: blending.then_some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-                unclipped_depth: false,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: shape.front_face(),
+                cull_mode: (!double_sided).then_some(wgpu::Face::Back),
+                polygon_mode: debug_view.polygon_mode(),
+                ..Default::default()
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: Texture::DEPTH_FORMAT,
+                format: Texture::DEPTH_STENCIL_FORMAT,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::Less,
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+            multisample: wgpu::MultisampleState::default(),
+        }
+    }
+
+    fn create_lods(
+        device: &Device,
+        shape: Shape,
+        lod: &LodSelector,
+    ) -> (Vec<Geometry>, Vec<MeshOptimization>) {
+        let (lods, optimizations): (Vec<_>, Vec<_>) = (0..lod.level_count())
+            .map(|level| shape.geometry(device, level))
+            .unzip();
+        (lods, optimizations.into_iter().flatten().collect())
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        let Some(pipeline) = self.pipelines.get(&self.pipeline_key) else {
+            return;
+        };
+        renderpass.set_pipeline(pipeline);
+        self.uniform.set(renderpass, 0);
+        self.light.uniform.set(renderpass, 1);
+
+        let mut first_instance = 0;
+        let levels = self.lods.iter().zip(self.level_counts.iter().copied());
+        for (level, (geometry, instance_count)) in levels.enumerate() {
+            if instance_count == 0 {
+                continue;
+            }
+            let (vertex_buffer_slice, index_buffer_slice, index_format) = geometry.slices();
+            renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+            renderpass.set_vertex_buffer(1, self.instance_buffer.slice(first_instance));
+            renderpass.set_index_buffer(index_buffer_slice, index_format);
+            self.per_draw.set(renderpass, PER_DRAW_GROUP, level);
+            renderpass.draw_indexed(0..geometry.index_count, 0, 0..instance_count);
+            first_instance += instance_count as usize;
+        }
+
+        if self.outline.enabled && self.outlined.is_some() {
+            self.render_outline(renderpass);
+        }
+    }
+
+    fn render_outline<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        let (Some(mark), Some(outline), Some(geometry)) = (
+            self.pipelines.get(&self.outline_keys[0]),
+            self.pipelines.get(&self.outline_keys[1]),
+            self.lods.first(),
+        ) else {
+            return;
+        };
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = geometry.slices();
+        renderpass.push_debug_group("Selection outline");
+        renderpass.set_stencil_reference(1);
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_vertex_buffer(1, self.outline_instance.slice(0));
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        self.per_draw.set(renderpass, PER_DRAW_GROUP, 0);
+        for pipeline in [mark, outline] {
+            renderpass.set_pipeline(pipeline);
+            renderpass.draw_indexed(0..geometry.index_count, 0, 0..1);
+        }
+        renderpass.pop_debug_group();
+    }
+
+    /// Culls the instances outside the view frustum when enabled, selects a level of detail
+    /// for the rest and packs them by level into the instance buffer
+    pub fn select_lods(
+        &mut self,
+        renderer: &mut Renderer,
+        camera: &MouseOrbit,
+        projection_view_matrix: &glm::Mat4,
+    ) {
+        let start = Instant::now();
+        let bounds = self.shape.bounds();
+        let radius = bounds.bounding_radius();
+        // The mesh may not be centered on the instance's origin
+        let cull_radius = radius + bounds.center().magnitude();
+        let frustum = Frustum::from_matrix(projection_view_matrix);
+        let mut levels = vec![Vec::new(); self.lod.level_count()];
+        self.culled_count = 0;
+        for instance in self.instances.iter() {
+            let scale = instance.scale.abs().max();
+            if self.frustum_culling
+                && !frustum.intersects_sphere(&instance.position, cull_radius * scale)
+            {
+                self.culled_count += 1;
+                continue;
+            }
+            let coverage = screen_coverage(
+                &instance.position,
+                radius,
+                &camera.transform.translation,
+                camera.camera.y_fov_rad,
+            );
+            levels[self.lod.select(coverage)].push(instance.model_matrix());
+        }
+        self.level_counts = levels.iter().map(|level| level.len() as u32).collect();
+        let palette = Palette::default();
+        let draws = (0..levels.len())
+            .map(|level| DrawData {
+                tint: match self.lod.visualize {
+                    true => palette.linear_color(level).push(1.0),
+                    false => glm::Vec4::zeros(),
+                },
+            })
+            .collect::<Vec<_>>();
+        self.per_draw.write(renderer, &draws);
+        self.instance_buffer.replace(&levels.concat());
+        self.instance_buffer.upload(renderer);
+        self.select_time = start.elapsed().as_secs_f64();
+    }
+
+    pub fn update(&mut self, view_projection_matrix: glm::Mat4, renderer: &mut Renderer) {
+        self.uniform.write(
+            renderer,
+            &UniformBuffer {
+                mvp: view_projection_matrix,
+                outline: self.outline.uniform(),
             },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fragment_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        })
+        );
+        let outlined = self.outlined.and_then(|index| self.instances.get(index));
+        if let Some(instance) = outlined {
+            self.outline_instance.set(0, instance.model_matrix());
+            self.outline_instance.upload(renderer);
+        }
     }
 }
 
-#[derive(Default)]
 struct App {
     pub scene: Option<Scene>,
     camera: MouseOrbit,
     depth_texture: Option<Texture>,
+    time_of_day: TimeOfDay,
+    day_night_cycle: bool,
+    clear_color: wgpu::Color,
+    scene_view: Option<SceneView>,
+    /// Shows the scene inside a docked tab next to the controls instead of behind a floating window
+    editor_layout: bool,
+    /// Taken while the dock is drawn so the tabs can borrow the rest of the app
+    layout: Option<EditorLayout>,
+    selection: Selection,
+    console: ConsolePanel,
+    grid: Option<Grid>,
+    grid_settings: GridSettings,
+    /// Running average frame times without and with frustum culling
+    frame_times: [Option<f64>; 2],
+}
+
+/// What the inspector shows, picked in the hierarchy
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Selection {
+    Instances,
+    /// A single instance by its index in the instance binding
+    Instance(usize),
+    Lights,
+    /// A single light by its index in the light binding
+    Light(usize),
+}
+
+const LAYOUT_PATH: &str = "lights_layout.ron";
+const SCENE_PATH: &str = "lights_scene.ron";
+
+impl Default for App {
+    fn default() -> Self {
+        Self {
+            scene: None,
+            camera: MouseOrbit::default(),
+            depth_texture: None,
+            time_of_day: TimeOfDay::default(),
+            day_night_cycle: false,
+            clear_color: Renderer::DEFAULT_CLEAR_COLOR,
+            scene_view: None,
+            editor_layout: true,
+            layout: None,
+            selection: Selection::Instances,
+            console: ConsolePanel::default(),
+            grid: None,
+            grid_settings: GridSettings::default(),
+            frame_times: [None; 2],
+        }
+    }
+}
+
+impl App {
+    const SUN_DISTANCE: f32 = 20.0;
+    /// Width of a light gizmo's handle in points
+    const GIZMO_SIZE: f32 = 16.0;
+
+    /// The scene view to draw into, if the editor layout is active and egui is showing it
+    fn active_scene_view(&self) -> Option<&SceneView> {
+        self.scene_view
+            .as_ref()
+            .filter(|scene_view| self.editor_layout && scene_view.is_visible())
+    }
+
+    fn show_controls(&mut self, ui: &mut egui::Ui, renderer: &mut Renderer) {
+        ui.checkbox(&mut self.editor_layout, "Editor layout");
+        self.show_instance_controls(ui, renderer);
+        ui.heading("Light");
+        self.show_light_controls(ui);
+    }
+
+    fn show_instance_controls(&mut self, ui: &mut egui::Ui, renderer: &mut Renderer) {
+        if let Some(scene) = self.scene.as_mut() {
+            let mut shape = scene.shape;
+            egui::ComboBox::from_label("Shape")
+                .selected_text(format!("{shape:?}"))
+                .show_ui(ui, |ui| {
+                    for option in Shape::ALL {
+                        ui.selectable_value(&mut shape, option, format!("{option:?}"));
+                    }
+                });
+            if shape != scene.shape {
+                scene.set_shape(&renderer.device, shape);
+            }
+            ui.collapsing("Pipeline", |ui| {
+                let mut debug_view = scene.debug_view;
+                let wireframe_supported = renderer
+                    .device
+                    .features()
+                    .contains(wgpu::Features::POLYGON_MODE_LINE);
+                egui::ComboBox::from_label("Debug view")
+                    .selected_text(format!("{debug_view:?}"))
+                    .show_ui(ui, |ui| {
+                        for option in DebugView::ALL {
+                            let supported = option != DebugView::Wireframe || wireframe_supported;
+                            ui.add_enabled_ui(supported, |ui| {
+                                ui.selectable_value(&mut debug_view, option, format!("{option:?}"))
+                            });
+                        }
+                    });
+                let double_sided = ui.checkbox(&mut scene.double_sided, "Double sided");
+                let blending = ui.checkbox(&mut scene.blending, "Alpha blending");
+                if debug_view != scene.debug_view || double_sided.changed() || blending.changed() {
+                    scene.debug_view = debug_view;
+                    scene.update_pipeline(&renderer.device);
+                }
+                scene.outline.show(ui);
+                ui.label(format!("Cached pipelines: {}", scene.pipelines.len()));
+            });
+            ui.collapsing("Level of detail", |ui| {
+                scene.lod.show(ui);
+                ui.label(format!("Instances per level: {:?}", scene.level_counts));
+            });
+            ui.collapsing("Culling", |ui| {
+                ui.checkbox(&mut scene.frustum_culling, "Frustum culling");
+                let visible = scene.instances.len() as u32 - scene.culled_count;
+                ui.label(format!(
+                    "Visible: {visible}, culled: {}",
+                    scene.culled_count
+                ));
+                ui.label(format!(
+                    "Culling and packing: {:.3} ms",
+                    scene.select_time * 1000.0
+                ));
+                let milliseconds = |time: Option<f64>| match time {
+                    Some(time) => format!("{:.2} ms", time * 1000.0),
+                    None => "not measured".to_string(),
+                };
+                let [without, with] = self.frame_times;
+                ui.label(format!("Frame time with culling: {}", milliseconds(with)));
+                ui.label(format!("Frame time without: {}", milliseconds(without)));
+                if let (Some(without), Some(with)) = (without, with) {
+                    ui.label(format!("Difference: {:+.2} ms", (with - without) * 1000.0));
+                }
+            });
+            ui.collapsing("Mesh optimization", |ui| {
+                match scene.optimizations.first() {
+                    Some(optimization) => optimization.show(ui),
+                    None => {
+                        ui.label("The triangle is drawn as authored");
+                    }
+                }
+            });
+        }
+        ui.collapsing("Grid", |ui| self.grid_settings.show(ui));
+    }
+
+    /// Drawn over the scene into the same pass, so it is hidden behind the instances
+    fn render_grid<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        if let (true, Some(grid)) = (self.grid_settings.enabled, self.grid.as_ref()) {
+            grid.render(render_pass);
+        }
+    }
+
+    fn save_scene(&self) -> Result<()> {
+        let Some(scene) = self.scene.as_ref() else {
+            return Ok(());
+        };
+        scene.to_saved().save(SCENE_PATH)?;
+        log::info!("Saved scene to {SCENE_PATH}");
+        Ok(())
+    }
+
+    fn open_scene(&mut self, renderer: &mut Renderer) -> Result<()> {
+        let saved = SavedScene::load(SCENE_PATH)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.apply_saved(renderer, saved);
+        }
+        self.day_night_cycle = false;
+        log::info!("Opened scene from {SCENE_PATH}");
+        Ok(())
+    }
+
+    fn show_light_controls(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.day_night_cycle, "Day/night cycle");
+        if self.day_night_cycle {
+            ui.add(
+                egui::Slider::new(&mut self.time_of_day.hours, 0.0..=TimeOfDay::HOURS_PER_DAY)
+                    .text("Time of day"),
+            );
+            ui.add(
+                egui::Slider::new(&mut self.time_of_day.hours_per_second, 0.0..=4.0)
+                    .text("Hours per second"),
+            );
+            ui.checkbox(&mut self.time_of_day.paused, "Paused");
+            ui.label(format!(
+                "{} ({:.0}K)",
+                if self.time_of_day.is_night() {
+                    "Moon"
+                } else {
+                    "Sun"
+                },
+                self.time_of_day.color_temperature()
+            ));
+            return;
+        }
+
+        let Some(scene) = self.scene.as_mut() else {
+            return;
+        };
+        ui.add(egui::Slider::new(&mut scene.light.ev100, -4.0..=16.0).text("Exposure (EV100)"));
+        ui.horizontal(|ui| {
+            for kind in LightKind::ALL {
+                let add = ui.add_enabled(
+                    scene.light.lights.len() < MAX_LIGHTS,
+                    egui::Button::new(format!("Add {kind:?}")),
+                );
+                if add.clicked() {
+                    scene.light.lights.push(Light::new(kind));
+                    self.selection = Selection::Light(scene.light.lights.len() - 1);
+                }
+            }
+        });
+
+        let mut removed = None;
+        for (index, light) in scene.light.lights.iter_mut().enumerate() {
+            egui::CollapsingHeader::new(light_label(index, light))
+                .id_source(("light", index))
+                .show(ui, |ui| {
+                    light.show_inspector(ui);
+                    if ui.button("Delete").clicked() {
+                        removed = Some(index);
+                    }
+                });
+        }
+        if let Some(index) = removed {
+            self.remove_light(index);
+        }
+    }
+
+    /// Maps world positions to points in the scene view's rect, `None` behind the camera
+    fn gizmo_projection(&self, rect: egui::Rect) -> impl Fn(&glm::Vec3) -> Option<egui::Pos2> {
+        let view_projection = self.camera.projection_view_matrix(rect.aspect_ratio());
+        move |point| {
+            let clip = view_projection * point.push(1.0);
+            (clip.w > f32::EPSILON).then(|| {
+                let ndc = clip.xy() / clip.w;
+                rect.min
+                    + egui::vec2(
+                        (ndc.x + 1.0) * 0.5 * rect.width(),
+                        (1.0 - ndc.y) * 0.5 * rect.height(),
+                    )
+            })
+        }
+    }
+
+    /// Lets the light gizmos be picked and dragged across the plane facing the camera.
+    /// Runs before the scene view is shown so the gizmos claim drags before the camera does.
+    fn interact_light_gizmos(&mut self, ui: &mut egui::Ui, rect: egui::Rect) {
+        if self.day_night_cycle {
+            return;
+        }
+        let project = self.gizmo_projection(rect);
+        let Some(scene) = self.scene.as_mut() else {
+            return;
+        };
+        for (index, light) in scene.light.lights.iter_mut().enumerate() {
+            let Some(center) = project(&light.position).filter(|center| rect.contains(*center))
+            else {
+                continue;
+            };
+            let response = ui
+                .interact(
+                    egui::Rect::from_center_size(center, egui::Vec2::splat(Self::GIZMO_SIZE)),
+                    ui.id().with(("light_gizmo", index)),
+                    egui::Sense::click_and_drag(),
+                )
+                .on_hover_text(light_label(index, light));
+            if response.clicked() || response.drag_started() {
+                self.selection = Selection::Light(index);
+            }
+            let Some(pointer) = response
+                .dragged()
+                .then(|| response.interact_pointer_pos())
+                .flatten()
+            else {
+                continue;
+            };
+            let cursor = pointer - rect.min;
+            let ray = self.camera.camera.screen_ray(
+                &self.camera.transform,
+                glm::vec2(cursor.x, cursor.y),
+                glm::vec2(rect.width(), rect.height()),
+            );
+            let normal = self.camera.transform.forward();
+            let denominator = glm::dot(&ray.direction, &normal);
+            if denominator.abs() > f32::EPSILON {
+                let distance = glm::dot(&(light.position - ray.origin), &normal) / denominator;
+                if distance > 0.0 {
+                    light.position = ray.at(distance);
+                }
+            }
+        }
+    }
+
+    /// Draws a sphere for point lights, an arrow for directional lights
+    /// and the outer cone for spot lights, over the scene view
+    fn paint_light_gizmos(&self, ui: &egui::Ui, rect: egui::Rect) {
+        if self.day_night_cycle {
+            return;
+        }
+        let Some(scene) = self.scene.as_ref() else {
+            return;
+        };
+        let project = self.gizmo_projection(rect);
+        let painter = ui.painter_at(rect);
+        for (index, light) in scene.light.lights.iter().enumerate() {
+            let Some(center) = project(&light.position) else {
+                continue;
+            };
+            let [r, g, b] = light
+                .color
+                .map(|channel| (channel.clamp(0.0, 1.0) * 255.0) as u8);
+            let color = egui::Color32::from_rgb(r, g, b);
+            let selected = self.selection == Selection::Light(index);
+            let stroke = egui::Stroke::new(if selected { 2.5 } else { 1.5 }, color);
+            let outline = egui::Stroke::new(1.0, egui::Color32::BLACK);
+            let direction = light.direction.normalize();
+
+            match light.kind {
+                LightKind::Point => {
+                    painter.circle_stroke(center, Self::GIZMO_SIZE * 0.75, stroke);
+                }
+                LightKind::Directional => {
+                    if let Some(end) = project(&(light.position + direction * 1.5)) {
+                        painter.arrow(center, end - center, stroke);
+                    }
+                }
+                LightKind::Spot => {
+                    let length = light.range.min(2.0);
+                    let radius = length * light.outer_angle.to_radians().tan();
+                    let tip = light.position + direction * length;
+                    let side = if direction.y.abs() < 0.99 {
+                        glm::Vec3::y()
+                    } else {
+                        glm::Vec3::x()
+                    };
+                    let u = direction.cross(&side).normalize();
+                    let v = direction.cross(&u);
+                    let rim = (0..=24)
+                        .filter_map(|step| {
+                            let angle = step as f32 / 24.0 * std::f32::consts::TAU;
+                            project(&(tip + (u * angle.cos() + v * angle.sin()) * radius))
+                        })
+                        .collect::<Vec<_>>();
+                    for point in rim.iter().step_by(6) {
+                        painter.line_segment([center, *point], stroke);
+                    }
+                    painter.add(egui::Shape::line(rim, stroke));
+                }
+            }
+            painter.circle(center, Self::GIZMO_SIZE * 0.25, color, outline);
+        }
+    }
+
+    fn remove_light(&mut self, index: usize) {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.light.lights.remove(index);
+        }
+        self.selection = match self.selection {
+            Selection::Light(selected) if selected == index => Selection::Lights,
+            Selection::Light(selected) if selected > index => Selection::Light(selected - 1),
+            selection => selection,
+        };
+    }
+
+    fn update_day_night_cycle(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        self.time_of_day.update(delta_time);
+
+        let sky_light = self.time_of_day.light();
+        let sun = Light {
+            kind: LightKind::Directional,
+            position: sky_light.direction * Self::SUN_DISTANCE,
+            direction: -sky_light.direction,
+            color: sky_light.color.into(),
+            intensity: sky_light.intensity,
+            ..Light::new(LightKind::Directional)
+        };
+        if let Some(scene) = self.scene.as_ref() {
+            scene
+                .light
+                .write_lights(renderer, &[sun], self.time_of_day.exposure());
+        }
+
+        let sky_color = self.time_of_day.sky_color();
+        self.clear_color = wgpu::Color {
+            r: sky_color.x as f64,
+            g: sky_color.y as f64,
+            b: sky_color.z as f64,
+            a: 1.0,
+        };
+    }
 }
 
 impl Application for App {
     fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
         self.camera.transform.translation = glm::vec3(4.0, 0.0, 4.0);
         self.camera.orientation.sensitivity = glm::vec2(0.1, 0.1);
-        self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
-        self.depth_texture = Some(Texture::create_depth_texture(
+        self.scene = Some(Scene::new(renderer, renderer.config.format));
+        self.depth_texture = Some(Texture::create_depth_stencil_texture(
             &renderer.device,
             renderer.config.width,
             renderer.config.height,
         ));
+        self.scene_view = Some(SceneView::with_depth_format(
+            renderer,
+            renderer.config.format,
+            Texture::DEPTH_STENCIL_FORMAT,
+        ));
+        self.layout = Some(EditorLayout::load_or_default(LAYOUT_PATH));
+        self.grid = Some(Grid::new(
+            &renderer.device,
+            renderer.config.format,
+            Texture::DEPTH_STENCIL_FORMAT,
+        ));
         Ok(())
     }
 
     fn depth_format(&mut self) -> Option<wgpu::TextureFormat> {
-        Some(Texture::DEPTH_FORMAT)
+        Some(Texture::DEPTH_STENCIL_FORMAT)
     }
 
-    fn update(&mut self, renderer: &mut Renderer, input: &Input, system: &System) -> Result<()> {
+    fn optional_features(&self) -> wgpu::Features {
+        wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::PUSH_CONSTANTS
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        let aspect_ratio = match self.active_scene_view() {
+            Some(scene_view) => {
+                scene_view.forward_input(input);
+                scene_view.aspect_ratio()
+            }
+            None => renderer.aspect_ratio(),
+        };
         self.camera.update(input, system)?;
-        let projection_view_matrix = self.camera.projection_view_matrix(renderer.aspect_ratio());
+        let projection_view_matrix = self.camera.projection_view_matrix(aspect_ratio);
         if let Some(scene) = self.scene.as_mut() {
-            scene.update(projection_view_matrix, &renderer.queue);
+            scene.outlined = match self.selection {
+                Selection::Instance(index) => Some(index),
+                _ => None,
+            };
+            scene.update(projection_view_matrix, renderer);
+            scene.select_lods(renderer, &self.camera, &projection_view_matrix);
+            let frame_time = &mut self.frame_times[usize::from(scene.frustum_culling)];
+            *frame_time = Some(match *frame_time {
+                Some(average) => average * 0.95 + system.delta_time * 0.05,
+                None => system.delta_time,
+            });
+        }
+        if let Some(grid) = self.grid.as_ref() {
+            grid.update(
+                renderer,
+                &self.grid_settings,
+                projection_view_matrix,
+                self.camera.transform.translation,
+            );
+        }
+        if self.day_night_cycle {
+            self.update_day_night_cycle(renderer, system.delta_time as f32);
+        } else {
+            self.clear_color = Renderer::DEFAULT_CLEAR_COLOR;
+            if let Some(scene) = self.scene.as_ref() {
+                scene.light.update_buffer(renderer);
+            }
         }
         Ok(())
     }
 
     fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
-        egui::Window::new("wgpu")
-            .resizable(false)
-            .fixed_pos((10.0, 10.0))
-            .show(context, |ui| {
-                ui.heading("Light");
-
-                if let Some(scene) = self.scene.as_mut() {
-                    ui.heading("Light color");
-                    let light_color = scene.light.light_uniform.color;
-                    let mut color = [light_color.x, light_color.y, light_color.z];
-                    let color_response = color_edit_button_rgb(ui, &mut color);
-
-                    ui.heading("Light position");
-                    let mut position = scene.light.light_uniform.position;
-                    let speed = 0.1;
-                    let position_response_x =
-                        ui.add(egui::DragValue::new(&mut position.x).speed(speed));
-                    let position_response_y =
-                        ui.add(egui::DragValue::new(&mut position.y).speed(speed));
-                    let position_response_z =
-                        ui.add(egui::DragValue::new(&mut position.z).speed(speed));
-
-                    if color_response.changed()
-                        || position_response_x.changed()
-                        || position_response_y.changed()
-                        || position_response_z.changed()
+        if !self.editor_layout {
+            egui::Window::new("wgpu")
+                .resizable(false)
+                .fixed_pos((10.0, 10.0))
+                .show(context, |ui| self.show_controls(ui, renderer));
+            return Ok(());
+        }
+
+        egui::TopBottomPanel::top("menu").show(context, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Save scene").clicked() {
+                        if let Err(error) = self.save_scene() {
+                            log::error!("{error:#}");
+                        }
+                        ui.close_menu();
+                    }
+                    let saved = Path::new(SCENE_PATH).exists();
+                    if ui
+                        .add_enabled(saved, egui::Button::new("Open scene"))
+                        .clicked()
                     {
-                        scene.light.update_buffer(
-                            &renderer.queue,
-                            0,
-                            LightUniformBuffer {
-                                position: glm::vec4(position[0], position[1], position[2], 1.0),
-                                color: glm::vec4(color[0], color[1], color[2], 1.0),
-                            },
-                        );
+                        if let Err(error) = self.open_scene(renderer) {
+                            log::error!("{error:#}");
+                        }
+                        ui.close_menu();
                     }
-                }
+                });
+                ui.menu_button("Window", |ui| {
+                    if let Some(layout) = self.layout.as_mut() {
+                        layout.show_menu(ui);
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.editor_layout, "Editor layout");
+                });
             });
+        });
+        if let Some(mut layout) = self.layout.take() {
+            layout.show(
+                context,
+                &mut LightsPanels {
+                    app: self,
+                    renderer,
+                },
+            );
+            self.layout = Some(layout);
+        }
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> Result<()> {
+        if let Some(layout) = self.layout.as_ref() {
+            layout.save(LAYOUT_PATH)?;
+        }
         Ok(())
     }
 
     fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
-        self.depth_texture = Some(Texture::create_depth_texture(
+        self.depth_texture = Some(Texture::create_depth_stencil_texture(
             &renderer.device,
             renderer.config.width,
             renderer.config.height,
@@ -532,45 +1584,139 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
+        let scene_view = self.active_scene_view();
+        if let (Some(scene_view), Some(scene)) = (scene_view, self.scene.as_ref()) {
+            let mut render_pass = scene_view.begin_pass(encoder, self.clear_color);
+            scene.render(&mut render_pass);
+            self.render_grid(&mut render_pass);
+        }
 
-        let depth_stencil_attachment = self.depth_texture.as_ref().map(|depth_texture| {
-            wgpu::RenderPassDepthStencilAttachment {
+        let depth = self
+            .depth_texture
+            .as_ref()
+            .map(|depth_texture| DepthAttachment {
                 view: &depth_texture.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
-                }),
-                stencil_ops: None,
-            }
-        });
-
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                stencil: true,
+            });
+        let mut render_pass = Renderer::begin_multi_target_pass(
+            encoder,
+            &[ColorAttachment {
                 view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment,
-        });
+                clear: Some(self.clear_color),
+            }],
+            depth,
+        );
 
-        if let Some(scene) = self.scene.as_ref() {
-            scene.render(&mut render_pass);
+        // Until egui shows the scene view, the scene is drawn straight to the surface
+        if scene_view.is_none() {
+            if let Some(scene) = self.scene.as_ref() {
+                scene.render(&mut render_pass);
+                self.render_grid(&mut render_pass);
+            }
         }
 
         Ok(Some(render_pass))
     }
 }
 
+struct LightsPanels<'a> {
+    app: &'a mut App,
+    renderer: &'a mut Renderer,
+}
+
+impl EditorPanels for LightsPanels<'_> {
+    fn show_tab(&mut self, ui: &mut egui::Ui, tab: EditorTab) {
+        let Self { app, renderer } = self;
+        match tab {
+            EditorTab::Scene => {
+                let rect = ui.available_rect_before_wrap();
+                app.interact_light_gizmos(ui, rect);
+                if let Some(scene_view) = app.scene_view.as_mut() {
+                    let rect = scene_view.show(ui, renderer).rect;
+                    app.paint_light_gizmos(ui, rect);
+                    let view = app.camera.transform.as_view_matrix();
+                    paint_axis_gizmo(&ui.painter_at(rect), rect, &view);
+                }
+            }
+            EditorTab::Hierarchy => {
+                ui.selectable_value(&mut app.selection, Selection::Lights, "Lights");
+                let lights = app
+                    .scene
+                    .as_ref()
+                    .map(|scene| scene.light.lights.clone())
+                    .unwrap_or_default();
+                egui::CollapsingHeader::new("Light list").show(ui, |ui| {
+                    for (index, light) in lights.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut app.selection,
+                            Selection::Light(index),
+                            light_label(index, light),
+                        );
+                    }
+                });
+                ui.selectable_value(&mut app.selection, Selection::Instances, "Instances");
+                let count = app.scene.as_ref().map_or(0, |scene| scene.instances.len());
+                egui::CollapsingHeader::new("Instance list").show(ui, |ui| {
+                    for index in 0..count {
+                        ui.selectable_value(
+                            &mut app.selection,
+                            Selection::Instance(index),
+                            format!("Instance {index}"),
+                        );
+                    }
+                });
+            }
+            EditorTab::Inspector => match app.selection {
+                Selection::Instances => app.show_instance_controls(ui, renderer),
+                Selection::Instance(index) => {
+                    if let Some(instance) = app
+                        .scene
+                        .as_mut()
+                        .and_then(|scene| scene.instances.get_mut(index))
+                    {
+                        ui.heading(format!("Instance {index}"));
+                        instance.show_inspector(ui);
+                    }
+                }
+                Selection::Lights => app.show_light_controls(ui),
+                Selection::Light(index) => {
+                    let mut delete = false;
+                    if let Some(light) = app
+                        .scene
+                        .as_mut()
+                        .and_then(|scene| scene.light.lights.get_mut(index))
+                    {
+                        ui.heading(light_label(index, light));
+                        light.show_inspector(ui);
+                        delete = ui.button("Delete").clicked();
+                    }
+                    if delete {
+                        app.remove_light(index);
+                    }
+                }
+            },
+            EditorTab::Console => app.console.show(ui),
+            EditorTab::Assets => {
+                let Some(scene) = app.scene.as_mut() else {
+                    return;
+                };
+                for shape in Shape::ALL {
+                    if ui
+                        .selectable_label(scene.shape == shape, format!("{shape:?}"))
+                        .clicked()
+                    {
+                        scene.set_shape(&renderer.device, shape);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn light_label(index: usize, light: &Light) -> String {
+    format!("{:?} light {index}", light.kind)
+}
+
 fn main() -> Result<()> {
     run(
         App::default(),
@@ -578,6 +1724,7 @@ fn main() -> Result<()> {
             title: "Light".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
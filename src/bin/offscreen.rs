@@ -0,0 +1,518 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::{MouseOrbit, PerspectiveCamera},
+    run, AppConfig, Application, BindingBuilder, ColorTargetDescription, Geometry, Input, MeshData,
+    MultiRenderTarget, PrimitiveVertex, Renderer, System, Texture, VertexDescription,
+};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, RenderPass, RenderPipeline,
+    TextureFormat,
+};
+
+/// Width and height of the offscreen preview in pixels
+const PREVIEW_SIZE: u32 = 512;
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.05,
+    g: 0.05,
+    b: 0.07,
+    a: 1.0,
+};
+
+const PREVIEW_CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.18,
+    g: 0.2,
+    b: 0.25,
+    a: 1.0,
+};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PreviewUniformBuffer {
+    view_projection: glm::Mat4,
+    model: glm::Mat4,
+    base_color: glm::Vec4,
+    /// Direction towards the light, with the shininess in w
+    light: glm::Vec4,
+    /// Camera position, with the specular strength in w
+    camera: glm::Vec4,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DisplayUniformBuffer {
+    view_projection: glm::Mat4,
+    model: glm::Mat4,
+}
+
+/// The material shown in the preview, edited live from the GUI
+struct Material {
+    base_color: [f32; 4],
+    shininess: f32,
+    specular: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color: [0.85, 0.35, 0.2, 1.0],
+            shininess: 48.0,
+            specular: 0.6,
+        }
+    }
+}
+
+struct Scene {
+    pub material: Material,
+    pub shape: usize,
+    pub rotation_speed: f32,
+    /// Which of the preview's attachments is shown
+    pub attachment: usize,
+    /// The preview's attachments as egui textures, registered once the GUI renderer exists
+    pub gui_textures: Vec<egui::TextureId>,
+    angle: f32,
+    /// Shaded color and world space normals, written in the same pass
+    target: MultiRenderTarget,
+    shapes: Vec<(&'static str, Geometry)>,
+    preview_uniform: Buffer,
+    preview_bind_group: BindGroup,
+    preview_pipeline: RenderPipeline,
+    display: Geometry,
+    display_uniform: Buffer,
+    /// One per preview attachment
+    display_bind_groups: Vec<BindGroup>,
+    display_pipeline: RenderPipeline,
+}
+
+impl Scene {
+    pub fn new(device: &Device, surface_format: TextureFormat) -> Result<Self> {
+        // Rendering the preview in the surface format lets it be shown on the quad and in egui unchanged
+        let target = MultiRenderTarget::new(
+            device,
+            &[
+                ColorTargetDescription {
+                    clear: PREVIEW_CLEAR_COLOR,
+                    ..ColorTargetDescription::new("Preview Color", surface_format)
+                },
+                ColorTargetDescription {
+                    clear: wgpu::Color::BLACK,
+                    ..ColorTargetDescription::new("Preview Normals", TextureFormat::Rgba8Unorm)
+                },
+            ],
+            PREVIEW_SIZE,
+            PREVIEW_SIZE,
+        )?;
+        let shapes = vec![
+            ("Torus", MeshData::torus(0.8, 0.35, 48, 24).geometry(device)),
+            ("Sphere", MeshData::uv_sphere(1.0, 48, 24).geometry(device)),
+            ("Cube", MeshData::cube(1.4).geometry(device)),
+            ("Cone", MeshData::cone(0.9, 1.6, 48).geometry(device)),
+        ];
+
+        let preview_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Preview Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[PreviewUniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let preview_layout = BindingBuilder::new()
+            .label("Preview")
+            .uniform(0, wgpu::ShaderStages::VERTEX_FRAGMENT)
+            .build(device);
+        let preview_bind_group =
+            preview_layout.bind_group(device, &[preview_uniform.as_entire_binding()]);
+        let preview_pipeline = Self::create_pipeline(
+            device,
+            &target.color_target_states(),
+            &preview_layout.layout,
+            PREVIEW_SHADER_SOURCE,
+            Some(wgpu::Face::Back),
+            true,
+        );
+
+        // Stand the plane up so it faces the camera, with the top row of the texture at the top
+        let display = MeshData::plane(2.0, 0).geometry(device);
+        let display_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Display Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[DisplayUniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let display_layout = BindingBuilder::new()
+            .label("Display")
+            .uniform(0, wgpu::ShaderStages::VERTEX)
+            .texture(1, wgpu::ShaderStages::FRAGMENT)
+            .sampler(2, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let display_bind_groups = target
+            .colors
+            .iter()
+            .map(|color| {
+                display_layout.bind_group(
+                    device,
+                    &[
+                        display_uniform.as_entire_binding(),
+                        wgpu::BindingResource::TextureView(&color.view),
+                        wgpu::BindingResource::Sampler(&color.sampler),
+                    ],
+                )
+            })
+            .collect();
+        let display_pipeline = Self::create_pipeline(
+            device,
+            &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            &display_layout.layout,
+            DISPLAY_SHADER_SOURCE,
+            None,
+            false,
+        );
+
+        Ok(Self {
+            material: Material::default(),
+            shape: 0,
+            rotation_speed: 0.6,
+            attachment: 0,
+            gui_textures: Vec::new(),
+            angle: 0.6,
+            target,
+            shapes,
+            preview_uniform,
+            preview_bind_group,
+            preview_pipeline,
+            display,
+            display_uniform,
+            display_bind_groups,
+            display_pipeline,
+        })
+    }
+
+    pub fn shape_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.shapes.iter().map(|(name, _)| *name)
+    }
+
+    pub fn attachment_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.target
+            .descriptions
+            .iter()
+            .map(|description| description.label)
+    }
+
+    pub fn preview_views(&self) -> impl Iterator<Item = &wgpu::TextureView> {
+        self.target.colors.iter().map(|color| &color.view)
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit, delta_time: f32) {
+        self.angle += self.rotation_speed * delta_time;
+
+        let eye = glm::vec3(0.0, 1.2, 3.2);
+        let projection = PerspectiveCamera {
+            y_fov_rad: 45_f32.to_radians(),
+            ..Default::default()
+        }
+        .projection_matrix(self.target.aspect_ratio());
+        let view = glm::look_at_rh(&eye, &glm::Vec3::zeros(), &glm::Vec3::y());
+        let model = glm::rotate(&glm::Mat4::identity(), self.angle, &glm::Vec3::y())
+            * glm::rotate(&glm::Mat4::identity(), 0.5, &glm::Vec3::x());
+        let light = glm::normalize(&glm::vec3(0.6, 1.0, 0.8));
+        let preview = PreviewUniformBuffer {
+            view_projection: projection * view,
+            model,
+            base_color: glm::Vec4::from(self.material.base_color),
+            light: glm::vec4(light.x, light.y, light.z, self.material.shininess),
+            camera: glm::vec4(eye.x, eye.y, eye.z, self.material.specular),
+        };
+        renderer.write_buffer(&self.preview_uniform, 0, bytemuck::cast_slice(&[preview]));
+
+        let display = DisplayUniformBuffer {
+            view_projection: camera.projection_view_matrix(renderer.aspect_ratio()),
+            model: glm::rotate(&glm::Mat4::identity(), 90_f32.to_radians(), &glm::Vec3::x()),
+        };
+        renderer.write_buffer(&self.display_uniform, 0, bytemuck::cast_slice(&[display]));
+    }
+
+    /// Draws the secondary scene into the offscreen target
+    pub fn render_preview(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut renderpass = self.target.begin_pass(encoder);
+        renderpass.set_pipeline(&self.preview_pipeline);
+        renderpass.set_bind_group(0, &self.preview_bind_group, &[]);
+        let geometry = &self.shapes[self.shape].1;
+        let (vertex_slice, index_slice, index_format) = geometry.slices();
+        renderpass.set_vertex_buffer(0, vertex_slice);
+        renderpass.set_index_buffer(index_slice, index_format);
+        renderpass.draw_indexed(0..geometry.index_count, 0, 0..1);
+    }
+
+    /// Draws the quad showing the offscreen target in the main scene
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.display_pipeline);
+        renderpass.set_bind_group(0, &self.display_bind_groups[self.attachment], &[]);
+        let (vertex_slice, index_slice, index_format) = self.display.slices();
+        renderpass.set_vertex_buffer(0, vertex_slice);
+        renderpass.set_index_buffer(index_slice, index_format);
+        renderpass.draw_indexed(0..self.display.index_count, 0, 0..1);
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        targets: &[Option<wgpu::ColorTargetState>],
+        bind_group_layout: &BindGroupLayout,
+        source: &str,
+        cull_mode: Option<wgpu::Face>,
+        depth: bool,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[PrimitiveVertex::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                cull_mode,
+                ..Default::default()
+            },
+            depth_stencil: depth.then(|| wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets,
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const PREVIEW_SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    model: mat4x4<f32>,
+    base_color: vec4<f32>,
+    light: vec4<f32>,
+    camera: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    let world_position = ubo.model * vec4<f32>(vert.position, 1.0);
+    var out: VertexOutput;
+    out.position = ubo.view_projection * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = (ubo.model * vec4<f32>(vert.normal, 0.0)).xyz;
+    return out;
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> FragmentOutput {
+    let normal = normalize(in.normal);
+    let light = normalize(ubo.light.xyz);
+    let view = normalize(ubo.camera.xyz - in.world_position);
+    let halfway = normalize(light + view);
+    let diffuse = max(dot(normal, light), 0.0);
+    let specular = pow(max(dot(normal, halfway), 0.0), ubo.light.w) * ubo.camera.w;
+    let ambient = 0.15;
+    let color = ubo.base_color.rgb * (ambient + diffuse) + vec3<f32>(specular);
+    var out: FragmentOutput;
+    out.color = vec4<f32>(color, 1.0);
+    out.normal = vec4<f32>(normal * 0.5 + 0.5, 1.0);
+    return out;
+}
+";
+
+const DISPLAY_SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    model: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var preview: texture_2d<f32>;
+
+@group(0) @binding(2)
+var preview_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = ubo.view_projection * ubo.model * vec4<f32>(vert.position, 1.0);
+    out.uv = vert.uv;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(preview, preview_sampler, in.uv);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(&renderer.device, renderer.config.format)?);
+        self.camera.orientation.direction = glm::vec2(25_f32.to_radians(), 80_f32.to_radians());
+        self.camera.orientation.radius = 2.5;
+        self.camera.transform.translation = self.camera.orientation.position();
+        self.camera.transform.rotation = self.camera.orientation.look_at_offset();
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        if scene.gui_textures.is_empty() {
+            scene.gui_textures = scene
+                .preview_views()
+                .filter_map(|view| {
+                    renderer.gui.register_native_texture(
+                        &renderer.device,
+                        view,
+                        wgpu::FilterMode::Linear,
+                    )
+                })
+                .collect();
+        }
+
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Render to Texture");
+                if let Some(texture) = scene.gui_textures.get(scene.attachment) {
+                    ui.image((*texture, egui::vec2(200.0, 200.0)));
+                }
+                let mut attachment = scene.attachment;
+                let names = scene.attachment_names().collect::<Vec<_>>();
+                egui::ComboBox::from_label("Attachment")
+                    .selected_text(names[attachment])
+                    .show_ui(ui, |ui| {
+                        for (index, name) in names.iter().enumerate() {
+                            ui.selectable_value(&mut attachment, index, *name);
+                        }
+                    });
+                scene.attachment = attachment;
+                let mut selected = scene.shape;
+                egui::ComboBox::from_label("Shape")
+                    .selected_text(scene.shapes[scene.shape].0)
+                    .show_ui(ui, |ui| {
+                        for (index, name) in scene.shape_names().enumerate() {
+                            ui.selectable_value(&mut selected, index, name);
+                        }
+                    });
+                scene.shape = selected;
+                ui.horizontal(|ui| {
+                    ui.label("Base color");
+                    ui.color_edit_button_rgba_unmultiplied(&mut scene.material.base_color);
+                });
+                ui.add(
+                    egui::Slider::new(&mut scene.material.shininess, 1.0..=256.0)
+                        .logarithmic(true)
+                        .text("Shininess"),
+                );
+                ui.add(egui::Slider::new(&mut scene.material.specular, 0.0..=1.0).text("Specular"));
+                ui.add(
+                    egui::Slider::new(&mut scene.rotation_speed, -3.0..=3.0).text("Rotation speed"),
+                );
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let Some(scene) = self.scene.as_ref() else {
+            return Ok(None);
+        };
+        scene.render_preview(encoder);
+
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, Some(CLEAR_COLOR), None);
+        scene.render(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Render to Texture".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,498 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::MouseOrbit, impl_vertex, run, Aabb, AppConfig, Application, BindingBuilder, Geometry,
+    Input, MeshData, Renderer, System, Texture, VertexDescription, WeightedBlendedOit, OIT_SOURCE,
+};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, RenderPass, RenderPipeline,
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 4],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x3, 2 => Float32x4]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    view_projection: glm::Mat4,
+    camera_position: glm::Vec4,
+    /// Opacity multiplier of the transparent surfaces
+    params: glm::Vec4,
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = ubo.view_projection * vec4<f32>(vert.position, 1.0);
+    out.color = vert.color;
+    out.normal = vert.normal;
+    out.world_position = vert.position;
+    return out;
+}
+
+// Two sided so the quads are lit from either side
+fn shade(in: VertexOutput) -> vec4<f32> {
+    let light = normalize(vec3<f32>(0.4, 1.0, 0.3));
+    let lighting = 0.35 + 0.65 * abs(dot(normalize(in.normal), light));
+    return vec4<f32>(in.color.rgb * lighting, in.color.a * ubo.params.x);
+}
+
+@fragment
+fn fragment_opaque(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(shade(in).rgb, 1.0);
+}
+
+@fragment
+fn fragment_sorted(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+@fragment
+fn fragment_oit(in: VertexOutput) -> OitOutput {
+    return oit_output(shade(in), distance(in.world_position, ubo.camera_position.xyz));
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum TransparencyMode {
+    /// Back to front by each quad's center, which can't order surfaces that intersect
+    Sorted,
+    #[default]
+    WeightedBlended,
+}
+
+impl TransparencyMode {
+    const ALL: [Self; 2] = [Self::Sorted, Self::WeightedBlended];
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TransparencySettings {
+    mode: TransparencyMode,
+    opacity: f32,
+}
+
+impl Default for TransparencySettings {
+    fn default() -> Self {
+        Self {
+            mode: TransparencyMode::default(),
+            opacity: 1.0,
+        }
+    }
+}
+
+/// A translucent quad, given by its center, orientation and straight alpha color
+struct Quad {
+    center: glm::Vec3,
+    rotation: glm::Quat,
+    color: [f32; 4],
+}
+
+impl Quad {
+    const HALF_SIZE: glm::Vec2 = glm::Vec2::new(1.4, 0.9);
+
+    fn vertices(&self) -> [Vertex; 4] {
+        let normal = glm::quat_rotate_vec3(&self.rotation, &glm::Vec3::z());
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+            let corner = glm::vec3(x * Self::HALF_SIZE.x, y * Self::HALF_SIZE.y, 0.0);
+            Vertex {
+                position: (self.center + glm::quat_rotate_vec3(&self.rotation, &corner)).into(),
+                normal: normal.into(),
+                color: self.color,
+            }
+        })
+    }
+}
+
+/// Intersecting translucent quads over an opaque floor and cube, drawn either sorted
+/// back to front with alpha blending or with weighted blended order independent transparency
+struct Scene {
+    settings: TransparencySettings,
+    quads: Vec<Quad>,
+    opaque: Geometry,
+    transparent: Geometry,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    opaque_pipeline: RenderPipeline,
+    sorted_pipeline: RenderPipeline,
+    oit_pipeline: RenderPipeline,
+    oit: WeightedBlendedOit,
+}
+
+impl Scene {
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = &renderer.device;
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = BindingBuilder::new()
+            .label("Uniform")
+            .uniform(0, wgpu::ShaderStages::VERTEX_FRAGMENT)
+            .build(device);
+        let uniform_bind_group = uniform_layout.bind_group(device, &[uniform.as_entire_binding()]);
+
+        let quads = Self::create_quads();
+        let transparent_vertices = quads
+            .iter()
+            .flat_map(|quad| quad.vertices())
+            .collect::<Vec<_>>();
+        let transparent = Geometry::new_dynamic(
+            device,
+            &renderer.queue,
+            &transparent_vertices,
+            &Self::quad_indices(0..quads.len()),
+        );
+
+        let format = renderer.config.format;
+        let opaque_target = [Some(wgpu::ColorTargetState {
+            format,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+        let sorted_target = [Some(wgpu::ColorTargetState {
+            format,
+            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+        let oit_targets = WeightedBlendedOit::color_targets();
+        let pipeline = |fragment_entry_point, targets, depth_write_enabled| {
+            Self::create_pipeline(
+                device,
+                &uniform_layout.layout,
+                fragment_entry_point,
+                targets,
+                depth_write_enabled,
+            )
+        };
+
+        Self {
+            settings: TransparencySettings::default(),
+            opaque: Self::create_opaque_geometry(device),
+            transparent,
+            quads,
+            uniform,
+            uniform_bind_group,
+            opaque_pipeline: pipeline("fragment_opaque", &opaque_target, true),
+            sorted_pipeline: pipeline("fragment_sorted", &sorted_target, false),
+            oit_pipeline: pipeline("fragment_oit", &oit_targets, false),
+            oit: WeightedBlendedOit::new(
+                device,
+                format,
+                renderer.config.width,
+                renderer.config.height,
+            ),
+        }
+    }
+
+    fn create_quads() -> Vec<Quad> {
+        let upright = |degrees: f32| glm::quat_angle_axis(degrees.to_radians(), &glm::Vec3::y());
+        vec![
+            Quad {
+                center: glm::vec3(-0.2, 0.0, 0.0),
+                rotation: upright(-20.0),
+                color: [1.0, 0.25, 0.2, 0.5],
+            },
+            Quad {
+                center: glm::vec3(0.0, 0.1, 0.1),
+                rotation: upright(40.0),
+                color: [0.25, 0.9, 0.3, 0.5],
+            },
+            Quad {
+                center: glm::vec3(0.2, -0.1, -0.1),
+                rotation: upright(100.0),
+                color: [0.25, 0.45, 1.0, 0.5],
+            },
+            Quad {
+                center: glm::vec3(0.0, 0.15, 0.0),
+                rotation: glm::quat_angle_axis(-80_f32.to_radians(), &glm::Vec3::x()),
+                color: [1.0, 0.85, 0.2, 0.4],
+            },
+        ]
+    }
+
+    /// Two triangles per quad, in the given order
+    fn quad_indices(order: impl IntoIterator<Item = usize>) -> Vec<u16> {
+        order
+            .into_iter()
+            .flat_map(|quad| {
+                let first = (quad * 4) as u16;
+                [0, 1, 2, 0, 2, 3].map(|offset| first + offset)
+            })
+            .collect()
+    }
+
+    fn create_opaque_geometry(device: &Device) -> Geometry {
+        let parts = [
+            (MeshData::plane(8.0, 0), glm::vec3(0.0, -1.2, 0.0), [0.5; 3]),
+            (
+                MeshData::cube(0.8),
+                glm::vec3(1.2, -0.8, 0.7),
+                [0.9, 0.55, 0.2],
+            ),
+        ];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (mesh, offset, [r, g, b]) in parts {
+            let first = vertices.len() as u32;
+            vertices.extend(mesh.vertices.iter().map(|vertex| Vertex {
+                position: (glm::Vec3::from(vertex.position) + offset).into(),
+                normal: vertex.normal,
+                color: [r, g, b, 1.0],
+            }));
+            indices.extend(mesh.indices.iter().map(|index| first + index));
+        }
+        Geometry::new(device, &vertices, &indices)
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        uniform_layout: &BindGroupLayout,
+        fragment_entry_point: &str,
+        targets: &[Option<wgpu::ColorTargetState>],
+        depth_write_enabled: bool,
+    ) -> RenderPipeline {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("OIT Example Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{OIT_SOURCE}{SHADER_SOURCE}"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("OIT Example Pipeline Layout"),
+            bind_group_layouts: &[uniform_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(fragment_entry_point),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vertex_main",
+                buffers: &[Vertex::layout().buffer_layout()],
+            },
+            // Translucent surfaces show their back faces
+            primitive: wgpu::PrimitiveState {
+                cull_mode: depth_write_enabled.then_some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: fragment_entry_point,
+                targets,
+            }),
+            multiview: None,
+        })
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit) {
+        let camera_position = camera.transform.translation;
+        let uniform = UniformBuffer {
+            view_projection: camera.projection_view_matrix(renderer.aspect_ratio()),
+            camera_position: camera_position.push(1.0),
+            params: glm::vec4(self.settings.opacity, 0.0, 0.0, 0.0),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+
+        if self.settings.mode == TransparencyMode::Sorted {
+            let distance = |quad: &Quad| glm::distance2(&quad.center, &camera_position);
+            let mut order = (0..self.quads.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| distance(&self.quads[*b]).total_cmp(&distance(&self.quads[*a])));
+            self.transparent.update_indices(
+                &renderer.device,
+                &renderer.queue,
+                &Self::quad_indices(order),
+            );
+        }
+    }
+
+    pub fn resize(&mut self, renderer: &Renderer) {
+        self.oit.resize(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        );
+    }
+
+    fn draw<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        pipeline: &'rpass RenderPipeline,
+        geometry: &'rpass Geometry,
+    ) {
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = geometry.slices();
+        renderpass.set_pipeline(pipeline);
+        renderpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        renderpass.draw_indexed(0..geometry.index_count, 0, 0..1);
+    }
+
+    /// Sorted transparent surfaces are blended straight into the opaque pass
+    pub fn render_opaque<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        self.draw(renderpass, &self.opaque_pipeline, &self.opaque);
+        if self.settings.mode == TransparencyMode::Sorted {
+            self.draw(renderpass, &self.sorted_pipeline, &self.transparent);
+        }
+    }
+
+    /// Accumulates the transparent surfaces in their own pass, to be composited afterward
+    pub fn render_oit(&self, encoder: &mut wgpu::CommandEncoder, depth: &wgpu::TextureView) {
+        if self.settings.mode != TransparencyMode::WeightedBlended {
+            return;
+        }
+        let mut renderpass = self.oit.begin_pass(encoder, depth);
+        self.draw(&mut renderpass, &self.oit_pipeline, &self.transparent);
+    }
+
+    pub fn composite<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        if self.settings.mode == TransparencyMode::WeightedBlended {
+            self.oit.composite(renderpass);
+        }
+    }
+}
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+    depth_texture: Option<Texture>,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(renderer));
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        self.camera.orientation.direction = glm::vec2(30_f32.to_radians(), 65_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-1.5, -1.2, -1.5),
+            glm::vec3(1.5, 1.0, 1.5),
+        ));
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        if let Some(scene) = self.scene.as_mut() {
+            scene.resize(renderer);
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Order Independent Transparency");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                let settings = &mut scene.settings;
+                egui::ComboBox::from_label("Transparency")
+                    .selected_text(format!("{:?}", settings.mode))
+                    .show_ui(ui, |ui| {
+                        for mode in TransparencyMode::ALL {
+                            ui.selectable_value(&mut settings.mode, mode, format!("{mode:?}"));
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=2.0).text("Opacity"));
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let (Some(scene), Some(depth_texture)) = (self.scene.as_ref(), self.depth_texture.as_ref())
+        else {
+            return Ok(None);
+        };
+        {
+            let mut render_pass = Renderer::begin_default_pass(
+                encoder,
+                view,
+                Some(Renderer::DEFAULT_CLEAR_COLOR),
+                Some(&depth_texture.view),
+            );
+            scene.render_opaque(&mut render_pass);
+        }
+        scene.render_oit(encoder, &depth_texture.view);
+
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, None, None);
+        scene.composite(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Order Independent Transparency".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
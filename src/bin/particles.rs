@@ -0,0 +1,372 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::MouseOrbit, impl_vertex, run, Aabb, AppConfig, Application, BindingBuilder, Geometry,
+    Input, MeshData, ParticleEmitter, ParticleSystem, Renderer, System, Texture, VertexDescription,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline, TextureFormat};
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.05,
+    g: 0.06,
+    b: 0.1,
+    a: 1.0,
+};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x3, 2 => Float32x3]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    view_projection: glm::Mat4,
+}
+
+/// Bakes each primitive's transform and color into one static mesh
+fn scene_mesh() -> (Vec<Vertex>, Vec<u32>) {
+    let objects = [
+        (
+            MeshData::plane(30.0, 1),
+            glm::Mat4::identity(),
+            glm::vec3(0.3, 0.3, 0.32),
+        ),
+        (
+            MeshData::cube(1.0),
+            glm::translation(&glm::vec3(-3.0, 0.5, 0.0)),
+            glm::vec3(0.45, 0.45, 0.5),
+        ),
+        (
+            MeshData::cylinder(1.2, 0.6, 32),
+            glm::translation(&glm::vec3(3.0, 0.0, 0.0)),
+            glm::vec3(0.45, 0.5, 0.6),
+        ),
+        (
+            MeshData::cube(2.0),
+            glm::translation(&glm::vec3(0.0, 1.0, -4.0)),
+            glm::vec3(0.5, 0.35, 0.3),
+        ),
+    ];
+
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    for (mesh, model, color) in objects {
+        let offset = vertices.len() as u32;
+        vertices.extend(mesh.vertices.iter().map(|vertex| {
+            let position = model * glm::Vec3::from(vertex.position).push(1.0);
+            let normal = model * glm::Vec3::from(vertex.normal).push(0.0);
+            Vertex {
+                position: position.xyz().into(),
+                normal: normal.xyz().normalize().into(),
+                color: color.into(),
+            }
+        }));
+        indices.extend(mesh.indices.iter().map(|index| index + offset));
+    }
+    (vertices, indices)
+}
+
+fn emitters() -> Vec<ParticleEmitter> {
+    vec![
+        ParticleEmitter {
+            name: "Fire".to_string(),
+            position: [-3.0, 1.0, 0.0],
+            spread_degrees: 15.0,
+            speed: 1.5,
+            spawn_rate: 400.0,
+            lifetime: 1.2,
+            gravity: [0.0, 1.5, 0.0],
+            drag: 1.0,
+            start_size: 0.6,
+            end_size: 0.1,
+            start_color: [1.0, 0.6, 0.1, 0.8],
+            end_color: [0.8, 0.1, 0.0, 0.0],
+            ..Default::default()
+        },
+        ParticleEmitter {
+            name: "Smoke".to_string(),
+            position: [-3.0, 1.8, 0.0],
+            spread_degrees: 25.0,
+            speed: 0.8,
+            spawn_rate: 60.0,
+            lifetime: 5.0,
+            gravity: [0.3, 0.4, 0.0],
+            drag: 0.3,
+            start_size: 0.5,
+            end_size: 2.5,
+            start_color: [0.3, 0.3, 0.3, 0.5],
+            end_color: [0.5, 0.5, 0.5, 0.0],
+            ..Default::default()
+        },
+        ParticleEmitter {
+            name: "Fountain".to_string(),
+            position: [3.0, 0.6, 0.0],
+            spread_degrees: 10.0,
+            speed: 6.0,
+            spawn_rate: 1500.0,
+            lifetime: 2.0,
+            lifetime_variance: 0.2,
+            gravity: [0.0, -9.8, 0.0],
+            drag: 0.0,
+            start_size: 0.15,
+            end_size: 0.25,
+            start_color: [0.5, 0.8, 1.0, 0.8],
+            end_color: [0.2, 0.4, 1.0, 0.2],
+            ..Default::default()
+        },
+    ]
+}
+
+struct Scene {
+    objects: Geometry,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    pipeline: RenderPipeline,
+    particles: ParticleSystem,
+}
+
+impl Scene {
+    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
+        let (vertices, indices) = scene_mesh();
+        let objects = Geometry::new(device, &vertices, &indices);
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = BindingBuilder::new()
+            .label("Uniform")
+            .uniform(0, wgpu::ShaderStages::VERTEX)
+            .build(device);
+        let uniform_bind_group = uniform_layout.bind_group(device, &[uniform.as_entire_binding()]);
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&uniform_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[Vertex::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let mut particles = ParticleSystem::new(device, surface_format, 1 << 14);
+        particles.emitters = emitters();
+
+        Self {
+            objects,
+            uniform,
+            uniform_bind_group,
+            pipeline,
+            particles,
+        }
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit, delta_time: f32) {
+        let projection = camera.camera.projection_matrix(renderer.aspect_ratio());
+        let view = camera.transform.as_view_matrix();
+        let uniform = UniformBuffer {
+            view_projection: projection * view,
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+        self.particles
+            .update(renderer, delta_time, &view, &projection);
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.objects.slices();
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        renderpass.draw_indexed(0..self.objects.index_count, 0, 0..1);
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.normal = vert.normal;
+    out.color = vert.color;
+    out.position = ubo.view_projection * vec4(vert.position, 1.0);
+    return out;
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_direction = normalize(vec3<f32>(0.4, 1.0, 0.3));
+    let diffuse = max(dot(normalize(in.normal), light_direction), 0.0);
+    return vec4<f32>(in.color * (0.3 + 0.7 * diffuse), 1.0);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+    depth_texture: Option<Texture>,
+}
+
+impl App {
+    fn create_depth_texture(&mut self, renderer: &Renderer) {
+        let depth_texture = Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        );
+        if let Some(scene) = self.scene.as_mut() {
+            scene
+                .particles
+                .set_depth_texture(&renderer.device, &depth_texture);
+        }
+        self.depth_texture = Some(depth_texture);
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
+        self.create_depth_texture(renderer);
+        self.camera.orientation.direction = glm::vec2(20_f32.to_radians(), 70_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-5.0, 0.0, -5.0),
+            glm::vec3(5.0, 5.0, 2.0),
+        ));
+        if let Some(scene) = self.scene.as_mut() {
+            let projection = self
+                .camera
+                .camera
+                .projection_matrix(renderer.aspect_ratio());
+            let view = self.camera.transform.as_view_matrix();
+            scene.particles.prewarm(renderer, 3.0, &view, &projection);
+        }
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Particles");
+                if let Some(scene) = self.scene.as_mut() {
+                    scene.particles.show(ui);
+                }
+            });
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.create_depth_texture(renderer);
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let Some(scene) = self.scene.as_ref() else {
+            return Ok(None);
+        };
+        scene.particles.compute(encoder);
+
+        {
+            let depth_view = self
+                .depth_texture
+                .as_ref()
+                .map(|depth_texture| &depth_texture.view);
+            let mut render_pass =
+                Renderer::begin_default_pass(encoder, view, Some(CLEAR_COLOR), depth_view);
+            scene.render(&mut render_pass);
+        }
+
+        // The particles read the scene depth for their soft fade, so it can't be attached here
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, None, None);
+        scene.particles.render(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Particles".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,346 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::sync::Arc;
+use support::{
+    camera::MouseOrbit, run, Aabb, AppConfig, Application, BindingBuilder, FullscreenPipeline,
+    Globals, GlobalsBinding, Input, Renderer, ShaderParamsBinding, ShaderReflection, System,
+    FULLSCREEN_VERTEX_SOURCE,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, TextureFormat};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    inverse_view_projection: glm::Mat4,
+    camera_position: glm::Vec3,
+    /// Seconds spent paused
+    paused_time: f32,
+    /// From the shadow quality setting, zero turns shadows off
+    shadow_steps: u32,
+    _padding: [u32; 3],
+}
+
+struct Scene {
+    /// The march settings, with controls generated from the shader's `Params` struct
+    params: ShaderParamsBinding,
+    animate: bool,
+    /// Subtracted from the global time so the scene holds still while not animating
+    paused_time: f32,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    globals: Arc<GlobalsBinding>,
+    pipeline: FullscreenPipeline,
+}
+
+impl Scene {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        globals: Arc<GlobalsBinding>,
+    ) -> Self {
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = BindingBuilder::new()
+            .label("Uniform")
+            .uniform(0, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let uniform_bind_group = uniform_layout.bind_group(device, &[uniform.as_entire_binding()]);
+        let source = format!("{}{SHADER_SOURCE}", Globals::SHADER_SOURCE);
+        let params = ShaderReflection::from_wgsl(&format!("{FULLSCREEN_VERTEX_SOURCE}{source}"))
+            .expect("the built in shader is valid")
+            .params(&source)
+            .expect("the built in shader declares params");
+        let params = ShaderParamsBinding::new(device, params);
+        let pipeline = FullscreenPipeline::new(
+            device,
+            surface_format,
+            &source,
+            "fragment_main",
+            &globals.pipeline_layouts(&[&uniform_layout.layout, params.layout()]),
+        );
+
+        Self {
+            params,
+            animate: true,
+            paused_time: 0.0,
+            uniform,
+            uniform_bind_group,
+            globals,
+            pipeline,
+        }
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit, delta_time: f32) {
+        if !self.animate {
+            self.paused_time += delta_time;
+        }
+        let view_projection = camera.projection_view_matrix(renderer.aspect_ratio());
+        let uniform = UniformBuffer {
+            inverse_view_projection: glm::inverse(&view_projection),
+            camera_position: camera.transform.translation,
+            paused_time: self.paused_time,
+            shadow_steps: renderer.settings.shadow_quality.trace_steps(),
+            _padding: [0; 3],
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        self.params.set(renderpass);
+        self.globals.set(renderpass, 2);
+        self.pipeline.draw(renderpass);
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    inverse_view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    paused_time: f32,
+    shadow_steps: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct Params {
+    shadow_sharpness: f32, // @range(1.0, 64.0) @default(12.0)
+    ambient_occlusion: f32, // @range(0.0, 2.0) @default(1.0)
+    max_steps: u32, // @range(16.0, 256.0) @default(128.0)
+    light_elevation: f32, // @range(0.1, 1.5) @default(0.6981317)
+};
+
+@group(1) @binding(0)
+var<uniform> params: Params;
+
+const FAR: f32 = 100.0;
+
+fn sd_sphere(p: vec3<f32>, radius: f32) -> f32 {
+    return length(p) - radius;
+}
+
+fn sd_round_box(p: vec3<f32>, half_size: vec3<f32>, radius: f32) -> f32 {
+    let q = abs(p) - half_size + radius;
+    return length(max(q, vec3(0.0))) + min(max(q.x, max(q.y, q.z)), 0.0) - radius;
+}
+
+fn sd_torus(p: vec3<f32>, radii: vec2<f32>) -> f32 {
+    let q = vec2(length(p.xz) - radii.x, p.y);
+    return length(q) - radii.y;
+}
+
+fn smooth_union(a: f32, b: f32, k: f32) -> f32 {
+    let h = clamp(0.5 + 0.5 * (b - a) / k, 0.0, 1.0);
+    return mix(b, a, h) - k * h * (1.0 - h);
+}
+
+fn rotate_y(p: vec3<f32>, angle: f32) -> vec3<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+    return vec3(c * p.x + s * p.z, p.y, -s * p.x + c * p.z);
+}
+
+// Distance to the nearest surface in x and a material id in y
+fn map(p: vec3<f32>) -> vec2<f32> {
+    let time = globals.time - ubo.paused_time;
+    var result = vec2(p.y, 0.0);
+
+    // Two spheres that melt into each other
+    let bob = sin(time) * 0.6;
+    let blob = smooth_union(
+        sd_sphere(p - vec3(-0.5, 1.0 + bob, 0.0), 0.7),
+        sd_sphere(p - vec3(0.6, 1.0 - bob, 0.0), 0.55),
+        0.4,
+    );
+    if blob < result.x {
+        result = vec2(blob, 1.0);
+    }
+
+    let cube = sd_round_box(rotate_y(p - vec3(-2.6, 0.6, 0.5), time * 0.5), vec3(0.6), 0.1);
+    if cube < result.x {
+        result = vec2(cube, 2.0);
+    }
+
+    // A ring standing on its edge
+    let ring_position = p - vec3(2.6, 0.9, -0.3);
+    let torus = sd_torus(vec3(ring_position.x, ring_position.z, ring_position.y), vec2(0.7, 0.2));
+    if torus < result.x {
+        result = vec2(torus, 3.0);
+    }
+    return result;
+}
+
+fn march(origin: vec3<f32>, direction: vec3<f32>) -> vec2<f32> {
+    var distance = 0.0;
+    for (var step = 0u; step < params.max_steps; step++) {
+        let hit = map(origin + direction * distance);
+        if hit.x < 0.0005 * distance {
+            return vec2(distance, hit.y);
+        }
+        distance += hit.x;
+        if distance > FAR {
+            break;
+        }
+    }
+    return vec2(FAR, -1.0);
+}
+
+fn normal(p: vec3<f32>) -> vec3<f32> {
+    let e = vec2(0.001, 0.0);
+    return normalize(vec3(
+        map(p + e.xyy).x - map(p - e.xyy).x,
+        map(p + e.yxy).x - map(p - e.yxy).x,
+        map(p + e.yyx).x - map(p - e.yyx).x,
+    ));
+}
+
+// Marches toward the light, darkening by how closely the ray grazes other surfaces on the way
+fn soft_shadow(origin: vec3<f32>, direction: vec3<f32>, sharpness: f32) -> f32 {
+    var shadow = 1.0;
+    var distance = 0.02;
+    for (var step = 0u; step < ubo.shadow_steps; step++) {
+        let nearest = map(origin + direction * distance).x;
+        if nearest < 0.0001 {
+            return 0.0;
+        }
+        shadow = min(shadow, sharpness * nearest / distance);
+        distance += clamp(nearest, 0.01, 0.5);
+        if distance > 20.0 {
+            break;
+        }
+    }
+    return clamp(shadow, 0.0, 1.0);
+}
+
+// Samples the field along the normal, occlusion grows where surfaces are closer than the sample distance
+fn ambient_occlusion(p: vec3<f32>, n: vec3<f32>) -> f32 {
+    var occlusion = 0.0;
+    var weight = 1.0;
+    for (var index = 1; index <= 5; index++) {
+        let distance = 0.03 + 0.1 * f32(index);
+        occlusion += (distance - map(p + n * distance).x) * weight;
+        weight *= 0.7;
+    }
+    return clamp(1.0 - params.ambient_occlusion * 1.5 * occlusion, 0.0, 1.0);
+}
+
+fn material_color(id: f32, p: vec3<f32>) -> vec3<f32> {
+    if id < 0.5 {
+        let checker = (i32(floor(p.x)) + i32(floor(p.z))) & 1;
+        return mix(vec3(0.45), vec3(0.6), f32(checker));
+    } else if id < 1.5 {
+        return vec3(0.85, 0.25, 0.2);
+    } else if id < 2.5 {
+        return vec3(0.25, 0.55, 0.85);
+    }
+    return vec3(0.9, 0.7, 0.2);
+}
+
+@fragment
+fn fragment_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    // Unproject the pixel onto the near plane to get the view ray
+    let ndc = vec4(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0, 0.0, 1.0);
+    let near = ubo.inverse_view_projection * ndc;
+    let origin = ubo.camera_position.xyz;
+    let direction = normalize(near.xyz / near.w - origin);
+
+    let sky = mix(vec3(0.75, 0.85, 1.0), vec3(0.35, 0.55, 0.9), clamp(direction.y * 2.0, 0.0, 1.0));
+    let hit = march(origin, direction);
+    if hit.y < 0.0 {
+        return vec4(sky, 1.0);
+    }
+
+    let position = origin + direction * hit.x;
+    let n = normal(position);
+    let elevation = params.light_elevation;
+    let light = normalize(vec3(cos(elevation) * 0.6, sin(elevation), cos(elevation) * 0.8));
+    let diffuse = max(dot(n, light), 0.0) * soft_shadow(position + n * 0.002, light, params.shadow_sharpness);
+    let occlusion = ambient_occlusion(position, n);
+    let specular = pow(max(dot(reflect(-light, n), -direction), 0.0), 32.0) * diffuse;
+    let ambient = (0.15 + 0.1 * n.y) * occlusion;
+    var color = material_color(hit.y, position) * (ambient + diffuse) + vec3(specular * 0.3);
+
+    let fog = 1.0 - exp(-0.002 * hit.x * hit.x);
+    color = mix(color, sky, fog);
+    return vec4(color, 1.0);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(
+            &renderer.device,
+            renderer.config.format,
+            renderer.globals.binding(),
+        ));
+        self.camera.orientation.direction = glm::vec2(20_f32.to_radians(), 70_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-3.5, 0.0, -1.0),
+            glm::vec3(3.5, 2.0, 1.0),
+        ));
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Signed Distance Fields");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                scene.params.show(ui, renderer);
+                ui.checkbox(&mut scene.animate, "Animate");
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        // Every pixel is written by the fullscreen pass, so nothing needs clearing
+        let mut render_pass = Renderer::begin_default_pass(encoder, view, None, None);
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Signed Distance Fields".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
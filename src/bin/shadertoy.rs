@@ -0,0 +1,393 @@
+use anyhow::Result;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use support::{
+    apply_default_sampling, run, AppConfig, Application, FullscreenPipeline, Globals,
+    GlobalsBinding, Input, Renderer, ShaderParamsBinding, ShaderReflection, System,
+};
+use wgpu::{Device, RenderPass, TextureFormat};
+
+/// Loads the shader from a file instead of the built in one, reloading it whenever it changes
+const SHADER_FLAG: &str = "--shader";
+
+const DEFAULT_SHADER: &str = include_str!("../../assets/shaders/shadertoy.wgsl");
+
+/// Appended to WGSL shaders, calling their `main_image` with Shadertoy's bottom left origin
+const WGSL_ENTRY_SOURCE: &str = "
+@fragment
+fn shadertoy_fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let position = in.position;
+    return main_image(vec2(position.x, globals.resolution.y - position.y));
+}
+";
+
+/// The fullscreen vertex stage's output, which the fragment stage has to take as input
+const FULLSCREEN_OUTPUT_SOURCE: &str = "
+struct FullscreenOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+";
+
+/// Prepended to GLSL shaders, declaring the globals under Shadertoy's names
+const GLSL_PREAMBLE: &str = "#version 450
+layout(set = 3, binding = 0) uniform Globals {
+    float time;
+    float delta_time;
+    uint frame;
+    vec2 resolution;
+    vec2 mouse;
+} globals;
+#define iTime globals.time
+#define iTimeDelta globals.delta_time
+#define iFrame int(globals.frame)
+#define iResolution vec3(globals.resolution, 1.0)
+#define iMouse vec4(globals.mouse.x, globals.resolution.y - globals.mouse.y, 0.0, 0.0)
+";
+
+/// Appended to GLSL shaders, calling their `mainImage` with Shadertoy's bottom left origin
+const GLSL_ENTRY_SOURCE: &str = "
+layout(location = 0) in vec2 shadertoy_uv;
+layout(location = 0) out vec4 shadertoy_color;
+void main() {
+    mainImage(shadertoy_color, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum Language {
+    #[default]
+    Wgsl,
+    Glsl,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("glsl" | "frag") => Self::Glsl,
+            _ => Self::Wgsl,
+        }
+    }
+
+    fn entry_point(&self) -> &'static str {
+        match self {
+            Self::Wgsl => "shadertoy_fragment",
+            Self::Glsl => "main",
+        }
+    }
+
+    /// Parses and validates the shader along with its entry point,
+    /// returning the diagnostics to show when it fails
+    fn compile(&self, source: &str, name: &str) -> Result<naga::Module, String> {
+        let (module, source) = match self {
+            Self::Wgsl => {
+                let source = format!(
+                    "{source}\n{FULLSCREEN_OUTPUT_SOURCE}{}{WGSL_ENTRY_SOURCE}",
+                    Globals::SHADER_SOURCE
+                );
+                let module = naga::front::wgsl::parse_str(&source)
+                    .map_err(|error| error.emit_to_string_with_path(&source, name))?;
+                (module, source)
+            }
+            Self::Glsl => {
+                let source = format!("{GLSL_PREAMBLE}{source}\n{GLSL_ENTRY_SOURCE}");
+                let options = naga::front::glsl::Options::from(naga::ShaderStage::Fragment);
+                let mut module = naga::front::glsl::Frontend::default()
+                    .parse(&options, &source)
+                    .map_err(|errors| glsl_diagnostics(&errors, &source, name))?;
+                apply_default_sampling(&mut module);
+                (module, source)
+            }
+        };
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .map_err(|error| error.emit_to_string_with_path(&source, name))?;
+        Ok(module)
+    }
+}
+
+/// One line per error, numbered from the start of the user's source rather than the preamble
+fn glsl_diagnostics(errors: &[naga::front::glsl::Error], source: &str, name: &str) -> String {
+    let preamble_lines = GLSL_PREAMBLE.lines().count() as u32;
+    errors
+        .iter()
+        .map(|error| {
+            let location = error.meta.location(source);
+            let line = location.line_number.saturating_sub(preamble_lines);
+            format!("{name}:{line}:{}: {}", location.line_position, error.kind)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A shader file on disk, polled for changes each frame
+struct ShaderFile {
+    path: PathBuf,
+    /// Unset until the file is first read
+    modified: Option<SystemTime>,
+}
+
+impl ShaderFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+        }
+    }
+
+    /// The file's contents if it changed since the last read
+    fn read_if_changed(&mut self) -> Result<Option<String>> {
+        let modified = std::fs::metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        self.modified = Some(modified);
+        Ok(Some(std::fs::read_to_string(&self.path)?))
+    }
+}
+
+/// Controls for the shader's `params` uniform, which has to be declared at group 0
+fn shader_params(
+    device: &Device,
+    module: &naga::Module,
+    source: &str,
+) -> Result<Option<ShaderParamsBinding>, String> {
+    let reflection =
+        ShaderReflection::from_module(module.clone()).map_err(|error| format!("{error:#}"))?;
+    let Some(params) = reflection.params(source) else {
+        return Ok(None);
+    };
+    if params.group != 0 {
+        return Err(format!(
+            "params is declared in group {}, but has to be in group 0",
+            params.group
+        ));
+    }
+    Ok(Some(ShaderParamsBinding::new(device, params)))
+}
+
+struct Scene {
+    globals: Arc<GlobalsBinding>,
+    format: TextureFormat,
+    /// The last shader that compiled, kept while a newer one has errors
+    pipeline: Option<FullscreenPipeline>,
+    params: Option<ShaderParamsBinding>,
+    language: Language,
+    diagnostics: Option<String>,
+}
+
+impl Scene {
+    pub fn new(device: &Device, format: TextureFormat, globals: Arc<GlobalsBinding>) -> Self {
+        let mut scene = Self {
+            globals,
+            format,
+            pipeline: None,
+            params: None,
+            language: Language::Wgsl,
+            diagnostics: None,
+        };
+        scene.load(device, DEFAULT_SHADER, Language::Wgsl, "shadertoy.wgsl");
+        scene
+    }
+
+    pub fn load(&mut self, device: &Device, source: &str, language: Language, name: &str) {
+        let pipeline = language.compile(source, name).and_then(|module| {
+            let params = shader_params(device, &module, source)?;
+            let layouts = params
+                .iter()
+                .map(ShaderParamsBinding::layout)
+                .collect::<Vec<_>>();
+            let pipeline = Renderer::validated(device, "Shadertoy pipeline", || {
+                let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(name),
+                    source: wgpu::ShaderSource::Naga(Cow::Owned(module)),
+                });
+                FullscreenPipeline::with_fragment_module(
+                    device,
+                    self.format,
+                    &module,
+                    language.entry_point(),
+                    &self.globals.pipeline_layouts(&layouts),
+                )
+            })
+            .map_err(|error| error.to_string())?;
+            Ok((pipeline, params))
+        });
+        match pipeline {
+            Ok((pipeline, params)) => {
+                log::info!("Loaded {name}");
+                self.pipeline = Some(pipeline);
+                self.params = params;
+                self.language = language;
+                self.diagnostics = None;
+            }
+            Err(diagnostics) => {
+                log::error!("Failed to compile {name}:\n{diagnostics}");
+                self.diagnostics = Some(diagnostics);
+            }
+        }
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        if let Some(pipeline) = self.pipeline.as_ref() {
+            if let Some(params) = self.params.as_ref() {
+                params.set(renderpass);
+            }
+            self.globals
+                .set(renderpass, u32::from(self.params.is_some()));
+            pipeline.draw(renderpass);
+        }
+    }
+}
+
+struct App {
+    scene: Option<Scene>,
+    file: Option<ShaderFile>,
+    time: f32,
+    frame: u32,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let mut args = std::env::args();
+        let file = args
+            .position(|argument| argument == SHADER_FLAG)
+            .and_then(|_| args.next())
+            .map(|path| ShaderFile::new(PathBuf::from(path)));
+        Self {
+            scene: None,
+            file,
+            time: 0.0,
+            frame: 0,
+        }
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(
+            &renderer.device,
+            renderer.config.format,
+            renderer.globals.binding(),
+        ));
+        Ok(())
+    }
+
+    fn on_file_dropped(&mut self, _renderer: &mut Renderer, path: &Path) -> Result<()> {
+        self.file = Some(ShaderFile::new(path.to_path_buf()));
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        _system: &System,
+    ) -> Result<()> {
+        let globals = renderer.globals.data();
+        self.time = globals.time;
+        self.frame = globals.frame;
+        let (Some(scene), Some(file)) = (self.scene.as_mut(), self.file.as_mut()) else {
+            return Ok(());
+        };
+        match file.read_if_changed() {
+            Ok(Some(source)) => {
+                let name = file.path.display().to_string();
+                scene.load(
+                    &renderer.device,
+                    &source,
+                    Language::from_path(&file.path),
+                    &name,
+                );
+            }
+            Ok(None) => {}
+            // Editors can briefly remove a file while saving it, so try again next frame
+            Err(error) => log::debug!("Failed to read {}: {error}", file.path.display()),
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Shadertoy");
+                let source = self
+                    .file
+                    .as_ref()
+                    .map(|file| file.path.display().to_string())
+                    .unwrap_or_else(|| "Built in".to_string());
+                ui.label(format!("Shader: {source}"));
+                if let Some(scene) = self.scene.as_ref() {
+                    ui.label(format!("Language: {:?}", scene.language));
+                }
+                ui.label(format!("Time: {:.2}s", self.time));
+                ui.label(format!("Frame: {}", self.frame));
+                ui.label("Drop a .wgsl or .glsl file here to load it");
+                if let Some(file) = self.file.as_mut() {
+                    if ui.button("Reload").clicked() {
+                        file.modified = None;
+                    }
+                }
+                if let Some(params) = self.scene.as_mut().and_then(|scene| scene.params.as_mut()) {
+                    ui.separator();
+                    params.show(ui, renderer);
+                }
+            });
+
+        let Some(diagnostics) = self
+            .scene
+            .as_ref()
+            .and_then(|scene| scene.diagnostics.as_ref())
+        else {
+            return Ok(());
+        };
+        egui::Window::new("Shader errors")
+            .default_pos((10.0, 220.0))
+            .default_width(600.0)
+            .show(context, |ui| {
+                ui.label("Showing the last shader that compiled");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.label(
+                        egui::RichText::new(diagnostics)
+                            .monospace()
+                            .color(ui.visuals().error_fg_color),
+                    );
+                });
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Shadertoy".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,652 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::{borrow::Cow, f32::consts::TAU, mem};
+use support::{
+    camera::MouseOrbit, impl_vertex, run, Aabb, AppConfig, Application, BindingBuilder,
+    ComputeKernel, Input, Renderer, ShadowQuality, System, Texture, VertexDescription,
+    VertexLayout,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+/// Joints in the chain, the shaders size their joint arrays to match
+const JOINT_COUNT: usize = 4;
+const TUBE_HEIGHT: f32 = 4.0;
+const TUBE_RADIUS: f32 = 0.3;
+const TUBE_RINGS: u32 = 48;
+const TUBE_SEGMENTS: u32 = 24;
+
+/// A bind pose vertex with the joints it follows and how strongly, also read by the compute kernel
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SkinVertex {
+    position: [f32; 4],
+    normal: [f32; 4],
+    joints: [u32; 4],
+    weights: [f32; 4],
+}
+
+impl_vertex!(SkinVertex, [0 => Float32x4, 1 => Float32x4, 2 => Uint32x4, 3 => Float32x4]);
+
+/// A vertex already in its final pose, as written by the compute kernel
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct StaticVertex {
+    position: [f32; 4],
+    normal: [f32; 4],
+}
+
+impl_vertex!(StaticVertex, [0 => Float32x4, 1 => Float32x4]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    view_projection: glm::Mat4,
+    /// Flattens geometry onto the ground along the light direction
+    shadow: glm::Mat4,
+    light_direction: glm::Vec4,
+}
+
+const JOINTS_SOURCE: &str = "
+struct Joints {
+    matrices: array<mat4x4<f32>, 4>,
+};
+
+fn skin_matrix(joint_indices: vec4<u32>, weights: vec4<f32>) -> mat4x4<f32> {
+    return joints.matrices[joint_indices.x] * weights.x
+        + joints.matrices[joint_indices.y] * weights.y
+        + joints.matrices[joint_indices.z] * weights.z
+        + joints.matrices[joint_indices.w] * weights.w;
+}
+";
+
+const SKIN_SOURCE: &str = "
+struct SkinVertex {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+    joints: vec4<u32>,
+    weights: vec4<f32>,
+};
+
+struct StaticVertex {
+    position: vec4<f32>,
+    normal: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> joints: Joints;
+
+@group(0) @binding(1)
+var<storage, read> source: array<SkinVertex>;
+
+@group(0) @binding(2)
+var<storage, read_write> skinned: array<StaticVertex>;
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn skin(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= arrayLength(&source) {
+        return;
+    }
+    let vertex = source[index];
+    let matrix = skin_matrix(vertex.joints, vertex.weights);
+    var out: StaticVertex;
+    out.position = matrix * vec4<f32>(vertex.position.xyz, 1.0);
+    out.normal = vec4<f32>(normalize((matrix * vec4<f32>(vertex.normal.xyz, 0.0)).xyz), 0.0);
+    skinned[index] = out;
+}
+";
+
+const RENDER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    shadow: mat4x4<f32>,
+    light_direction: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var<uniform> joints: Joints;
+
+struct SkinInput {
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+    @location(2) joints: vec4<u32>,
+    @location(3) weights: vec4<f32>,
+};
+
+struct StaticInput {
+    @location(0) position: vec4<f32>,
+    @location(1) normal: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) height: f32,
+};
+
+fn output(world: vec4<f32>, normal: vec3<f32>, transform: mat4x4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = ubo.view_projection * transform * world;
+    out.normal = normal;
+    out.height = world.y;
+    return out;
+}
+
+fn skin(in: SkinInput) -> StaticInput {
+    let matrix = skin_matrix(in.joints, in.weights);
+    var out: StaticInput;
+    out.position = matrix * vec4<f32>(in.position.xyz, 1.0);
+    out.normal = vec4<f32>(normalize((matrix * vec4<f32>(in.normal.xyz, 0.0)).xyz), 0.0);
+    return out;
+}
+
+const IDENTITY: mat4x4<f32> = mat4x4<f32>(
+    vec4<f32>(1.0, 0.0, 0.0, 0.0),
+    vec4<f32>(0.0, 1.0, 0.0, 0.0),
+    vec4<f32>(0.0, 0.0, 1.0, 0.0),
+    vec4<f32>(0.0, 0.0, 0.0, 1.0),
+);
+
+@vertex
+fn vertex_skinned(in: SkinInput) -> VertexOutput {
+    let posed = skin(in);
+    return output(posed.position, posed.normal.xyz, IDENTITY);
+}
+
+@vertex
+fn vertex_static(in: StaticInput) -> VertexOutput {
+    return output(in.position, in.normal.xyz, IDENTITY);
+}
+
+@vertex
+fn shadow_skinned(in: SkinInput) -> VertexOutput {
+    let posed = skin(in);
+    return output(posed.position, posed.normal.xyz, ubo.shadow);
+}
+
+@vertex
+fn shadow_static(in: StaticInput) -> VertexOutput {
+    return output(in.position, in.normal.xyz, ubo.shadow);
+}
+
+// Two sided, the tube is open at both ends
+@fragment
+fn fragment_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    var normal = normalize(in.normal);
+    if !front_facing {
+        normal = -normal;
+    }
+    let diffuse = max(dot(normal, ubo.light_direction.xyz), 0.0);
+    var albedo = mix(vec3<f32>(0.9, 0.35, 0.1), vec3<f32>(1.0, 0.8, 0.3), clamp(in.height / 4.0, 0.0, 1.0));
+    if in.height < 0.001 {
+        albedo = vec3<f32>(0.45, 0.45, 0.5);
+    }
+    return vec4<f32>(albedo * (0.25 + 0.75 * diffuse), 1.0);
+}
+
+@fragment
+fn fragment_shadow(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.15, 0.15, 0.18, 1.0);
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum SkinningMode {
+    /// Every pass drawing the mesh skins its vertices again in the vertex shader
+    VertexShader,
+    /// A compute pass skins each vertex once into a buffer that every pass draws as a static mesh
+    #[default]
+    Compute,
+}
+
+impl SkinningMode {
+    const ALL: [Self; 2] = [Self::VertexShader, Self::Compute];
+}
+
+/// The main and shadow pipelines for one kind of vertex input
+struct MeshPipelines {
+    main: RenderPipeline,
+    shadow: RenderPipeline,
+}
+
+/// A tube bent by a chain of joints, skinned either in the vertex shader or ahead of time by a
+/// compute kernel. It is drawn twice, once lit and once flattened into a shadow on the ground,
+/// so the compute path's single skinning per frame can be compared with skinning in every pass.
+struct Scene {
+    mode: SkinningMode,
+    /// Follows the shadow quality setting, the planar shadow has no levels in between
+    shadows: bool,
+    time: f32,
+    amplitude: f32,
+    speed: f32,
+    vertex_count: u32,
+    index_count: u32,
+    skin_vertices: Buffer,
+    skinned_vertices: Buffer,
+    indices: Buffer,
+    ground: Buffer,
+    uniform: Buffer,
+    joints: Buffer,
+    render_bind_group: BindGroup,
+    compute_bind_group: BindGroup,
+    kernel: ComputeKernel,
+    skinned_pipelines: MeshPipelines,
+    static_pipelines: MeshPipelines,
+}
+
+impl Scene {
+    pub fn new(device: &Device, format: wgpu::TextureFormat) -> Self {
+        let (vertices, indices) = create_tube();
+        let skin_vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Skin Vertices"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+        });
+        let skinned_vertices = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Skinned Vertices"),
+            size: (vertices.len() * mem::size_of::<StaticVertex>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let indices_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tube Indices"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let ground = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ground Vertices"),
+            contents: bytemuck::cast_slice(&create_ground(6.0)),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let joints = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Joint Matrices"),
+            contents: bytemuck::cast_slice(&[glm::Mat4::identity(); JOINT_COUNT]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let render_layout = BindingBuilder::new()
+            .label("Skinning Render")
+            .uniform(0, wgpu::ShaderStages::VERTEX_FRAGMENT)
+            .uniform(1, wgpu::ShaderStages::VERTEX)
+            .build(device);
+        let compute_layout = BindingBuilder::new()
+            .label("Skinning Compute")
+            .uniform(0, wgpu::ShaderStages::COMPUTE)
+            .storage(1, wgpu::ShaderStages::COMPUTE, true)
+            .storage(2, wgpu::ShaderStages::COMPUTE, false)
+            .build(device);
+        let render_bind_group = render_layout.bind_group(
+            device,
+            &[uniform.as_entire_binding(), joints.as_entire_binding()],
+        );
+        let compute_bind_group = compute_layout.bind_group(
+            device,
+            &[
+                joints.as_entire_binding(),
+                skin_vertices.as_entire_binding(),
+                skinned_vertices.as_entire_binding(),
+            ],
+        );
+
+        let kernel = ComputeKernel::new(
+            device,
+            &format!("{JOINTS_SOURCE}{SKIN_SOURCE}"),
+            "skin",
+            &[&compute_layout.layout],
+            64,
+        );
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skinning Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{JOINTS_SOURCE}{RENDER_SOURCE}"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skinning Pipeline Layout"),
+            bind_group_layouts: &[&render_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipelines =
+            |main_entry_point, shadow_entry_point, vertex_layout: VertexLayout| MeshPipelines {
+                main: create_pipeline(
+                    device,
+                    &layout,
+                    &module,
+                    format,
+                    (main_entry_point, "fragment_main"),
+                    &vertex_layout,
+                ),
+                shadow: create_pipeline(
+                    device,
+                    &layout,
+                    &module,
+                    format,
+                    (shadow_entry_point, "fragment_shadow"),
+                    &vertex_layout,
+                ),
+            };
+
+        Self {
+            mode: SkinningMode::default(),
+            shadows: true,
+            time: 0.0,
+            amplitude: 0.6,
+            speed: 1.5,
+            vertex_count: vertices.len() as u32,
+            index_count: indices.len() as u32,
+            skin_vertices,
+            skinned_vertices,
+            indices: indices_buffer,
+            ground,
+            uniform,
+            joints,
+            render_bind_group,
+            compute_bind_group,
+            kernel,
+            skinned_pipelines: pipelines("vertex_skinned", "shadow_skinned", SkinVertex::layout()),
+            static_pipelines: pipelines("vertex_static", "shadow_static", StaticVertex::layout()),
+        }
+    }
+
+    /// Skinning matrices for the animated pose, each joint's world transform
+    /// times the inverse of its bind pose
+    fn joint_matrices(&self) -> [glm::Mat4; JOINT_COUNT] {
+        let length = TUBE_HEIGHT / JOINT_COUNT as f32;
+        let mut parent = glm::Mat4::identity();
+        std::array::from_fn(|joint| {
+            let phase = self.time * self.speed - joint as f32 * 0.8;
+            let bend = glm::rotation(phase.sin() * self.amplitude, &glm::Vec3::z())
+                * glm::rotation((phase * 0.7).cos() * self.amplitude * 0.5, &glm::Vec3::x());
+            let offset = if joint == 0 { 0.0 } else { length };
+            parent = parent * glm::translation(&glm::vec3(0.0, offset, 0.0)) * bend;
+            let inverse_bind = glm::translation(&glm::vec3(0.0, -(joint as f32) * length, 0.0));
+            parent * inverse_bind
+        })
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit, delta_time: f32) {
+        self.time += delta_time;
+        self.shadows = renderer.settings.shadow_quality != ShadowQuality::Off;
+        let light_direction = glm::vec3(0.5, 1.0, 0.3).normalize();
+        let uniform = UniformBuffer {
+            view_projection: camera.projection_view_matrix(renderer.aspect_ratio()),
+            shadow: planar_shadow(&light_direction, 0.005),
+            light_direction: light_direction.push(0.0),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+        renderer.write_buffer(
+            &self.joints,
+            0,
+            bytemuck::cast_slice(&self.joint_matrices()),
+        );
+    }
+
+    /// Skins the mesh once for every pass that draws it this frame
+    pub fn compute(&self, encoder: &mut wgpu::CommandEncoder) {
+        if self.mode != SkinningMode::Compute {
+            return;
+        }
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Skinning Compute Pass"),
+        });
+        pass.set_bind_group(0, &self.compute_bind_group, &[]);
+        self.kernel.dispatch(&mut pass, self.vertex_count);
+    }
+
+    /// How many times each vertex is skinned per frame
+    pub fn skinning_runs(&self) -> u32 {
+        match self.mode {
+            SkinningMode::VertexShader => 2,
+            SkinningMode::Compute => 1,
+        }
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_bind_group(0, &self.render_bind_group, &[]);
+
+        renderpass.set_pipeline(&self.static_pipelines.main);
+        renderpass.set_vertex_buffer(0, self.ground.slice(..));
+        renderpass.draw(0..6, 0..1);
+
+        let (pipelines, vertices) = match self.mode {
+            SkinningMode::VertexShader => (&self.skinned_pipelines, &self.skin_vertices),
+            SkinningMode::Compute => (&self.static_pipelines, &self.skinned_vertices),
+        };
+        renderpass.set_vertex_buffer(0, vertices.slice(..));
+        renderpass.set_index_buffer(self.indices.slice(..), wgpu::IndexFormat::Uint32);
+        if self.shadows {
+            renderpass.set_pipeline(&pipelines.shadow);
+            renderpass.draw_indexed(0..self.index_count, 0, 0..1);
+        }
+        renderpass.set_pipeline(&pipelines.main);
+        renderpass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
+
+fn create_pipeline(
+    device: &Device,
+    layout: &wgpu::PipelineLayout,
+    module: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    (vertex_entry_point, fragment_entry_point): (&str, &str),
+    vertex_layout: &VertexLayout,
+) -> RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(vertex_entry_point),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module,
+            entry_point: vertex_entry_point,
+            buffers: &[vertex_layout.buffer_layout()],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module,
+            entry_point: fragment_entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+/// An open tube standing on the origin, each ring weighted between the two nearest joints
+fn create_tube() -> (Vec<SkinVertex>, Vec<u32>) {
+    let length = TUBE_HEIGHT / JOINT_COUNT as f32;
+    let mut vertices = Vec::new();
+    for ring in 0..=TUBE_RINGS {
+        let height = TUBE_HEIGHT * ring as f32 / TUBE_RINGS as f32;
+        // Blends from each joint's middle to the next one's
+        let along = (height / length - 0.5).max(0.0);
+        let first = (along.floor() as usize).min(JOINT_COUNT - 1);
+        let second = (first + 1).min(JOINT_COUNT - 1);
+        let blend = if first == second {
+            0.0
+        } else {
+            along - first as f32
+        };
+        // Narrows toward the tip
+        let radius = TUBE_RADIUS * (1.0 - 0.6 * height / TUBE_HEIGHT);
+        for segment in 0..TUBE_SEGMENTS {
+            let angle = TAU * segment as f32 / TUBE_SEGMENTS as f32;
+            let (sin, cos) = angle.sin_cos();
+            vertices.push(SkinVertex {
+                position: [cos * radius, height, sin * radius, 1.0],
+                normal: [cos, 0.0, sin, 0.0],
+                joints: [first as u32, second as u32, 0, 0],
+                weights: [1.0 - blend, blend, 0.0, 0.0],
+            });
+        }
+    }
+    let mut indices = Vec::new();
+    for ring in 0..TUBE_RINGS {
+        for segment in 0..TUBE_SEGMENTS {
+            let next = (segment + 1) % TUBE_SEGMENTS;
+            let current = ring * TUBE_SEGMENTS;
+            let above = current + TUBE_SEGMENTS;
+            indices.extend([
+                current + segment,
+                above + segment,
+                current + next,
+                current + next,
+                above + segment,
+                above + next,
+            ]);
+        }
+    }
+    (vertices, indices)
+}
+
+fn create_ground(size: f32) -> [StaticVertex; 6] {
+    let half = size * 0.5;
+    [
+        (-half, -half),
+        (-half, half),
+        (half, half),
+        (-half, -half),
+        (half, half),
+        (half, -half),
+    ]
+    .map(|(x, z)| StaticVertex {
+        position: [x, 0.0, z, 1.0],
+        normal: [0.0, 1.0, 0.0, 0.0],
+    })
+}
+
+/// Projects points along the direction toward a light onto the plane at the given height
+fn planar_shadow(light_direction: &glm::Vec3, height: f32) -> glm::Mat4 {
+    let slope = glm::vec2(light_direction.x, light_direction.z) / light_direction.y;
+    let mut matrix = glm::Mat4::identity();
+    matrix[(0, 1)] = -slope.x;
+    matrix[(1, 1)] = 0.0;
+    matrix[(2, 1)] = -slope.y;
+    matrix[(0, 3)] = slope.x * height;
+    matrix[(1, 3)] = height;
+    matrix[(2, 3)] = slope.y * height;
+    matrix
+}
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+    depth_texture: Option<Texture>,
+}
+
+impl App {
+    fn resize_depth(&mut self, renderer: &Renderer) {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
+        self.resize_depth(renderer);
+        self.camera.orientation.direction = glm::vec2(30_f32.to_radians(), 70_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-2.0, 0.0, -2.0),
+            glm::vec3(2.0, TUBE_HEIGHT, 2.0),
+        ));
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Skinning");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                egui::ComboBox::from_label("Mode")
+                    .selected_text(format!("{:?}", scene.mode))
+                    .show_ui(ui, |ui| {
+                        for mode in SkinningMode::ALL {
+                            ui.selectable_value(&mut scene.mode, mode, format!("{mode:?}"));
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut scene.amplitude, 0.0..=1.2).text("Bend"));
+                ui.add(egui::Slider::new(&mut scene.speed, 0.0..=5.0).text("Speed"));
+                ui.label(format!("Vertices: {}", scene.vertex_count));
+                ui.label(format!(
+                    "Skinned per frame: {} times",
+                    scene.skinning_runs()
+                ));
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let (Some(scene), Some(depth_texture)) = (self.scene.as_ref(), self.depth_texture.as_ref())
+        else {
+            return Ok(None);
+        };
+        // Recorded first, so the draws see this frame's pose
+        scene.compute(encoder);
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(Renderer::DEFAULT_CLEAR_COLOR),
+            Some(&depth_texture.view),
+        );
+        scene.render(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Skinning".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
@@ -0,0 +1,441 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::MouseOrbit, impl_vertex, run, Aabb, AppConfig, Application, Geometry, Input, MeshData,
+    Palette, PerDrawData, Renderer, SplitLayout, System, Texture, VertexDescription, Viewport,
+};
+use wgpu::{Device, RenderPass, RenderPipeline};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 4],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x3, 2 => Float32x4]);
+
+/// Everything that differs between viewports, set before each viewport's draw
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ViewData {
+    view_projection: glm::Mat4,
+    camera_position: glm::Vec4,
+    /// The debug view's index
+    params: glm::Vec4,
+}
+
+const SHADER_SOURCE: &str = "
+struct ViewData {
+    view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    params: vec4<f32>,
+};
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_position: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = draw.view_projection * vec4<f32>(vert.position, 1.0);
+    out.color = vert.color;
+    out.normal = vert.normal;
+    out.world_position = vert.position;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.normal);
+    let mode = u32(draw.params.x);
+    if mode == 1u {
+        return vec4<f32>(in.color.rgb, 1.0);
+    }
+    if mode == 2u {
+        return vec4<f32>(normal * 0.5 + 0.5, 1.0);
+    }
+    if mode == 3u {
+        let depth = distance(in.world_position, draw.camera_position.xyz) / 30.0;
+        return vec4<f32>(vec3<f32>(1.0 - clamp(depth, 0.0, 1.0)), 1.0);
+    }
+    let light = normalize(vec3<f32>(0.4, 1.0, 0.3));
+    let lighting = 0.25 + 0.75 * max(dot(normal, light), 0.0);
+    return vec4<f32>(in.color.rgb * lighting, 1.0);
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+enum DebugView {
+    #[default]
+    Shaded,
+    /// Unlit vertex colors
+    Albedo,
+    Normals,
+    /// Distance from the camera, nearer is brighter
+    Depth,
+}
+
+impl DebugView {
+    const ALL: [Self; 4] = [Self::Shaded, Self::Albedo, Self::Normals, Self::Depth];
+}
+
+/// What one viewport of the layout shows
+#[derive(Debug, Copy, Clone)]
+struct ViewportSettings {
+    /// Index into the app's cameras, several viewports may share one
+    camera: usize,
+    view: DebugView,
+}
+
+/// A field of shapes on a floor, drawn once per viewport with that viewport's camera and view
+struct Scene {
+    geometry: Geometry,
+    views: PerDrawData<ViewData>,
+    pipeline: RenderPipeline,
+}
+
+impl Scene {
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = &renderer.device;
+        let views = PerDrawData::new(renderer, wgpu::ShaderStages::VERTEX_FRAGMENT);
+        Self {
+            geometry: Self::create_geometry(device),
+            pipeline: Self::create_pipeline(device, renderer.config.format, &views),
+            views,
+        }
+    }
+
+    fn create_geometry(device: &Device) -> Geometry {
+        let palette = Palette::default();
+        let mut parts = vec![(
+            MeshData::plane(16.0, 0),
+            glm::vec3(0.0, 0.0, 0.0),
+            glm::vec3(0.5, 0.5, 0.5),
+        )];
+        for index in 0..25 {
+            let (column, row) = ((index % 5) as f32 - 2.0, (index / 5) as f32 - 2.0);
+            let mesh = match index % 3 {
+                0 => MeshData::cube(0.8),
+                1 => MeshData::icosphere(0.45, 2),
+                _ => MeshData::cylinder(0.35, 0.9, 16),
+            };
+            parts.push((
+                mesh,
+                glm::vec3(column * 2.0, 0.45, row * 2.0),
+                palette.linear_color(index),
+            ));
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (mesh, offset, color) in parts {
+            let first = vertices.len() as u32;
+            vertices.extend(mesh.vertices.iter().map(|vertex| Vertex {
+                position: (glm::Vec3::from(vertex.position) + offset).into(),
+                normal: vertex.normal,
+                color: color.push(1.0).into(),
+            }));
+            indices.extend(mesh.indices.iter().map(|index| first + index));
+        }
+        Geometry::new(device, &vertices, &indices)
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        views: &PerDrawData<ViewData>,
+    ) -> RenderPipeline {
+        let source = format!("{}{SHADER_SOURCE}", views.declaration("ViewData", 0));
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Split Screen Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let bind_group_layouts = views.bind_group_layout().into_iter().collect::<Vec<_>>();
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Split Screen Pipeline Layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &views.push_constant_ranges(),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Split Screen Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vertex_main",
+                buffers: &[Vertex::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+
+    /// One draw per viewport, each restricted to its region of the target
+    pub fn render<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        viewports: &[Viewport],
+    ) {
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.geometry.slices();
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        for (index, viewport) in viewports.iter().enumerate() {
+            renderpass.push_debug_group("Viewport");
+            Renderer::set_viewport(renderpass, viewport);
+            self.views.set(renderpass, 0, index);
+            renderpass.draw_indexed(0..self.geometry.index_count, 0, 0..1);
+            renderpass.pop_debug_group();
+        }
+    }
+}
+
+struct App {
+    scene: Option<Scene>,
+    depth_texture: Option<Texture>,
+    cameras: Vec<MouseOrbit>,
+    layout: SplitLayout,
+    /// One per viewport of the largest layout, the first ones are used by smaller layouts
+    settings: Vec<ViewportSettings>,
+    viewports: Vec<Viewport>,
+    /// The viewport whose camera the mouse controls, kept while a button is held
+    focused: usize,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let views = [
+            DebugView::Shaded,
+            DebugView::Albedo,
+            DebugView::Normals,
+            DebugView::Depth,
+        ];
+        Self {
+            scene: None,
+            depth_texture: None,
+            cameras: Vec::new(),
+            layout: SplitLayout::default(),
+            settings: views
+                .into_iter()
+                .enumerate()
+                .map(|(camera, view)| ViewportSettings { camera, view })
+                .collect(),
+            viewports: Vec::new(),
+            focused: 0,
+        }
+    }
+}
+
+impl App {
+    fn create_cameras() -> Vec<MouseOrbit> {
+        let bounds = Aabb::new(glm::vec3(-5.0, 0.0, -5.0), glm::vec3(5.0, 1.0, 5.0));
+        // Angles around the vertical axis and down from straight above, in degrees
+        [(30.0, 60.0), (-60.0, 45.0), (0.0, 15.0), (150.0, 80.0)]
+            .into_iter()
+            .map(|(around, down): (f32, f32)| {
+                let mut camera = MouseOrbit::default();
+                camera.orientation.direction = glm::vec2(around.to_radians(), down.to_radians());
+                camera.frame_bounds(&bounds);
+                camera
+            })
+            .collect()
+    }
+
+    fn resize_depth(&mut self, renderer: &Renderer) {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(renderer));
+        self.cameras = Self::create_cameras();
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn optional_features(&self) -> wgpu::Features {
+        wgpu::Features::PUSH_CONSTANTS
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.viewports = renderer.split_viewports(self.layout);
+
+        let dragging = input.mouse.is_left_clicked || input.mouse.is_right_clicked;
+        if !dragging {
+            if let Some(index) = self
+                .viewports
+                .iter()
+                .position(|viewport| viewport.contains(input.mouse.position))
+            {
+                self.focused = index;
+            }
+        }
+        if let Some(settings) = self.settings.get(self.focused) {
+            self.cameras[settings.camera].update(input, system)?;
+        }
+
+        let views = self
+            .viewports
+            .iter()
+            .zip(self.settings.iter())
+            .map(|(viewport, settings)| {
+                let camera = &self.cameras[settings.camera];
+                ViewData {
+                    view_projection: camera.projection_view_matrix(viewport.aspect_ratio()),
+                    camera_position: camera.transform.translation.push(1.0),
+                    params: glm::vec4(settings.view as u32 as f32, 0.0, 0.0, 0.0),
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(scene) = self.scene.as_mut() {
+            scene.views.write(renderer, &views);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Split Screen");
+                egui::ComboBox::from_label("Layout")
+                    .selected_text(format!("{:?}", self.layout))
+                    .show_ui(ui, |ui| {
+                        for layout in SplitLayout::ALL {
+                            ui.selectable_value(&mut self.layout, layout, format!("{layout:?}"));
+                        }
+                    });
+                let camera_count = self.cameras.len();
+                let viewport_count = self.layout.viewport_count();
+                for (index, settings) in self.settings.iter_mut().take(viewport_count).enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Viewport {}", index + 1));
+                        egui::ComboBox::from_id_source(("camera", index))
+                            .selected_text(format!("Camera {}", settings.camera + 1))
+                            .show_ui(ui, |ui| {
+                                for camera in 0..camera_count {
+                                    ui.selectable_value(
+                                        &mut settings.camera,
+                                        camera,
+                                        format!("Camera {}", camera + 1),
+                                    );
+                                }
+                            });
+                        egui::ComboBox::from_id_source(("view", index))
+                            .selected_text(format!("{:?}", settings.view))
+                            .show_ui(ui, |ui| {
+                                for view in DebugView::ALL {
+                                    ui.selectable_value(
+                                        &mut settings.view,
+                                        view,
+                                        format!("{view:?}"),
+                                    );
+                                }
+                            });
+                    });
+                }
+            });
+
+        // Labels and borders, in points rather than the viewports' physical pixels
+        let scale = context.pixels_per_point();
+        let painter = context.layer_painter(egui::LayerId::background());
+        for (index, (viewport, settings)) in self.viewports.iter().zip(&self.settings).enumerate() {
+            let rect = egui::Rect::from_min_size(
+                egui::pos2(viewport.x as f32 / scale, viewport.y as f32 / scale),
+                egui::vec2(viewport.width as f32, viewport.height as f32) / scale,
+            );
+            let stroke_color = match index == self.focused {
+                true => egui::Color32::from_rgb(255, 160, 40),
+                false => egui::Color32::from_gray(20),
+            };
+            painter.rect_stroke(rect.shrink(1.0), 0.0, egui::Stroke::new(2.0, stroke_color));
+            painter.text(
+                rect.right_bottom() - egui::vec2(8.0, 8.0),
+                egui::Align2::RIGHT_BOTTOM,
+                format!("Camera {} - {:?}", settings.camera + 1, settings.view),
+                egui::FontId::proportional(14.0),
+                egui::Color32::WHITE,
+            );
+        }
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let (Some(scene), Some(depth_texture)) = (self.scene.as_ref(), self.depth_texture.as_ref())
+        else {
+            return Ok(None);
+        };
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(Renderer::DEFAULT_CLEAR_COLOR),
+            Some(&depth_texture.view),
+        );
+        scene.render(&mut render_pass, &self.viewports);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Split Screen".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
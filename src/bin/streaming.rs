@@ -0,0 +1,443 @@
+use anyhow::Result;
+use image::RgbaImage;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::PerspectiveCamera, impl_vertex, run, screen_coverage, AppConfig, Application,
+    BindingBuilder, BindingLayout, Input, Palette, PerDrawData, Renderer, StreamedTexture, System,
+    Texture, TextureStreamer, VertexDescription,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+const PANEL_ROWS: usize = 6;
+const PANEL_SIZE: f32 = 3.0;
+/// How far the panels turn toward the road, in degrees
+const PANEL_ANGLE: f32 = 40.0;
+const ROW_SPACING: f32 = 6.0;
+const TEXTURE_SIZE: u32 = 1024;
+const EYE_HEIGHT: f32 = 1.7;
+/// Where the camera starts, in front of the first row
+const START_Z: f32 = 8.0;
+const ROAD_LENGTH: f32 = ROW_SPACING * PANEL_ROWS as f32;
+/// The per draw data follows the texture bind group, which exists on every path
+const PER_DRAW_GROUP: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    uv: [f32; 2],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x2]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DrawData {
+    view_projection: glm::Mat4,
+    /// Multiplies a grayscale copy of the texture, mixed in by its alpha to show the resident level
+    tint: glm::Vec4,
+}
+
+const SHADER_SOURCE: &str = "
+struct DrawData {
+    view_projection: mat4x4<f32>,
+    tint: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var panel_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var panel_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = draw.view_projection * vec4<f32>(vert.position, 1.0);
+    out.uv = vert.uv;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(panel_texture, panel_sampler, in.uv).rgb;
+    let tinted = dot(color, vec3<f32>(0.299, 0.587, 0.114)) * draw.tint.rgb;
+    return vec4<f32>(mix(color, tinted, draw.tint.w), 1.0);
+}
+";
+
+/// A texture's bind group, rebuilt whenever the streamer reallocates the texture
+struct PanelBinding {
+    bind_group: BindGroup,
+    generation: u64,
+}
+
+impl PanelBinding {
+    fn new(
+        device: &Device,
+        layout: &BindingLayout,
+        sampler: &wgpu::Sampler,
+        texture: &StreamedTexture,
+    ) -> Self {
+        Self {
+            bind_group: layout.bind_group(
+                device,
+                &[
+                    wgpu::BindingResource::TextureView(&texture.view),
+                    wgpu::BindingResource::Sampler(sampler),
+                ],
+            ),
+            generation: texture.generation,
+        }
+    }
+}
+
+/// Rows of large textured panels along a road. Only small mip levels are uploaded at first,
+/// and the streamer brings in detail for the panels the camera approaches within a memory budget.
+struct Scene {
+    streamer: TextureStreamer,
+    bindings: Vec<PanelBinding>,
+    texture_layout: BindingLayout,
+    sampler: wgpu::Sampler,
+    vertices: Buffer,
+    per_draw: PerDrawData<DrawData>,
+    pipeline: RenderPipeline,
+    camera: PerspectiveCamera,
+    /// Distance traveled, the camera goes down the road and back
+    travel: f32,
+    speed: f32,
+    fly: bool,
+    visualize: bool,
+}
+
+impl Scene {
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = &renderer.device;
+        let mut streamer = TextureStreamer::default();
+        for index in 0..panel_count() {
+            streamer.add(
+                device,
+                &renderer.queue,
+                &format!("Panel {index}"),
+                create_panel_image(index),
+            );
+        }
+        let texture_layout = BindingBuilder::new()
+            .label("Panel Texture")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Panel Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let bindings = streamer
+            .textures
+            .iter()
+            .map(|texture| PanelBinding::new(device, &texture_layout, &sampler, texture))
+            .collect();
+        let per_draw = PerDrawData::new(renderer, wgpu::ShaderStages::VERTEX_FRAGMENT);
+        Self {
+            bindings,
+            vertices: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Panel Vertices"),
+                contents: bytemuck::cast_slice(&create_panel_vertices()),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            pipeline: create_pipeline(device, renderer.config.format, &texture_layout, &per_draw),
+            streamer,
+            texture_layout,
+            sampler,
+            per_draw,
+            camera: PerspectiveCamera::default(),
+            travel: 0.0,
+            speed: 4.0,
+            fly: true,
+            visualize: false,
+        }
+    }
+
+    fn eye(&self) -> glm::Vec3 {
+        // Back and forth along the road
+        let progress = 1.0 - ((self.travel / ROAD_LENGTH) % 2.0 - 1.0).abs();
+        glm::vec3(0.0, EYE_HEIGHT, START_Z - progress * ROAD_LENGTH)
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        if self.fly {
+            self.travel += self.speed * delta_time;
+        }
+        let eye = self.eye();
+
+        // Asks for each visible panel's level from how many pixels tall it is on screen
+        let height = renderer.config.height as f32;
+        for (index, center) in panel_centers().enumerate() {
+            if center.z > eye.z {
+                continue;
+            }
+            let coverage = screen_coverage(&center, PANEL_SIZE * 0.5, &eye, self.camera.y_fov_rad);
+            self.streamer.request(index, coverage * height);
+        }
+        self.streamer.update(&renderer.device, &renderer.queue);
+        self.update_bindings(&renderer.device);
+
+        let view = glm::look_at(&eye, &(eye - glm::Vec3::z()), &glm::Vec3::y());
+        let view_projection = self.camera.projection_matrix(renderer.aspect_ratio()) * view;
+        let visualize = if self.visualize { 1.0 } else { 0.0 };
+        let values = self
+            .streamer
+            .textures
+            .iter()
+            .map(|texture| {
+                // Green at full detail through red at the floor level
+                let coarseness =
+                    texture.resident_level as f32 / texture.mips.floor_level().max(1) as f32;
+                DrawData {
+                    view_projection,
+                    tint: glm::vec4(coarseness, 1.0 - coarseness, 0.0, visualize),
+                }
+            })
+            .collect::<Vec<_>>();
+        self.per_draw.write(renderer, &values);
+    }
+
+    /// Rebuilds the bind groups of textures the streamer reallocated
+    fn update_bindings(&mut self, device: &Device) {
+        for (binding, texture) in self.bindings.iter_mut().zip(self.streamer.textures.iter()) {
+            if binding.generation != texture.generation {
+                *binding = PanelBinding::new(device, &self.texture_layout, &self.sampler, texture);
+            }
+        }
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_vertex_buffer(0, self.vertices.slice(..));
+        for (index, binding) in self.bindings.iter().enumerate() {
+            renderpass.set_bind_group(0, &binding.bind_group, &[]);
+            self.per_draw.set(renderpass, PER_DRAW_GROUP, index);
+            let first = index as u32 * 6;
+            renderpass.draw(first..first + 6, 0..1);
+        }
+    }
+}
+
+fn panel_count() -> usize {
+    PANEL_ROWS * 2
+}
+
+/// Which side of the road each panel stands on, -1 for the left and 1 for the right
+fn panel_side(index: usize) -> f32 {
+    if index % 2 == 0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+/// Pairs of panels on either side of the road
+fn panel_centers() -> impl Iterator<Item = glm::Vec3> {
+    (0..panel_count()).map(|index| {
+        let row = (index / 2) as f32;
+        glm::vec3(panel_side(index) * 3.0, EYE_HEIGHT, -row * ROW_SPACING)
+    })
+}
+
+/// Each panel is turned toward the middle of the road
+fn create_panel_vertices() -> Vec<Vertex> {
+    let half = PANEL_SIZE * 0.5;
+    let angle = PANEL_ANGLE.to_radians();
+    panel_centers()
+        .enumerate()
+        .flat_map(|(index, center)| {
+            let right = glm::vec3(angle.cos(), 0.0, panel_side(index) * angle.sin());
+            [
+                (-1.0, -1.0),
+                (1.0, -1.0),
+                (1.0, 1.0),
+                (-1.0, -1.0),
+                (1.0, 1.0),
+                (-1.0, 1.0),
+            ]
+            .map(|(x, y)| Vertex {
+                position: (center + right * (x * half) + glm::Vec3::y() * (y * half)).into(),
+                uv: [(x + 1.0) * 0.5, (1.0 - y) * 0.5],
+            })
+        })
+        .collect()
+}
+
+/// A checkerboard in the panel's palette color, crossed by fine lines that only the most
+/// detailed levels resolve, inside a white border
+fn create_panel_image(index: usize) -> RgbaImage {
+    let color = Palette::default().color(index);
+    RgbaImage::from_fn(TEXTURE_SIZE, TEXTURE_SIZE, |x, y| {
+        let border = 16;
+        if x < border || y < border || x >= TEXTURE_SIZE - border || y >= TEXTURE_SIZE - border {
+            return image::Rgba([255; 4]);
+        }
+        let checker = ((x / 128) + (y / 128)) % 2 == 0;
+        let line = x % 16 == 0 || y % 16 == 0;
+        let shade = match (checker, line) {
+            (_, true) => 0.25,
+            (true, false) => 1.0,
+            (false, false) => 0.6,
+        };
+        let [r, g, b] = (color * shade * 255.0).into();
+        image::Rgba([r as u8, g as u8, b as u8, 255])
+    })
+}
+
+fn create_pipeline(
+    device: &Device,
+    format: wgpu::TextureFormat,
+    texture_layout: &BindingLayout,
+    per_draw: &PerDrawData<DrawData>,
+) -> RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Streaming Shader"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!(
+            "{}{SHADER_SOURCE}",
+            per_draw.declaration("DrawData", PER_DRAW_GROUP)
+        ))),
+    });
+    let mut bind_group_layouts = vec![&texture_layout.layout];
+    bind_group_layouts.extend(per_draw.bind_group_layout());
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Streaming Pipeline Layout"),
+        bind_group_layouts: &bind_group_layouts,
+        push_constant_ranges: &per_draw.push_constant_ranges(),
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Streaming Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: "vertex_main",
+            buffers: &[Vertex::layout().buffer_layout()],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: "fragment_main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
+}
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    depth_texture: Option<Texture>,
+}
+
+impl App {
+    fn resize_depth(&mut self, renderer: &Renderer) {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(renderer));
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.resize_depth(renderer);
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Texture Streaming");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                ui.checkbox(&mut scene.fly, "Fly");
+                ui.add(egui::Slider::new(&mut scene.speed, 0.0..=20.0).text("Speed"));
+                ui.checkbox(&mut scene.visualize, "Tint by resident level");
+                ui.separator();
+                scene.streamer.show(ui);
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let (Some(scene), Some(depth_texture)) = (self.scene.as_ref(), self.depth_texture.as_ref())
+        else {
+            return Ok(None);
+        };
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(Renderer::DEFAULT_CLEAR_COLOR),
+            Some(&depth_texture.view),
+        );
+        scene.render(&mut render_pass);
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Texture Streaming".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
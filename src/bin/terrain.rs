@@ -0,0 +1,761 @@
+use anyhow::{Context, Result};
+use nalgebra_glm as glm;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+use support::{
+    camera::FirstPersonCamera, impl_vertex, run, srgb_to_linear, Aabb, AppConfig, Application,
+    BindingBuilder, BindingLayout, Bvh, Frustum, Geometry, Input, NoiseKind, NoiseSettings,
+    NoiseTexture, RenderStats, Renderer, System, Texture, VertexDescription,
+};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, Device, RenderPass, RenderPipeline,
+    TextureFormat,
+};
+
+/// Loads a grayscale heightmap image instead of generating one, e.g. `--heightmap height.png`
+const HEIGHTMAP_FLAG: &str = "--heightmap";
+
+/// Number of quads along each side of a chunk
+const CHUNK_SIZE: usize = 32;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x3]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    view_projection: glm::Mat4,
+    sun_direction: glm::Vec4,
+    /// Max height, texture scale, snow line and rock slope
+    splat: glm::Vec4,
+    /// Linear grass, rock and snow colors
+    layer_colors: [glm::Vec4; 3],
+}
+
+/// Texels along each side of a splat layer's noise texture
+const LAYER_NOISE_SIZE: u32 = 256;
+
+/// One of the splat layers, its color varied by a tiling noise texture
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SplatLayer {
+    name: &'static str,
+    /// sRGB, as picked in the GUI
+    color: [f32; 3],
+    noise: NoiseSettings,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct SplatSettings {
+    layers: [SplatLayer; 3],
+    texture_scale: f32,
+    /// Fraction of the max height above which snow settles
+    snow_line: f32,
+    /// Slopes steeper than this show bare rock, where zero is flat and one is vertical
+    rock_slope: f32,
+}
+
+impl Default for SplatSettings {
+    fn default() -> Self {
+        Self {
+            layers: [
+                SplatLayer {
+                    name: "Grass",
+                    color: [0.22, 0.42, 0.12],
+                    noise: NoiseSettings {
+                        period: 16,
+                        ..Default::default()
+                    },
+                },
+                SplatLayer {
+                    name: "Rock",
+                    color: [0.42, 0.38, 0.34],
+                    noise: NoiseSettings {
+                        kind: NoiseKind::Worley,
+                        period: 8,
+                        octaves: 3,
+                        seed: 1,
+                        ..Default::default()
+                    },
+                },
+                SplatLayer {
+                    name: "Snow",
+                    color: [0.92, 0.94, 0.98],
+                    noise: NoiseSettings {
+                        period: 4,
+                        octaves: 3,
+                        seed: 2,
+                        ..Default::default()
+                    },
+                },
+            ],
+            texture_scale: 0.125,
+            snow_line: 0.6,
+            rock_slope: 0.25,
+        }
+    }
+}
+
+/// Heights sampled on a grid with one world unit between samples, centered on the origin
+struct Heightmap {
+    width: usize,
+    depth: usize,
+    heights: Vec<f32>,
+    max_height: f32,
+}
+
+impl Heightmap {
+    const DEFAULT_SIZE: usize = 257;
+    const DEFAULT_MAX_HEIGHT: f32 = 48.0;
+
+    fn from_image(image: &image::DynamicImage, max_height: f32) -> Self {
+        let image = image.to_luma16();
+        let heights = image
+            .pixels()
+            .map(|pixel| pixel.0[0] as f32 / u16::MAX as f32 * max_height)
+            .collect();
+        Self {
+            width: image.width() as usize,
+            depth: image.height() as usize,
+            heights,
+            max_height,
+        }
+    }
+
+    fn load(path: &Path, max_height: f32) -> Result<Self> {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load heightmap from {}", path.display()))?;
+        let heightmap = Self::from_image(&image, max_height);
+        anyhow::ensure!(
+            heightmap.width >= 2 && heightmap.depth >= 2,
+            "Heightmap must be at least 2x2 pixels"
+        );
+        Ok(heightmap)
+    }
+
+    /// Rolling hills made from fractal value noise, with sharpened peaks
+    fn generate(size: usize, max_height: f32) -> Self {
+        let heights = (0..size * size)
+            .map(|index| {
+                let (x, z) = ((index % size) as f32, (index / size) as f32);
+                let height = fbm(x / 64.0, z / 64.0, 6, 1 << 16);
+                height.powf(2.0) * max_height
+            })
+            .collect();
+        Self {
+            width: size,
+            depth: size,
+            heights,
+            max_height,
+        }
+    }
+
+    fn origin(&self) -> glm::Vec2 {
+        glm::vec2(self.width as f32 - 1.0, self.depth as f32 - 1.0) * -0.5
+    }
+
+    fn sample(&self, x: usize, z: usize) -> f32 {
+        self.heights[z.min(self.depth - 1) * self.width + x.min(self.width - 1)]
+    }
+
+    fn position(&self, x: usize, z: usize) -> glm::Vec3 {
+        let origin = self.origin();
+        glm::vec3(origin.x + x as f32, self.sample(x, z), origin.y + z as f32)
+    }
+
+    /// Central differences over the whole map so normals match across chunk seams
+    fn normal(&self, x: usize, z: usize) -> glm::Vec3 {
+        let left = self.sample(x.saturating_sub(1), z);
+        let right = self.sample(x + 1, z);
+        let back = self.sample(x, z.saturating_sub(1));
+        let front = self.sample(x, z + 1);
+        glm::vec3(left - right, 2.0, back - front).normalize()
+    }
+
+    /// Bilinearly interpolated height at a world position
+    fn height_at(&self, position: &glm::Vec3) -> f32 {
+        let local = glm::vec2(position.x, position.z) - self.origin();
+        let x = local.x.clamp(0.0, (self.width - 1) as f32);
+        let z = local.y.clamp(0.0, (self.depth - 1) as f32);
+        let (x0, z0) = (x.floor() as usize, z.floor() as usize);
+        let (tx, tz) = (x.fract(), z.fract());
+        let top = glm::lerp_scalar(self.sample(x0, z0), self.sample(x0 + 1, z0), tx);
+        let bottom = glm::lerp_scalar(self.sample(x0, z0 + 1), self.sample(x0 + 1, z0 + 1), tx);
+        glm::lerp_scalar(top, bottom, tz)
+    }
+
+    /// Splits the grid into chunks of `CHUNK_SIZE` quads, duplicating the shared edge vertices
+    fn chunks(&self) -> Vec<(Vec<Vertex>, Vec<u16>, Aabb)> {
+        let mut chunks = Vec::new();
+        for chunk_z in (0..self.depth - 1).step_by(CHUNK_SIZE) {
+            for chunk_x in (0..self.width - 1).step_by(CHUNK_SIZE) {
+                let columns = CHUNK_SIZE.min(self.width - 1 - chunk_x) + 1;
+                let rows = CHUNK_SIZE.min(self.depth - 1 - chunk_z) + 1;
+                let mut vertices = Vec::with_capacity(columns * rows);
+                for z in chunk_z..chunk_z + rows {
+                    for x in chunk_x..chunk_x + columns {
+                        vertices.push(Vertex {
+                            position: self.position(x, z).into(),
+                            normal: self.normal(x, z).into(),
+                        });
+                    }
+                }
+                let mut indices = Vec::with_capacity((columns - 1) * (rows - 1) * 6);
+                for z in 0..rows - 1 {
+                    for x in 0..columns - 1 {
+                        let top_left = (z * columns + x) as u16;
+                        let top_right = top_left + 1;
+                        let bottom_left = top_left + columns as u16;
+                        let bottom_right = bottom_left + 1;
+                        indices.extend_from_slice(&[
+                            top_left,
+                            bottom_left,
+                            top_right,
+                            top_right,
+                            bottom_left,
+                            bottom_right,
+                        ]);
+                    }
+                }
+                let bounds = Aabb::from_points(
+                    vertices
+                        .iter()
+                        .map(|vertex| glm::Vec3::from(vertex.position)),
+                );
+                chunks.push((vertices, indices, bounds));
+            }
+        }
+        chunks
+    }
+}
+
+fn hash(x: i32, z: i32) -> f32 {
+    let mut hash = (x as u32).wrapping_mul(0x27d4_eb2d) ^ (z as u32).wrapping_mul(0x1656_67b1);
+    hash = (hash ^ (hash >> 15)).wrapping_mul(0x2c1b_3c6d);
+    hash = (hash ^ (hash >> 12)).wrapping_mul(0x297a_2d39);
+    hash ^= hash >> 15;
+    hash as f32 / u32::MAX as f32
+}
+
+/// Smoothly interpolated lattice noise in the zero to one range, repeating every `period` units
+fn value_noise(x: f32, z: f32, period: i32) -> f32 {
+    let (x0, z0) = (x.floor(), z.floor());
+    let fade = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, tz) = (fade(x - x0), fade(z - z0));
+    let corner = |dx: i32, dz: i32| {
+        hash(
+            (x0 as i32 + dx).rem_euclid(period),
+            (z0 as i32 + dz).rem_euclid(period),
+        )
+    };
+    let top = glm::lerp_scalar(corner(0, 0), corner(1, 0), tx);
+    let bottom = glm::lerp_scalar(corner(0, 1), corner(1, 1), tx);
+    glm::lerp_scalar(top, bottom, tz)
+}
+
+fn fbm(x: f32, z: f32, octaves: u32, period: i32) -> f32 {
+    let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 0.5, 1, 0.0);
+    for _ in 0..octaves {
+        sum += amplitude
+            * value_noise(
+                x * frequency as f32,
+                z * frequency as f32,
+                period * frequency,
+            );
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2;
+    }
+    sum / total
+}
+
+struct Scene {
+    heightmap: Heightmap,
+    chunks: Vec<Geometry>,
+    /// Bounds of each chunk, indexed the same as the chunks
+    bounds: Vec<Aabb>,
+    bvh: Bvh,
+    visible: Vec<usize>,
+    splat: SplatSettings,
+    /// Generated on the GPU, indexed the same as the splat layers
+    layer_noise: [NoiseTexture; 3],
+    uniform_buffer: Buffer,
+    bind_group: BindGroup,
+    pipeline: RenderPipeline,
+    /// Absent when the device can't draw lines in place of triangles
+    wireframe_pipeline: Option<RenderPipeline>,
+    wireframe: bool,
+}
+
+impl Scene {
+    pub fn new(renderer: &mut Renderer, heightmap: Heightmap) -> Self {
+        let device = &renderer.device;
+        let surface_format = renderer.config.format;
+        let (chunks, bounds): (Vec<_>, Vec<_>) = heightmap
+            .chunks()
+            .into_iter()
+            .map(|(vertices, indices, bounds)| (Geometry::new(device, &vertices, &indices), bounds))
+            .unzip();
+        let bvh = Bvh::new(&bounds);
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Terrain Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let splat = SplatSettings::default();
+        let layer_noise = splat
+            .layers
+            .map(|layer| NoiseTexture::new(device, LAYER_NOISE_SIZE, layer.name));
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let bind_group_layout = Self::create_bind_group_layout(device);
+        let bind_group = bind_group_layout.bind_group(
+            device,
+            &[
+                uniform_buffer.as_entire_binding(),
+                wgpu::BindingResource::TextureView(&layer_noise[0].texture.view),
+                wgpu::BindingResource::TextureView(&layer_noise[1].texture.view),
+                wgpu::BindingResource::TextureView(&layer_noise[2].texture.view),
+                wgpu::BindingResource::Sampler(&sampler),
+            ],
+        );
+        let pipeline = Self::create_pipeline(
+            device,
+            surface_format,
+            &bind_group_layout.layout,
+            wgpu::PolygonMode::Fill,
+        );
+        let wireframe_pipeline = device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+            .then(|| {
+                Self::create_pipeline(
+                    device,
+                    surface_format,
+                    &bind_group_layout.layout,
+                    wgpu::PolygonMode::Line,
+                )
+            });
+
+        for (noise, layer) in layer_noise.iter().zip(splat.layers.iter()) {
+            noise.generate(renderer, &layer.noise);
+        }
+
+        Self {
+            visible: (0..chunks.len()).collect(),
+            heightmap,
+            chunks,
+            bounds,
+            bvh,
+            splat,
+            layer_noise,
+            uniform_buffer,
+            bind_group,
+            pipeline,
+            wireframe_pipeline,
+            wireframe: false,
+        }
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, view_projection: glm::Mat4) {
+        let frustum = Frustum::from_matrix(&view_projection);
+        self.visible = self.bvh.query_frustum(&frustum, &self.bounds);
+
+        let uniform = UniformBuffer {
+            view_projection,
+            sun_direction: glm::vec4(0.4, 0.8, 0.3, 0.0).normalize(),
+            splat: glm::vec4(
+                self.heightmap.max_height,
+                self.splat.texture_scale,
+                self.splat.snow_line,
+                self.splat.rock_slope,
+            ),
+            layer_colors: self
+                .splat
+                .layers
+                .map(|layer| glm::Vec3::from(layer.color).map(srgb_to_linear).push(1.0)),
+        };
+        renderer.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        stats: &mut RenderStats,
+    ) {
+        match self.wireframe_pipeline.as_ref() {
+            Some(wireframe_pipeline) if self.wireframe => {
+                renderpass.set_pipeline(wireframe_pipeline)
+            }
+            _ => renderpass.set_pipeline(&self.pipeline),
+        }
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        for chunk in self.visible.iter().map(|index| &self.chunks[*index]) {
+            let (vertex_buffer_slice, index_buffer_slice, index_format) = chunk.slices();
+            renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+            renderpass.set_index_buffer(index_buffer_slice, index_format);
+            renderpass.draw_indexed(0..chunk.index_count, 0, 0..1);
+            stats.record_draw(chunk.index_count, 1);
+        }
+        for chunk in self.chunks.iter() {
+            stats.record_geometry(chunk);
+        }
+    }
+
+    fn create_bind_group_layout(device: &Device) -> BindingLayout {
+        BindingBuilder::new()
+            .label("Terrain")
+            .uniform(0, wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT)
+            .texture(1, wgpu::ShaderStages::FRAGMENT)
+            .texture(2, wgpu::ShaderStages::FRAGMENT)
+            .texture(3, wgpu::ShaderStages::FRAGMENT)
+            .sampler(4, wgpu::ShaderStages::FRAGMENT)
+            .build(device)
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        bind_group_layout: &BindGroupLayout,
+        polygon_mode: wgpu::PolygonMode,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[Vertex::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode,
+                conservative: false,
+                unclipped_depth: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    sun_direction: vec4<f32>,
+    splat: vec4<f32>,
+    layer_colors: array<vec4<f32>, 3>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(0) @binding(1)
+var grass_texture: texture_2d<f32>;
+
+@group(0) @binding(2)
+var rock_texture: texture_2d<f32>;
+
+@group(0) @binding(3)
+var snow_texture: texture_2d<f32>;
+
+@group(0) @binding(4)
+var layer_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_position = vert.position;
+    out.normal = vert.normal;
+    out.position = ubo.view_projection * vec4(vert.position, 1.0);
+    return out;
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.normal);
+    let uv = in.world_position.xz * ubo.splat.y;
+    let grass = ubo.layer_colors[0].rgb * (0.7 + 0.6 * textureSample(grass_texture, layer_sampler, uv).r);
+    let rock = ubo.layer_colors[1].rgb * (0.7 + 0.6 * textureSample(rock_texture, layer_sampler, uv).r);
+    let snow = ubo.layer_colors[2].rgb * (0.7 + 0.6 * textureSample(snow_texture, layer_sampler, uv).r);
+
+    let height = in.world_position.y / ubo.splat.x;
+    let slope = 1.0 - normal.y;
+    let rock_weight = smoothstep(ubo.splat.w - 0.05, ubo.splat.w + 0.05, slope);
+    let snow_weight = smoothstep(ubo.splat.z - 0.05, ubo.splat.z + 0.05, height) * (1.0 - rock_weight);
+    var albedo = mix(grass, rock, rock_weight);
+    albedo = mix(albedo, snow, snow_weight);
+
+    let diffuse = max(dot(normal, ubo.sun_direction.xyz), 0.0);
+    return vec4<f32>(albedo * (0.2 + 0.8 * diffuse), 1.0);
+}
+";
+
+struct App {
+    scene: Option<Scene>,
+    camera: FirstPersonCamera,
+    depth_texture: Option<Texture>,
+    stats: RenderStats,
+    /// Keeps the camera at eye height above the ground
+    walk: bool,
+    eye_height: f32,
+    heightmap_path: Option<PathBuf>,
+    pending_heightmap: Option<PathBuf>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        let mut args = std::env::args();
+        let heightmap_path = args
+            .position(|argument| argument == HEIGHTMAP_FLAG)
+            .and_then(|_| args.next())
+            .map(PathBuf::from);
+        Self {
+            scene: None,
+            camera: FirstPersonCamera {
+                speed: 20.0,
+                ..Default::default()
+            },
+            depth_texture: None,
+            stats: RenderStats::default(),
+            walk: false,
+            eye_height: 2.0,
+            heightmap_path,
+            pending_heightmap: None,
+        }
+    }
+}
+
+impl App {
+    fn load_terrain(&mut self, renderer: &mut Renderer, path: Option<&Path>) -> Result<()> {
+        let heightmap = match path {
+            Some(path) => Heightmap::load(path, Heightmap::DEFAULT_MAX_HEIGHT)?,
+            None => Heightmap::generate(Heightmap::DEFAULT_SIZE, Heightmap::DEFAULT_MAX_HEIGHT),
+        };
+        let extent = heightmap.width.max(heightmap.depth) as f32;
+        self.camera.transform.translation =
+            glm::vec3(0.0, heightmap.max_height * 1.2, extent * 0.6);
+        self.camera
+            .look_at(&glm::vec3(0.0, heightmap.max_height * 0.3, 0.0));
+        self.scene = Some(Scene::new(renderer, heightmap));
+        Ok(())
+    }
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.load_terrain(renderer, self.heightmap_path.clone().as_deref())?;
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        Ok(())
+    }
+
+    fn depth_format(&mut self) -> Option<wgpu::TextureFormat> {
+        Some(Texture::DEPTH_FORMAT)
+    }
+
+    fn optional_features(&self) -> wgpu::Features {
+        wgpu::Features::POLYGON_MODE_LINE
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(path) = self.pending_heightmap.take() {
+            match self.load_terrain(renderer, Some(&path)) {
+                Ok(()) => self.heightmap_path = Some(path),
+                Err(error) => log::error!("{error:#}"),
+            }
+        }
+
+        input.set_relative_mode(input.mouse.is_right_clicked);
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            if self.walk {
+                let translation = &mut self.camera.transform.translation;
+                translation.y = scene.heightmap.height_at(translation) + self.eye_height;
+            }
+            let view_projection = self.camera.projection_view_matrix(renderer.aspect_ratio());
+            scene.update(renderer, view_projection);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Terrain");
+                ui.label("Hold the right mouse button to look around, WASD to move");
+                ui.label("Drop a grayscale image onto the window to use it as the heightmap");
+                ui.checkbox(&mut self.walk, "Walk on the ground");
+                ui.add(egui::Slider::new(&mut self.camera.speed, 1.0..=100.0).text("Speed"));
+                if let Some(scene) = self.scene.as_mut() {
+                    ui.label(format!(
+                        "Chunks drawn: {} / {}",
+                        scene.visible.len(),
+                        scene.chunks.len()
+                    ));
+                    ui.add_enabled(
+                        scene.wireframe_pipeline.is_some(),
+                        egui::Checkbox::new(&mut scene.wireframe, "Wireframe"),
+                    )
+                    .on_disabled_hover_text("The adapter can't draw wireframes");
+                    ui.collapsing("Splatting", |ui| {
+                        let splat = &mut scene.splat;
+                        ui.add(
+                            egui::Slider::new(&mut splat.texture_scale, 0.01..=1.0)
+                                .text("Texture scale"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut splat.snow_line, 0.0..=1.0).text("Snow line"),
+                        );
+                        ui.add(
+                            egui::Slider::new(&mut splat.rock_slope, 0.0..=1.0).text("Rock slope"),
+                        );
+                        for (layer, noise) in splat.layers.iter_mut().zip(scene.layer_noise.iter())
+                        {
+                            ui.collapsing(layer.name, |ui| {
+                                ui.horizontal(|ui| {
+                                    egui::color_picker::color_edit_button_rgb(ui, &mut layer.color);
+                                    ui.label("Color");
+                                });
+                                if layer.noise.show(ui) {
+                                    noise.generate(renderer, &layer.noise);
+                                }
+                            });
+                        }
+                    });
+                }
+                ui.collapsing("Statistics", |ui| {
+                    self.stats.show(ui);
+                });
+            });
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        Ok(())
+    }
+
+    fn on_file_dropped(&mut self, _renderer: &mut Renderer, path: &Path) -> Result<()> {
+        self.pending_heightmap = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let depth_view = self
+            .depth_texture
+            .as_ref()
+            .map(|depth_texture| &depth_texture.view);
+        let mut render_pass = Renderer::begin_default_pass(
+            encoder,
+            view,
+            Some(wgpu::Color {
+                r: 0.55,
+                g: 0.7,
+                b: 0.9,
+                a: 1.0,
+            }),
+            depth_view,
+        );
+
+        self.stats.reset();
+        if let Some(depth_texture) = self.depth_texture.as_ref() {
+            self.stats.record_texture(&depth_texture.texture);
+        }
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass, &mut self.stats);
+        }
+
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Terrain".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
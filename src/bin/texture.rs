@@ -1,166 +1,320 @@
-use anyhow::Result;
-use std::{borrow::Cow, mem};
-use support::{run, AppConfig, Application, Geometry, Renderer, Texture};
-use wgpu::{
-    vertex_attr_array, BindGroup, BindGroupLayout, Device, Queue, RenderPass, RenderPipeline,
-    TextureFormat, VertexAttribute,
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use support::{
+    run, AnimatedTexture, AppConfig, Application, AssetBrowser, AssetCache, AssetKind, Download,
+    Input, Renderer, Session, System, Texture, TexturedQuad, Tutorial,
 };
-
-const VERTICES: [Vertex; 4] = [
-    Vertex {
-        position: [0.6, -0.6, 0.0, 1.0],
-        tex_coords: [1.0, 0.0],
-    },
-    Vertex {
-        position: [-0.6, -0.6, 0.0, 1.0],
-        tex_coords: [0.0, 0.0],
-    },
-    Vertex {
-        position: [0.6, 0.6, 0.0, 1.0],
-        tex_coords: [1.0, 1.0],
-    },
-    Vertex {
-        position: [-0.6, 0.6, 0.0, 1.0],
-        tex_coords: [0.0, 1.0],
-    },
-];
-
-const INDICES: [u32; 6] = [0, 1, 2, 1, 2, 3]; // Clockwise winding order
-
-const SHADER_SOURCE: &str = "
-struct VertexInput {
-    @location(0) position: vec4<f32>,
-    @location(1) tex_coords: vec2<f32>,
-};
-struct VertexOutput {
-    @builtin(position) position: vec4<f32>,
-    @location(0) tex_coords: vec2<f32>,
-};
-
-@vertex
-fn vertex_main(vert: VertexInput) -> VertexOutput {
-    var out: VertexOutput;
-    out.tex_coords = vert.tex_coords;
-    out.position = vert.position;
-    return out;
-};
-
-@group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1)
-var s_diffuse: sampler;
-
-
-@fragment
-fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
-}
-";
+use wgpu::{BindGroup, Device, Queue, RenderPass, TextureFormat};
 
 struct Scene {
-    pub geometry: Geometry,
-    pub pipeline: RenderPipeline,
+    pub quad: TexturedQuad,
     pub texture: TextureBinding,
 }
 
 impl Scene {
-    pub fn new(device: &Device, queue: &Queue, surface_format: TextureFormat) -> Result<Self> {
-        let geometry = Geometry::new(device, &VERTICES, &INDICES);
-        let texture = TextureBinding::new(device, queue)?;
-        let pipeline = Self::create_pipeline(device, surface_format, &texture);
-        Ok(Self {
-            geometry,
-            pipeline,
-            texture,
-        })
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        cache: &AssetCache,
+        surface_format: TextureFormat,
+    ) -> Result<Self> {
+        let quad = TexturedQuad::new(device, surface_format, 0.6)?;
+        let texture = TextureBinding::new(device, queue, cache, &quad)?;
+        Ok(Self { quad, texture })
     }
 
     pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
-        renderpass.set_pipeline(&self.pipeline);
-        renderpass.set_bind_group(0, &self.texture.bind_group, &[]);
-
-        let (vertex_slice, index_slice) = self.geometry.slices();
-        renderpass.set_vertex_buffer(0, vertex_slice);
-        renderpass.set_index_buffer(index_slice, wgpu::IndexFormat::Uint32);
-
-        renderpass.draw_indexed(0..(INDICES.len() as _), 0, 0..1);
+        self.quad.render(renderpass, &self.texture.bind_group);
     }
+}
 
-    fn create_pipeline(
-        device: &Device,
-        surface_format: TextureFormat,
-        texture: &TextureBinding,
-    ) -> RenderPipeline {
-        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
-        });
+const SESSION_PATH: &str = "texture_session.ron";
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[&texture.bind_group_layout],
-            push_constant_ranges: &[],
-        });
+/// How long each image of a dropped folder stays on screen
+const SEQUENCE_FRAME_DURATION: f32 = 1.0 / 12.0;
 
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vertex_main",
-                buffers: &[Vertex::description(&Vertex::vertex_attributes())],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-                unclipped_depth: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fragment_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        })
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TextureSession {
+    image: String,
 }
 
 #[derive(Default)]
 struct App {
     scene: Option<Scene>,
+    tutorial: Tutorial,
+    asset_cache: AssetCache,
+    url: String,
+    download: Option<Download>,
+    session: Session<TextureSession>,
+    restore_prompt: bool,
+    asset_browser: Option<AssetBrowser>,
+}
+
+impl App {
+    /// Opens an image from a file path or an http(s) URL
+    fn open(&mut self, renderer: &mut Renderer, source: &str) -> Result<()> {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            self.download = Some(Download::start(
+                source,
+                &self.asset_cache,
+                Download::validate_image,
+            ));
+            return Ok(());
+        }
+        let path = Path::new(source);
+        if path.is_dir() {
+            let animation = AnimatedTexture::from_image_sequence(
+                &renderer.device,
+                &renderer.queue,
+                path,
+                SEQUENCE_FRAME_DURATION,
+            )?;
+            if let Some(scene) = self.scene.as_mut() {
+                scene
+                    .texture
+                    .set_animation(&renderer.device, &scene.quad, animation);
+            }
+            self.record_opened(source);
+            return Ok(());
+        }
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read image from {}", path.display()))?;
+        let label = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(source);
+        self.set_image(renderer, &bytes, label)?;
+        self.record_opened(source);
+        Ok(())
+    }
+
+    fn record_opened(&mut self, source: &str) {
+        self.session.recent_files.push(source);
+        self.session.last_session = Some(TextureSession {
+            image: source.to_string(),
+        });
+        if let Err(error) = self.session.save(SESSION_PATH) {
+            log::error!("{error:#}");
+        }
+    }
+
+    /// Drops a file that failed to open from the saved session, so it stays gone after a restart
+    fn forget(&mut self, source: &str) {
+        self.session.recent_files.remove(source);
+        if self
+            .session
+            .last_session
+            .as_ref()
+            .is_some_and(|session| session.image == source)
+        {
+            self.session.last_session = None;
+        }
+        if let Err(error) = self.session.save(SESSION_PATH) {
+            log::error!("{error:#}");
+        }
+    }
+
+    fn set_image(&mut self, renderer: &mut Renderer, bytes: &[u8], label: &str) -> Result<()> {
+        if matches!(image::guess_format(bytes), Ok(image::ImageFormat::Gif)) {
+            let animation =
+                AnimatedTexture::from_gif_bytes(&renderer.device, &renderer.queue, bytes, label)?;
+            if let Some(scene) = self.scene.as_mut() {
+                scene
+                    .texture
+                    .set_animation(&renderer.device, &scene.quad, animation);
+            }
+            return Ok(());
+        }
+        let texture = Texture::from_bytes_cached(
+            &renderer.device,
+            &renderer.queue,
+            &self.asset_cache,
+            bytes,
+            label,
+        )?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene
+                .texture
+                .set_texture(&renderer.device, &scene.quad, texture);
+        }
+        Ok(())
+    }
 }
 
 impl Application for App {
     fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.tutorial = Tutorial::from_ron(include_str!("../../assets/tutorials/texture.ron"))?;
         self.scene = Some(Scene::new(
             &renderer.device,
             &renderer.queue,
+            &self.asset_cache,
             renderer.config.format,
         )?);
+        if Path::new(SESSION_PATH).exists() {
+            match Session::load(SESSION_PATH) {
+                Ok(session) => self.session = session,
+                Err(error) => log::warn!("{error:#}"),
+            }
+        }
+        self.restore_prompt = self.session.last_session.is_some();
+        self.asset_browser = Some(AssetBrowser::new(
+            AssetBrowser::DEFAULT_DIRECTORY,
+            self.asset_cache.clone(),
+        ));
         Ok(())
     }
 
-    fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
-        egui::Window::new("wgpu")
+    fn on_file_dropped(&mut self, renderer: &mut Renderer, path: &Path) -> Result<()> {
+        self.open(renderer, &path.to_string_lossy())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene
+                .texture
+                .update(&renderer.queue, system.delta_time as f32);
+        }
+        let Some(result) = self.download.as_ref().and_then(Download::poll) else {
+            return Ok(());
+        };
+        let download = self.download.take().expect("download was polled");
+        match result {
+            Ok(bytes) => {
+                self.set_image(renderer, &bytes, &download.url)?;
+                self.record_opened(&download.url);
+            }
+            Err(error) => log::error!("{error:#}"),
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        let mut selected = None;
+        let window = egui::Window::new("wgpu")
             .resizable(false)
             .fixed_pos((10.0, 10.0))
             .show(context, |ui| {
+                egui::menu::bar(ui, |ui| {
+                    ui.menu_button("File", |ui| {
+                        selected = self.session.recent_files.show(ui);
+                        let last_session = self.session.last_session.clone();
+                        if ui
+                            .add_enabled(
+                                last_session.is_some(),
+                                egui::Button::new("Restore last session"),
+                            )
+                            .clicked()
+                        {
+                            selected = last_session.map(|session| session.image);
+                            ui.close_menu();
+                        }
+                    });
+                });
                 ui.heading("Texture");
+                ui.label("Drop an image, GIF or folder of images onto the window to display it");
+                ui.horizontal(|ui| {
+                    ui.label("URL");
+                    ui.text_edit_singleline(&mut self.url);
+                    let idle = self.download.is_none() && !self.url.is_empty();
+                    if ui.add_enabled(idle, egui::Button::new("Open")).clicked() {
+                        selected = Some(self.url.trim().to_string());
+                    }
+                });
+                if let Some(download) = self.download.as_ref() {
+                    match download.progress() {
+                        Some(progress) => {
+                            ui.add(egui::ProgressBar::new(progress).show_percentage());
+                        }
+                        None => {
+                            ui.label(format!("Downloaded {} bytes", download.received()));
+                        }
+                    }
+                }
+                if let Some(animation) = self
+                    .scene
+                    .as_mut()
+                    .and_then(|scene| scene.texture.animation.as_mut())
+                {
+                    ui.separator();
+                    ui.label(format!(
+                        "Frame {} of {}",
+                        animation.current_frame() + 1,
+                        animation.frame_count()
+                    ));
+                    ui.horizontal(|ui| {
+                        let label = if animation.playing { "Pause" } else { "Play" };
+                        if ui.button(label).clicked() {
+                            animation.playing = !animation.playing;
+                        }
+                        if ui.button("Next frame").clicked() {
+                            let next = animation.current_frame() + 1;
+                            animation.set_frame(&renderer.queue, next);
+                        }
+                    });
+                    ui.add(egui::Slider::new(&mut animation.speed, 0.1..=4.0).text("Speed"));
+                }
+                ui.checkbox(&mut self.tutorial.visible, "Tutorial");
             });
+
+        if let Some(asset_browser) = self.asset_browser.as_mut() {
+            egui::SidePanel::right("assets")
+                .default_width(250.0)
+                .show(context, |ui| {
+                    ui.heading("Assets");
+                    ui.label("Double click an image to display it");
+                    match asset_browser.show(ui) {
+                        Some(path) if AssetKind::from_path(&path) == AssetKind::Texture => {
+                            selected = Some(path.to_string_lossy().into_owned());
+                        }
+                        Some(path) => log::warn!("{} is not an image", path.display()),
+                        None => {}
+                    }
+                });
+        }
+
+        if self.restore_prompt {
+            egui::Window::new("Restore session")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+                .show(context, |ui| {
+                    if let Some(session) = self.session.last_session.as_ref() {
+                        ui.label(format!("Reopen {}?", session.image));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Restore").clicked() {
+                            selected = self
+                                .session
+                                .last_session
+                                .as_ref()
+                                .map(|session| session.image.clone());
+                            self.restore_prompt = false;
+                        }
+                        if ui.button("Dismiss").clicked() {
+                            self.restore_prompt = false;
+                        }
+                    });
+                });
+        }
+
+        if let Some(source) = selected {
+            if let Err(error) = self.open(renderer, &source) {
+                log::error!("{error:#}");
+                self.forget(&source);
+            }
+        }
+
+        if let Some(window) = window {
+            self.tutorial
+                .highlight(context, "window", window.response.rect);
+        }
+        self.tutorial.show(context);
         Ok(())
     }
 
@@ -169,25 +323,8 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
-
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
 
         if let Some(scene) = self.scene.as_ref() {
             scene.render(&mut render_pass);
@@ -198,78 +335,50 @@ impl Application for App {
 }
 
 struct TextureBinding {
-    _texture: Texture,
+    _texture: Option<Texture>,
+    /// Set instead of the still texture while an animated source is shown
+    pub animation: Option<AnimatedTexture>,
     pub bind_group: BindGroup,
-    pub bind_group_layout: BindGroupLayout,
 }
 
 impl TextureBinding {
-    pub fn new(device: &Device, queue: &Queue) -> Result<Self> {
+    pub fn new(
+        device: &Device,
+        queue: &Queue,
+        cache: &AssetCache,
+        quad: &TexturedQuad,
+    ) -> Result<Self> {
         let texture_bytes = include_bytes!("../../assets/textures/planks.jpg");
-        let texture = Texture::from_bytes(device, queue, texture_bytes, "planks.jpg")?;
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-            label: Some("texture_bind_group_layout"),
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
-                },
-            ],
-            label: Some("texture_bind_group"),
-        });
-
+        let texture =
+            Texture::from_bytes_cached(device, queue, cache, texture_bytes, "planks.jpg")?;
+        let bind_group = quad.create_bind_group(device, &texture);
         Ok(Self {
-            _texture: texture,
+            _texture: Some(texture),
+            animation: None,
             bind_group,
-            bind_group_layout,
         })
     }
-}
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct Vertex {
-    position: [f32; 4],
-    tex_coords: [f32; 2],
-}
+    pub fn set_texture(&mut self, device: &Device, quad: &TexturedQuad, texture: Texture) {
+        self.bind_group = quad.create_bind_group(device, &texture);
+        self._texture = Some(texture);
+        self.animation = None;
+    }
 
-impl Vertex {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![0 => Float32x4, 1 => Float32x2].to_vec()
+    pub fn set_animation(
+        &mut self,
+        device: &Device,
+        quad: &TexturedQuad,
+        animation: AnimatedTexture,
+    ) {
+        self.bind_group = quad.create_bind_group(device, &animation.texture);
+        self._texture = None;
+        self.animation = Some(animation);
     }
 
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes,
+    pub fn update(&mut self, queue: &Queue, delta_time: f32) {
+        if let Some(animation) = self.animation.as_mut() {
+            animation.update(queue, delta_time);
         }
     }
 }
@@ -281,6 +390,7 @@ fn main() -> Result<()> {
             title: "Texture".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    run, AppConfig, Application, BindingBuilder, Input, Renderer, System, FULLSCREEN_VERTEX_SOURCE,
+};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline, TextureFormat};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct UniformBuffer {
+    /// Time, aspect ratio, opacity and whether to premultiply the output
+    params: glm::Vec4,
+    accent: glm::Vec4,
+}
+
+const SHADER_SOURCE: &str = "
+struct Uniforms {
+    params: vec4<f32>,
+    accent: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+fn rounded_box(p: vec2<f32>, half_size: vec2<f32>, radius: f32) -> f32 {
+    let q = abs(p) - half_size + radius;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+}
+
+@fragment
+fn fragment_main(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let time = uniforms.params.x;
+    let aspect = uniforms.params.y;
+    let p = (in.uv - 0.5) * vec2<f32>(aspect, 1.0) * 2.0;
+
+    let card = rounded_box(p, vec2<f32>(0.7, 0.45), 0.15);
+    let edge = fwidth(card);
+    let inside = 1.0 - smoothstep(-edge, edge, card);
+    let glow = exp(-max(card, 0.0) * 12.0) * 0.35 * (1.0 - inside);
+
+    let angle = atan2(p.y, p.x);
+    let sweep = fract((angle / 6.2831853) + 0.5 - time * 0.25);
+    let ring = abs(length(p) - 0.28) - 0.03;
+    let ring_coverage = (1.0 - smoothstep(-edge, edge, ring)) * (0.25 + 0.75 * sweep);
+
+    var color = mix(vec3<f32>(0.08, 0.09, 0.12), uniforms.accent.rgb, ring_coverage);
+    color = mix(uniforms.accent.rgb, color, inside);
+    let alpha = max(inside * 0.92, glow) * uniforms.params.z;
+
+    if uniforms.params.w > 0.5 {
+        return vec4<f32>(color * alpha, alpha);
+    }
+    return vec4<f32>(color, alpha);
+}
+";
+
+#[derive(Debug, Copy, Clone)]
+struct WidgetSettings {
+    opacity: f32,
+    /// Alpha of the background cleared behind the widget
+    background: f32,
+    accent: [f32; 3],
+}
+
+impl Default for WidgetSettings {
+    fn default() -> Self {
+        Self {
+            opacity: 1.0,
+            background: 0.0,
+            accent: [0.2, 0.6, 1.0],
+        }
+    }
+}
+
+/// A rounded card with a spinning ring, drawn over a fully transparent
+/// clear so only the widget and its glow cover the desktop
+struct Scene {
+    settings: WidgetSettings,
+    time: f32,
+    uniform: Buffer,
+    uniform_bind_group: BindGroup,
+    pipeline: RenderPipeline,
+}
+
+impl Scene {
+    pub fn new(device: &Device, surface_format: TextureFormat, blend: wgpu::BlendState) -> Self {
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let uniform_layout = BindingBuilder::new()
+            .label("Uniform")
+            .uniform(0, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let uniform_bind_group = uniform_layout.bind_group(device, &[uniform.as_entire_binding()]);
+        let pipeline = Self::create_pipeline(device, surface_format, blend, &uniform_layout.layout);
+
+        Self {
+            settings: WidgetSettings::default(),
+            time: 0.0,
+            uniform,
+            uniform_bind_group,
+            pipeline,
+        }
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        self.time += delta_time;
+        let premultiply = renderer.config.alpha_mode == wgpu::CompositeAlphaMode::PreMultiplied;
+        let uniform = UniformBuffer {
+            params: glm::vec4(
+                self.time,
+                renderer.aspect_ratio(),
+                self.settings.opacity,
+                if premultiply { 1.0 } else { 0.0 },
+            ),
+            accent: glm::Vec3::from(self.settings.accent).push(1.0),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        renderpass.draw(0..3, 0..1);
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        blend: wgpu::BlendState,
+        uniform_layout: &wgpu::BindGroupLayout,
+    ) -> RenderPipeline {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Widget Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!(
+                "{FULLSCREEN_VERTEX_SOURCE}{SHADER_SOURCE}"
+            ))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Widget Pipeline Layout"),
+            bind_group_layouts: &[uniform_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Widget Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "fullscreen_vertex",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    clear_color: wgpu::Color,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.scene = Some(Scene::new(
+            &renderer.device,
+            renderer.config.format,
+            renderer.blend_state(),
+        ));
+        Ok(())
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, system.delta_time as f32);
+            self.clear_color = renderer.clear_color(wgpu::Color {
+                a: scene.settings.background as f64,
+                ..wgpu::Color::BLACK
+            });
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Transparent Window");
+                ui.label(format!("Alpha mode {:?}", renderer.config.alpha_mode));
+                if !renderer.is_transparent() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The surface is opaque, the desktop won't show through",
+                    );
+                }
+                if let Some(scene) = self.scene.as_mut() {
+                    let settings = &mut scene.settings;
+                    ui.add(egui::Slider::new(&mut settings.opacity, 0.0..=1.0).text("Opacity"));
+                    ui.add(
+                        egui::Slider::new(&mut settings.background, 0.0..=1.0).text("Background"),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Accent");
+                        ui.color_edit_button_rgb(&mut settings.accent);
+                    });
+                }
+            });
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(self.clear_color), None);
+
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Transparent".to_string(),
+            width: 800,
+            height: 600,
+            transparent: true,
+            ..Default::default()
+        },
+    )
+}
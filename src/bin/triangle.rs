@@ -1,7 +1,9 @@
 use anyhow::Result;
-use std::{borrow::Cow, mem};
-use support::{run, AppConfig, Application, Geometry, Renderer};
-use wgpu::{vertex_attr_array, Device, RenderPass, RenderPipeline, TextureFormat, VertexAttribute};
+use std::borrow::Cow;
+use support::{
+    impl_vertex, run, AppConfig, Application, Geometry, Renderer, Tutorial, VertexDescription,
+};
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -10,19 +12,7 @@ struct Vertex {
     color: [f32; 4],
 }
 
-impl Vertex {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![0 => Float32x4, 1 => Float32x4].to_vec()
-    }
-
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes,
-        }
-    }
-}
+impl_vertex!(Vertex, [0 => Float32x4, 1 => Float32x4]);
 
 const VERTICES: [Vertex; 3] = [
     Vertex {
@@ -81,9 +71,9 @@ impl Scene {
     pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
         renderpass.set_pipeline(&self.pipeline);
 
-        let (vertex_buffer_slice, index_buffer_slice) = self.geometry.slices();
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.geometry.slices();
         renderpass.set_vertex_buffer(0, vertex_buffer_slice);
-        renderpass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
 
         renderpass.draw_indexed(0..(INDICES.len() as _), 0, 0..1);
     }
@@ -106,11 +96,11 @@ impl Scene {
             vertex: wgpu::VertexState {
                 module: &shader_module,
                 entry_point: "vertex_main",
-                buffers: &[Vertex::description(&Vertex::vertex_attributes())],
+                buffers: &[Vertex::layout().buffer_layout()],
             },
             primitive: wgpu::PrimitiveState {
                 topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
+                strip_index_format: None,
                 front_face: wgpu::FrontFace::Cw,
                 cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
@@ -140,21 +130,29 @@ impl Scene {
 #[derive(Default)]
 struct App {
     scene: Option<Scene>,
+    tutorial: Tutorial,
 }
 
 impl Application for App {
     fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.tutorial = Tutorial::from_ron(include_str!("../../assets/tutorials/triangle.ron"))?;
         self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
         Ok(())
     }
 
     fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
-        egui::Window::new("wgpu")
+        let window = egui::Window::new("wgpu")
             .resizable(false)
             .fixed_pos((10.0, 10.0))
             .show(context, |ui| {
                 ui.heading("Triangle");
+                ui.checkbox(&mut self.tutorial.visible, "Tutorial");
             });
+        if let Some(window) = window {
+            self.tutorial
+                .highlight(context, "window", window.response.rect);
+        }
+        self.tutorial.show(context);
         Ok(())
     }
 
@@ -163,25 +161,8 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
-
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
 
         if let Some(scene) = self.scene.as_ref() {
             scene.render(&mut render_pass);
@@ -198,6 +179,7 @@ fn main() -> Result<()> {
             title: "Triangle".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
@@ -1,11 +1,11 @@
 use anyhow::Result;
 use nalgebra_glm as glm;
-use std::{borrow::Cow, mem};
-use support::{run, AppConfig, Application, Geometry, Input, Renderer, System};
-use wgpu::{
-    util::DeviceExt, vertex_attr_array, BindGroup, BindGroupLayout, Buffer, BufferAddress, Device,
-    Queue, RenderPass, RenderPipeline, TextureFormat, VertexAttribute,
+use std::borrow::Cow;
+use support::{
+    impl_vertex, run, AppConfig, Application, Geometry, Input, Renderer, ShaderReflection, System,
+    Tutorial, Uniform, VertexDescription,
 };
+use wgpu::{Device, RenderPass, RenderPipeline, TextureFormat};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -14,19 +14,7 @@ struct Vertex {
     color: [f32; 4],
 }
 
-impl Vertex {
-    pub fn vertex_attributes() -> Vec<VertexAttribute> {
-        vertex_attr_array![0 => Float32x4, 1 => Float32x4].to_vec()
-    }
-
-    pub fn description(attributes: &[VertexAttribute]) -> wgpu::VertexBufferLayout {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes,
-        }
-    }
-}
+impl_vertex!(Vertex, [0 => Float32x4, 1 => Float32x4]);
 
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -34,64 +22,6 @@ struct UniformBuffer {
     mvp: glm::Mat4,
 }
 
-struct UniformBinding {
-    pub buffer: Buffer,
-    pub bind_group: BindGroup,
-    pub bind_group_layout: BindGroupLayout,
-}
-
-impl UniformBinding {
-    pub fn new(device: &Device) -> Self {
-        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[UniformBuffer::default()]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-            label: Some("uniform_bind_group_layout"),
-        });
-
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer.as_entire_binding(),
-            }],
-            label: Some("uniform_bind_group"),
-        });
-
-        Self {
-            buffer,
-            bind_group,
-            bind_group_layout,
-        }
-    }
-
-    pub fn update_buffer(
-        &mut self,
-        queue: &Queue,
-        offset: BufferAddress,
-        uniform_buffer: UniformBuffer,
-    ) {
-        queue.write_buffer(
-            &self.buffer,
-            offset,
-            bytemuck::cast_slice(&[uniform_buffer]),
-        )
-    }
-}
-
 const VERTICES: [Vertex; 3] = [
     Vertex {
         position: [1.0, -1.0, 0.0, 1.0],
@@ -143,36 +73,37 @@ fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
 struct Scene {
     pub model: glm::Mat4,
     pub geometry: Geometry,
-    pub uniform: UniformBinding,
+    pub uniform: Uniform<UniformBuffer>,
     pub pipeline: RenderPipeline,
 }
 
 impl Scene {
-    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
+    pub fn new(device: &Device, surface_format: TextureFormat) -> Result<Self> {
         let geometry = Geometry::new(device, &VERTICES, &INDICES);
-        let uniform = UniformBinding::new(device);
-        let pipeline = Self::create_pipeline(device, surface_format, &uniform);
-        Self {
+        let uniform = Uniform::new(device);
+        let pipeline = Self::create_pipeline(device, surface_format, &geometry, &uniform)?;
+        Ok(Self {
             model: glm::Mat4::identity(),
             geometry,
             uniform,
             pipeline,
-        }
+        })
     }
 
     pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
         renderpass.set_pipeline(&self.pipeline);
-        renderpass.set_bind_group(0, &self.uniform.bind_group, &[]);
+        self.uniform.set(renderpass, 0);
 
-        let (vertex_buffer_slice, index_buffer_slice) = self.geometry.slices();
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.geometry.slices();
         renderpass.set_vertex_buffer(0, vertex_buffer_slice);
-        renderpass.set_index_buffer(index_buffer_slice, wgpu::IndexFormat::Uint32);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
 
         renderpass.draw_indexed(0..(INDICES.len() as _), 0, 0..1);
     }
 
-    pub fn update(&mut self, queue: &Queue, aspect_ratio: f32) {
-        let projection = glm::perspective_lh_zo(aspect_ratio, 80_f32.to_radians(), 0.1, 1000.0);
+    pub fn update(&mut self, renderer: &mut Renderer) {
+        let projection =
+            glm::perspective_lh_zo(renderer.aspect_ratio(), 80_f32.to_radians(), 0.1, 1000.0);
         let view = glm::look_at_lh(
             &glm::vec3(0.0, 0.0, 3.0),
             &glm::vec3(0.0, 0.0, 0.0),
@@ -180,10 +111,9 @@ impl Scene {
         );
         self.model = glm::rotate(&self.model, 1_f32.to_radians(), &glm::Vec3::y());
 
-        self.uniform.update_buffer(
-            queue,
-            0,
-            UniformBuffer {
+        self.uniform.write(
+            renderer,
+            &UniformBuffer {
                 mvp: projection * view * self.model,
             },
         )
@@ -192,8 +122,13 @@ impl Scene {
     fn create_pipeline(
         device: &Device,
         surface_format: TextureFormat,
-        uniform: &UniformBinding,
-    ) -> RenderPipeline {
+        geometry: &Geometry,
+        uniform: &Uniform<UniformBuffer>,
+    ) -> Result<RenderPipeline> {
+        let reflection = ShaderReflection::from_wgsl(SHADER_SOURCE)?;
+        reflection.validate_group(0, uniform.binding_layout().entries())?;
+        geometry.validate(&reflection, "vertex_main")?;
+
         let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: None,
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
@@ -201,72 +136,87 @@ impl Scene {
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&uniform.bind_group_layout],
+            bind_group_layouts: &[uniform.layout()],
             push_constant_ranges: &[],
         });
 
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader_module,
-                entry_point: "vertex_main",
-                buffers: &[Vertex::description(&Vertex::vertex_attributes())],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleStrip,
-                strip_index_format: Some(wgpu::IndexFormat::Uint32),
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-                unclipped_depth: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader_module,
-                entry_point: "fragment_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
+        Ok(
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vertex_main",
+                    buffers: &[Vertex::layout().buffer_layout()],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleStrip,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                    unclipped_depth: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: "fragment_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
             }),
-            multiview: None,
-        })
+        )
     }
 }
 
 #[derive(Default)]
 struct App {
     scene: Option<Scene>,
+    tutorial: Tutorial,
 }
 
 impl Application for App {
     fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
-        self.scene = Some(Scene::new(&renderer.device, renderer.config.format));
+        self.tutorial = Tutorial::from_ron(include_str!("../../assets/tutorials/uniforms.ron"))?;
+        self.scene = Some(Scene::new(&renderer.device, renderer.config.format)?);
         Ok(())
     }
 
-    fn update(&mut self, renderer: &mut Renderer, _input: &Input, _system: &System) -> Result<()> {
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        _input: &mut Input,
+        _system: &System,
+    ) -> Result<()> {
         if let Some(scene) = self.scene.as_mut() {
-            scene.update(&renderer.queue, renderer.aspect_ratio());
+            scene.update(renderer);
         }
         Ok(())
     }
 
     fn update_gui(&mut self, _renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
-        egui::Window::new("wgpu")
+        let window = egui::Window::new("wgpu")
             .resizable(false)
             .fixed_pos((10.0, 10.0))
             .show(context, |ui| {
                 ui.heading("Uniforms");
+                ui.checkbox(&mut self.tutorial.visible, "Tutorial");
             });
+        if let Some(window) = window {
+            self.tutorial
+                .highlight(context, "window", window.response.rect);
+        }
+        self.tutorial.show(context);
         Ok(())
     }
 
@@ -275,25 +225,8 @@ impl Application for App {
         view: &'a wgpu::TextureView,
         encoder: &'b mut wgpu::CommandEncoder,
     ) -> Result<Option<RenderPass<'b>>> {
-        encoder.insert_debug_marker("Render scene");
-
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(Renderer::DEFAULT_CLEAR_COLOR), None);
 
         if let Some(scene) = self.scene.as_ref() {
             scene.render(&mut render_pass);
@@ -310,6 +243,7 @@ fn main() -> Result<()> {
             title: "Uniforms".to_string(),
             width: 800,
             height: 600,
+            ..Default::default()
         },
     )
 }
@@ -0,0 +1,763 @@
+use anyhow::Result;
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use support::{
+    camera::MouseOrbit, impl_vertex, run, Aabb, AppConfig, Application, BindingBuilder,
+    BindingLayout, Geometry, Indices, Input, MeshData, NoiseSettings, NoiseTexture, RenderTarget,
+    Renderer, System, Texture, Uniform, VertexDescription,
+};
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPass, RenderPipeline, TextureFormat};
+
+const SKY_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.55,
+    g: 0.72,
+    b: 0.92,
+    a: 1.0,
+};
+
+/// Texels along each side of the detail normal noise
+const DETAIL_NOISE_SIZE: u32 = 256;
+
+/// Keeps geometry slightly past the water plane in the clipped passes so the edges don't show gaps
+const CLIP_SLACK: f32 = 0.1;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+    color: [f32; 3],
+}
+
+impl_vertex!(Vertex, [0 => Float32x3, 1 => Float32x3, 2 => Float32x3]);
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SceneUniform {
+    view_projection: glm::Mat4,
+    /// Fragments where dot(position, plane.xyz) + plane.w is negative are discarded
+    clip_plane: glm::Vec4,
+    light_direction: glm::Vec4,
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct WaterUniform {
+    view_projection: glm::Mat4,
+    inverse_view_projection: glm::Mat4,
+    camera_position: glm::Vec4,
+    light_direction: glm::Vec4,
+    /// Deep water color, with the depth at which it becomes opaque in w
+    color: glm::Vec4,
+    /// Time, wave amplitude, distortion strength and render target width
+    params: glm::Vec4,
+    /// Render target height
+    screen_height: glm::Vec4,
+    /// Detail ripple height and the world size of one noise tile
+    detail: glm::Vec4,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct WaterSettings {
+    color: [f32; 3],
+    fade_depth: f32,
+    wave_amplitude: f32,
+    distortion: f32,
+    /// Height of the small ripples layered over the waves, in world units
+    detail_strength: f32,
+    /// World units covered by one repeat of the detail noise
+    detail_tile_size: f32,
+    detail_noise: NoiseSettings,
+}
+
+impl Default for WaterSettings {
+    fn default() -> Self {
+        Self {
+            color: [0.02, 0.18, 0.24],
+            fade_depth: 3.0,
+            wave_amplitude: 0.15,
+            distortion: 0.03,
+            detail_strength: 0.1,
+            detail_tile_size: 8.0,
+            detail_noise: NoiseSettings::default(),
+        }
+    }
+}
+
+/// Bakes each primitive's transform and color into one static mesh
+fn scene_mesh() -> (Vec<Vertex>, Vec<u32>) {
+    let shore = {
+        let mut mesh = MeshData::plane(60.0, 40);
+        for vertex in mesh.vertices.iter_mut() {
+            // A bowl whose rim rises out of the water
+            let (x, z) = (vertex.position[0], vertex.position[2]);
+            let rise = 6.0 / (30.0 * 30.0);
+            vertex.position[1] = -4.0 + rise * (x * x + z * z);
+            vertex.normal = glm::vec3(-2.0 * rise * x, 1.0, -2.0 * rise * z)
+                .normalize()
+                .into();
+        }
+        mesh
+    };
+    let objects = [
+        (shore, glm::Mat4::identity(), glm::vec3(0.76, 0.68, 0.5)),
+        (
+            MeshData::uv_sphere(1.5, 32, 16),
+            glm::translation(&glm::vec3(0.0, 0.3, 0.0)),
+            glm::vec3(0.8, 0.2, 0.15),
+        ),
+        (
+            MeshData::torus(1.2, 0.35, 32, 16),
+            glm::translation(&glm::vec3(-4.0, 0.8, 4.0))
+                * glm::rotation(45_f32.to_radians(), &glm::Vec3::x()),
+            glm::vec3(0.9, 0.75, 0.2),
+        ),
+        (
+            MeshData::cube(2.0),
+            glm::translation(&glm::vec3(5.0, 0.5, -3.0))
+                * glm::rotation(30_f32.to_radians(), &glm::Vec3::y()),
+            glm::vec3(0.3, 0.6, 0.3),
+        ),
+        (
+            MeshData::cylinder(0.5, 8.0, 24),
+            glm::translation(&glm::vec3(-7.0, -1.0, -6.0)),
+            glm::vec3(0.85, 0.85, 0.8),
+        ),
+        (
+            MeshData::cylinder(0.5, 8.0, 24),
+            glm::translation(&glm::vec3(7.0, -1.0, 6.0)),
+            glm::vec3(0.85, 0.85, 0.8),
+        ),
+    ];
+
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    for (mesh, model, color) in objects {
+        let offset = vertices.len() as u32;
+        vertices.extend(mesh.vertices.iter().map(|vertex| {
+            let position = model * glm::Vec3::from(vertex.position).push(1.0);
+            let normal = model * glm::Vec3::from(vertex.normal).push(0.0);
+            Vertex {
+                position: position.xyz().into(),
+                normal: normal.xyz().normalize().into(),
+                color: color.into(),
+            }
+        }));
+        indices.extend(mesh.indices.iter().map(|index| index + offset));
+    }
+    (vertices, indices)
+}
+
+struct Scene {
+    objects: Geometry,
+    water: Geometry,
+    settings: WaterSettings,
+    time: f32,
+    main: Uniform<SceneUniform>,
+    reflection: Uniform<SceneUniform>,
+    refraction: Uniform<SceneUniform>,
+    water_uniform: Uniform<WaterUniform>,
+    /// The scene above the water, seen from the camera mirrored below the water plane
+    reflection_target: RenderTarget,
+    /// The scene below the water, seen from the camera
+    refraction_target: RenderTarget,
+    targets_layout: BindingLayout,
+    targets_bind_group: BindGroup,
+    sampler: wgpu::Sampler,
+    /// Heights scrolled across the surface to perturb the wave normals
+    detail_noise: NoiseTexture,
+    detail_bind_group: BindGroup,
+    scene_pipeline: RenderPipeline,
+    water_pipeline: RenderPipeline,
+}
+
+impl Scene {
+    pub fn new(device: &Device, surface_format: TextureFormat, width: u32, height: u32) -> Self {
+        let (vertices, indices) = scene_mesh();
+        let objects = Geometry::new(device, &vertices, &indices);
+
+        let water_mesh = MeshData::plane(60.0, 127);
+        let water_vertices = water_mesh
+            .vertices
+            .iter()
+            .map(|vertex| Vertex {
+                position: vertex.position,
+                normal: vertex.normal,
+                color: [0.0; 3],
+            })
+            .collect::<Vec<_>>();
+        let water = match Indices::narrow(&water_mesh.indices) {
+            Some(indices) => Geometry::new(device, &water_vertices, &indices),
+            None => Geometry::new(device, &water_vertices, &water_mesh.indices),
+        };
+
+        let main = Uniform::new(device);
+        let water_uniform = Uniform::new(device);
+        let targets_layout = Self::create_targets_layout(device);
+
+        let reflection_target = RenderTarget::new(device, surface_format, width, height);
+        let refraction_target = RenderTarget::new(device, surface_format, width, height);
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let targets_bind_group = Self::create_targets_bind_group(
+            device,
+            &targets_layout,
+            &reflection_target,
+            &refraction_target,
+            &sampler,
+        );
+
+        let detail_noise = NoiseTexture::new(device, DETAIL_NOISE_SIZE, "Water Detail Noise");
+        let detail_layout = Self::create_detail_layout(device);
+        let detail_bind_group = detail_layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&detail_noise.texture.view),
+                wgpu::BindingResource::Sampler(&detail_noise.texture.sampler),
+            ],
+        );
+
+        let scene_pipeline = Self::create_pipeline(
+            device,
+            surface_format,
+            &[main.layout()],
+            SCENE_SHADER_SOURCE,
+        );
+        let water_pipeline = Self::create_pipeline(
+            device,
+            surface_format,
+            &[
+                water_uniform.layout(),
+                &targets_layout.layout,
+                &detail_layout.layout,
+            ],
+            WATER_SHADER_SOURCE,
+        );
+
+        Self {
+            objects,
+            water,
+            settings: WaterSettings::default(),
+            time: 0.0,
+            main,
+            // Their layouts match the main uniform's, so they fit the scene pipeline too
+            reflection: Uniform::new(device),
+            refraction: Uniform::new(device),
+            water_uniform,
+            reflection_target,
+            refraction_target,
+            targets_layout,
+            targets_bind_group,
+            sampler,
+            detail_noise,
+            detail_bind_group,
+            scene_pipeline,
+            water_pipeline,
+        }
+    }
+
+    /// Fits the reflection and refraction targets to the render resolution
+    pub fn resize(&mut self, renderer: &Renderer) {
+        let reflection_resized = self.reflection_target.fit_render_resolution(renderer);
+        let refraction_resized = self.refraction_target.fit_render_resolution(renderer);
+        if reflection_resized || refraction_resized {
+            self.targets_bind_group = Self::create_targets_bind_group(
+                &renderer.device,
+                &self.targets_layout,
+                &self.reflection_target,
+                &self.refraction_target,
+                &self.sampler,
+            );
+        }
+    }
+
+    pub fn update(&mut self, renderer: &mut Renderer, camera: &MouseOrbit, delta_time: f32) {
+        self.time += delta_time;
+        let light_direction = glm::vec3(0.3, 0.8, 0.4).normalize().push(0.0);
+        let projection = camera
+            .camera
+            .projection_matrix(self.reflection_target.aspect_ratio());
+        let view = camera.transform.as_view_matrix();
+        let view_projection = projection * view;
+        // Mirroring the world across the water plane before the view turns the camera upside down beneath it
+        let mirror = glm::scaling(&glm::vec3(1.0, -1.0, 1.0));
+
+        let scene_uniforms = [
+            (&self.main, view_projection, glm::vec4(0.0, 0.0, 0.0, 1.0)),
+            (
+                &self.reflection,
+                projection * view * mirror,
+                glm::vec4(0.0, 1.0, 0.0, CLIP_SLACK),
+            ),
+            (
+                &self.refraction,
+                view_projection,
+                glm::vec4(0.0, -1.0, 0.0, CLIP_SLACK),
+            ),
+        ];
+        for (uniform, view_projection, clip_plane) in scene_uniforms {
+            uniform.write(
+                renderer,
+                &SceneUniform {
+                    view_projection,
+                    clip_plane,
+                    light_direction,
+                },
+            );
+        }
+
+        let settings = &self.settings;
+        let water = WaterUniform {
+            view_projection,
+            inverse_view_projection: glm::inverse(&view_projection),
+            camera_position: camera.transform.translation.push(1.0),
+            light_direction,
+            color: glm::Vec3::from(settings.color).push(settings.fade_depth),
+            params: glm::vec4(
+                self.time,
+                settings.wave_amplitude,
+                settings.distortion,
+                renderer.config.width as f32,
+            ),
+            screen_height: glm::vec4(renderer.config.height as f32, 0.0, 0.0, 0.0),
+            detail: glm::vec4(
+                settings.detail_strength,
+                settings.detail_tile_size,
+                0.0,
+                0.0,
+            ),
+        };
+        self.water_uniform.write(renderer, &water);
+    }
+
+    /// Fills the reflection and refraction targets
+    pub fn render_targets(&self, encoder: &mut wgpu::CommandEncoder) {
+        for (target, uniform) in [
+            (&self.reflection_target, &self.reflection),
+            (&self.refraction_target, &self.refraction),
+        ] {
+            let mut renderpass = target.begin_pass(encoder, SKY_COLOR);
+            self.render_objects(&mut renderpass, uniform);
+        }
+    }
+
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        self.render_objects(renderpass, &self.main);
+
+        renderpass.set_pipeline(&self.water_pipeline);
+        self.water_uniform.set(renderpass, 0);
+        renderpass.set_bind_group(1, &self.targets_bind_group, &[]);
+        renderpass.set_bind_group(2, &self.detail_bind_group, &[]);
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.water.slices();
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        renderpass.draw_indexed(0..self.water.index_count, 0, 0..1);
+    }
+
+    fn render_objects<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        uniform: &'rpass Uniform<SceneUniform>,
+    ) {
+        renderpass.set_pipeline(&self.scene_pipeline);
+        uniform.set(renderpass, 0);
+        let (vertex_buffer_slice, index_buffer_slice, index_format) = self.objects.slices();
+        renderpass.set_vertex_buffer(0, vertex_buffer_slice);
+        renderpass.set_index_buffer(index_buffer_slice, index_format);
+        renderpass.draw_indexed(0..self.objects.index_count, 0, 0..1);
+    }
+
+    fn create_targets_layout(device: &Device) -> BindingLayout {
+        BindingBuilder::new()
+            .label("Targets")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .texture(1, wgpu::ShaderStages::FRAGMENT)
+            // Loaded as a plain float so the depth can be read without a comparison sampler on every backend
+            .unfilterable_texture(2, wgpu::ShaderStages::FRAGMENT)
+            .sampler(3, wgpu::ShaderStages::FRAGMENT)
+            .build(device)
+    }
+
+    fn create_detail_layout(device: &Device) -> BindingLayout {
+        BindingBuilder::new()
+            .label("Detail")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            .build(device)
+    }
+
+    fn create_targets_bind_group(
+        device: &Device,
+        layout: &BindingLayout,
+        reflection: &RenderTarget,
+        refraction: &RenderTarget,
+        sampler: &wgpu::Sampler,
+    ) -> BindGroup {
+        layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&reflection.color.view),
+                wgpu::BindingResource::TextureView(&refraction.color.view),
+                wgpu::BindingResource::TextureView(&refraction.depth.view),
+                wgpu::BindingResource::Sampler(sampler),
+            ],
+        )
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        bind_group_layouts: &[&BindGroupLayout],
+        source: &str,
+    ) -> RenderPipeline {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[Vertex::layout().buffer_layout()],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // The mirrored reflection pass flips the winding order
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+                unclipped_depth: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const SCENE_SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    clip_plane: vec4<f32>,
+    light_direction: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.world_position = vert.position;
+    out.normal = vert.normal;
+    out.color = vert.color;
+    out.position = ubo.view_projection * vec4(vert.position, 1.0);
+    return out;
+};
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if dot(in.world_position, ubo.clip_plane.xyz) + ubo.clip_plane.w < 0.0 {
+        discard;
+    }
+    let diffuse = max(dot(normalize(in.normal), ubo.light_direction.xyz), 0.0);
+    return vec4<f32>(in.color * (0.25 + 0.75 * diffuse), 1.0);
+}
+";
+
+const WATER_SHADER_SOURCE: &str = "
+struct Uniform {
+    view_projection: mat4x4<f32>,
+    inverse_view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    light_direction: vec4<f32>,
+    color: vec4<f32>,
+    params: vec4<f32>,
+    screen_height: vec4<f32>,
+    detail: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+@group(1) @binding(0)
+var reflection_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var refraction_texture: texture_2d<f32>;
+
+@group(1) @binding(2)
+var refraction_depth: texture_2d<f32>;
+
+@group(1) @binding(3)
+var target_sampler: sampler;
+
+@group(2) @binding(0)
+var detail_texture: texture_2d<f32>;
+
+@group(2) @binding(1)
+var detail_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+// Sum of directional sine waves, returning the height and its x and z derivatives
+fn waves(position: vec2<f32>, time: f32) -> vec3<f32> {
+    var directions = array<vec2<f32>, 3>(vec2(1.0, 0.0), vec2(0.6, 0.8), vec2(-0.7, 0.7));
+    let frequencies = vec3<f32>(0.6, 1.1, 1.9);
+    let speeds = vec3<f32>(1.0, 1.6, 2.3);
+    let amplitudes = vec3<f32>(1.0, 0.5, 0.25);
+    var result = vec3<f32>(0.0);
+    for (var index = 0; index < 3; index++) {
+        let direction = directions[index];
+        let phase = dot(direction, position) * frequencies[index] + time * speeds[index];
+        let amplitude = amplitudes[index] * ubo.params.y;
+        result.x += amplitude * sin(phase);
+        result.y += amplitude * frequencies[index] * direction.x * cos(phase);
+        result.z += amplitude * frequencies[index] * direction.y * cos(phase);
+    }
+    return result;
+}
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    let wave = waves(vert.position.xz, ubo.params.x);
+    var out: VertexOutput;
+    out.world_position = vec3(vert.position.x, wave.x, vert.position.z);
+    out.normal = normalize(vec3(-wave.y, 1.0, -wave.z));
+    out.position = ubo.view_projection * vec4(out.world_position, 1.0);
+    return out;
+};
+
+// Two layers of the detail noise scrolling in different directions, summed as a height field
+fn detail_height(position: vec2<f32>) -> f32 {
+    let time = ubo.params.x;
+    let uv = position / ubo.detail.y;
+    let first = textureSample(detail_texture, detail_sampler, uv + vec2(0.02, 0.01) * time).r;
+    let second = textureSample(detail_texture, detail_sampler, uv * 1.7 - vec2(0.015, 0.025) * time).r;
+    return (first + second) * 0.5 * ubo.detail.x;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let step = ubo.detail.y / f32(textureDimensions(detail_texture).x);
+    let slope = vec2<f32>(
+        detail_height(in.world_position.xz + vec2(step, 0.0)) - detail_height(in.world_position.xz - vec2(step, 0.0)),
+        detail_height(in.world_position.xz + vec2(0.0, step)) - detail_height(in.world_position.xz - vec2(0.0, step)),
+    ) / (2.0 * step);
+    let normal = normalize(in.normal + vec3(-slope.x, 0.0, -slope.y));
+    let screen_size = vec2<f32>(ubo.params.w, ubo.screen_height.x);
+    let screen_uv = in.position.xy / screen_size;
+    let distortion = normal.xz * ubo.params.z;
+
+    let reflection = textureSample(reflection_texture, target_sampler, clamp(screen_uv + distortion, vec2(0.001), vec2(0.999))).rgb;
+    let refraction = textureSample(refraction_texture, target_sampler, clamp(screen_uv - distortion, vec2(0.001), vec2(0.999))).rgb;
+
+    // Reconstruct the position of whatever lies beneath the surface to measure the water's thickness
+    let depth = textureLoad(refraction_depth, vec2<i32>(screen_uv * vec2<f32>(textureDimensions(refraction_depth))), 0).r;
+    var thickness = ubo.color.w;
+    if depth < 1.0 {
+        let ndc = vec4(screen_uv.x * 2.0 - 1.0, 1.0 - screen_uv.y * 2.0, depth, 1.0);
+        let floor = ubo.inverse_view_projection * ndc;
+        thickness = distance(floor.xyz / floor.w, in.world_position);
+    }
+    let fade = clamp(thickness / ubo.color.w, 0.0, 1.0);
+    let water = mix(refraction, ubo.color.rgb, fade);
+
+    let view = normalize(ubo.camera_position.xyz - in.world_position);
+    let fresnel = 0.02 + 0.98 * pow(1.0 - max(dot(view, normal), 0.0), 5.0);
+    let specular = pow(max(dot(reflect(-ubo.light_direction.xyz, normal), view), 0.0), 256.0);
+    return vec4<f32>(mix(water, reflection, fresnel) + vec3(specular), 1.0);
+}
+";
+
+#[derive(Default)]
+struct App {
+    scene: Option<Scene>,
+    camera: MouseOrbit,
+    depth_texture: Option<Texture>,
+}
+
+impl Application for App {
+    fn initialize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        let (width, height) = renderer.render_resolution();
+        let scene = Scene::new(&renderer.device, renderer.config.format, width, height);
+        scene
+            .detail_noise
+            .generate(renderer, &scene.settings.detail_noise);
+        // Frame the objects in the middle of the pool rather than the whole shore
+        self.camera.orientation.direction = glm::vec2(30_f32.to_radians(), 65_f32.to_radians());
+        self.camera.frame_bounds(&Aabb::new(
+            glm::vec3(-8.0, -1.0, -7.0),
+            glm::vec3(8.0, 3.0, 7.0),
+        ));
+        self.scene = Some(scene);
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        Ok(())
+    }
+
+    fn depth_format(&mut self) -> Option<wgpu::TextureFormat> {
+        Some(Texture::DEPTH_FORMAT)
+    }
+
+    fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &mut Input,
+        system: &System,
+    ) -> Result<()> {
+        self.camera.update(input, system)?;
+        if let Some(scene) = self.scene.as_mut() {
+            scene.update(renderer, &self.camera, system.delta_time as f32);
+        }
+        Ok(())
+    }
+
+    fn update_gui(&mut self, renderer: &mut Renderer, context: &mut egui::Context) -> Result<()> {
+        egui::Window::new("wgpu")
+            .resizable(false)
+            .fixed_pos((10.0, 10.0))
+            .show(context, |ui| {
+                ui.heading("Water");
+                let Some(scene) = self.scene.as_mut() else {
+                    return;
+                };
+                let settings = &mut scene.settings;
+                ui.horizontal(|ui| {
+                    egui::color_picker::color_edit_button_rgb(ui, &mut settings.color);
+                    ui.label("Deep water color");
+                });
+                ui.add(egui::Slider::new(&mut settings.fade_depth, 0.1..=10.0).text("Fade depth"));
+                ui.add(
+                    egui::Slider::new(&mut settings.wave_amplitude, 0.0..=0.5)
+                        .text("Wave amplitude"),
+                );
+                ui.add(egui::Slider::new(&mut settings.distortion, 0.0..=0.1).text("Distortion"));
+                ui.collapsing("Detail ripples", |ui| {
+                    ui.add(
+                        egui::Slider::new(&mut settings.detail_strength, 0.0..=1.0)
+                            .text("Strength"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut settings.detail_tile_size, 1.0..=32.0)
+                            .text("Tile size"),
+                    );
+                    if settings.detail_noise.show(ui) {
+                        scene
+                            .detail_noise
+                            .generate(renderer, &settings.detail_noise);
+                    }
+                });
+            });
+        Ok(())
+    }
+
+    fn resize(&mut self, renderer: &mut Renderer) -> Result<()> {
+        self.depth_texture = Some(Texture::create_depth_texture(
+            &renderer.device,
+            renderer.config.width,
+            renderer.config.height,
+        ));
+        if let Some(scene) = self.scene.as_mut() {
+            scene.resize(renderer);
+        }
+        Ok(())
+    }
+
+    fn render<'a: 'b, 'b>(
+        &'a mut self,
+        view: &'a wgpu::TextureView,
+        encoder: &'b mut wgpu::CommandEncoder,
+    ) -> Result<Option<RenderPass<'b>>> {
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render_targets(encoder);
+        }
+
+        let depth_view = self
+            .depth_texture
+            .as_ref()
+            .map(|depth_texture| &depth_texture.view);
+        let mut render_pass =
+            Renderer::begin_default_pass(encoder, view, Some(SKY_COLOR), depth_view);
+
+        if let Some(scene) = self.scene.as_ref() {
+            scene.render(&mut render_pass);
+        }
+
+        Ok(Some(render_pass))
+    }
+}
+
+fn main() -> Result<()> {
+    run(
+        App::default(),
+        AppConfig {
+            title: "Water".to_string(),
+            width: 800,
+            height: 600,
+            ..Default::default()
+        },
+    )
+}
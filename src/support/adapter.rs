@@ -0,0 +1,116 @@
+use crate::Renderer;
+
+/// Which adapter the renderer asks for, so machines with several GPUs can pick one
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum AdapterPreference {
+    /// Whatever wgpu picks first
+    #[default]
+    Default,
+    /// Usually the discrete GPU
+    HighPerformance,
+    /// Usually the integrated GPU
+    LowPower,
+    /// The first adapter whose name contains this, ignoring case
+    Name(String),
+    Backend(wgpu::Backend),
+}
+
+impl AdapterPreference {
+    /// Set to part of an adapter name, such as "nvidia", to override the app's preference
+    pub const NAME_VARIABLE: &'static str = "WGPU_ADAPTER_NAME";
+    /// Set to "high" or "low" to override the app's preference
+    pub const POWER_VARIABLE: &'static str = "WGPU_POWER_PREF";
+
+    /// The preference given by the environment, if any
+    pub fn from_env() -> Option<Self> {
+        if let Ok(name) = std::env::var(Self::NAME_VARIABLE) {
+            return Some(Self::Name(name));
+        }
+        match std::env::var(Self::POWER_VARIABLE)
+            .ok()?
+            .to_lowercase()
+            .as_str()
+        {
+            "high" => Some(Self::HighPerformance),
+            "low" => Some(Self::LowPower),
+            other => {
+                log::warn!("Ignoring unknown {}: {other}", Self::POWER_VARIABLE);
+                None
+            }
+        }
+    }
+
+    pub fn power_preference(&self) -> wgpu::PowerPreference {
+        match self {
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
+            Self::Default | Self::Name(_) | Self::Backend(_) => wgpu::PowerPreference::default(),
+        }
+    }
+
+    /// True for adapters this preference asks for by name or backend.
+    /// Power preferences are left to wgpu and match nothing here.
+    pub fn matches(&self, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+            Self::Backend(backend) => info.backend == *backend,
+            Self::Default | Self::HighPerformance | Self::LowPower => false,
+        }
+    }
+}
+
+/// Picks an adapter for the preference, falling back to wgpu's choice when nothing matches
+pub(crate) async fn select_adapter(
+    instance: &wgpu::Instance,
+    surface: Option<&wgpu::Surface>,
+    backends: wgpu::Backends,
+    preference: &AdapterPreference,
+) -> Option<wgpu::Adapter> {
+    if matches!(
+        preference,
+        AdapterPreference::Name(_) | AdapterPreference::Backend(_)
+    ) {
+        let adapter = instance.enumerate_adapters(backends).find(|adapter| {
+            preference.matches(&adapter.get_info())
+                && surface.map_or(true, |surface| adapter.is_surface_supported(surface))
+        });
+        if adapter.is_some() {
+            return adapter;
+        }
+        log::warn!("No adapter matches {preference:?}, using the default adapter");
+    }
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: preference.power_preference(),
+            compatible_surface: surface,
+            force_fallback_adapter: false,
+        })
+        .await
+}
+
+/// A dropdown listing the available adapters with the one in use selected.
+/// Switching adapters needs a new device, so other entries explain how to relaunch instead.
+pub fn show_adapters(ui: &mut egui::Ui, renderer: &Renderer) {
+    let current = &renderer.adapter_info;
+    let label = |info: &wgpu::AdapterInfo| format!("{} ({:?})", info.name, info.backend);
+    egui::ComboBox::from_label("Adapter")
+        .selected_text(label(current))
+        .show_ui(ui, |ui| {
+            for info in renderer.enumerate_adapters() {
+                let in_use = info.name == current.name && info.backend == current.backend;
+                let response = ui.selectable_label(in_use, label(&info));
+                if !in_use {
+                    response.on_hover_text(format!(
+                        "Relaunch with {}=\"{}\" to use this adapter",
+                        AdapterPreference::NAME_VARIABLE,
+                        info.name
+                    ));
+                }
+            }
+        });
+    ui.label(format!(
+        "{:?} on {:?}, driver {} {}",
+        current.device_type, current.backend, current.driver, current.driver_info
+    ));
+    ui.label(format!("Surface format {:?}", renderer.config.format));
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use egui::{Context as GuiContext, FullOutput};
+use std::path::Path;
 use wgpu::RenderPass;
 use winit::{
     dpi::PhysicalSize,
@@ -8,7 +9,12 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
-use crate::{create_screen_descriptor, Gui, Input, Renderer, System, Viewport};
+use crate::{
+    apply_config_overrides, benchmark_frames, capture_doc, capture_doc_directory, capture_frame,
+    capture_frame_arguments, create_screen_descriptor, init_console_logger, AdapterPreference,
+    Benchmark, FrameCapture, Gui, Input, KeyBindings, RenderSettings, Renderer, RendererOptions,
+    System, Viewport,
+};
 
 pub struct Resources<'a> {
     pub application: &'a mut (dyn Application + 'static),
@@ -17,6 +23,8 @@ pub struct Resources<'a> {
     pub system: &'a mut System,
     pub renderer: &'a mut Renderer,
     pub window: &'a mut Window,
+    /// Present when started with `--benchmark`
+    pub benchmark: &'a mut Option<Benchmark>,
 }
 
 pub trait Application {
@@ -24,7 +32,12 @@ pub trait Application {
         Ok(())
     }
 
-    fn update(&mut self, _renderer: &mut Renderer, _input: &Input, _system: &System) -> Result<()> {
+    fn update(
+        &mut self,
+        _renderer: &mut Renderer,
+        _input: &mut Input,
+        _system: &System,
+    ) -> Result<()> {
         Ok(())
     }
 
@@ -40,6 +53,21 @@ pub trait Application {
         None
     }
 
+    /// Features the device must have, checked before it is created
+    fn required_features(&self) -> wgpu::Features {
+        wgpu::Features::empty()
+    }
+
+    /// Features enabled when the adapter has them, check `device.features()` before use
+    fn optional_features(&self) -> wgpu::Features {
+        wgpu::Features::empty()
+    }
+
+    /// Limits the device must support, checked before it is created
+    fn required_limits(&self) -> wgpu::Limits {
+        wgpu::Limits::default()
+    }
+
     fn render<'a: 'b, 'b>(
         &'a mut self,
         _view: &'a wgpu::TextureView,
@@ -52,6 +80,12 @@ pub trait Application {
         Ok(())
     }
 
+    /// Called before each update while benchmarking, with how far along the run is from 0 to 1.
+    /// Examples move their camera along a fixed path here so runs can be compared.
+    fn benchmark_step(&mut self, _renderer: &mut Renderer, _progress: f32) -> Result<()> {
+        Ok(())
+    }
+
     fn on_mouse(&mut self, _button: &MouseButton, _button_state: &ElementState) -> Result<()> {
         Ok(())
     }
@@ -60,6 +94,10 @@ pub trait Application {
         Ok(())
     }
 
+    fn on_file_dropped(&mut self, _renderer: &mut Renderer, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
     fn handle_event(&mut self, _event: &Event<()>, _window: &Window) -> Result<()> {
         Ok(())
     }
@@ -69,19 +107,86 @@ pub struct AppConfig {
     pub title: String,
     pub width: u32,
     pub height: u32,
+    /// Overridden by `WGPU_ADAPTER_NAME` or `WGPU_POWER_PREF` when either is set
+    pub adapter_preference: AdapterPreference,
+    /// Falls back to the automatic choice when the surface doesn't support it.
+    /// The format in use is `renderer.config.format`, which pipelines should target.
+    pub surface_format: Option<wgpu::TextureFormat>,
+    /// Pick an sRGB surface format when none is requested
+    pub prefer_srgb: bool,
+    /// Lets the desktop show through where the window's alpha is below one,
+    /// see `Renderer::clear_color` and `Renderer::blend_state`
+    pub transparent: bool,
+    /// Borderless fullscreen on the current monitor
+    pub fullscreen: bool,
+    /// Applied once the renderer is created
+    pub render_settings: RenderSettings,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            title: "wgpu".to_string(),
+            width: 800,
+            height: 600,
+            adapter_preference: AdapterPreference::default(),
+            surface_format: None,
+            prefer_srgb: true,
+            transparent: false,
+            fullscreen: false,
+            render_settings: RenderSettings::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
 }
 
-pub fn run(mut application: impl Application + 'static, config: AppConfig) -> Result<()> {
-    env_logger::init();
+impl AppConfig {
+    pub fn renderer_options(&self, application: &dyn Application) -> RendererOptions {
+        RendererOptions {
+            adapter_preference: AdapterPreference::from_env()
+                .unwrap_or_else(|| self.adapter_preference.clone()),
+            required_features: application.required_features(),
+            optional_features: application.optional_features(),
+            required_limits: application.required_limits(),
+            surface_format: self.surface_format,
+            prefer_srgb: self.prefer_srgb,
+            transparent: self.transparent,
+        }
+    }
+}
+
+pub fn run(mut application: impl Application + 'static, mut config: AppConfig) -> Result<()> {
+    init_console_logger(log::LevelFilter::Info)?;
     log::info!("App started");
 
+    apply_config_overrides(&mut config)?;
+
+    if let Some(directory) = capture_doc_directory() {
+        return capture_doc(&mut application, &config, &directory);
+    }
+
+    if let Some((path, frames)) = capture_frame_arguments() {
+        return capture_frame(&mut application, &config, &path, frames);
+    }
+
     let event_loop = EventLoop::new();
     let mut window = WindowBuilder::new()
-        .with_title(config.title)
+        .with_title(&config.title)
         .with_inner_size(PhysicalSize::new(config.width, config.height))
-        .with_transparent(true)
+        .with_transparent(config.transparent)
+        .with_fullscreen(
+            config
+                .fullscreen
+                .then_some(winit::window::Fullscreen::Borderless(None)),
+        )
         .build(&event_loop)?;
 
+    let benchmark_frames = benchmark_frames();
+    let mut options = config.renderer_options(&application);
+    if benchmark_frames.is_some() {
+        options.optional_features |= wgpu::Features::TIMESTAMP_QUERY;
+    }
     let mut renderer = Renderer::new(
         &window,
         &Viewport {
@@ -89,16 +194,29 @@ pub fn run(mut application: impl Application + 'static, config: AppConfig) -> Re
             height: config.height,
             ..Default::default()
         },
+        &options,
     )?;
 
+    renderer.apply_settings(config.render_settings);
+
     let mut gui = Gui::new(&window, &event_loop);
 
     let window_dimensions = window.inner_size();
     let mut input = Input::default();
+    input.bindings = config.key_bindings.clone();
     let mut system = System::new(window_dimensions);
 
     application.initialize(&mut renderer)?;
 
+    let mut benchmark = benchmark_frames.map(|frames| {
+        log::info!("Benchmarking {frames} frames");
+        renderer.apply_settings(RenderSettings {
+            vsync: false,
+            ..renderer.settings
+        });
+        Benchmark::new(&renderer.device, &renderer.queue, frames)
+    });
+
     event_loop.run(move |event, _, control_flow| {
         let mut resources = Resources {
             application: &mut application,
@@ -107,6 +225,7 @@ pub fn run(mut application: impl Application + 'static, config: AppConfig) -> Re
             system: &mut system,
             renderer: &mut renderer,
             window: &mut window,
+            benchmark: &mut benchmark,
         };
         if let Err(error) = run_loop(&mut resources, &event, control_flow) {
             log::error!("Application error: {}", error);
@@ -126,15 +245,12 @@ fn run_loop(
         input,
         system,
         window,
+        benchmark,
     } = resources;
 
     let gui_captured_event = match event {
-        Event::WindowEvent { event, window_id } => {
-            if *window_id == window.id() {
-                gui.handle_window_event(event).consumed
-            } else {
-                false
-            }
+        Event::WindowEvent { event, window_id } if *window_id == window.id() => {
+            gui.handle_window_event(event).consumed
         }
         _ => false,
     };
@@ -144,6 +260,9 @@ fn run_loop(
         input.handle_event(event, system.window_center());
     }
 
+    let exit_key = input.bindings.key("exit", VirtualKeyCode::Escape);
+    let frame_capture_key = input.bindings.key("frame_capture", FrameCapture::HOTKEY);
+
     match event {
         Event::MainEventsCleared => {
             let output =
@@ -155,7 +274,13 @@ fn run_loop(
             } = output;
             let paint_jobs = gui.context.tessellate(shapes);
             let screen_descriptor = create_screen_descriptor(window);
+            if let Some(benchmark) = benchmark.as_mut() {
+                benchmark.begin_frame();
+                application.benchmark_step(renderer, benchmark.progress())?;
+            }
+            renderer.update_globals(system.delta_time, input.mouse.position);
             application.update(renderer, input, system)?;
+            input.update_cursor(window);
 
             renderer.render_frame(
                 &textures_delta,
@@ -163,12 +288,29 @@ fn run_loop(
                 application.depth_format(),
                 &screen_descriptor,
                 |view, encoder, gui| {
+                    if let Some(benchmark) = benchmark.as_ref() {
+                        benchmark.begin_commands(encoder);
+                    }
                     if let Ok(Some(mut render_pass)) = application.render(view, encoder) {
                         gui.render(&mut render_pass, &screen_descriptor, &paint_jobs);
                     }
+                    if let Some(benchmark) = benchmark.as_ref() {
+                        benchmark.end_commands(encoder);
+                    }
                     Ok(())
                 },
             )?;
+
+            if let Some(benchmark) = benchmark.as_mut() {
+                benchmark.end_frame(&renderer.device)?;
+                if benchmark.is_finished() {
+                    benchmark.write(
+                        &renderer.adapter_info,
+                        (renderer.config.width, renderer.config.height),
+                    )?;
+                    *control_flow = ControlFlow::Exit;
+                }
+            }
         }
         Event::WindowEvent {
             ref event,
@@ -176,10 +318,13 @@ fn run_loop(
         } if *window_id == window.id() => match event {
             WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
             WindowEvent::KeyboardInput { input, .. } => {
-                if let (Some(VirtualKeyCode::Escape), ElementState::Pressed) =
-                    (input.virtual_keycode, input.state)
-                {
-                    *control_flow = ControlFlow::Exit;
+                if input.state == ElementState::Pressed {
+                    if input.virtual_keycode == Some(exit_key) {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    if input.virtual_keycode == Some(frame_capture_key) {
+                        renderer.trigger_capture();
+                    }
                 }
 
                 if let Some(keycode) = input.virtual_keycode.as_ref() {
@@ -187,6 +332,10 @@ fn run_loop(
                 }
             }
             WindowEvent::MouseInput { button, state, .. } => application.on_mouse(button, state)?,
+            WindowEvent::DroppedFile(path) => {
+                log::info!("File dropped: {}", path.display());
+                application.on_file_dropped(renderer, path)?;
+            }
             WindowEvent::Resized(physical_size) => {
                 renderer.resize([physical_size.width, physical_size.height]);
                 application.resize(renderer)?;
@@ -194,6 +343,12 @@ fn run_loop(
             _ => {}
         },
         Event::LoopDestroyed => {
+            if let Some(benchmark) = benchmark.as_mut() {
+                benchmark.write(
+                    &renderer.adapter_info,
+                    (renderer.config.width, renderer.config.height),
+                )?;
+            }
             application.cleanup()?;
         }
         _ => {}
@@ -0,0 +1,252 @@
+use crate::AssetCache;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// What an asset file holds, judged by its extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AssetKind {
+    Texture,
+    Model,
+    Other,
+}
+
+impl AssetKind {
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("png" | "jpg" | "jpeg" | "gif" | "bmp" | "tga" | "hdr") => Self::Texture,
+            Some("gltf" | "glb" | "obj") => Self::Model,
+            _ => Self::Other,
+        }
+    }
+
+    /// Shown on the tile until a thumbnail exists, and for kinds that never get one
+    fn icon(&self) -> &'static str {
+        match self {
+            Self::Texture => "🖼",
+            Self::Model => "📦",
+            Self::Other => "📄",
+        }
+    }
+}
+
+enum Thumbnail {
+    Pending,
+    Ready(egui::TextureHandle),
+    Unavailable,
+}
+
+struct AssetEntry {
+    path: PathBuf,
+    name: String,
+    kind: AssetKind,
+    thumbnail: Thumbnail,
+}
+
+/// Lists the files under an asset directory as a grid of thumbnails.
+/// Thumbnails are decoded a few per frame and kept in the asset cache between launches.
+pub struct AssetBrowser {
+    pub root: PathBuf,
+    /// Edge length of a tile in points
+    pub tile_size: f32,
+    pub filter: String,
+    cache: AssetCache,
+    entries: Vec<AssetEntry>,
+    selected: Option<usize>,
+}
+
+impl AssetBrowser {
+    pub const DEFAULT_DIRECTORY: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
+
+    /// Edge length of generated thumbnails in pixels
+    const THUMBNAIL_SIZE: u32 = 128;
+
+    /// Thumbnails generated per frame, so a large folder doesn't stall the GUI
+    const THUMBNAILS_PER_FRAME: usize = 2;
+
+    pub fn new(root: impl Into<PathBuf>, cache: AssetCache) -> Self {
+        let mut browser = Self {
+            root: root.into(),
+            tile_size: 96.0,
+            filter: String::new(),
+            cache,
+            entries: Vec::new(),
+            selected: None,
+        };
+        browser.rescan();
+        browser
+    }
+
+    /// Walks the root directory again, dropping thumbnails of files that are gone
+    pub fn rescan(&mut self) {
+        let mut paths = Vec::new();
+        if let Err(error) = collect_files(&self.root, &mut paths) {
+            log::warn!("{error:#}");
+        }
+        paths.sort();
+
+        let mut previous = std::mem::take(&mut self.entries);
+        self.entries = paths
+            .into_iter()
+            .map(|path| {
+                if let Some(index) = previous.iter().position(|entry| entry.path == path) {
+                    return previous.swap_remove(index);
+                }
+                let name = path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .into_owned();
+                let kind = AssetKind::from_path(&path);
+                let thumbnail = match kind {
+                    AssetKind::Texture => Thumbnail::Pending,
+                    AssetKind::Model | AssetKind::Other => Thumbnail::Unavailable,
+                };
+                AssetEntry {
+                    path,
+                    name,
+                    kind,
+                    thumbnail,
+                }
+            })
+            .collect();
+        self.selected = None;
+    }
+
+    /// Shows the browser, returning the asset the user double clicked
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<PathBuf> {
+        self.generate_thumbnails(ui.ctx());
+
+        ui.horizontal(|ui| {
+            if ui.button("Rescan").clicked() {
+                self.rescan();
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text("Filter"));
+        });
+        ui.separator();
+
+        let filter = self.filter.to_lowercase();
+        let visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| filter.is_empty() || entry.name.to_lowercase().contains(&filter))
+            .collect::<Vec<_>>();
+        let tile = egui::vec2(self.tile_size, self.tile_size);
+        let spacing = ui.spacing().item_spacing.x;
+        let columns = ((ui.available_width() + spacing) / (self.tile_size + spacing)).max(1.0);
+        let mut opened = None;
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for row in visible.chunks(columns as usize) {
+                    ui.horizontal_top(|ui| {
+                        for (index, entry) in row.iter().copied() {
+                            let selected = self.selected == Some(index);
+                            let response = ui
+                                .vertical(|ui| {
+                                    ui.set_width(self.tile_size);
+                                    let response = match &entry.thumbnail {
+                                        Thumbnail::Ready(texture) => ui.add(
+                                            egui::ImageButton::new((texture.id(), tile))
+                                                .selected(selected),
+                                        ),
+                                        Thumbnail::Pending | Thumbnail::Unavailable => ui
+                                            .add_sized(
+                                                tile,
+                                                egui::SelectableLabel::new(
+                                                    selected,
+                                                    egui::RichText::new(entry.kind.icon())
+                                                        .size(32.0),
+                                                ),
+                                            ),
+                                    };
+                                    ui.add(egui::Label::new(&entry.name).truncate(true));
+                                    response
+                                })
+                                .inner
+                                .on_hover_text(entry.path.display().to_string());
+                            if response.clicked() {
+                                self.selected = Some(index);
+                            }
+                            if response.double_clicked() {
+                                opened = Some(entry.path.clone());
+                            }
+                        }
+                    });
+                }
+            });
+        opened
+    }
+
+    fn generate_thumbnails(&mut self, context: &egui::Context) {
+        let pending = self
+            .entries
+            .iter_mut()
+            .filter(|entry| matches!(entry.thumbnail, Thumbnail::Pending))
+            .take(Self::THUMBNAILS_PER_FRAME);
+        for entry in pending {
+            entry.thumbnail = match load_thumbnail(&self.cache, &entry.path) {
+                Ok(image) => Thumbnail::Ready(context.load_texture(
+                    entry.name.clone(),
+                    image,
+                    egui::TextureOptions::LINEAR,
+                )),
+                Err(error) => {
+                    log::warn!("{error:#}");
+                    Thumbnail::Unavailable
+                }
+            };
+        }
+    }
+}
+
+fn collect_files(directory: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read asset directory {}", directory.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Downscales an image to fit the thumbnail size, reusing a cached result for unchanged files
+fn load_thumbnail(cache: &AssetCache, path: &Path) -> Result<egui::ColorImage> {
+    const HEADER_SIZE: usize = 8;
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read asset {}", path.display()))?;
+    let thumbnail = cache.get_or_insert_with("thumbnail", &bytes, || {
+        let image = image::load_from_memory(&bytes)
+            .with_context(|| format!("Failed to decode {}", path.display()))?
+            .thumbnail(AssetBrowser::THUMBNAIL_SIZE, AssetBrowser::THUMBNAIL_SIZE)
+            .to_rgba8();
+        let mut encoded = Vec::with_capacity(HEADER_SIZE + image.len());
+        encoded.extend_from_slice(&image.width().to_le_bytes());
+        encoded.extend_from_slice(&image.height().to_le_bytes());
+        encoded.extend_from_slice(&image);
+        Ok(encoded)
+    })?;
+    anyhow::ensure!(
+        thumbnail.len() >= HEADER_SIZE,
+        "Cached thumbnail is truncated"
+    );
+    let (header, rgba) = thumbnail.split_at(HEADER_SIZE);
+    let width = u32::from_le_bytes(header[0..4].try_into()?) as usize;
+    let height = u32::from_le_bytes(header[4..8].try_into()?) as usize;
+    anyhow::ensure!(
+        rgba.len() == 4 * width * height,
+        "Cached thumbnail has the wrong size"
+    );
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        [width, height],
+        rgba,
+    ))
+}
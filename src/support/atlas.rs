@@ -0,0 +1,343 @@
+use crate::Texture;
+use anyhow::{bail, Result};
+use image::RgbaImage;
+use std::collections::HashMap;
+
+/// Where an image ended up in an atlas, in pixels and in texture coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AtlasRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SkylineSegment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+/// Packs rectangles into a fixed size area by tracking the top edge of everything placed so far,
+/// putting each new rectangle wherever its top edge ends up lowest
+#[derive(Debug, Clone)]
+pub struct SkylinePacker {
+    width: u32,
+    height: u32,
+    skyline: Vec<SkylineSegment>,
+    used_area: u64,
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![SkylineSegment { x: 0, y: 0, width }],
+            used_area: 0,
+        }
+    }
+
+    /// Returns the top left corner of the placed rectangle, or `None` if it doesn't fit
+    pub fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (index, x, y) = (0..self.skyline.len())
+            .filter_map(|index| {
+                self.fit(index, width, height)
+                    .map(|y| (index, self.skyline[index].x, y))
+            })
+            .min_by_key(|&(_, x, y)| (y + height, x))?;
+
+        self.skyline.insert(
+            index,
+            SkylineSegment {
+                x,
+                y: y + height,
+                width,
+            },
+        );
+
+        // Trim the segments now covered by the new one
+        let right = x + width;
+        let next = index + 1;
+        while next < self.skyline.len() && self.skyline[next].x < right {
+            let segment = &mut self.skyline[next];
+            let segment_right = segment.x + segment.width;
+            if segment_right <= right {
+                self.skyline.remove(next);
+            } else {
+                segment.width = segment_right - right;
+                segment.x = right;
+                break;
+            }
+        }
+
+        // Merge neighbors at the same height
+        self.skyline.dedup_by(|next, previous| {
+            let merge = previous.y == next.y;
+            if merge {
+                previous.width += next.width;
+            }
+            merge
+        });
+
+        self.used_area += width as u64 * height as u64;
+        Some((x, y))
+    }
+
+    /// Fraction of the area covered by packed rectangles
+    pub fn occupancy(&self) -> f32 {
+        self.used_area as f32 / (self.width as u64 * self.height as u64).max(1) as f32
+    }
+
+    /// The height a rectangle would be placed at if its left edge starts at this segment
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].x;
+        if x + width > self.width {
+            return None;
+        }
+        let mut y = 0;
+        let mut remaining = width as i64;
+        for segment in &self.skyline[index..] {
+            if remaining <= 0 {
+                break;
+            }
+            y = y.max(segment.y);
+            remaining -= segment.width as i64;
+        }
+        (y + height <= self.height).then_some(y)
+    }
+}
+
+/// Collects images and packs them into a single texture when built,
+/// so everything drawn from the atlas can share one bind group
+#[derive(Default)]
+pub struct AtlasBuilder {
+    /// Border around each image, filled by repeating its edge pixels so filtering never bleeds between neighbors
+    pub padding: u32,
+    images: Vec<(String, RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new(padding: u32) -> Self {
+        Self {
+            padding,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, image: RgbaImage) -> &mut Self {
+        self.images.push((name.into(), image));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Packs into the smallest power of two square that fits, up to `max_size` on a side
+    pub fn build(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        max_size: u32,
+    ) -> Result<TextureAtlas> {
+        let (image, regions, occupancy) = self.pack(max_size)?;
+        let (width, height) = image.dimensions();
+        let texture = Texture::from_rgba(device, queue, &image, width, height, Some("Atlas"));
+        Ok(TextureAtlas {
+            texture,
+            width,
+            height,
+            occupancy,
+            regions,
+        })
+    }
+
+    /// Packs the images on the CPU, returning the atlas pixels, regions and occupancy
+    pub fn pack(&self, max_size: u32) -> Result<(RgbaImage, HashMap<String, AtlasRegion>, f32)> {
+        if let Some((name, _)) = self
+            .images
+            .iter()
+            .find(|(_, image)| image.width() == 0 || image.height() == 0)
+        {
+            bail!("Atlas image {name} is empty");
+        }
+        let padding = self.padding;
+        let padded =
+            |image: &RgbaImage| (image.width() + 2 * padding, image.height() + 2 * padding);
+
+        // Placing tall images first leaves a flatter skyline for the rest
+        let mut order = (0..self.images.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| {
+            let (width, height) = padded(&self.images[index].1);
+            std::cmp::Reverse((height, width))
+        });
+
+        let area = self
+            .images
+            .iter()
+            .map(|(_, image)| {
+                let (width, height) = padded(image);
+                width as u64 * height as u64
+            })
+            .sum::<u64>();
+        let widest = self
+            .images
+            .iter()
+            .map(|(_, image)| {
+                let (width, height) = padded(image);
+                width.max(height)
+            })
+            .max()
+            .unwrap_or(1);
+        let mut size = ((area as f64).sqrt().ceil() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        loop {
+            if size > max_size {
+                bail!(
+                    "{} images don't fit in a {max_size}x{max_size} atlas",
+                    self.images.len()
+                );
+            }
+            let mut packer = SkylinePacker::new(size, size);
+            let placements = order
+                .iter()
+                .map(|&index| {
+                    let (width, height) = padded(&self.images[index].1);
+                    packer.pack(width, height).map(|corner| (index, corner))
+                })
+                .collect::<Option<Vec<_>>>();
+            let Some(placements) = placements else {
+                size *= 2;
+                continue;
+            };
+
+            let mut atlas = RgbaImage::new(size, size);
+            let mut regions = HashMap::with_capacity(placements.len());
+            for (index, (x, y)) in placements {
+                let (name, image) = &self.images[index];
+                let (width, height) = image.dimensions();
+                let (padded_width, padded_height) = padded(image);
+                for row in 0..padded_height {
+                    for column in 0..padded_width {
+                        let source_x = column.saturating_sub(padding).min(width - 1);
+                        let source_y = row.saturating_sub(padding).min(height - 1);
+                        atlas.put_pixel(x + column, y + row, *image.get_pixel(source_x, source_y));
+                    }
+                }
+                let (x, y) = (x + padding, y + padding);
+                regions.insert(
+                    name.clone(),
+                    AtlasRegion {
+                        x,
+                        y,
+                        width,
+                        height,
+                        uv_min: [x as f32 / size as f32, y as f32 / size as f32],
+                        uv_max: [
+                            (x + width) as f32 / size as f32,
+                            (y + height) as f32 / size as f32,
+                        ],
+                    },
+                );
+            }
+            return Ok((atlas, regions, packer.occupancy()));
+        }
+    }
+}
+
+/// Many images packed into one texture, looked up by the names they were added with
+pub struct TextureAtlas {
+    pub texture: Texture,
+    pub width: u32,
+    pub height: u32,
+    /// Fraction of the texture covered by images and their padding
+    pub occupancy: f32,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn packed_rectangles_stay_in_bounds_without_overlapping() {
+        let mut state = 0x2545_F491_u32;
+        let mut random = move |range: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            1 + state % range
+        };
+        let mut packer = SkylinePacker::new(256, 256);
+        let mut placed = Vec::<(u32, u32, u32, u32)>::new();
+        let mut area = 0;
+        for _ in 0..400 {
+            let (width, height) = (random(40), random(40));
+            let Some((x, y)) = packer.pack(width, height) else {
+                continue;
+            };
+            assert!(x + width <= 256 && y + height <= 256);
+            let rectangle = (x, y, width, height);
+            assert!(placed.iter().all(|other| !overlaps(rectangle, *other)));
+            placed.push(rectangle);
+            area += width * height;
+        }
+        assert!(placed.len() > 40);
+        assert_eq!(packer.occupancy(), area as f32 / (256.0 * 256.0));
+    }
+
+    #[test]
+    fn rectangles_larger_than_the_area_are_rejected() {
+        let mut packer = SkylinePacker::new(64, 32);
+        assert_eq!(packer.pack(65, 1), None);
+        assert_eq!(packer.pack(1, 33), None);
+        assert_eq!(packer.pack(64, 32), Some((0, 0)));
+        assert_eq!(packer.pack(1, 1), None);
+    }
+
+    #[test]
+    fn builder_regions_hold_their_images() {
+        let mut builder = AtlasBuilder::new(2);
+        for (index, (width, height)) in [(30, 10), (8, 40), (16, 16), (5, 5)]
+            .into_iter()
+            .enumerate()
+        {
+            let color = image::Rgba([index as u8 * 60, 0, 0, 255]);
+            builder.add(
+                format!("image{index}"),
+                RgbaImage::from_pixel(width, height, color),
+            );
+        }
+        let (atlas, regions, _) = builder.pack(1024).unwrap();
+        assert_eq!(regions.len(), 4);
+        for (name, region) in &regions {
+            let index = name["image".len()..].parse::<u8>().unwrap();
+            assert!(region.x + region.width <= atlas.width());
+            assert!(region.y + region.height <= atlas.height());
+            for (x, y) in [(0, 0), (region.width - 1, region.height - 1)] {
+                let pixel = atlas.get_pixel(region.x + x, region.y + y);
+                assert_eq!(pixel[0], index * 60);
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::Transform;
+use anyhow::{Context, Result};
+use nalgebra_glm as glm;
+use rodio::{
+    source::SineWave, Decoder, OutputStream, OutputStreamHandle, Sink, Source, SpatialSink,
+};
+use std::{collections::HashMap, io::Cursor, sync::Arc, time::Duration};
+
+/// Sound data registered under a name
+#[derive(Debug, Clone)]
+pub enum Sound {
+    /// An encoded file such as wav, ogg, flac or mp3, decoded each time it plays
+    Encoded(Arc<[u8]>),
+    /// A sine tone, handy for examples that ship without audio files
+    Tone { frequency: f32, duration: f32 },
+}
+
+impl Sound {
+    fn source(&self) -> Result<Box<dyn Source<Item = f32> + Send>> {
+        Ok(match self {
+            Self::Encoded(bytes) => Box::new(
+                Decoder::new(Cursor::new(bytes.clone()))
+                    .context("Failed to decode sound")?
+                    .convert_samples(),
+            ),
+            Self::Tone {
+                frequency,
+                duration,
+            } => {
+                let duration = Duration::from_secs_f32(*duration);
+                Box::new(
+                    SineWave::new(*frequency)
+                        .take_duration(duration)
+                        .fade_in(duration / 8)
+                        .amplify(0.25),
+                )
+            }
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Listener {
+    left_ear: glm::Vec3,
+    right_ear: glm::Vec3,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Self {
+            left_ear: glm::vec3(-Audio::EAR_OFFSET, 0.0, 0.0),
+            right_ear: glm::vec3(Audio::EAR_OFFSET, 0.0, 0.0),
+        }
+    }
+}
+
+/// Plays named sounds on the default output device. Positional sounds are panned and
+/// attenuated relative to a listener that examples move along with their camera.
+pub struct Audio {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    sounds: HashMap<String, Sound>,
+    /// Sounds played when an event is triggered, keyed by event name
+    bindings: HashMap<String, String>,
+    listener: Listener,
+    /// Positional sounds still playing, kept so they follow the listener
+    emitters: Vec<SpatialSink>,
+    pub volume: f32,
+    /// Distance in world units at which positional sounds play at full volume,
+    /// falling off with the inverse square of the distance beyond it
+    pub reference_distance: f32,
+}
+
+impl Audio {
+    /// Half the distance between the listener's ears in world units
+    const EAR_OFFSET: f32 = 0.1;
+
+    pub fn new() -> Result<Self> {
+        let (stream, handle) =
+            OutputStream::try_default().context("Failed to open the audio output device")?;
+        Ok(Self {
+            _stream: stream,
+            handle,
+            sounds: HashMap::new(),
+            bindings: HashMap::new(),
+            listener: Listener::default(),
+            emitters: Vec::new(),
+            volume: 1.0,
+            reference_distance: 2.0,
+        })
+    }
+
+    /// Registers an encoded sound file, checking that it decodes
+    pub fn load(&mut self, name: &str, bytes: impl Into<Arc<[u8]>>) -> Result<()> {
+        let sound = Sound::Encoded(bytes.into());
+        sound
+            .source()
+            .with_context(|| format!("Failed to load sound '{name}'"))?;
+        self.sounds.insert(name.to_string(), sound);
+        Ok(())
+    }
+
+    pub fn load_tone(&mut self, name: &str, frequency: f32, duration: f32) {
+        self.sounds.insert(
+            name.to_string(),
+            Sound::Tone {
+                frequency,
+                duration,
+            },
+        );
+    }
+
+    /// Plays a sound at the same volume in both ears
+    pub fn play(&self, name: &str) -> Result<()> {
+        let sink = Sink::try_new(&self.handle)?;
+        sink.set_volume(self.volume);
+        sink.append(self.sound(name)?.source()?);
+        sink.detach();
+        Ok(())
+    }
+
+    /// Plays a sound from a point in the world
+    pub fn play_at(&mut self, name: &str, position: glm::Vec3) -> Result<()> {
+        let source = self.sound(name)?.source()?;
+        let sink = SpatialSink::try_new(
+            &self.handle,
+            self.to_audio_space(&position),
+            self.to_audio_space(&self.listener.left_ear),
+            self.to_audio_space(&self.listener.right_ear),
+        )?;
+        sink.set_volume(self.volume);
+        sink.append(source);
+        self.emitters.push(sink);
+        Ok(())
+    }
+
+    /// Plays the sound bound to an event, if any, from a point in the world when one is given
+    pub fn trigger(&mut self, event: &str, position: Option<glm::Vec3>) -> Result<()> {
+        let Some(name) = self.bindings.get(event).cloned() else {
+            return Ok(());
+        };
+        match position {
+            Some(position) => self.play_at(&name, position),
+            None => self.play(&name),
+        }
+    }
+
+    pub fn bind(&mut self, event: &str, sound: &str) {
+        self.bindings.insert(event.to_string(), sound.to_string());
+    }
+
+    /// Moves the listener's ears to the transform, usually the camera's,
+    /// and drops positional sounds that have finished
+    pub fn set_listener(&mut self, transform: &Transform) {
+        let offset = transform.right() * Self::EAR_OFFSET;
+        self.listener = Listener {
+            left_ear: transform.translation - offset,
+            right_ear: transform.translation + offset,
+        };
+        self.emitters.retain(|sink| !sink.empty());
+        let left_ear = self.to_audio_space(&self.listener.left_ear);
+        let right_ear = self.to_audio_space(&self.listener.right_ear);
+        for sink in self.emitters.iter() {
+            sink.set_left_ear_position(left_ear);
+            sink.set_right_ear_position(right_ear);
+        }
+    }
+
+    fn sound(&self, name: &str) -> Result<&Sound> {
+        self.sounds
+            .get(name)
+            .with_context(|| format!("No sound named '{name}'"))
+    }
+
+    /// rodio attenuates by the inverse square of the distance in its own units,
+    /// so positions are scaled to make one unit the reference distance
+    fn to_audio_space(&self, position: &glm::Vec3) -> [f32; 3] {
+        let scaled = position / self.reference_distance.max(f32::EPSILON);
+        [scaled.x, scaled.y, scaled.z]
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{example_name, QueryPool};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// Runs the example for a fixed number of frames with `--benchmark [frames]`,
+/// then writes the frame times to the benchmark directory and exits
+pub const BENCHMARK_FLAG: &str = "--benchmark";
+pub const DEFAULT_BENCHMARK_DIRECTORY: &str = "benchmarks";
+
+/// The frame count if the example was started with `--benchmark [frames]`
+pub fn benchmark_frames() -> Option<usize> {
+    let mut arguments = std::env::args().skip_while(|argument| argument != BENCHMARK_FLAG);
+    arguments.next()?;
+    Some(
+        arguments
+            .next()
+            .and_then(|frames| frames.parse().ok())
+            .unwrap_or(Benchmark::DEFAULT_FRAMES),
+    )
+}
+
+/// Timings for one benchmarked frame in milliseconds
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct FrameSample {
+    pub frame: usize,
+    /// From the start of the frame's update until it was presented
+    pub cpu: f64,
+    /// Between timestamps written at the start and end of the frame's commands,
+    /// absent when the adapter lacks timestamp queries
+    pub gpu: Option<f64>,
+}
+
+/// Mean and percentiles of a series of frame times in milliseconds
+#[derive(Debug, Copy, Clone, Default, Serialize)]
+pub struct TimingSummary {
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl TimingSummary {
+    pub fn new(mut times: Vec<f64>) -> Option<Self> {
+        if times.is_empty() {
+            return None;
+        }
+        times.sort_by(f64::total_cmp);
+        let percentile = |percentile: f64| {
+            let index = ((times.len() - 1) as f64 * percentile).round() as usize;
+            times[index]
+        };
+        Some(Self {
+            mean: times.iter().sum::<f64>() / times.len() as f64,
+            min: times[0],
+            max: times[times.len() - 1],
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BenchmarkReport<'a> {
+    example: &'a str,
+    adapter: &'a str,
+    backend: String,
+    width: u32,
+    height: u32,
+    frames: usize,
+    cpu: Option<TimingSummary>,
+    gpu: Option<TimingSummary>,
+    samples: &'a [FrameSample],
+}
+
+/// Measures GPU time with a pair of timestamps around a frame's commands.
+/// Several are used in turn so reading one back doesn't stall the frames after it.
+struct GpuTimer {
+    queries: QueryPool,
+    /// The recorded sample waiting for this timer's results, unset during warmup
+    sample: Option<usize>,
+}
+
+impl GpuTimer {
+    /// Timers in flight, the results of a frame are read back this many frames later at most
+    const COUNT: usize = 3;
+
+    fn new(device: &wgpu::Device) -> Self {
+        Self {
+            queries: QueryPool::new(
+                device,
+                "Benchmark Timestamps",
+                wgpu::QueryType::Timestamp,
+                2,
+            ),
+            sample: None,
+        }
+    }
+
+    /// Stores the frame's duration in its sample once the timestamps reached the CPU,
+    /// `period` being nanoseconds per timestamp tick
+    fn collect(&mut self, samples: &mut [FrameSample], period: f64) -> Result<()> {
+        let Some(timestamps) = self.queries.try_read()? else {
+            return Ok(());
+        };
+        if let Some(sample) = self.sample.take() {
+            let ticks = timestamps[1].saturating_sub(timestamps[0]);
+            samples[sample].gpu = Some(ticks as f64 * period / 1_000_000.0);
+        }
+        Ok(())
+    }
+}
+
+/// Records CPU and GPU frame times while the example runs a fixed number of frames.
+/// The application's `benchmark_step` hook moves its camera along a repeatable path.
+pub struct Benchmark {
+    pub frames: usize,
+    pub directory: PathBuf,
+    samples: Vec<FrameSample>,
+    /// Empty when the adapter lacks timestamp queries
+    gpu_timers: Vec<GpuTimer>,
+    /// Nanoseconds per timestamp tick
+    timestamp_period: f64,
+    /// Counts warmup frames too, to pick the GPU timer
+    frame_index: usize,
+    frame_start: Option<Instant>,
+    warmup_remaining: usize,
+    written: bool,
+}
+
+impl Benchmark {
+    pub const DEFAULT_FRAMES: usize = 1000;
+
+    /// Frames rendered before recording starts, so shader compilation
+    /// and first use allocations don't skew the results
+    pub const WARMUP_FRAMES: usize = 10;
+
+    /// GPU times need the device to have been created with `wgpu::Features::TIMESTAMP_QUERY`
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, frames: usize) -> Self {
+        let gpu_timers = if device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            (0..GpuTimer::COUNT)
+                .map(|_| GpuTimer::new(device))
+                .collect()
+        } else {
+            log::warn!("The adapter lacks timestamp queries, only CPU times will be recorded");
+            Vec::new()
+        };
+        Self {
+            frames,
+            directory: PathBuf::from(DEFAULT_BENCHMARK_DIRECTORY),
+            samples: Vec::with_capacity(frames),
+            gpu_timers,
+            timestamp_period: queue.get_timestamp_period() as f64,
+            frame_index: 0,
+            frame_start: None,
+            warmup_remaining: Self::WARMUP_FRAMES,
+            written: false,
+        }
+    }
+
+    /// How far along the recorded frames the benchmark is, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.samples.len() as f32 / self.frames.max(1) as f32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.samples.len() >= self.frames
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Some(Instant::now());
+    }
+
+    /// Writes the starting timestamp, call with the frame's first encoder
+    pub fn begin_commands(&self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(timer) = self.current_timer() {
+            encoder.write_timestamp(&timer.queries.query_set, 0);
+        }
+    }
+
+    /// Writes the ending timestamp and copies both to the readback buffer
+    pub fn end_commands(&self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(timer) = self.current_timer() {
+            encoder.write_timestamp(&timer.queries.query_set, 1);
+            timer.queries.resolve(encoder);
+        }
+    }
+
+    /// Call after the frame is submitted. GPU times are filled in a few frames later,
+    /// once their timestamps reach the CPU, and waited for at the end of the run.
+    pub fn end_frame(&mut self, device: &wgpu::Device) -> Result<()> {
+        let Some(start) = self.frame_start.take() else {
+            return Ok(());
+        };
+        let cpu = start.elapsed().as_secs_f64() * 1000.0;
+        let sample = if self.warmup_remaining > 0 {
+            self.warmup_remaining -= 1;
+            None
+        } else {
+            self.samples.push(FrameSample {
+                frame: self.samples.len(),
+                cpu,
+                gpu: None,
+            });
+            Some(self.samples.len() - 1)
+        };
+
+        if !self.gpu_timers.is_empty() {
+            let count = self.gpu_timers.len();
+            let timer = &mut self.gpu_timers[self.frame_index % count];
+            timer.sample = sample;
+            timer.queries.map();
+
+            // The next frame resolves into the next timer, which has to be read by then
+            let next = &self.gpu_timers[(self.frame_index + 1) % count];
+            let maintain = if next.queries.is_mapping() || self.is_finished() {
+                wgpu::Maintain::Wait
+            } else {
+                wgpu::Maintain::Poll
+            };
+            device.poll(maintain);
+            for timer in self.gpu_timers.iter_mut() {
+                timer.collect(&mut self.samples, self.timestamp_period)?;
+            }
+        }
+        self.frame_index += 1;
+        Ok(())
+    }
+
+    fn current_timer(&self) -> Option<&GpuTimer> {
+        let count = self.gpu_timers.len();
+        (count > 0).then(|| &self.gpu_timers[self.frame_index % count])
+    }
+
+    /// Writes a CSV of every sample and a JSON report with summaries, named after the example.
+    /// Only the first call writes, so an early exit and a finished run don't both report.
+    pub fn write(&mut self, adapter_info: &wgpu::AdapterInfo, size: (u32, u32)) -> Result<()> {
+        if std::mem::replace(&mut self.written, true) {
+            return Ok(());
+        }
+        let example = example_name()?;
+        std::fs::create_dir_all(&self.directory).with_context(|| {
+            format!(
+                "Failed to create benchmark directory {}",
+                self.directory.display()
+            )
+        })?;
+
+        let mut csv = String::from("frame,cpu_ms,gpu_ms\n");
+        for sample in self.samples.iter() {
+            let gpu = sample.gpu.map(|gpu| gpu.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{},{gpu}\n", sample.frame, sample.cpu));
+        }
+        let csv_path = self.directory.join(format!("{example}.csv"));
+        write_file(&csv_path, &csv)?;
+
+        let report = BenchmarkReport {
+            example: &example,
+            adapter: &adapter_info.name,
+            backend: format!("{:?}", adapter_info.backend),
+            width: size.0,
+            height: size.1,
+            frames: self.samples.len(),
+            cpu: TimingSummary::new(self.samples.iter().map(|sample| sample.cpu).collect()),
+            gpu: TimingSummary::new(
+                self.samples
+                    .iter()
+                    .filter_map(|sample| sample.gpu)
+                    .collect(),
+            ),
+            samples: &self.samples,
+        };
+        let json_path = self.directory.join(format!("{example}.json"));
+        write_file(&json_path, &serde_json::to_string_pretty(&report)?)?;
+
+        if let Some(cpu) = report.cpu {
+            log::info!(
+                "Benchmarked {} frames, CPU mean {:.3} ms, p99 {:.3} ms",
+                report.frames,
+                cpu.mean,
+                cpu.p99
+            );
+        }
+        if let Some(gpu) = report.gpu {
+            log::info!("GPU mean {:.3} ms, p99 {:.3} ms", gpu.mean, gpu.p99);
+        }
+        log::info!(
+            "Wrote benchmark results to {} and {}",
+            csv_path.display(),
+            json_path.display()
+        );
+        Ok(())
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
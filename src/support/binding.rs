@@ -0,0 +1,184 @@
+use wgpu::{
+    BindGroup, BindGroupLayout, BindGroupLayoutEntry, BindingResource, BindingType, Device,
+    ShaderStages,
+};
+
+/// Builds a bind group layout one binding at a time, e.g.
+/// `BindingBuilder::new().uniform(0, VERTEX).texture(1, FRAGMENT).sampler(2, FRAGMENT).build(device)`
+#[derive(Default)]
+pub struct BindingBuilder {
+    label: Option<String>,
+    entries: Vec<BindGroupLayoutEntry>,
+}
+
+impl BindingBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the layout and every bind group created from it in debuggers and validation errors
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn entry(mut self, binding: u32, visibility: ShaderStages, ty: BindingType) -> Self {
+        self.entries.push(BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty,
+            count: None,
+        });
+        self
+    }
+
+    pub fn uniform(self, binding: u32, visibility: ShaderStages) -> Self {
+        self.buffer(binding, visibility, wgpu::BufferBindingType::Uniform, false)
+    }
+
+    /// A uniform whose offset is given when the bind group is set, to index an array of values
+    pub fn dynamic_uniform(self, binding: u32, visibility: ShaderStages) -> Self {
+        self.buffer(binding, visibility, wgpu::BufferBindingType::Uniform, true)
+    }
+
+    pub fn storage(self, binding: u32, visibility: ShaderStages, read_only: bool) -> Self {
+        let ty = wgpu::BufferBindingType::Storage { read_only };
+        self.buffer(binding, visibility, ty, false)
+    }
+
+    /// A 2D float texture that can be sampled with filtering
+    pub fn texture(self, binding: u32, visibility: ShaderStages) -> Self {
+        let sample_type = wgpu::TextureSampleType::Float { filterable: true };
+        self.texture_with(binding, visibility, sample_type, false)
+    }
+
+    /// A 2D float texture read without filtering, such as a depth buffer on backends that can't
+    /// bind depth textures directly
+    pub fn unfilterable_texture(self, binding: u32, visibility: ShaderStages) -> Self {
+        let sample_type = wgpu::TextureSampleType::Float { filterable: false };
+        self.texture_with(binding, visibility, sample_type, false)
+    }
+
+    pub fn depth_texture(self, binding: u32, visibility: ShaderStages) -> Self {
+        self.texture_with(binding, visibility, wgpu::TextureSampleType::Depth, false)
+    }
+
+    pub fn texture_with(
+        self,
+        binding: u32,
+        visibility: ShaderStages,
+        sample_type: wgpu::TextureSampleType,
+        multisampled: bool,
+    ) -> Self {
+        let ty = BindingType::Texture {
+            sample_type,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled,
+        };
+        self.entry(binding, visibility, ty)
+    }
+
+    /// A write only 2D storage texture
+    pub fn storage_texture(
+        self,
+        binding: u32,
+        visibility: ShaderStages,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let ty = BindingType::StorageTexture {
+            access: wgpu::StorageTextureAccess::WriteOnly,
+            format,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        };
+        self.entry(binding, visibility, ty)
+    }
+
+    pub fn sampler(self, binding: u32, visibility: ShaderStages) -> Self {
+        let ty = BindingType::Sampler(wgpu::SamplerBindingType::Filtering);
+        self.entry(binding, visibility, ty)
+    }
+
+    pub fn non_filtering_sampler(self, binding: u32, visibility: ShaderStages) -> Self {
+        let ty = BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering);
+        self.entry(binding, visibility, ty)
+    }
+
+    /// For depth comparisons, such as sampling a shadow map
+    pub fn comparison_sampler(self, binding: u32, visibility: ShaderStages) -> Self {
+        let ty = BindingType::Sampler(wgpu::SamplerBindingType::Comparison);
+        self.entry(binding, visibility, ty)
+    }
+
+    pub fn build(self, device: &Device) -> BindingLayout {
+        let layout_label = self
+            .label
+            .as_ref()
+            .map(|label| format!("{label} Bind Group Layout"));
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: layout_label.as_deref(),
+            entries: &self.entries,
+        });
+        BindingLayout {
+            layout,
+            label: self.label,
+            entries: self.entries,
+        }
+    }
+
+    fn buffer(
+        self,
+        binding: u32,
+        visibility: ShaderStages,
+        ty: wgpu::BufferBindingType,
+        has_dynamic_offset: bool,
+    ) -> Self {
+        let ty = BindingType::Buffer {
+            ty,
+            has_dynamic_offset,
+            min_binding_size: None,
+        };
+        self.entry(binding, visibility, ty)
+    }
+}
+
+/// A bind group layout that remembers its bindings, so bind groups can be made from resources alone
+pub struct BindingLayout {
+    pub layout: BindGroupLayout,
+    label: Option<String>,
+    entries: Vec<BindGroupLayoutEntry>,
+}
+
+impl BindingLayout {
+    /// The entries in the order they were added, such as for validating against a shader's reflection
+    pub fn entries(&self) -> &[BindGroupLayoutEntry] {
+        &self.entries
+    }
+
+    /// Resources are given in the order their bindings were added to the builder
+    pub fn bind_group(&self, device: &Device, resources: &[BindingResource]) -> BindGroup {
+        assert_eq!(
+            resources.len(),
+            self.entries.len(),
+            "{:?} expects one resource per binding",
+            self.label
+        );
+        let label = self
+            .label
+            .as_ref()
+            .map(|label| format!("{label} Bind Group"));
+        let entries = self
+            .entries
+            .iter()
+            .zip(resources.iter())
+            .map(|(entry, resource)| wgpu::BindGroupEntry {
+                binding: entry.binding,
+                resource: resource.clone(),
+            })
+            .collect::<Vec<_>>();
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: label.as_deref(),
+            layout: &self.layout,
+            entries: &entries,
+        })
+    }
+}
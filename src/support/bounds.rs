@@ -0,0 +1,99 @@
+use nalgebra_glm as glm;
+use serde::{Deserialize, Serialize};
+
+/// An axis aligned bounding box
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: glm::Vec3,
+    pub max: glm::Vec3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Aabb {
+    /// An inverted box that any point or box can be merged into
+    pub const EMPTY: Self = Self {
+        min: glm::Vec3::new(f32::MAX, f32::MAX, f32::MAX),
+        max: glm::Vec3::new(f32::MIN, f32::MIN, f32::MIN),
+    };
+
+    pub fn new(min: glm::Vec3, max: glm::Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = glm::Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |mut aabb, point| {
+            aabb.expand_to_include(&point);
+            aabb
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn expand_to_include(&mut self, point: &glm::Vec3) {
+        self.min = glm::min2(&self.min, point);
+        self.max = glm::max2(&self.max, point);
+    }
+
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            min: glm::min2(&self.min, &other.min),
+            max: glm::max2(&self.max, &other.max),
+        }
+    }
+
+    /// Grows the box by the same amount along every axis
+    pub fn inflate(&self, amount: f32) -> Self {
+        let amount = glm::vec3(amount, amount, amount);
+        Self {
+            min: self.min - amount,
+            max: self.max + amount,
+        }
+    }
+
+    pub fn center(&self) -> glm::Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> glm::Vec3 {
+        self.max - self.min
+    }
+
+    pub fn half_extents(&self) -> glm::Vec3 {
+        self.extents() * 0.5
+    }
+
+    /// Radius of the sphere centered on the box that contains all of its corners
+    pub fn bounding_radius(&self) -> f32 {
+        self.half_extents().magnitude()
+    }
+
+    pub fn corners(&self) -> [glm::Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            glm::vec3(min.x, min.y, min.z),
+            glm::vec3(max.x, min.y, min.z),
+            glm::vec3(min.x, max.y, min.z),
+            glm::vec3(max.x, max.y, min.z),
+            glm::vec3(min.x, min.y, max.z),
+            glm::vec3(max.x, min.y, max.z),
+            glm::vec3(min.x, max.y, max.z),
+            glm::vec3(max.x, max.y, max.z),
+        ]
+    }
+
+    /// The box enclosing this box after it has been transformed
+    pub fn transform(&self, matrix: &glm::Mat4) -> Self {
+        Self::from_points(
+            self.corners()
+                .iter()
+                .map(|corner| (matrix * corner.push(1.0)).xyz()),
+        )
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{Aabb, Frustum, Ray};
+use nalgebra_glm as glm;
+
+/// A bounding volume hierarchy over a set of boxes, built once and queried every frame
+#[derive(Default, Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Indices into the boxes the hierarchy was built from, ordered so each leaf covers a contiguous range
+    items: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    bounds: Aabb,
+    /// For leaves this is the first item, for interior nodes the index of the second child
+    offset: usize,
+    /// Zero for interior nodes, whose first child immediately follows them
+    count: usize,
+}
+
+impl Bvh {
+    pub const MAX_LEAF_SIZE: usize = 4;
+
+    pub fn new(boxes: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(boxes.len() * 2),
+            items: (0..boxes.len())
+                .filter(|index| !boxes[*index].is_empty())
+                .collect(),
+        };
+        if !bvh.items.is_empty() {
+            let centers = boxes.iter().map(Aabb::center).collect::<Vec<_>>();
+            bvh.build(boxes, &centers, 0, bvh.items.len());
+        }
+        bvh
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.bounds)
+    }
+
+    /// Splits the items at the median of the longest axis of their centers
+    fn build(&mut self, boxes: &[Aabb], centers: &[glm::Vec3], start: usize, end: usize) -> usize {
+        let items = &mut self.items[start..end];
+        let bounds = items
+            .iter()
+            .fold(Aabb::EMPTY, |bounds, item| bounds.merge(&boxes[*item]));
+        let node_index = self.nodes.len();
+        self.nodes.push(BvhNode {
+            bounds,
+            offset: start,
+            count: items.len(),
+        });
+        if items.len() <= Self::MAX_LEAF_SIZE {
+            return node_index;
+        }
+
+        let axis = Aabb::from_points(items.iter().map(|item| centers[*item]))
+            .extents()
+            .imax();
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |a, b| {
+            centers[*a][axis].total_cmp(&centers[*b][axis])
+        });
+
+        self.build(boxes, centers, start, start + middle);
+        let second_child = self.build(boxes, centers, start + middle, end);
+        let node = &mut self.nodes[node_index];
+        node.offset = second_child;
+        node.count = 0;
+        node_index
+    }
+
+    /// Visits the item indices of every leaf whose bounds pass the test
+    pub fn traverse(&self, mut test: impl FnMut(&Aabb) -> bool, mut visit: impl FnMut(usize)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !test(&node.bounds) {
+                continue;
+            }
+            if node.count > 0 {
+                self.items[node.offset..node.offset + node.count]
+                    .iter()
+                    .for_each(|item| visit(*item));
+            } else {
+                stack.push(node.offset);
+                stack.push(node_index + 1);
+            }
+        }
+    }
+
+    /// Indices of the boxes that may be visible, for frustum culling
+    pub fn query_frustum(&self, frustum: &Frustum, boxes: &[Aabb]) -> Vec<usize> {
+        let mut visible = Vec::new();
+        self.traverse(
+            |bounds| frustum.intersects_aabb(bounds),
+            |item| {
+                if frustum.intersects_aabb(&boxes[item]) {
+                    visible.push(item);
+                }
+            },
+        );
+        visible
+    }
+
+    /// Finds the closest item hit by the ray, where `intersect` returns the exact distance to an item
+    pub fn raycast(
+        &self,
+        ray: &Ray,
+        mut intersect: impl FnMut(usize) -> Option<f32>,
+    ) -> Option<(usize, f32)> {
+        let mut closest: Option<(usize, f32)> = None;
+        let mut stack = Vec::new();
+        if let Some(node) = self.nodes.first() {
+            if ray.intersect_aabb(&node.bounds).is_some() {
+                stack.push(0);
+            }
+        }
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.count > 0 {
+                for item in &self.items[node.offset..node.offset + node.count] {
+                    if let Some(distance) = intersect(*item) {
+                        if closest.map_or(true, |(_, closest)| distance < closest) {
+                            closest = Some((*item, distance));
+                        }
+                    }
+                }
+                continue;
+            }
+            // Visit the nearer child first so farther subtrees can be skipped once a hit is found
+            let mut children = [node_index + 1, node.offset].map(|child| {
+                (
+                    child,
+                    ray.intersect_aabb(&self.nodes[child].bounds)
+                        .filter(|distance| {
+                            closest.map_or(true, |(_, closest)| *distance < closest)
+                        }),
+                )
+            });
+            children.sort_by(|a, b| match (a.1, b.1) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            });
+            stack.extend(
+                children
+                    .into_iter()
+                    .filter_map(|(child, distance)| distance.map(|_| child)),
+            );
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic boxes scattered through a cube, some of them empty
+    fn random_boxes(count: usize) -> Vec<Aabb> {
+        let mut state = 0x9E37_79B9_u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32
+        };
+        (0..count)
+            .map(|index| {
+                if index % 17 == 0 {
+                    return Aabb::EMPTY;
+                }
+                let center =
+                    glm::vec3(random(), random(), random()) * 40.0 - glm::vec3(20.0, 20.0, 20.0);
+                let half_extents = glm::vec3(random(), random(), random()) * 1.5;
+                Aabb::new(center - half_extents, center + half_extents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frustum_query_matches_brute_force() {
+        let boxes = random_boxes(500);
+        let bvh = Bvh::new(&boxes);
+        let projection = glm::perspective_rh_zo(1.5, 60_f32.to_radians(), 0.1, 30.0);
+        for eye in [glm::vec3(0.0, 0.0, 25.0), glm::vec3(-15.0, 10.0, 5.0)] {
+            let view = glm::look_at_rh(&eye, &glm::vec3(3.0, 0.0, 0.0), &glm::Vec3::y());
+            let frustum = Frustum::from_matrix(&(projection * view));
+
+            let mut visible = bvh.query_frustum(&frustum, &boxes);
+            visible.sort_unstable();
+            let expected = (0..boxes.len())
+                .filter(|index| frustum.intersects_aabb(&boxes[*index]))
+                .collect::<Vec<_>>();
+            assert!(!expected.is_empty() && expected.len() < boxes.len());
+            assert_eq!(visible, expected);
+        }
+    }
+
+    #[test]
+    fn raycast_finds_the_closest_box() {
+        let boxes = random_boxes(500);
+        let bvh = Bvh::new(&boxes);
+        let mut hits = 0;
+        for step in 0..64 {
+            let angle = step as f32 * 0.3;
+            let origin = glm::vec3(angle.cos(), 0.5, angle.sin()) * 30.0;
+            let target = glm::vec3(angle.sin(), (angle * 0.7).cos(), angle.cos()) * 10.0;
+            let ray = Ray::new(origin, (target - origin).normalize());
+
+            let hit = bvh.raycast(&ray, |item| ray.intersect_aabb(&boxes[item]));
+            let expected = boxes
+                .iter()
+                .enumerate()
+                .filter_map(|(index, aabb)| {
+                    ray.intersect_aabb(aabb).map(|distance| (index, distance))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            assert_eq!(
+                hit.map(|(_, distance)| distance),
+                expected.map(|(_, distance)| distance)
+            );
+            hits += usize::from(hit.is_some());
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn frustum_planes_bound_the_view() {
+        let projection = glm::perspective_rh_zo(1.0, 90_f32.to_radians(), 1.0, 10.0);
+        let view = glm::look_at_rh(
+            &glm::vec3(0.0, 0.0, 5.0),
+            &glm::Vec3::zeros(),
+            &glm::Vec3::y(),
+        );
+        let frustum = Frustum::from_matrix(&(projection * view));
+        assert!(frustum.contains_point(&glm::Vec3::zeros()));
+        assert!(!frustum.contains_point(&glm::vec3(0.0, 0.0, 6.0)));
+        assert!(!frustum.contains_point(&glm::vec3(0.0, 0.0, -6.0)));
+        assert!(!frustum.contains_point(&glm::vec3(6.0, 0.0, 0.0)));
+        assert!(frustum.intersects_aabb(&Aabb::new(
+            glm::vec3(4.0, -1.0, -1.0),
+            glm::vec3(6.0, 1.0, 1.0)
+        )));
+        assert!(!frustum.intersects_aabb(&Aabb::EMPTY));
+    }
+}
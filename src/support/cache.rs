@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// A content addressed on-disk cache for processed assets, shared by all examples
+#[derive(Debug, Clone)]
+pub struct AssetCache {
+    pub directory: PathBuf,
+    pub enabled: bool,
+}
+
+impl Default for AssetCache {
+    fn default() -> Self {
+        let directory = std::env::var_os(Self::DIRECTORY_VARIABLE)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(Self::DEFAULT_DIRECTORY));
+        Self::new(directory)
+    }
+}
+
+impl AssetCache {
+    /// Overrides the cache location when set
+    pub const DIRECTORY_VARIABLE: &'static str = "WGPU_EXAMPLES_ASSET_CACHE";
+    pub const DEFAULT_DIRECTORY: &'static str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/target/asset_cache");
+
+    /// Bump this when the format of any processed asset changes to invalidate old entries
+    pub const VERSION: u32 = 1;
+
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            enabled: true,
+        }
+    }
+
+    /// Hashes the source bytes of an asset along with the kind of processing applied to it
+    pub fn key(kind: &str, source: &[u8]) -> String {
+        let mut hash = Fnv1a::default();
+        hash.write(&Self::VERSION.to_le_bytes());
+        hash.write(kind.as_bytes());
+        hash.write(&[0]);
+        hash.write(source);
+        format!("{:016x}", hash.0)
+    }
+
+    pub fn path(&self, kind: &str, key: &str) -> PathBuf {
+        self.directory.join(kind).join(format!("{key}.bin"))
+    }
+
+    pub fn get(&self, kind: &str, source: &[u8]) -> Option<Vec<u8>> {
+        if !self.enabled {
+            return None;
+        }
+        std::fs::read(self.path(kind, &Self::key(kind, source))).ok()
+    }
+
+    pub fn insert(&self, kind: &str, source: &[u8], processed: &[u8]) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let path = self.path(kind, &Self::key(kind, source));
+        write_atomic(&path, processed)
+            .with_context(|| format!("Failed to write cached asset to {}", path.display()))
+    }
+
+    /// Returns the cached result of processing the source, running and storing the processing on a miss
+    pub fn get_or_insert_with(
+        &self,
+        kind: &str,
+        source: &[u8],
+        process: impl FnOnce() -> Result<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        if let Some(processed) = self.get(kind, source) {
+            log::debug!("Asset cache hit for {kind}");
+            return Ok(processed);
+        }
+        let processed = process()?;
+        if let Err(error) = self.insert(kind, source, &processed) {
+            log::warn!("{error:#}");
+        }
+        Ok(processed)
+    }
+
+    /// Removes every cached asset
+    pub fn clear(&self) -> Result<()> {
+        if self.directory.exists() {
+            std::fs::remove_dir_all(&self.directory).with_context(|| {
+                format!(
+                    "Failed to clear asset cache at {}",
+                    self.directory.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes to a temporary file first so concurrently running examples never read a partial entry
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temporary, contents)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use crate::{Input, System, Transform};
-use anyhow::Result;
+use crate::{Aabb, Input, Ray, System, Transform};
+use anyhow::{Context, Result};
 use nalgebra_glm as glm;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use winit::event::VirtualKeyCode;
 
 #[derive(Default)]
 pub struct MouseOrbit {
@@ -17,7 +19,7 @@ impl MouseOrbit {
 
         if input.mouse.is_left_clicked {
             let mut delta = input.mouse.position_delta;
-            delta.x = -1.0 * input.mouse.position_delta.x;
+            delta.x = -input.mouse.position_delta.x;
             delta *= system.delta_time as f32;
             self.orientation.rotate(&delta);
         }
@@ -36,8 +38,195 @@ impl MouseOrbit {
     pub fn projection_view_matrix(&self, aspect_ratio: f32) -> glm::Mat4 {
         self.camera.projection_matrix(aspect_ratio) * self.transform.as_view_matrix()
     }
+
+    /// Centers the orbit on the box and backs away until the whole box fits in view
+    pub fn frame_bounds(&mut self, aabb: &Aabb) {
+        if aabb.is_empty() {
+            return;
+        }
+        let half_fov = self.camera.y_fov_rad * 0.5;
+        let radius = (aabb.bounding_radius() / half_fov.sin()).max(self.orientation.min_radius);
+        self.orientation.max_radius = self.orientation.max_radius.max(radius);
+        self.orientation.radius = radius;
+        self.orientation.offset = aabb.center();
+        self.transform.translation = self.orientation.position();
+        self.transform.rotation = self.orientation.look_at_offset();
+    }
+}
+
+/// Rotates, pans and zooms around a pivot point, typically picked on the surface under the cursor
+pub struct Arcball {
+    pub camera: PerspectiveCamera,
+    pub transform: Transform,
+    pub pivot: glm::Vec3,
+    pub rotate_sensitivity: f32,
+    pub pan_sensitivity: f32,
+    pub zoom_sensitivity: f32,
+    pub min_distance: f32,
+}
+
+impl Default for Arcball {
+    fn default() -> Self {
+        Self {
+            camera: PerspectiveCamera::default(),
+            transform: Transform::default(),
+            pivot: glm::vec3(0.0, 0.0, 0.0),
+            rotate_sensitivity: 0.005,
+            pan_sensitivity: 0.002,
+            zoom_sensitivity: 0.1,
+            min_distance: 0.1,
+        }
+    }
+}
+
+impl Arcball {
+    pub fn update(&mut self, input: &Input, _system: &System) -> Result<()> {
+        let delta = input.mouse.position_delta;
+
+        if input.mouse.is_left_clicked && delta.magnitude() > 0.0 {
+            let yaw = glm::quat_angle_axis(-delta.x * self.rotate_sensitivity, &glm::Vec3::y());
+            let pitch =
+                glm::quat_angle_axis(-delta.y * self.rotate_sensitivity, &self.transform.right());
+            self.orbit(&(yaw * pitch));
+        }
+
+        if input.mouse.is_right_clicked {
+            let scale = self.distance() * self.pan_sensitivity;
+            let offset = (self.transform.up() * delta.y - self.transform.right() * delta.x) * scale;
+            self.transform.translation += offset;
+            self.pivot += offset;
+        }
+
+        if input.mouse.wheel_delta.y != 0.0 {
+            let to_pivot = self.pivot - self.transform.translation;
+            let distance = to_pivot.magnitude();
+            let step = (distance * self.zoom_sensitivity * input.mouse.wheel_delta.y)
+                .min(distance - self.min_distance);
+            if distance > 0.0 {
+                self.transform.translation += to_pivot / distance * step;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves the pivot without moving the camera, so the next rotation turns around the new point
+    pub fn set_pivot(&mut self, pivot: glm::Vec3) {
+        self.pivot = pivot;
+    }
+
+    /// Places the camera at a position facing the pivot
+    pub fn look_at_pivot(&mut self, position: glm::Vec3, pivot: glm::Vec3) {
+        self.transform.translation = position;
+        self.pivot = pivot;
+        self.transform.look_at(&(pivot - position), &glm::Vec3::y());
+    }
+
+    pub fn distance(&self) -> f32 {
+        glm::distance(&self.pivot, &self.transform.translation)
+    }
+
+    pub fn projection_view_matrix(&self, aspect_ratio: f32) -> glm::Mat4 {
+        self.camera.projection_matrix(aspect_ratio) * self.transform.as_view_matrix()
+    }
+
+    fn orbit(&mut self, rotation: &glm::Quat) {
+        let offset = self.transform.translation - self.pivot;
+        let rotated = glm::quat_rotate_vec3(rotation, &offset);
+        let rotated_rotation = (rotation * self.transform.rotation).normalize();
+
+        // Refuse rotations that would flip the camera over the poles
+        let up = glm::quat_rotate_vec3(&rotated_rotation, &glm::Vec3::y());
+        if up.y <= 0.0 {
+            return;
+        }
+
+        self.transform.translation = self.pivot + rotated;
+        self.transform.rotation = rotated_rotation;
+    }
+}
+
+pub struct FirstPersonCamera {
+    pub camera: PerspectiveCamera,
+    pub transform: Transform,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub speed: f32,
+    pub sensitivity: f32,
+}
+
+impl Default for FirstPersonCamera {
+    fn default() -> Self {
+        Self {
+            camera: PerspectiveCamera::default(),
+            transform: Transform::default(),
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 4.0,
+            sensitivity: 0.002,
+        }
+    }
 }
 
+impl FirstPersonCamera {
+    const MAX_PITCH: f32 = 89.0;
+
+    /// Looks around using raw mouse motion while the input is in relative mode
+    pub fn update(&mut self, input: &Input, system: &System) -> Result<()> {
+        if input.relative_mode() {
+            let delta = input.mouse.motion_delta * self.sensitivity;
+            self.yaw -= delta.x;
+            let max_pitch = Self::MAX_PITCH.to_radians();
+            self.pitch = (self.pitch - delta.y).clamp(-max_pitch, max_pitch);
+        }
+
+        let forward = self.forward();
+        let right = forward.cross(&glm::Vec3::y()).normalize();
+        let mut direction = glm::vec3(0.0, 0.0, 0.0);
+        for (action, keycode, axis) in [
+            ("move_forward", VirtualKeyCode::W, forward),
+            ("move_backward", VirtualKeyCode::S, -forward),
+            ("move_right", VirtualKeyCode::D, right),
+            ("move_left", VirtualKeyCode::A, -right),
+            ("move_up", VirtualKeyCode::Space, glm::Vec3::y()),
+            ("move_down", VirtualKeyCode::LShift, -glm::Vec3::y()),
+        ] {
+            if input.is_action_pressed(action, keycode) {
+                direction += axis;
+            }
+        }
+        if direction.magnitude() > 0.0 {
+            self.transform.translation +=
+                direction.normalize() * self.speed * system.delta_time as f32;
+        }
+
+        self.transform.rotation =
+            glm::quat_conjugate(&glm::quat_look_at(&forward, &glm::Vec3::y()));
+
+        Ok(())
+    }
+
+    pub fn forward(&self) -> glm::Vec3 {
+        glm::vec3(
+            -self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    /// Points the camera at a target, keeping its current position
+    pub fn look_at(&mut self, target: &glm::Vec3) {
+        let direction = (target - self.transform.translation).normalize();
+        self.pitch = direction.y.asin();
+        self.yaw = (-direction.x).atan2(-direction.z);
+    }
+
+    pub fn projection_view_matrix(&self, aspect_ratio: f32) -> glm::Mat4 {
+        self.camera.projection_matrix(aspect_ratio) * self.transform.as_view_matrix()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Orientation {
     pub min_radius: f32,
     pub max_radius: f32,
@@ -139,6 +328,20 @@ impl Default for PerspectiveCamera {
 }
 
 impl PerspectiveCamera {
+    /// The world space origin and direction of the ray under a cursor position given in pixels
+    pub fn screen_ray(&self, transform: &Transform, cursor: glm::Vec2, viewport: glm::Vec2) -> Ray {
+        let ndc = glm::vec2(
+            2.0 * cursor.x / viewport.x - 1.0,
+            1.0 - 2.0 * cursor.y / viewport.y,
+        );
+        let aspect_ratio = self.aspect_ratio.unwrap_or(viewport.x / viewport.y);
+        let tan_half_fov = (self.y_fov_rad * 0.5).tan();
+        let direction = transform.forward()
+            + transform.right() * ndc.x * tan_half_fov * aspect_ratio
+            + transform.up() * ndc.y * tan_half_fov;
+        Ray::new(transform.translation, direction)
+    }
+
     pub fn projection_matrix(&self, viewport_aspect_ratio: f32) -> glm::Mat4 {
         let aspect_ratio = if let Some(aspect_ratio) = self.aspect_ratio {
             aspect_ratio
@@ -153,3 +356,344 @@ impl PerspectiveCamera {
         }
     }
 }
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub translation: glm::Vec3,
+    pub rotation: glm::Quat,
+}
+
+/// A camera fly-through that passes smoothly through every keyframe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    pub looping: bool,
+    /// Seconds between keyframes recorded with `record`
+    pub interval: f32,
+    #[serde(skip)]
+    pub time: f32,
+    #[serde(skip)]
+    pub playing: bool,
+    /// Views the scene through the path at the current time, even while paused
+    #[serde(skip)]
+    pub active: bool,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            looping: false,
+            interval: 2.0,
+            time: 0.0,
+            playing: false,
+            active: false,
+        }
+    }
+}
+
+impl CameraPath {
+    pub const DEFAULT_PATH: &'static str = "camera_path.ron";
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Appends a keyframe one interval after the last one
+    pub fn record(&mut self, transform: &Transform) {
+        let time = if self.keyframes.is_empty() {
+            0.0
+        } else {
+            self.duration() + self.interval
+        };
+        self.insert(time, transform);
+    }
+
+    pub fn insert(&mut self, time: f32, transform: &Transform) {
+        let index = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(
+            index,
+            CameraKeyframe {
+                time,
+                translation: transform.translation,
+                rotation: transform.rotation,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.time = 0.0;
+        self.playing = false;
+        self.active = false;
+    }
+
+    /// Advances playback, returning the camera transform while the path is active
+    pub fn update(&mut self, delta_time: f32) -> Option<Transform> {
+        if !self.active || self.keyframes.is_empty() {
+            return None;
+        }
+        if self.playing {
+            self.time += delta_time;
+            let duration = self.duration();
+            if self.time > duration {
+                if self.looping && duration > 0.0 {
+                    self.time = self.time.rem_euclid(duration);
+                } else {
+                    self.time = duration;
+                    self.playing = false;
+                }
+            }
+        }
+        self.sample(self.time)
+    }
+
+    /// Interpolates the path with a Catmull-Rom spline through the keyframes
+    pub fn sample(&self, time: f32) -> Option<Transform> {
+        let keyframes = &self.keyframes;
+        let (first, last) = (keyframes.first()?, keyframes.last()?);
+        if keyframes.len() == 1 || time <= first.time {
+            return Some(Self::keyframe_transform(first));
+        }
+        if time >= last.time {
+            return Some(Self::keyframe_transform(last));
+        }
+
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let current = next - 1;
+        let previous = current.saturating_sub(1);
+        let after = (next + 1).min(keyframes.len() - 1);
+        let [k0, k1, k2, k3] = [previous, current, next, after].map(|index| &keyframes[index]);
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            (time - k1.time) / span
+        } else {
+            0.0
+        };
+
+        let translation = catmull_rom(
+            &k0.translation,
+            &k1.translation,
+            &k2.translation,
+            &k3.translation,
+            t,
+        );
+
+        // Keep every quaternion in the same hemisphere so the spline takes the short way around
+        let align = |rotation: &glm::Quat, reference: &glm::Quat| {
+            if rotation.coords.dot(&reference.coords) < 0.0 {
+                -rotation.coords
+            } else {
+                rotation.coords
+            }
+        };
+        let q1 = k1.rotation.coords;
+        let q0 = align(&k0.rotation, &k1.rotation);
+        let q2 = align(&k2.rotation, &k1.rotation);
+        let q3 = align(&k3.rotation, &glm::Quat::from(q2));
+        let rotation = glm::Quat::from(catmull_rom(&q0, &q1, &q2, &q3, t)).normalize();
+
+        Some(Transform {
+            translation,
+            rotation,
+            ..Default::default()
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read camera path from {}", path.display()))?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write camera path to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Draws the record, playback and scrubbing controls
+    pub fn show(&mut self, ui: &mut egui::Ui, transform: &Transform) {
+        ui.horizontal(|ui| {
+            if ui.button("Record keyframe").clicked() {
+                self.record(transform);
+            }
+            if ui.button("Clear").clicked() {
+                self.clear();
+            }
+        });
+        ui.add(
+            egui::Slider::new(&mut self.interval, 0.1..=10.0)
+                .text("Keyframe interval")
+                .suffix("s"),
+        );
+
+        let can_play = self.keyframes.len() > 1;
+        ui.horizontal(|ui| {
+            let label = if self.playing { "Pause" } else { "Play" };
+            if ui.add_enabled(can_play, egui::Button::new(label)).clicked() {
+                if !self.playing && self.time >= self.duration() {
+                    self.time = 0.0;
+                }
+                self.playing = !self.playing;
+                self.active = true;
+            }
+            ui.checkbox(&mut self.looping, "Loop");
+            ui.checkbox(&mut self.active, "View through path");
+        });
+
+        let duration = self.duration();
+        let scrubbed = ui
+            .add_enabled(
+                can_play,
+                egui::Slider::new(&mut self.time, 0.0..=duration)
+                    .text("Time")
+                    .suffix("s"),
+            )
+            .changed();
+        if scrubbed {
+            self.active = true;
+        }
+        ui.label(format!("{} keyframes", self.keyframes.len()));
+
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                if let Err(error) = self.save(Self::DEFAULT_PATH) {
+                    log::error!("{error:#}");
+                }
+            }
+            if ui.button("Load").clicked() {
+                match Self::load(Self::DEFAULT_PATH) {
+                    Ok(path) => *self = path,
+                    Err(error) => log::error!("{error:#}"),
+                }
+            }
+        });
+    }
+
+    fn keyframe_transform(keyframe: &CameraKeyframe) -> Transform {
+        Transform {
+            translation: keyframe.translation,
+            rotation: keyframe.rotation,
+            ..Default::default()
+        }
+    }
+}
+
+fn catmull_rom<const D: usize>(
+    p0: &glm::TVec<f32, D>,
+    p1: &glm::TVec<f32, D>,
+    p2: &glm::TVec<f32, D>,
+    p3: &glm::TVec<f32, D>,
+    t: f32,
+) -> glm::TVec<f32, D> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(translation: glm::Vec3, yaw_degrees: f32) -> Transform {
+        Transform {
+            translation,
+            rotation: glm::quat_angle_axis(yaw_degrees.to_radians(), &glm::Vec3::y()),
+            ..Default::default()
+        }
+    }
+
+    fn recorded_path(looping: bool) -> CameraPath {
+        let mut path = CameraPath {
+            looping,
+            interval: 1.0,
+            active: true,
+            ..Default::default()
+        };
+        path.record(&transform(glm::vec3(0.0, 0.0, 0.0), 0.0));
+        path.record(&transform(glm::vec3(4.0, 0.0, 0.0), 30.0));
+        path.record(&transform(glm::vec3(4.0, 2.0, 4.0), 60.0));
+        path.record(&transform(glm::vec3(0.0, 2.0, 8.0), 90.0));
+        path
+    }
+
+    /// The rotation angle between two quaternions, ignoring which hemisphere each is stored in
+    fn angle_between(a: &glm::Quat, b: &glm::Quat) -> f32 {
+        2.0 * a.coords.dot(&b.coords).abs().min(1.0).acos()
+    }
+
+    #[test]
+    fn sample_passes_through_every_keyframe() {
+        let path = recorded_path(false);
+        assert_eq!(path.duration(), 3.0);
+        for keyframe in &path.keyframes {
+            let sample = path.sample(keyframe.time).unwrap();
+            assert!(glm::distance(&sample.translation, &keyframe.translation) < 1e-5);
+            assert!(angle_between(&sample.rotation, &keyframe.rotation) < 1e-3);
+        }
+        let first = &path.keyframes[0];
+        let last = &path.keyframes[3];
+        assert_eq!(path.sample(-1.0).unwrap().translation, first.translation);
+        assert_eq!(path.sample(10.0).unwrap().translation, last.translation);
+        assert!(CameraPath::default().sample(0.0).is_none());
+    }
+
+    #[test]
+    fn rotations_take_the_short_way_around() {
+        let mut path = CameraPath::default();
+        path.insert(0.0, &transform(glm::Vec3::zeros(), 0.0));
+        let mut flipped = transform(glm::Vec3::zeros(), 90.0);
+        flipped.rotation = glm::Quat::from(-flipped.rotation.coords);
+        path.insert(1.0, &flipped);
+        let halfway = path.sample(0.5).unwrap().rotation;
+        let expected = glm::quat_angle_axis(45_f32.to_radians(), &glm::Vec3::y());
+        assert!(angle_between(&halfway, &expected) < 1e-3);
+    }
+
+    #[test]
+    fn insert_keeps_keyframes_ordered() {
+        let mut path = recorded_path(false);
+        path.insert(1.5, &transform(glm::Vec3::zeros(), 0.0));
+        path.insert(0.0, &transform(glm::Vec3::zeros(), 0.0));
+        let times = path
+            .keyframes
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .collect::<Vec<_>>();
+        assert_eq!(times, [0.0, 0.0, 1.0, 1.5, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn playback_stops_at_the_end_or_loops() {
+        let mut path = recorded_path(false);
+        path.playing = true;
+        path.update(2.5);
+        assert!(path.playing);
+        let end = path.update(2.5).unwrap();
+        assert!(!path.playing);
+        assert_eq!(path.time, 3.0);
+        assert_eq!(end.translation, path.keyframes[3].translation);
+
+        let mut path = recorded_path(true);
+        path.playing = true;
+        path.update(2.5);
+        path.update(1.0);
+        assert!(path.playing);
+        assert!((path.time - 0.5).abs() < 1e-5);
+
+        path.active = false;
+        assert!(path.update(1.0).is_none());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{AppConfig, Application, Input, Renderer, System, Viewport};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use winit::dpi::PhysicalSize;
+
+/// Renders a single frame headlessly and writes it with the example's scene setup code to a gallery
+pub const CAPTURE_DOC_FLAG: &str = "--capture-doc";
+pub const DEFAULT_GALLERY_DIRECTORY: &str = "gallery";
+
+/// Renders headlessly and writes the final frame to the given png path
+pub const CAPTURE_FRAME_FLAG: &str = "--capture-frame";
+/// How many frames to run before a `--capture-frame` capture
+pub const FRAMES_FLAG: &str = "--frames";
+
+/// The gallery directory if the example was started with `--capture-doc [directory]`
+pub fn capture_doc_directory() -> Option<PathBuf> {
+    let mut arguments = std::env::args().skip_while(|argument| argument != CAPTURE_DOC_FLAG);
+    arguments.next()?;
+    Some(
+        arguments
+            .next()
+            .filter(|argument| !argument.starts_with("--"))
+            .map_or_else(|| PathBuf::from(DEFAULT_GALLERY_DIRECTORY), PathBuf::from),
+    )
+}
+
+/// The image path and frame count if the example was started with
+/// `--capture-frame <path> [--frames <count>]`
+pub fn capture_frame_arguments() -> Option<(PathBuf, usize)> {
+    let arguments = std::env::args().collect::<Vec<_>>();
+    let value_of = |flag: &str| {
+        arguments
+            .iter()
+            .position(|argument| argument == flag)
+            .and_then(|index| arguments.get(index + 1))
+    };
+    let path = PathBuf::from(value_of(CAPTURE_FRAME_FLAG)?);
+    let frames = value_of(FRAMES_FLAG)
+        .and_then(|frames| frames.parse().ok())
+        .unwrap_or(1);
+    Some((path, frames))
+}
+
+/// Writes the last of `frames` headless frames to a png, used by the golden image tests.
+/// Cleanup is skipped so a capture leaves no saved state behind.
+pub fn capture_frame(
+    application: &mut dyn Application,
+    config: &AppConfig,
+    path: &Path,
+    frames: usize,
+) -> Result<()> {
+    let image = render_headless(application, config, frames)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    image
+        .save(path)
+        .with_context(|| format!("Failed to save {}", path.display()))
+}
+
+pub fn capture_doc(
+    application: &mut dyn Application,
+    config: &AppConfig,
+    directory: &Path,
+) -> Result<()> {
+    let name = example_name()?;
+    log::info!("Capturing {name} to {}", directory.display());
+
+    let image = render_headless(application, config, 1)?;
+
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create gallery at {}", directory.display()))?;
+    let image_path = directory.join(format!("{name}.png"));
+    image
+        .save(&image_path)
+        .with_context(|| format!("Failed to save {}", image_path.display()))?;
+
+    let source_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/bin")
+        .join(format!("{name}.rs"));
+    let snippet = std::fs::read_to_string(&source_path)
+        .ok()
+        .and_then(|source| scene_snippet(&source));
+    if snippet.is_none() {
+        log::warn!("No scene setup found in {}", source_path.display());
+    }
+
+    let mut page = format!("# {}\n\n![{name}]({name}.png)\n", config.title);
+    if let Some(snippet) = snippet {
+        page.push_str(&format!("\n```rust\n{snippet}```\n"));
+    }
+    std::fs::write(directory.join(format!("{name}.md")), page)?;
+    write_index(directory)?;
+
+    application.cleanup()?;
+    Ok(())
+}
+
+/// Initializes the application against a headless renderer, then updates and renders it
+/// `frames` times with a fixed time step, reading back the last frame
+pub fn render_headless(
+    application: &mut dyn Application,
+    config: &AppConfig,
+    frames: usize,
+) -> Result<image::RgbaImage> {
+    let mut renderer = Renderer::new_headless(
+        &Viewport {
+            width: config.width,
+            height: config.height,
+            ..Default::default()
+        },
+        &config.renderer_options(application),
+    )?;
+    renderer.apply_settings(config.render_settings);
+    let mut input = Input::default();
+    let system = System::new(PhysicalSize::new(config.width, config.height));
+
+    application.initialize(&mut renderer)?;
+
+    let texture = renderer.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Capture Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: renderer.config.format,
+        usage: renderer.config.usage,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    for _ in 0..frames.max(1) {
+        renderer.update_globals(system.delta_time, input.mouse.position);
+        application.update(&mut renderer, &mut input, &system)?;
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+        drop(application.render(&view, &mut encoder)?);
+        renderer.submit(encoder);
+    }
+
+    read_texture(&renderer.device, &renderer.queue, &texture)
+}
+
+/// Copies a 2D texture with a four byte per pixel format back to the CPU
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<image::RgbaImage> {
+    let (width, height) = (texture.width(), texture.height());
+    let unpadded_bytes_per_row = 4 * width;
+    let padded_bytes_per_row =
+        wgpu::util::align_to(unpadded_bytes_per_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    let swap_red_blue = matches!(
+        texture.format(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+    );
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    for row in slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as usize)
+    {
+        pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+    buffer.unmap();
+    if swap_red_blue {
+        pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.swap(0, 2));
+    }
+
+    image::RgbaImage::from_raw(width, height, pixels).context("Readback has the wrong size")
+}
+
+pub(crate) fn example_name() -> Result<String> {
+    let executable = std::env::current_exe()?;
+    executable
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .context("Failed to determine the example name")
+}
+
+/// Extracts `Scene::new`, the part of each example that sets up its GPU resources
+fn scene_snippet(source: &str) -> Option<String> {
+    let scene = source.find("impl Scene {")?;
+    let start = scene + source[scene..].find("fn new(")?;
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+
+    let mut depth = 0;
+    let mut end = None;
+    for (offset, character) in source[start..].char_indices() {
+        match character {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(start + offset + 1);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let snippet = &source[line_start..end?];
+    let indentation = snippet.len() - snippet.trim_start().len();
+    Some(
+        snippet
+            .lines()
+            .map(|line| format!("{}\n", line.get(indentation..).unwrap_or(line.trim_start())))
+            .collect(),
+    )
+}
+
+fn write_index(directory: &Path) -> Result<()> {
+    let mut pages = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|extension| extension == "md")
+                && path.file_stem().is_some_and(|stem| stem != "README")
+        })
+        .collect::<Vec<_>>();
+    pages.sort();
+
+    let mut index = String::from(
+        "# Gallery\n\nGenerated with `cargo run --bin <example> -- --capture-doc`.\n\n",
+    );
+    for page in pages {
+        if let Some(name) = page.file_stem().and_then(|stem| stem.to_str()) {
+            index.push_str(&format!("- [{name}]({name}.md)\n"));
+        }
+    }
+    std::fs::write(directory.join("README.md"), index)?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::borrow::Cow;
+use wgpu::{util::DeviceExt, BindGroupLayout, Buffer, ComputePass, ComputePipeline, Device};
+
+/// A compute pipeline built from WGSL that writes `WORKGROUP_SIZE` wherever the workgroup size
+/// belongs, e.g. `@workgroup_size(WORKGROUP_SIZE)`, so the size can be tuned by rebuilding the kernel
+pub struct ComputeKernel {
+    pub pipeline: ComputePipeline,
+    pub workgroup_size: u32,
+}
+
+impl ComputeKernel {
+    pub const WORKGROUP_SIZE_TOKEN: &'static str = "WORKGROUP_SIZE";
+
+    /// The workgroup size is clamped to the device's limits
+    pub fn new(
+        device: &Device,
+        source: &str,
+        entry_point: &str,
+        bind_group_layouts: &[&BindGroupLayout],
+        workgroup_size: u32,
+    ) -> Self {
+        let limits = device.limits();
+        let workgroup_size = workgroup_size
+            .min(limits.max_compute_workgroup_size_x)
+            .min(limits.max_compute_invocations_per_workgroup)
+            .max(1);
+        let source = source.replace(Self::WORKGROUP_SIZE_TOKEN, &workgroup_size.to_string());
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(entry_point),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(entry_point),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&layout),
+            module: &module,
+            entry_point,
+        });
+
+        Self {
+            pipeline,
+            workgroup_size,
+        }
+    }
+
+    /// Workgroups needed to cover one invocation per item
+    pub fn workgroup_count(&self, invocations: u32) -> u32 {
+        (invocations + self.workgroup_size - 1) / self.workgroup_size
+    }
+
+    /// Sets the pipeline and dispatches one invocation per item, bind groups are set by the caller
+    pub fn dispatch<'a>(&'a self, pass: &mut ComputePass<'a>, invocations: u32) {
+        pass.set_pipeline(&self.pipeline);
+        pass.dispatch_workgroups(self.workgroup_count(invocations), 1, 1);
+    }
+
+    /// Dispatches one invocation per pixel of an image, with the column in
+    /// `global_invocation_id.x` and the row in `global_invocation_id.y`
+    pub fn dispatch_2d<'a>(&'a self, pass: &mut ComputePass<'a>, width: u32, height: u32) {
+        pass.set_pipeline(&self.pipeline);
+        pass.dispatch_workgroups(self.workgroup_count(width), height, 1);
+    }
+}
+
+/// Two storage buffers that trade places each step, so a pass can read
+/// the previous state from one while writing the next into the other
+pub struct PingPongBuffer {
+    pub buffers: [Buffer; 2],
+    current: usize,
+}
+
+impl PingPongBuffer {
+    /// Both buffers start with the same contents and get `STORAGE` usage in addition to `usage`
+    pub fn new(device: &Device, label: &str, contents: &[u8], usage: wgpu::BufferUsages) -> Self {
+        let buffers = [0, 1].map(|index| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{label} {index}")),
+                contents,
+                usage: usage | wgpu::BufferUsages::STORAGE,
+            })
+        });
+        Self {
+            buffers,
+            current: 0,
+        }
+    }
+
+    /// Index of the buffer holding the latest state, useful for picking between per direction bind groups
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    /// The buffer holding the latest state
+    pub fn current(&self) -> &Buffer {
+        &self.buffers[self.current]
+    }
+
+    /// The buffer the next step writes into
+    pub fn next(&self) -> &Buffer {
+        &self.buffers[1 - self.current]
+    }
+
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{AppConfig, KeyBindings, RenderPreset, RenderSettings};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Loads a config file from another path than `DEFAULT_CONFIG_PATH`
+pub const CONFIG_FLAG: &str = "--config";
+/// Read from the working directory when present
+pub const DEFAULT_CONFIG_PATH: &str = "config.ron";
+
+/// Overrides for an example's `AppConfig`, read from a RON file and from command line flags
+/// so demo machines can be set up without recompiling. Unset fields keep the example's values.
+///
+/// ```ron
+/// (
+///     width: 1920,
+///     height: 1080,
+///     fullscreen: true,
+///     preset: Ultra,
+///     vsync: false,
+///     key_bindings: { "move_forward": Up, "exit": Q },
+/// )
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigOverrides {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fullscreen: Option<bool>,
+    /// Applied before `vsync` and `resolution_scale`, which adjust it
+    pub preset: Option<RenderPreset>,
+    pub vsync: Option<bool>,
+    pub resolution_scale: Option<f32>,
+    pub key_bindings: KeyBindings,
+}
+
+impl ConfigOverrides {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config from {}", path.display()))?;
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(&contents)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Reads `--width <pixels>`, `--height <pixels>`, `--fullscreen`, `--windowed`,
+    /// `--preset <name>`, `--vsync`, `--no-vsync` and `--resolution-scale <scale>`
+    pub fn from_args(arguments: &[String]) -> Result<Self> {
+        let mut overrides = Self::default();
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .with_context(|| format!("{argument} needs a value"))
+            };
+            match argument.as_str() {
+                "--width" => overrides.width = Some(value()?.parse()?),
+                "--height" => overrides.height = Some(value()?.parse()?),
+                "--fullscreen" => overrides.fullscreen = Some(true),
+                "--windowed" => overrides.fullscreen = Some(false),
+                "--vsync" => overrides.vsync = Some(true),
+                "--no-vsync" => overrides.vsync = Some(false),
+                "--resolution-scale" => overrides.resolution_scale = Some(value()?.parse()?),
+                "--preset" => {
+                    let name = value()?;
+                    overrides.preset = Some(
+                        RenderPreset::ALL
+                            .into_iter()
+                            .find(|preset| preset.to_string().eq_ignore_ascii_case(name))
+                            .with_context(|| format!("Unknown render preset {name}"))?,
+                    );
+                }
+                _ => {}
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Replaces fields set in `other`
+    pub fn merge(&mut self, other: ConfigOverrides) {
+        self.width = other.width.or(self.width);
+        self.height = other.height.or(self.height);
+        self.fullscreen = other.fullscreen.or(self.fullscreen);
+        self.preset = other.preset.or(self.preset);
+        self.vsync = other.vsync.or(self.vsync);
+        self.resolution_scale = other.resolution_scale.or(self.resolution_scale);
+        self.key_bindings.merge(&other.key_bindings);
+    }
+
+    pub fn apply(&self, config: &mut AppConfig) {
+        config.width = self.width.unwrap_or(config.width);
+        config.height = self.height.unwrap_or(config.height);
+        config.fullscreen = self.fullscreen.unwrap_or(config.fullscreen);
+        if let Some(preset) = self.preset {
+            config.render_settings = RenderSettings {
+                post_effects: config.render_settings.post_effects,
+                debug_palette: config.render_settings.debug_palette,
+                ..preset.settings()
+            };
+        }
+        if let Some(vsync) = self.vsync {
+            config.render_settings.vsync = vsync;
+        }
+        if let Some(resolution_scale) = self.resolution_scale {
+            config.render_settings.resolution_scale = resolution_scale;
+        }
+        config.key_bindings.merge(&self.key_bindings);
+    }
+}
+
+/// Layers the config file, then the command line, over the example's config.
+/// The file is `--config <path>` when given, otherwise `DEFAULT_CONFIG_PATH` if it exists.
+pub fn apply_config_overrides(config: &mut AppConfig) -> Result<()> {
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+    let explicit_path = arguments
+        .iter()
+        .position(|argument| argument == CONFIG_FLAG)
+        .map(|index| {
+            arguments
+                .get(index + 1)
+                .map(PathBuf::from)
+                .with_context(|| format!("{CONFIG_FLAG} needs a path"))
+        })
+        .transpose()?;
+    let path = explicit_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+    let mut overrides = if explicit_path.is_some() || path.exists() {
+        log::info!("Loading config from {}", path.display());
+        ConfigOverrides::load(&path)?
+    } else {
+        ConfigOverrides::default()
+    };
+    overrides.merge(ConfigOverrides::from_args(&arguments)?);
+    overrides.apply(config);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(arguments: &str) -> Vec<String> {
+        arguments.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn from_args_reads_every_flag() {
+        let overrides = ConfigOverrides::from_args(&arguments(
+            "--width 1920 --height 1080 --fullscreen --preset ULTRA --no-vsync --resolution-scale 0.5",
+        ))
+        .unwrap();
+        assert_eq!(overrides.width, Some(1920));
+        assert_eq!(overrides.height, Some(1080));
+        assert_eq!(overrides.fullscreen, Some(true));
+        assert_eq!(overrides.preset, Some(RenderPreset::Ultra));
+        assert_eq!(overrides.vsync, Some(false));
+        assert_eq!(overrides.resolution_scale, Some(0.5));
+    }
+
+    #[test]
+    fn from_args_skips_other_arguments_and_rejects_bad_values() {
+        let overrides =
+            ConfigOverrides::from_args(&arguments("--config demo.ron --frames 5 --windowed"))
+                .unwrap();
+        assert_eq!(
+            overrides,
+            ConfigOverrides {
+                fullscreen: Some(false),
+                ..Default::default()
+            }
+        );
+        assert!(ConfigOverrides::from_args(&arguments("--width")).is_err());
+        assert!(ConfigOverrides::from_args(&arguments("--width wide")).is_err());
+        assert!(ConfigOverrides::from_args(&arguments("--preset extreme")).is_err());
+    }
+
+    #[test]
+    fn later_overrides_take_precedence() {
+        let mut file = ConfigOverrides {
+            width: Some(1280),
+            height: Some(720),
+            vsync: Some(false),
+            ..Default::default()
+        };
+        file.merge(ConfigOverrides::from_args(&arguments("--width 1920 --vsync")).unwrap());
+        assert_eq!(file.width, Some(1920));
+        assert_eq!(file.height, Some(720));
+        assert_eq!(file.vsync, Some(true));
+    }
+
+    #[test]
+    fn apply_adjusts_the_preset_and_keeps_unset_fields() {
+        let mut config = AppConfig {
+            width: 800,
+            height: 600,
+            ..Default::default()
+        };
+        config.render_settings.post_effects.motion_blur = true;
+        ConfigOverrides {
+            height: Some(900),
+            preset: Some(RenderPreset::Low),
+            resolution_scale: Some(1.5),
+            ..Default::default()
+        }
+        .apply(&mut config);
+        assert_eq!((config.width, config.height), (800, 900));
+        assert_eq!(
+            config.render_settings,
+            RenderSettings {
+                resolution_scale: 1.5,
+                post_effects: config.render_settings.post_effects,
+                ..RenderPreset::Low.settings()
+            }
+        );
+        assert!(config.render_settings.post_effects.motion_blur);
+    }
+
+    #[test]
+    fn load_reads_fields_without_some() {
+        let path = std::env::temp_dir().join(format!("config_test_{}.ron", std::process::id()));
+        std::fs::write(
+            &path,
+            "(width: 1920, preset: Medium, key_bindings: { \"exit\": Q })",
+        )
+        .unwrap();
+        let overrides = ConfigOverrides::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let overrides = overrides.unwrap();
+        assert_eq!(overrides.width, Some(1920));
+        assert_eq!(overrides.preset, Some(RenderPreset::Medium));
+        assert_eq!(
+            overrides.key_bindings.0.get("exit"),
+            Some(&winit::event::VirtualKeyCode::Q)
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+/// A log record kept for the in-app console
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Increases by one with every record, so panels can fetch only what they have not seen
+    pub sequence: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /// Seconds since the logger was installed
+    pub time: f32,
+}
+
+struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    next_sequence: u64,
+}
+
+static LOG_BUFFER: OnceLock<Mutex<LogBuffer>> = OnceLock::new();
+
+/// Forwards records to env_logger so they still reach the terminal,
+/// and keeps the most recent ones in a ring buffer for the console panel
+struct ConsoleLogger {
+    terminal: env_logger::Logger,
+    level: LevelFilter,
+    start: Instant,
+}
+
+impl ConsoleLogger {
+    /// Records kept before the oldest are dropped
+    const CAPACITY: usize = 2048;
+
+    /// Crates that log every resource they create at info level,
+    /// kept to warnings unless `RUST_LOG` asks for more
+    const VERBOSE_TARGETS: [&'static str; 3] = ["wgpu_core", "wgpu_hal", "naga"];
+
+    fn captures(&self, record: &Record) -> bool {
+        let verbose = Self::VERBOSE_TARGETS
+            .iter()
+            .any(|target| record.target().starts_with(target));
+        let level = if verbose {
+            self.level.min(LevelFilter::Warn)
+        } else {
+            self.level
+        };
+        record.level() <= level || self.terminal.matches(record)
+    }
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level || self.terminal.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.terminal.matches(record) {
+            self.terminal.log(record);
+        }
+        if !self.captures(record) {
+            return;
+        }
+        let Some(buffer) = LOG_BUFFER.get() else {
+            return;
+        };
+        let Ok(mut buffer) = buffer.lock() else {
+            return;
+        };
+        let sequence = buffer.next_sequence;
+        buffer.next_sequence += 1;
+        if buffer.entries.len() == Self::CAPACITY {
+            buffer.entries.pop_front();
+        }
+        buffer.entries.push_back(LogEntry {
+            sequence,
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            time: self.start.elapsed().as_secs_f32(),
+        });
+    }
+
+    fn flush(&self) {
+        self.terminal.flush();
+    }
+}
+
+/// Installs the global logger. The terminal still follows `RUST_LOG`, while the console
+/// keeps everything at `console_level` and above as well as whatever `RUST_LOG` enables.
+pub fn init_console_logger(console_level: LevelFilter) -> Result<()> {
+    let terminal = env_logger::Builder::from_default_env().build();
+    LOG_BUFFER.get_or_init(|| {
+        Mutex::new(LogBuffer {
+            entries: VecDeque::with_capacity(ConsoleLogger::CAPACITY),
+            next_sequence: 0,
+        })
+    });
+    log::set_max_level(console_level.max(terminal.filter()));
+    log::set_boxed_logger(Box::new(ConsoleLogger {
+        terminal,
+        level: console_level,
+        start: Instant::now(),
+    }))?;
+    Ok(())
+}
+
+/// Copies the buffered records newer than `sequence`
+pub fn log_entries_since(sequence: Option<u64>) -> Vec<LogEntry> {
+    let Some(Ok(buffer)) = LOG_BUFFER.get().map(Mutex::lock) else {
+        return Vec::new();
+    };
+    buffer
+        .entries
+        .iter()
+        .filter(|entry| sequence.map_or(true, |sequence| entry.sequence > sequence))
+        .cloned()
+        .collect()
+}
+
+/// Shows captured log records with level and text filtering
+pub struct ConsolePanel {
+    /// Records at this level and above are shown
+    pub level: LevelFilter,
+    pub search: String,
+    pub show_targets: bool,
+    entries: VecDeque<LogEntry>,
+    last_sequence: Option<u64>,
+}
+
+impl Default for ConsolePanel {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Info,
+            search: String::new(),
+            show_targets: false,
+            entries: VecDeque::new(),
+            last_sequence: None,
+        }
+    }
+}
+
+impl ConsolePanel {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        self.poll();
+
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_id_source("console_level")
+                .selected_text(self.level.to_string())
+                .show_ui(ui, |ui| {
+                    for level in LevelFilter::iter().skip(1) {
+                        ui.selectable_value(&mut self.level, level, level.to_string());
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Filter"));
+            ui.checkbox(&mut self.show_targets, "Targets");
+            if ui.button("Clear").clicked() {
+                self.entries.clear();
+            }
+        });
+        ui.separator();
+
+        let search = self.search.to_lowercase();
+        let visible = self
+            .entries
+            .iter()
+            .filter(|entry| entry.level <= self.level)
+            .filter(|entry| search.is_empty() || entry.message.to_lowercase().contains(&search));
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for entry in visible {
+                    let mut text = format!("{:>8.2} {:<5} ", entry.time, entry.level);
+                    if self.show_targets {
+                        text.push_str(&entry.target);
+                        text.push(' ');
+                    }
+                    text.push_str(&entry.message);
+                    let text = egui::RichText::new(text)
+                        .monospace()
+                        .color(level_color(ui, entry.level));
+                    ui.add(egui::Label::new(text).wrap(false));
+                }
+            });
+    }
+
+    /// Pulls the records logged since the last poll
+    fn poll(&mut self) {
+        let new_entries = log_entries_since(self.last_sequence);
+        if let Some(last) = new_entries.last() {
+            self.last_sequence = Some(last.sequence);
+        }
+        self.entries.extend(new_entries);
+        let excess = self.entries.len().saturating_sub(ConsoleLogger::CAPACITY);
+        self.entries.drain(..excess);
+    }
+}
+
+fn level_color(ui: &egui::Ui, level: Level) -> egui::Color32 {
+    match level {
+        Level::Error => ui.visuals().error_fg_color,
+        Level::Warn => ui.visuals().warn_fg_color,
+        Level::Info => ui.visuals().text_color(),
+        Level::Debug | Level::Trace => ui.visuals().weak_text_color(),
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{BindingBuilder, BindingLayout, FullscreenPipeline, Renderer, Texture};
+use nalgebra_glm as glm;
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, TextureFormat};
+
+const DEPTH_OF_FIELD_SOURCE: &str = "
+struct DepthOfField {
+    inverse_projection: mat4x4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var depth_texture: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> dof: DepthOfField;
+
+const GOLDEN_ANGLE: f32 = 2.39996323;
+const RADIUS_STEP: f32 = 0.5;
+
+fn view_depth(uv: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let coords = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2<i32>(0), size - 1);
+    let depth = textureLoad(depth_texture, coords, 0).x;
+    let view = dof.inverse_projection * vec4<f32>(0.0, 0.0, depth, 1.0);
+    return -view.z / max(view.w, 1e-6);
+}
+
+// Blur radius in pixels for a view space depth
+fn circle_of_confusion(depth: f32) -> f32 {
+    let focus_distance = dof.params.x;
+    let aperture = dof.params.y;
+    let max_radius = dof.params.z;
+    return clamp(aperture * abs(depth - focus_distance) / max(depth, 1e-4), 0.0, 1.0) * max_radius;
+}
+
+@fragment
+fn depth_of_field_fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let max_radius = dof.params.z;
+    let center = textureSampleLevel(source_texture, source_sampler, in.uv, 0.0);
+    let center_depth = view_depth(in.uv);
+    let center_size = circle_of_confusion(center_depth);
+
+    // Gathers along a spiral, each sample only contributing if its own blur reaches this pixel
+    var color = center.rgb;
+    var total = 1.0;
+    var radius = RADIUS_STEP;
+    var angle = 0.0;
+    loop {
+        if radius >= max_radius {
+            break;
+        }
+        let uv = in.uv + vec2<f32>(cos(angle), sin(angle)) * texel * radius;
+        let sample_color = textureSampleLevel(source_texture, source_sampler, uv, 0.0).rgb;
+        let sample_depth = view_depth(uv);
+        var sample_size = circle_of_confusion(sample_depth);
+        // Sharp foreground shouldn't bleed over blurred background behind it
+        if sample_depth > center_depth {
+            sample_size = clamp(sample_size, 0.0, center_size * 2.0);
+        }
+        let weight = smoothstep(radius - 0.5, radius + 0.5, sample_size);
+        color += mix(color / total, sample_color, weight);
+        total += 1.0;
+        radius += RADIUS_STEP / radius;
+        angle += GOLDEN_ANGLE;
+    }
+    return vec4<f32>(color / total, center.a);
+}
+";
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DepthOfFieldUniform {
+    inverse_projection: glm::Mat4,
+    /// Focus distance, aperture and maximum blur radius in pixels
+    params: glm::Vec4,
+}
+
+/// Where the camera focuses and how strongly out of focus areas blur
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FocusSettings {
+    /// View space distance that stays sharp
+    pub focus_distance: f32,
+    /// Scales how quickly blur grows away from the focus distance
+    pub aperture: f32,
+    pub max_blur_radius: f32,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            focus_distance: 6.0,
+            aperture: 1.0,
+            max_blur_radius: 8.0,
+        }
+    }
+}
+
+impl FocusSettings {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.add(
+            egui::Slider::new(&mut self.focus_distance, 0.1..=100.0)
+                .logarithmic(true)
+                .text("Focus distance"),
+        );
+        ui.add(egui::Slider::new(&mut self.aperture, 0.0..=4.0).text("Aperture"));
+        ui.add(egui::Slider::new(&mut self.max_blur_radius, 1.0..=16.0).text("Max blur radius"));
+    }
+}
+
+/// A bokeh blur of the scene's color, sized per pixel by how far its depth is from the
+/// focus distance. The circle of confusion, gather and composite all happen in one fullscreen pass.
+pub struct DepthOfField {
+    pipeline: FullscreenPipeline,
+    bind_group_layout: BindingLayout,
+    bind_group: BindGroup,
+    uniform: Buffer,
+}
+
+impl DepthOfField {
+    /// The color needs a filtering sampler, the depth is the scene's depth attachment
+    pub fn new(device: &Device, format: TextureFormat, color: &Texture, depth: &Texture) -> Self {
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Depth Of Field Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[DepthOfFieldUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = BindingBuilder::new()
+            .label("Depth Of Field")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            // Read as a float texture, GL can't load from depth textures
+            .unfilterable_texture(2, wgpu::ShaderStages::FRAGMENT)
+            .uniform(3, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let pipeline = FullscreenPipeline::new(
+            device,
+            format,
+            DEPTH_OF_FIELD_SOURCE,
+            "depth_of_field_fragment",
+            &[&bind_group_layout.layout],
+        );
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, color, depth, &uniform);
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            uniform,
+        }
+    }
+
+    /// Call when the source textures are recreated, such as after their render target resizes
+    pub fn set_source(&mut self, device: &Device, color: &Texture, depth: &Texture) {
+        self.bind_group =
+            Self::create_bind_group(device, &self.bind_group_layout, color, depth, &self.uniform);
+    }
+
+    /// The projection must be the one the depth was rendered with
+    pub fn update(&self, renderer: &mut Renderer, settings: &FocusSettings, projection: glm::Mat4) {
+        let uniform = DepthOfFieldUniform {
+            inverse_projection: glm::inverse(&projection),
+            params: glm::vec4(
+                settings.focus_distance,
+                settings.aperture,
+                settings.max_blur_radius,
+                0.0,
+            ),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /// Draws into a pass without a depth attachment, the scene's depth is being read
+    pub fn render<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.push_debug_group("Depth of field");
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        self.pipeline.draw(renderpass);
+        renderpass.pop_debug_group();
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindingLayout,
+        color: &Texture,
+        depth: &Texture,
+        uniform: &Buffer,
+    ) -> BindGroup {
+        layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&color.view),
+                wgpu::BindingResource::Sampler(&color.sampler),
+                wgpu::BindingResource::TextureView(&depth.view),
+                uniform.as_entire_binding(),
+            ],
+        )
+    }
+}
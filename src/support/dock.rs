@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use egui_dock::{DockArea, DockState, NodeIndex, Style, TabStyle, TabViewer};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The panels an editor layout can dock
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EditorTab {
+    Scene,
+    Hierarchy,
+    Inspector,
+    Console,
+    Assets,
+}
+
+impl EditorTab {
+    pub const ALL: [Self; 5] = [
+        Self::Scene,
+        Self::Hierarchy,
+        Self::Inspector,
+        Self::Console,
+        Self::Assets,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Scene => "Scene",
+            Self::Hierarchy => "Hierarchy",
+            Self::Inspector => "Inspector",
+            Self::Console => "Console",
+            Self::Assets => "Assets",
+        }
+    }
+}
+
+/// Draws the contents of each tab, implemented by the example hosting the layout
+pub trait EditorPanels {
+    fn show_tab(&mut self, ui: &mut egui::Ui, tab: EditorTab);
+}
+
+/// Editor tabs docked around the scene view. The user can drag tabs to rearrange them,
+/// and the arrangement can be saved so it persists across launches.
+pub struct EditorLayout {
+    pub dock_state: DockState<EditorTab>,
+}
+
+impl Default for EditorLayout {
+    fn default() -> Self {
+        let mut dock_state = DockState::new(vec![EditorTab::Scene]);
+        let surface = dock_state.main_surface_mut();
+        let [scene, _] = surface.split_left(NodeIndex::root(), 0.2, vec![EditorTab::Hierarchy]);
+        let [scene, _] = surface.split_right(scene, 0.75, vec![EditorTab::Inspector]);
+        surface.split_below(scene, 0.72, vec![EditorTab::Console, EditorTab::Assets]);
+        Self { dock_state }
+    }
+}
+
+impl EditorLayout {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read editor layout from {}", path.display()))?;
+        Ok(Self {
+            dock_state: ron::from_str(&contents)?,
+        })
+    }
+
+    /// Loads a saved layout if there is one, falling back to the default arrangement
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::load(path).unwrap_or_else(|error| {
+            log::warn!("{error:#}");
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents =
+            ron::ser::to_string_pretty(&self.dock_state, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write editor layout to {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn is_open(&self, tab: EditorTab) -> bool {
+        self.dock_state.find_tab(&tab).is_some()
+    }
+
+    /// Fills the window with the docked tabs
+    pub fn show(&mut self, context: &egui::Context, panels: &mut impl EditorPanels) {
+        DockArea::new(&mut self.dock_state)
+            .style(Style::from_egui(context.style().as_ref()))
+            .show(context, &mut PanelViewer(panels));
+    }
+
+    /// Menu entries for reopening closed tabs and restoring the default arrangement
+    pub fn show_menu(&mut self, ui: &mut egui::Ui) {
+        for tab in EditorTab::ALL {
+            let mut open = self.is_open(tab);
+            if ui.checkbox(&mut open, tab.title()).changed() {
+                match self.dock_state.find_tab(&tab) {
+                    Some(location) => {
+                        self.dock_state.remove_tab(location);
+                    }
+                    None => self.dock_state.push_to_focused_leaf(tab),
+                }
+            }
+        }
+        ui.separator();
+        if ui.button("Reset layout").clicked() {
+            *self = Self::default();
+            ui.close_menu();
+        }
+    }
+}
+
+struct PanelViewer<'a, P>(&'a mut P);
+
+impl<P: EditorPanels> TabViewer for PanelViewer<'_, P> {
+    type Tab = EditorTab;
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        self.0.show_tab(ui, *tab);
+    }
+
+    /// The scene view fills its tab edge to edge
+    fn tab_style_override(&self, tab: &Self::Tab, global_style: &TabStyle) -> Option<TabStyle> {
+        (*tab == EditorTab::Scene).then(|| {
+            let mut style = global_style.clone();
+            style.tab_body.inner_margin = egui::Margin::same(0.0);
+            style
+        })
+    }
+
+    fn scroll_bars(&self, tab: &Self::Tab) -> [bool; 2] {
+        let scrolls = *tab != EditorTab::Scene;
+        [scrolls, scrolls]
+    }
+}
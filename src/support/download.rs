@@ -0,0 +1,196 @@
+use crate::AssetCache;
+use anyhow::{Context, Result};
+use std::{
+    io::Read,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+};
+
+/// Fetches a URL on a background thread. Previous downloads are kept in the asset cache
+/// and revalidated with the server's `ETag` or `Last-Modified` header, so they're only
+/// fetched again once they change.
+pub struct Download {
+    pub url: String,
+    received: Arc<AtomicU64>,
+    total: Arc<AtomicU64>,
+    receiver: Receiver<Result<Vec<u8>>>,
+}
+
+impl Download {
+    const CACHE_KIND: &'static str = "http";
+    /// The conditional request header that revalidates a URL's cached response
+    const VALIDATOR_KIND: &'static str = "http-validator";
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Responses that fail `validate` are returned as errors and never cached,
+    /// so an error page served with a 200 status isn't kept
+    pub fn start(
+        url: impl Into<String>,
+        cache: &AssetCache,
+        validate: impl Fn(&[u8]) -> Result<()> + Send + 'static,
+    ) -> Self {
+        let url = url.into();
+        let received = Arc::new(AtomicU64::new(0));
+        let total = Arc::new(AtomicU64::new(0));
+        let (sender, receiver) = mpsc::channel();
+
+        let cache = cache.clone();
+        let thread_url = url.clone();
+        let thread_received = received.clone();
+        let thread_total = total.clone();
+        std::thread::spawn(move || {
+            let result = fetch_cached(
+                &cache,
+                &thread_url,
+                &thread_received,
+                &thread_total,
+                validate,
+            );
+            let _ = sender.send(result);
+        });
+
+        Self {
+            url,
+            received,
+            total,
+            receiver,
+        }
+    }
+
+    /// A `validate` function for `start` that accepts only bytes that decode as an image
+    pub fn validate_image(bytes: &[u8]) -> Result<()> {
+        image::load_from_memory(bytes).context("Downloaded data is not an image")?;
+        Ok(())
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of the download that has completed, if the server reported a length
+    pub fn progress(&self) -> Option<f32> {
+        match self.total.load(Ordering::Relaxed) {
+            0 => None,
+            total => Some(self.received() as f32 / total as f32),
+        }
+    }
+
+    /// Returns the downloaded bytes once the download has finished
+    pub fn poll(&self) -> Option<Result<Vec<u8>>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!(
+                "Download of {} stopped unexpectedly",
+                self.url
+            ))),
+        }
+    }
+}
+
+/// Sends a conditional request when the URL has a cached response,
+/// falling back to that response if the server can't be reached
+fn fetch_cached(
+    cache: &AssetCache,
+    url: &str,
+    received: &AtomicU64,
+    total: &AtomicU64,
+    validate: impl Fn(&[u8]) -> Result<()>,
+) -> Result<Vec<u8>> {
+    let cached = cache
+        .get(Download::VALIDATOR_KIND, url.as_bytes())
+        .and_then(|validator| String::from_utf8(validator).ok())
+        .and_then(|validator| {
+            let bytes = cache.get(Download::CACHE_KIND, &cache_source(url, &validator))?;
+            Some((validator, bytes))
+        });
+
+    let mut request = ureq::get(url);
+    if let Some((header, value)) = cached
+        .as_ref()
+        .and_then(|(validator, _)| validator.split_once(": "))
+    {
+        request = request.set(header, value);
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Transport(error)) => {
+            let Some((_, bytes)) = cached else {
+                return Err(error).with_context(|| format!("Failed to request {url}"));
+            };
+            log::warn!("Failed to request {url}, using the cached copy: {error}");
+            return Ok(bytes);
+        }
+        Err(error) => return Err(error).with_context(|| format!("Failed to request {url}")),
+    };
+    if response.status() == 304 {
+        if let Some((_, bytes)) = cached {
+            log::debug!("{url} is unchanged since it was cached");
+            return Ok(bytes);
+        }
+    }
+
+    // Responses without either header aren't cached, since there'd be no way to tell when they change
+    let validator = response
+        .header("ETag")
+        .map(|etag| format!("If-None-Match: {etag}"))
+        .or_else(|| {
+            response
+                .header("Last-Modified")
+                .map(|modified| format!("If-Modified-Since: {modified}"))
+        });
+    let bytes = read_body(url, response, received, total)?;
+    validate(&bytes).with_context(|| format!("Invalid response from {url}"))?;
+    if let Some(validator) = validator {
+        let stored = cache
+            .insert(Download::CACHE_KIND, &cache_source(url, &validator), &bytes)
+            .and_then(|_| {
+                cache.insert(
+                    Download::VALIDATOR_KIND,
+                    url.as_bytes(),
+                    validator.as_bytes(),
+                )
+            });
+        if let Err(error) = stored {
+            log::warn!("{error:#}");
+        }
+    }
+    Ok(bytes)
+}
+
+/// Cached responses are keyed by their validator as well as the URL, so a changed resource never reuses old bytes
+fn cache_source(url: &str, validator: &str) -> Vec<u8> {
+    format!("{url}\n{validator}").into_bytes()
+}
+
+fn read_body(
+    url: &str,
+    response: ureq::Response,
+    received: &AtomicU64,
+    total: &AtomicU64,
+) -> Result<Vec<u8>> {
+    if let Some(length) = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<u64>().ok())
+    {
+        total.store(length, Ordering::Relaxed);
+    }
+
+    let mut reader = response.into_reader();
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0; Download::CHUNK_SIZE];
+    loop {
+        let count = reader
+            .read(&mut chunk)
+            .with_context(|| format!("Failed to download {url}"))?;
+        if count == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..count]);
+        received.fetch_add(count as u64, Ordering::Relaxed);
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,64 @@
+use winit::event::VirtualKeyCode;
+
+/// Triggers RenderDoc captures from code or a hotkey. Captures only work when the crate is
+/// built with the `renderdoc` feature and the example was launched from RenderDoc,
+/// otherwise triggering logs a warning and does nothing.
+pub struct FrameCapture {
+    #[cfg(feature = "renderdoc")]
+    api: Option<renderdoc::RenderDoc<renderdoc::V110>>,
+}
+
+impl FrameCapture {
+    /// RenderDoc's own overlay already listens for F12 and Print Screen
+    pub const HOTKEY: VirtualKeyCode = VirtualKeyCode::F11;
+
+    /// Connects to RenderDoc if it is loaded into the process.
+    /// Call this before creating the wgpu instance so RenderDoc can hook the device.
+    pub fn new() -> Self {
+        #[cfg(feature = "renderdoc")]
+        {
+            let api = match renderdoc::RenderDoc::new() {
+                Ok(api) => {
+                    log::info!(
+                        "RenderDoc attached, press {:?} to capture a frame",
+                        Self::HOTKEY
+                    );
+                    Some(api)
+                }
+                Err(error) => {
+                    log::info!("RenderDoc is not attached: {error}");
+                    None
+                }
+            };
+            Self { api }
+        }
+        #[cfg(not(feature = "renderdoc"))]
+        Self {}
+    }
+
+    pub fn is_available(&self) -> bool {
+        #[cfg(feature = "renderdoc")]
+        return self.api.is_some();
+        #[cfg(not(feature = "renderdoc"))]
+        false
+    }
+
+    /// Captures the next presented frame
+    pub fn trigger(&mut self) {
+        #[cfg(feature = "renderdoc")]
+        if let Some(api) = self.api.as_mut() {
+            api.trigger_capture();
+            log::info!("Capturing the next frame with RenderDoc");
+            return;
+        }
+        log::warn!(
+            "Frame capture needs the renderdoc feature and the app to be launched from RenderDoc"
+        );
+    }
+}
+
+impl Default for FrameCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
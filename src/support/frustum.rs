@@ -0,0 +1,68 @@
+use crate::Aabb;
+use nalgebra_glm as glm;
+
+/// The six clip planes of a camera, with normals pointing inwards
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Each plane is stored as (normal, distance), with normal.dot(point) + distance >= 0 inside
+    pub planes: [glm::Vec4; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a projection view matrix with a zero to one depth range
+    pub fn from_matrix(projection_view_matrix: &glm::Mat4) -> Self {
+        let row = |index: usize| projection_view_matrix.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.xyz().magnitude();
+            // An infinite far plane has no normal and culls nothing
+            if length < f32::EPSILON {
+                glm::vec4(0.0, 0.0, 0.0, 1.0)
+            } else {
+                plane / length
+            }
+        });
+        Self { planes }
+    }
+
+    pub fn contains_point(&self, point: &glm::Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.xyz().dot(point) + plane.w >= 0.0)
+    }
+
+    /// Conservative test that may accept spheres just outside the corners of the frustum
+    pub fn intersects_sphere(&self, center: &glm::Vec3, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.xyz().dot(center) + plane.w >= -radius)
+    }
+
+    /// Conservative test that may accept boxes just outside the corners of the frustum
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        self.planes.iter().all(|plane| {
+            let normal = plane.xyz();
+            let positive_vertex = glm::vec3(
+                if normal.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if normal.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if normal.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            normal.dot(&positive_vertex) + plane.w >= 0.0
+        })
+    }
+}
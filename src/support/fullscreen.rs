@@ -0,0 +1,141 @@
+use std::borrow::Cow;
+use wgpu::{BindGroupLayout, Device, RenderPass, RenderPipeline, ShaderModule, TextureFormat};
+
+/// Prepended to the fragment source given to `FullscreenPipeline`.
+/// Fragment entry points take a `FullscreenOutput`, whose uv runs from
+/// the top left corner of the screen at (0, 0) to the bottom right at (1, 1).
+pub const FULLSCREEN_VERTEX_SOURCE: &str = "
+struct FullscreenOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn fullscreen_vertex(@builtin(vertex_index) vertex_index: u32) -> FullscreenOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: FullscreenOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+";
+
+/// Runs a fragment shader over every pixel of the target by drawing
+/// a single triangle that covers the screen, with no vertex buffers
+pub struct FullscreenPipeline {
+    pub pipeline: RenderPipeline,
+}
+
+impl FullscreenPipeline {
+    pub fn new(
+        device: &Device,
+        format: TextureFormat,
+        fragment_source: &str,
+        fragment_entry_point: &str,
+        bind_group_layouts: &[&BindGroupLayout],
+    ) -> Self {
+        Self::with_blend(
+            device,
+            format,
+            fragment_source,
+            fragment_entry_point,
+            bind_group_layouts,
+            wgpu::BlendState::REPLACE,
+        )
+    }
+
+    /// For passes that blend over what the target already holds, such as a composite
+    pub fn with_blend(
+        device: &Device,
+        format: TextureFormat,
+        fragment_source: &str,
+        fragment_entry_point: &str,
+        bind_group_layouts: &[&BindGroupLayout],
+        blend: wgpu::BlendState,
+    ) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Fullscreen Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!(
+                "{FULLSCREEN_VERTEX_SOURCE}{fragment_source}"
+            ))),
+        });
+        Self::from_modules(
+            device,
+            format,
+            &module,
+            &module,
+            fragment_entry_point,
+            bind_group_layouts,
+            blend,
+        )
+    }
+
+    /// For fragment shaders that aren't WGSL source, such as a module parsed by naga.
+    /// The fragment stage has to take the uv at `@location(0)` as input, even when it doesn't use it.
+    pub fn with_fragment_module(
+        device: &Device,
+        format: TextureFormat,
+        fragment_module: &ShaderModule,
+        fragment_entry_point: &str,
+        bind_group_layouts: &[&BindGroupLayout],
+    ) -> Self {
+        let vertex_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Fullscreen Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(FULLSCREEN_VERTEX_SOURCE)),
+        });
+        Self::from_modules(
+            device,
+            format,
+            &vertex_module,
+            fragment_module,
+            fragment_entry_point,
+            bind_group_layouts,
+            wgpu::BlendState::REPLACE,
+        )
+    }
+
+    fn from_modules(
+        device: &Device,
+        format: TextureFormat,
+        vertex_module: &ShaderModule,
+        fragment_module: &ShaderModule,
+        fragment_entry_point: &str,
+        bind_group_layouts: &[&BindGroupLayout],
+        blend: wgpu::BlendState,
+    ) -> Self {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Fullscreen Pipeline Layout"),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Fullscreen Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: vertex_module,
+                entry_point: "fullscreen_vertex",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: fragment_module,
+                entry_point: fragment_entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+        Self { pipeline }
+    }
+
+    /// Bind groups are set by the caller
+    pub fn draw<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.draw(0..3, 0..1);
+    }
+}
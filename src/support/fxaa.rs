@@ -0,0 +1,117 @@
+use crate::{BindingBuilder, BindingLayout, FullscreenPipeline, Texture};
+use wgpu::{BindGroup, Device, RenderPass, TextureFormat};
+
+const FXAA_SOURCE: &str = "
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const EDGE_THRESHOLD_MAX: f32 = 0.125;
+const SPAN_MAX: f32 = 8.0;
+const REDUCE_MUL: f32 = 0.125;
+const REDUCE_MIN: f32 = 0.0078125;
+
+fn sample_source(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv, 0.0);
+}
+
+// Sampling an srgb target returns linear values, edges are found in perceptual space
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(sqrt(max(color, vec3<f32>(0.0))), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fxaa_fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let center = sample_source(in.uv);
+    let luma_m = luma(center.rgb);
+    let luma_nw = luma(sample_source(in.uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(sample_source(in.uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(sample_source(in.uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(sample_source(in.uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+    if luma_max - luma_min < max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD_MAX) {
+        return center;
+    }
+
+    var direction = vec2<f32>(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let inner = 0.5 * (
+        sample_source(in.uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        sample_source(in.uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let outer = inner * 0.5 + 0.25 * (
+        sample_source(in.uv - direction * 0.5).rgb +
+        sample_source(in.uv + direction * 0.5).rgb
+    );
+    let luma_outer = luma(outer);
+    if luma_outer < luma_min || luma_outer > luma_max {
+        return vec4<f32>(inner, center.a);
+    }
+    return vec4<f32>(outer, center.a);
+}
+";
+
+/// Fast approximate anti-aliasing over a finished LDR image, such as a `RenderTarget`'s color.
+/// Much cheaper than MSAA, at the cost of slightly softening fine texture detail.
+pub struct Fxaa {
+    pipeline: FullscreenPipeline,
+    bind_group_layout: BindingLayout,
+    bind_group: BindGroup,
+}
+
+impl Fxaa {
+    /// The source needs a filtering sampler
+    pub fn new(device: &Device, format: TextureFormat, source: &Texture) -> Self {
+        let bind_group_layout = BindingBuilder::new()
+            .label("FXAA")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let pipeline = FullscreenPipeline::new(
+            device,
+            format,
+            FXAA_SOURCE,
+            "fxaa_fragment",
+            &[&bind_group_layout.layout],
+        );
+        let bind_group = Self::create_bind_group(device, &bind_group_layout, source);
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /// Call when the source texture is recreated, such as after its render target resizes
+    pub fn set_source(&mut self, device: &Device, source: &Texture) {
+        self.bind_group = Self::create_bind_group(device, &self.bind_group_layout, source);
+    }
+
+    pub fn render<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.push_debug_group("FXAA");
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        self.pipeline.draw(renderpass);
+        renderpass.pop_debug_group();
+    }
+
+    fn create_bind_group(device: &Device, layout: &BindingLayout, source: &Texture) -> BindGroup {
+        layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&source.view),
+                wgpu::BindingResource::Sampler(&source.sampler),
+            ],
+        )
+    }
+}
@@ -1,23 +1,266 @@
+use crate::{Renderer, ShaderReflection, VertexDescription, VertexLayout};
+use anyhow::Result;
+use std::borrow::Cow;
 use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
-    Buffer, Device,
+    Buffer, Device, Queue,
 };
 
+/// Index data in either 16 or 32 bit form. 16 bit indices halve index memory for meshes
+/// with fewer than 65536 vertices
+#[derive(Debug, Clone, Copy)]
+pub enum Indices<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
+
+impl<'a> Indices<'a> {
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Self::U16(_) => wgpu::IndexFormat::Uint16,
+            Self::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        match self {
+            Self::U16(indices) => bytemuck::cast_slice(indices),
+            Self::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+
+    /// Narrows 32 bit indices to 16 bits when every index fits
+    pub fn narrow(indices: &[u32]) -> Option<Vec<u16>> {
+        indices
+            .iter()
+            .map(|index| u16::try_from(*index).ok())
+            .collect()
+    }
+}
+
+impl<'a> From<&'a [u16]> for Indices<'a> {
+    fn from(indices: &'a [u16]) -> Self {
+        Self::U16(indices)
+    }
+}
+
+impl<'a> From<&'a [u32]> for Indices<'a> {
+    fn from(indices: &'a [u32]) -> Self {
+        Self::U32(indices)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u16; N]> for Indices<'a> {
+    fn from(indices: &'a [u16; N]) -> Self {
+        Self::U16(indices)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u32; N]> for Indices<'a> {
+    fn from(indices: &'a [u32; N]) -> Self {
+        Self::U32(indices)
+    }
+}
+
+impl<'a> From<&'a Vec<u16>> for Indices<'a> {
+    fn from(indices: &'a Vec<u16>) -> Self {
+        Self::U16(indices)
+    }
+}
+
+impl<'a> From<&'a Vec<u32>> for Indices<'a> {
+    fn from(indices: &'a Vec<u32>) -> Self {
+        Self::U32(indices)
+    }
+}
+
 pub struct Geometry {
     pub vertex_buffer: Buffer,
     pub index_buffer: Buffer,
+    /// Number of indices to draw
+    pub index_count: u32,
+    pub index_format: wgpu::IndexFormat,
+    vertex_layout: VertexLayout,
+    vertex_bytes: u64,
+    index_bytes: u64,
 }
 
 impl Geometry {
-    pub fn new<T: bytemuck::Pod>(device: &wgpu::Device, vertices: &[T], indices: &[u32]) -> Self {
+    /// Smallest buffer allocated for dynamic geometry, so empty meshes still get a valid buffer
+    const MIN_DYNAMIC_SIZE: u64 = 256;
+
+    pub fn new<'a, T: VertexDescription>(
+        device: &wgpu::Device,
+        vertices: &[T],
+        indices: impl Into<Indices<'a>>,
+    ) -> Self {
+        let indices = indices.into();
         Self {
             vertex_buffer: Self::create_vertex_buffer(device, vertices),
-            index_buffer: Self::create_index_buffer(device, indices),
+            index_buffer: Self::create_index_buffer(device, indices.bytes()),
+            index_count: indices.len() as u32,
+            index_format: indices.format(),
+            vertex_layout: T::layout(),
+            vertex_bytes: std::mem::size_of_val(vertices) as u64,
+            index_bytes: indices.bytes().len() as u64,
         }
     }
 
-    pub fn slices(&self) -> (wgpu::BufferSlice, wgpu::BufferSlice) {
-        (self.vertex_buffer.slice(..), self.index_buffer.slice(..))
+    /// Creates geometry whose buffers can be rewritten with `update_vertices` and `update_indices`
+    pub fn new_dynamic<'a, T: VertexDescription>(
+        device: &wgpu::Device,
+        queue: &Queue,
+        vertices: &[T],
+        indices: impl Into<Indices<'a>>,
+    ) -> Self {
+        let indices = indices.into();
+        let vertex_bytes = std::mem::size_of_val(vertices) as u64;
+        let index_bytes = indices.bytes().len() as u64;
+        let mut geometry = Self {
+            vertex_buffer: Self::create_dynamic_buffer(
+                device,
+                "Dynamic Vertex Buffer",
+                wgpu::BufferUsages::VERTEX,
+                vertex_bytes,
+            ),
+            index_buffer: Self::create_dynamic_buffer(
+                device,
+                "Dynamic Index Buffer",
+                wgpu::BufferUsages::INDEX,
+                index_bytes,
+            ),
+            index_count: 0,
+            index_format: indices.format(),
+            vertex_layout: T::layout(),
+            vertex_bytes: 0,
+            index_bytes: 0,
+        };
+        geometry.update_vertices(device, queue, vertices);
+        geometry.update_indices(device, queue, indices);
+        geometry
+    }
+
+    /// Uploads new vertices, reallocating the vertex buffer if they no longer fit
+    pub fn update_vertices<T: VertexDescription>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        vertices: &[T],
+    ) {
+        self.debug_assert_layout::<T>();
+        let contents: &[u8] = bytemuck::cast_slice(vertices);
+        Self::write_dynamic_buffer(
+            device,
+            queue,
+            &mut self.vertex_buffer,
+            "Dynamic Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            contents,
+        );
+        self.vertex_bytes = contents.len() as u64;
+    }
+
+    /// Like `update_vertices`, but copied through the renderer's staging belt along with the
+    /// frame's other uploads, which suits meshes rewritten every frame
+    pub fn stream_vertices<T: VertexDescription>(
+        &mut self,
+        renderer: &mut Renderer,
+        vertices: &[T],
+    ) {
+        self.debug_assert_layout::<T>();
+        let contents: &[u8] = bytemuck::cast_slice(vertices);
+        self.vertex_bytes = contents.len() as u64;
+        if contents.is_empty() {
+            return;
+        }
+        Self::reserve_dynamic_buffer(
+            &renderer.device,
+            &mut self.vertex_buffer,
+            "Dynamic Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            contents.len() as u64,
+        );
+        renderer.write_buffer(&self.vertex_buffer, 0, &Self::padded(contents));
+    }
+
+    /// Uploads new indices, reallocating the index buffer if they no longer fit
+    pub fn update_indices<'a>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        indices: impl Into<Indices<'a>>,
+    ) {
+        let indices = indices.into();
+        let contents = indices.bytes();
+        Self::write_dynamic_buffer(
+            device,
+            queue,
+            &mut self.index_buffer,
+            "Dynamic Index Buffer",
+            wgpu::BufferUsages::INDEX,
+            contents,
+        );
+        self.index_bytes = contents.len() as u64;
+        self.index_count = indices.len() as u32;
+        self.index_format = indices.format();
+    }
+
+    /// Slices covering only the vertices and indices in use, along with the index format to bind them with
+    pub fn slices(
+        &self,
+    ) -> (
+        wgpu::BufferSlice<'_>,
+        wgpu::BufferSlice<'_>,
+        wgpu::IndexFormat,
+    ) {
+        (
+            Self::used_slice(&self.vertex_buffer, self.vertex_bytes),
+            Self::used_slice(&self.index_buffer, self.index_bytes),
+            self.index_format,
+        )
+    }
+
+    /// The layout of the vertex type the geometry was created with
+    pub fn vertex_layout(&self) -> &VertexLayout {
+        &self.vertex_layout
+    }
+
+    /// Checks that the vertices provide every input the shader's vertex entry point reads,
+    /// so a mismatched vertex type is reported before pipeline creation
+    pub fn validate(&self, reflection: &ShaderReflection, entry_point: &str) -> Result<()> {
+        reflection.validate_vertex_input(entry_point, std::slice::from_ref(&self.vertex_layout))
+    }
+
+    /// Total size of the vertex and index buffers, including any unused capacity
+    pub fn allocated_bytes(&self) -> u64 {
+        self.vertex_buffer.size() + self.index_buffer.size()
+    }
+
+    fn debug_assert_layout<T: VertexDescription>(&self) {
+        debug_assert_eq!(
+            T::layout(),
+            self.vertex_layout,
+            "vertices must have the layout the geometry was created with"
+        );
+    }
+
+    fn used_slice(buffer: &Buffer, bytes: u64) -> wgpu::BufferSlice<'_> {
+        if bytes == 0 {
+            buffer.slice(..)
+        } else {
+            buffer.slice(..bytes)
+        }
     }
 
     fn create_vertex_buffer(device: &Device, vertices: &[impl bytemuck::Pod]) -> Buffer {
@@ -28,11 +271,65 @@ impl Geometry {
         })
     }
 
-    fn create_index_buffer(device: &Device, indices: &[impl bytemuck::Pod]) -> Buffer {
+    fn create_index_buffer(device: &Device, indices: &[u8]) -> Buffer {
         device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(indices),
+            contents: indices,
             usage: wgpu::BufferUsages::INDEX,
         })
     }
+
+    fn create_dynamic_buffer(
+        device: &Device,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        size: u64,
+    ) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: size.max(Self::MIN_DYNAMIC_SIZE).next_power_of_two(),
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn write_dynamic_buffer(
+        device: &Device,
+        queue: &Queue,
+        buffer: &mut Buffer,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        contents: &[u8],
+    ) {
+        if contents.is_empty() {
+            return;
+        }
+        Self::reserve_dynamic_buffer(device, buffer, label, usage, contents.len() as u64);
+        queue.write_buffer(buffer, 0, &Self::padded(contents));
+    }
+
+    fn reserve_dynamic_buffer(
+        device: &Device,
+        buffer: &mut Buffer,
+        label: &str,
+        usage: wgpu::BufferUsages,
+        size: u64,
+    ) {
+        if size > buffer.size() || !buffer.usage().contains(wgpu::BufferUsages::COPY_DST) {
+            log::debug!("Growing {label} to hold {size} bytes");
+            *buffer = Self::create_dynamic_buffer(device, label, usage, size);
+        }
+    }
+
+    /// Buffer writes must be a multiple of four bytes long
+    fn padded(contents: &[u8]) -> Cow<'_, [u8]> {
+        let size = contents.len() as u64;
+        let aligned = wgpu::util::align_to(size, wgpu::COPY_BUFFER_ALIGNMENT);
+        if aligned == size {
+            return Cow::Borrowed(contents);
+        }
+        let mut padded = contents.to_vec();
+        padded.resize(aligned as usize, 0);
+        Cow::Owned(padded)
+    }
 }
@@ -0,0 +1,131 @@
+use crate::{BindingBuilder, BindingLayout, Uniform, Uploader};
+use nalgebra_glm as glm;
+use std::sync::Arc;
+use wgpu::{BindGroup, BindGroupLayout, Device, RenderPass};
+
+/// What the globals uniform holds, laid out as `Globals` in `Globals::SHADER_SOURCE`
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GlobalsData {
+    /// Seconds since the first frame, the sum of every frame's delta time
+    pub time: f32,
+    pub delta_time: f32,
+    /// Starts at zero for the first frame
+    pub frame: u32,
+    pub _padding: u32,
+    /// The surface size in pixels
+    pub resolution: glm::Vec2,
+    /// The cursor in pixels from the window's top left corner
+    pub mouse: glm::Vec2,
+}
+
+/// Per frame values the renderer writes before each update, so procedural shaders
+/// don't need their own time plumbing. Include `SHADER_SOURCE` in the shader and keep
+/// the `binding` to make pipeline layouts and bind the values while rendering.
+pub struct Globals {
+    binding: Arc<GlobalsBinding>,
+    data: GlobalsData,
+    frames: u32,
+}
+
+impl Globals {
+    /// Reserved for the globals, pipelines using them have their own groups below it
+    pub const GROUP: u32 = 3;
+
+    pub const SHADER_SOURCE: &'static str = "
+struct Globals {
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+};
+
+@group(3) @binding(0)
+var<uniform> globals: Globals;
+";
+
+    pub fn new(device: &Device) -> Self {
+        let empty_layout = BindingBuilder::new().label("Empty").build(device);
+        let empty_bind_group = empty_layout.bind_group(device, &[]);
+        Self {
+            binding: Arc::new(GlobalsBinding {
+                uniform: Uniform::new(device),
+                empty_layout,
+                empty_bind_group,
+            }),
+            data: GlobalsData::default(),
+            frames: 0,
+        }
+    }
+
+    /// Shared, since `Application::render` has no access to the renderer
+    pub fn binding(&self) -> Arc<GlobalsBinding> {
+        self.binding.clone()
+    }
+
+    /// The values written for the current frame
+    pub fn data(&self) -> &GlobalsData {
+        &self.data
+    }
+
+    /// Moves on to the next frame and queues the new values for upload
+    pub fn advance(
+        &mut self,
+        device: &Device,
+        uploader: &mut Uploader,
+        delta_time: f32,
+        resolution: glm::Vec2,
+        mouse: glm::Vec2,
+    ) {
+        self.data = GlobalsData {
+            time: self.data.time + delta_time,
+            delta_time,
+            frame: self.frames,
+            resolution,
+            mouse,
+            ..Default::default()
+        };
+        self.frames += 1;
+        uploader.write(
+            device,
+            self.binding.uniform.buffer(),
+            0,
+            bytemuck::bytes_of(&self.data),
+        );
+    }
+}
+
+/// The globals uniform along with the empty groups that fill the gap
+/// between a pipeline's own groups and `Globals::GROUP`
+pub struct GlobalsBinding {
+    uniform: Uniform<GlobalsData>,
+    empty_layout: BindingLayout,
+    empty_bind_group: BindGroup,
+}
+
+impl GlobalsBinding {
+    /// The pipeline's own layouts padded with empty ones up to `Globals::GROUP`, followed by the globals
+    pub fn pipeline_layouts<'a>(
+        &'a self,
+        layouts: &[&'a BindGroupLayout],
+    ) -> Vec<&'a BindGroupLayout> {
+        assert!(
+            layouts.len() <= Globals::GROUP as usize,
+            "group {} is reserved for the globals",
+            Globals::GROUP
+        );
+        let mut all = layouts.to_vec();
+        all.resize(Globals::GROUP as usize, &self.empty_layout.layout);
+        all.push(self.uniform.layout());
+        all
+    }
+
+    /// Binds the globals, along with the empty groups after the pipeline's own `groups`
+    pub fn set<'a>(&'a self, renderpass: &mut RenderPass<'a>, groups: u32) {
+        for group in groups..Globals::GROUP {
+            renderpass.set_bind_group(group, &self.empty_bind_group, &[]);
+        }
+        self.uniform.set(renderpass, Globals::GROUP);
+    }
+}
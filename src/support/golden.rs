@@ -0,0 +1,80 @@
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+
+/// How far a captured frame is from its golden image
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    pub different_pixels: usize,
+    pub total_pixels: usize,
+    /// Largest perceptual difference of any pixel, from 0 to 1
+    pub max_difference: f32,
+    /// The expected image dimmed, with differing pixels in red
+    pub image: RgbaImage,
+}
+
+impl ImageDiff {
+    /// Share of pixels that differ, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        self.different_pixels as f32 / self.total_pixels.max(1) as f32
+    }
+}
+
+/// Compares two images pixel by pixel in YIQ space, which weighs brightness over hue the way
+/// the eye does. Pixels whose difference exceeds `tolerance`, from 0 to 1, count as different.
+pub fn compare_images(
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+    tolerance: f32,
+) -> Result<ImageDiff> {
+    anyhow::ensure!(
+        actual.dimensions() == expected.dimensions(),
+        "Image is {:?} but the golden image is {:?}",
+        actual.dimensions(),
+        expected.dimensions()
+    );
+    let mut image = RgbaImage::new(expected.width(), expected.height());
+    let mut different_pixels = 0;
+    let mut max_difference = 0_f32;
+    for ((actual, expected), output) in actual
+        .pixels()
+        .zip(expected.pixels())
+        .zip(image.pixels_mut())
+    {
+        let difference = perceptual_difference(actual, expected);
+        max_difference = max_difference.max(difference);
+        *output = if difference > tolerance {
+            different_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [y, _, _] = yiq(expected);
+            let gray = (y * 0.25 * 255.0) as u8;
+            Rgba([gray, gray, gray, 255])
+        };
+    }
+    Ok(ImageDiff {
+        different_pixels,
+        total_pixels: (expected.width() * expected.height()) as usize,
+        max_difference,
+        image,
+    })
+}
+
+/// Pixels are blended over white first so differences in fully transparent areas are ignored
+fn yiq(pixel: &Rgba<u8>) -> [f32; 3] {
+    let alpha = pixel[3] as f32 / 255.0;
+    let [r, g, b] = [0, 1, 2].map(|channel| 1.0 + (pixel[channel] as f32 / 255.0 - 1.0) * alpha);
+    [
+        0.298_895_3 * r + 0.586_622_5 * g + 0.114_482_2 * b,
+        0.595_977_99 * r - 0.274_171_6 * g - 0.321_801_6 * b,
+        0.211_470_2 * r - 0.522_617_2 * g + 0.311_146_9 * b,
+    ]
+}
+
+/// Weighted YIQ distance scaled so black against white is 1
+fn perceptual_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    const MAX_DELTA: f32 = 35215.0 / (255.0 * 255.0);
+    let [ya, ia, qa] = yiq(a);
+    let [yb, ib, qb] = yiq(b);
+    let (y, i, q) = (ya - yb, ia - ib, qa - qb);
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_DELTA
+}
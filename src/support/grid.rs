@@ -0,0 +1,270 @@
+use crate::{BindingBuilder, Renderer, FULLSCREEN_VERTEX_SOURCE};
+use nalgebra_glm as glm;
+use std::borrow::Cow;
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, RenderPipeline, TextureFormat};
+
+const GRID_SOURCE: &str = "
+struct Grid {
+    view_projection: mat4x4<f32>,
+    inverse_view_projection: mat4x4<f32>,
+    camera_position: vec4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> grid: Grid;
+
+struct GridOutput {
+    @location(0) color: vec4<f32>,
+    @builtin(frag_depth) depth: f32,
+};
+
+const MINOR_COLOR: vec3<f32> = vec3<f32>(0.35, 0.35, 0.35);
+const MAJOR_COLOR: vec3<f32> = vec3<f32>(0.6, 0.6, 0.6);
+const X_AXIS_COLOR: vec3<f32> = vec3<f32>(0.9, 0.2, 0.2);
+const Z_AXIS_COLOR: vec3<f32> = vec3<f32>(0.2, 0.4, 0.9);
+
+fn unproject(ndc: vec2<f32>, depth: f32) -> vec3<f32> {
+    let point = grid.inverse_view_projection * vec4<f32>(ndc, depth, 1.0);
+    return point.xyz / point.w;
+}
+
+// Coverage of one pixel wide lines every cell, antialiased by the screen space rate of change.
+// Derivatives are taken by the caller, the GL backend also compiles helpers into the vertex stage.
+fn grid_lines(coords: vec2<f32>, derivative: vec2<f32>, cell_size: f32) -> f32 {
+    let scaled = coords / cell_size;
+    let lines = abs(fract(scaled - 0.5) - 0.5) / (derivative / cell_size);
+    return 1.0 - min(min(lines.x, lines.y), 1.0);
+}
+
+@fragment
+fn grid_fragment(in: FullscreenOutput) -> GridOutput {
+    let cell_size = grid.params.x;
+    let major_every = grid.params.y;
+    let fade_distance = grid.params.z;
+    let height = grid.params.w;
+
+    // The depth of 0.5 stays finite with an infinite far plane
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    let near = unproject(ndc, 0.0);
+    let direction = unproject(ndc, 0.5) - near;
+    let t = (height - near.y) / direction.y;
+    let world = near + direction * t;
+    let coords = world.xz;
+
+    // Derivatives are taken before anything is discarded
+    let derivative = fwidth(coords);
+    let minor = grid_lines(coords, derivative, cell_size);
+    let major = grid_lines(coords, derivative, cell_size * major_every);
+    let axes = abs(coords) / derivative;
+
+    var color = MINOR_COLOR;
+    var alpha = minor * 0.5;
+    if major > 0.0 {
+        color = MAJOR_COLOR;
+        alpha = max(alpha, major);
+    }
+    if axes.y < 1.0 {
+        color = X_AXIS_COLOR;
+        alpha = 1.0;
+    }
+    if axes.x < 1.0 {
+        color = Z_AXIS_COLOR;
+        alpha = 1.0;
+    }
+
+    let distance = length(world.xz - grid.camera_position.xz);
+    alpha *= 1.0 - smoothstep(fade_distance * 0.5, fade_distance, distance);
+    if t <= 0.0 || alpha <= 0.0 {
+        discard;
+    }
+
+    let clip = grid.view_projection * vec4<f32>(world, 1.0);
+    var out: GridOutput;
+    out.color = vec4<f32>(color, alpha);
+    out.depth = clip.z / clip.w;
+    return out;
+}
+";
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct GridUniform {
+    view_projection: glm::Mat4,
+    inverse_view_projection: glm::Mat4,
+    camera_position: glm::Vec4,
+    /// Cell size, cells per major line, fade distance and the plane's height
+    params: glm::Vec4,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GridSettings {
+    pub enabled: bool,
+    pub cell_size: f32,
+    /// Every this many cells a brighter major line is drawn
+    pub major_every: u32,
+    /// Distance from the camera where the grid has faded out entirely
+    pub fade_distance: f32,
+    /// World space height of the horizontal plane the grid lies on
+    pub height: f32,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cell_size: 1.0,
+            major_every: 10,
+            fade_distance: 50.0,
+            height: 0.0,
+        }
+    }
+}
+
+impl GridSettings {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.add(
+            egui::Slider::new(&mut self.cell_size, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Cell size"),
+        );
+        ui.add(egui::Slider::new(&mut self.major_every, 2..=20).text("Major line every"));
+        ui.add(egui::Slider::new(&mut self.fade_distance, 5.0..=200.0).text("Fade distance"));
+        ui.add(egui::Slider::new(&mut self.height, -10.0..=10.0).text("Height"));
+    }
+}
+
+/// An endless grid on a horizontal plane, drawn by intersecting each pixel's view ray with the
+/// plane in a fullscreen pass. Lines fade with distance, every few lines is a major line and
+/// the lines through the origin are colored like the X and Z axes. Draw it after the opaque
+/// scene, it tests against the depth without writing to it.
+pub struct Grid {
+    pipeline: RenderPipeline,
+    uniform: Buffer,
+    bind_group: BindGroup,
+}
+
+impl Grid {
+    pub fn new(device: &Device, format: TextureFormat, depth_format: TextureFormat) -> Self {
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[GridUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = BindingBuilder::new()
+            .label("Grid")
+            .uniform(0, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let bind_group = bind_group_layout.bind_group(device, &[uniform.as_entire_binding()]);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Grid Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!(
+                "{FULLSCREEN_VERTEX_SOURCE}{GRID_SOURCE}"
+            ))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Grid Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Grid Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "fullscreen_vertex",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "grid_fragment",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform,
+            bind_group,
+        }
+    }
+
+    /// The view projection must be the one the scene's depth was rendered with
+    pub fn update(
+        &self,
+        renderer: &mut Renderer,
+        settings: &GridSettings,
+        view_projection: glm::Mat4,
+        camera_position: glm::Vec3,
+    ) {
+        let uniform = GridUniform {
+            view_projection,
+            inverse_view_projection: glm::inverse(&view_projection),
+            camera_position: camera_position.push(1.0),
+            params: glm::vec4(
+                settings.cell_size.max(0.001),
+                settings.major_every.max(1) as f32,
+                settings.fade_distance,
+                settings.height,
+            ),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn render<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.push_debug_group("Grid");
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.draw(0..3, 0..1);
+        renderpass.pop_debug_group();
+    }
+}
+
+/// Paints the world axes as seen through the view matrix in the bottom left corner of a
+/// viewport, so the camera's orientation can be read at a glance
+pub fn paint_axis_gizmo(painter: &egui::Painter, rect: egui::Rect, view: &glm::Mat4) {
+    const LENGTH: f32 = 28.0;
+    const MARGIN: f32 = 16.0;
+    let center = egui::pos2(
+        rect.left() + MARGIN + LENGTH,
+        rect.bottom() - MARGIN - LENGTH,
+    );
+    let mut axes = [
+        (glm::Vec3::x(), "X", egui::Color32::from_rgb(230, 60, 60)),
+        (glm::Vec3::y(), "Y", egui::Color32::from_rgb(80, 200, 80)),
+        (glm::Vec3::z(), "Z", egui::Color32::from_rgb(60, 110, 230)),
+    ]
+    .map(|(axis, label, color)| (view * axis.push(0.0), label, color));
+
+    // Axes pointing away from the camera are painted first so nearer ones overlap them
+    axes.sort_by(|a, b| a.0.z.total_cmp(&b.0.z));
+    painter.circle_filled(center, LENGTH + 6.0, egui::Color32::from_black_alpha(96));
+    for (direction, label, color) in axes {
+        let end = center + egui::vec2(direction.x, -direction.y) * LENGTH;
+        painter.line_segment([center, end], egui::Stroke::new(2.0, color));
+        painter.circle_filled(end, 7.0, color);
+        painter.text(
+            end,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::monospace(10.0),
+            egui::Color32::WHITE,
+        );
+    }
+}
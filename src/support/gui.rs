@@ -68,6 +68,31 @@ impl GuiRender {
         self.renderer.is_some()
     }
 
+    /// Makes a texture view drawable with egui image widgets.
+    /// Returns `None` until the GUI renderer is created along with the first frame.
+    pub fn register_native_texture(
+        &mut self,
+        device: &Device,
+        view: &wgpu::TextureView,
+        filter: wgpu::FilterMode,
+    ) -> Option<egui::TextureId> {
+        let renderer = self.renderer.as_mut()?;
+        Some(renderer.register_native_texture(device, view, filter))
+    }
+
+    /// Points a registered texture id at a new view, such as after its texture was recreated at a new size
+    pub fn update_native_texture(
+        &mut self,
+        device: &Device,
+        view: &wgpu::TextureView,
+        filter: wgpu::FilterMode,
+        id: egui::TextureId,
+    ) {
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.update_egui_texture_from_wgpu_texture(device, view, filter, id);
+        }
+    }
+
     pub fn update_textures(
         &mut self,
         device: &Device,
@@ -1,27 +1,52 @@
 use nalgebra_glm as glm;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use winit::{
     dpi::PhysicalPosition,
     event::{
-        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
-        WindowEvent,
+        DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
     },
+    window::{CursorGrabMode, Window},
 };
 
 pub type KeyMap = HashMap<VirtualKeyCode, ElementState>;
 
+/// Keys assigned to named actions, such as "move_forward", overriding each action's default key
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyBindings(pub HashMap<String, VirtualKeyCode>);
+
+impl KeyBindings {
+    pub fn key(&self, action: &str, default: VirtualKeyCode) -> VirtualKeyCode {
+        self.0.get(action).copied().unwrap_or(default)
+    }
+
+    /// Rebinds the actions in `other`, keeping the rest
+    pub fn merge(&mut self, other: &KeyBindings) {
+        self.0
+            .extend(other.0.iter().map(|(action, key)| (action.clone(), *key)));
+    }
+}
+
 pub struct Input {
     pub keystates: KeyMap,
+    pub bindings: KeyBindings,
     pub mouse: Mouse,
     pub allowed: bool,
+    relative_mode: bool,
+    cursor_grabbed: bool,
 }
 
 impl Default for Input {
     fn default() -> Self {
         Self {
             keystates: KeyMap::default(),
+            bindings: KeyBindings::default(),
             mouse: Mouse::default(),
             allowed: true,
+            relative_mode: false,
+            cursor_grabbed: false,
         }
     }
 }
@@ -31,6 +56,41 @@ impl Input {
         self.keystates.contains_key(&keycode) && self.keystates[&keycode] == ElementState::Pressed
     }
 
+    /// Whether the key bound to the action, or its default key, is held
+    pub fn is_action_pressed(&self, action: &str, default: VirtualKeyCode) -> bool {
+        self.is_key_pressed(self.bindings.key(action, default))
+    }
+
+    /// Hides and grabs the cursor so mouse motion is reported without hitting the window edges
+    pub fn set_relative_mode(&mut self, relative_mode: bool) {
+        self.relative_mode = relative_mode;
+    }
+
+    pub fn relative_mode(&self) -> bool {
+        self.relative_mode
+    }
+
+    /// Applies the requested cursor mode to the window if it has changed
+    pub fn update_cursor(&mut self, window: &Window) {
+        if self.relative_mode == self.cursor_grabbed {
+            return;
+        }
+
+        let result = if self.relative_mode {
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+        if let Err(error) = result {
+            log::warn!("Failed to set cursor grab mode: {error}");
+        }
+
+        window.set_cursor_visible(!self.relative_mode);
+        self.cursor_grabbed = self.relative_mode;
+    }
+
     pub fn handle_event<T>(&mut self, event: &Event<T>, window_center: glm::Vec2) {
         if let Event::WindowEvent {
             event:
@@ -60,6 +120,7 @@ pub struct Mouse {
     pub position_delta: glm::Vec2,
     pub offset_from_center: glm::Vec2,
     pub wheel_delta: glm::Vec2,
+    pub motion_delta: glm::Vec2,
     pub moved: bool,
     pub scrolled: bool,
 }
@@ -79,11 +140,17 @@ impl Mouse {
                 } => self.mouse_wheel(h_lines, v_lines),
                 _ => {}
             },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => self.mouse_motion(*delta),
             _ => {}
         }
     }
 
     fn new_events(&mut self) {
+        self.motion_delta = glm::vec2(0.0, 0.0);
+
         if !self.scrolled {
             self.wheel_delta = glm::vec2(0.0, 0.0);
         }
@@ -104,6 +171,10 @@ impl Mouse {
         self.moved = true;
     }
 
+    fn mouse_motion(&mut self, (x, y): (f64, f64)) {
+        self.motion_delta += glm::vec2(x as f32, y as f32);
+    }
+
     fn mouse_wheel(&mut self, h_lines: f32, v_lines: f32) {
         self.wheel_delta = glm::vec2(h_lines, v_lines);
         self.scrolled = true;
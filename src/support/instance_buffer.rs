@@ -0,0 +1,213 @@
+use crate::{Renderer, VertexLayout};
+use std::ops::Range;
+use wgpu::{
+    util::DeviceExt, Buffer, BufferAddress, BufferSlice, BufferUsages, Device, VertexAttribute,
+};
+
+/// Per instance data kept on the CPU and mirrored into a vertex buffer.
+/// Changes mark a dirty range, and `upload` writes only that range unless the buffer has to grow.
+pub struct InstanceBuffer<T> {
+    buffer: Buffer,
+    instances: Vec<T>,
+    capacity: usize,
+    usage: BufferUsages,
+    dirty: Option<Range<usize>>,
+}
+
+impl<T: bytemuck::Pod> InstanceBuffer<T> {
+    pub fn new(device: &Device, instances: Vec<T>) -> Self {
+        Self::with_usage(device, instances, BufferUsages::empty())
+    }
+
+    /// Adds `usage` to the vertex and copy usages, such as `STORAGE` for reading in a compute pass
+    pub fn with_usage(device: &Device, instances: Vec<T>, usage: BufferUsages) -> Self {
+        let usage = usage | BufferUsages::VERTEX | BufferUsages::COPY_DST;
+        let capacity = instances.len().max(1);
+        let mut contents = bytemuck::cast_slice(&instances).to_vec();
+        contents.resize(capacity * Self::STRIDE as usize, 0);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: &contents,
+            usage,
+        });
+        Self {
+            buffer,
+            instances,
+            capacity,
+            usage,
+            dirty: None,
+        }
+    }
+
+    /// The vertex buffer layout for instances of `T`, read from the given shader locations
+    pub fn layout(attributes: &[VertexAttribute]) -> VertexLayout {
+        VertexLayout::new(Self::STRIDE, wgpu::VertexStepMode::Instance, attributes)
+    }
+
+    /// Fails to compile for instance types the staging belt can't write at arbitrary indices,
+    /// since its offsets and sizes must be multiples of `COPY_BUFFER_ALIGNMENT`
+    const STRIDE: BufferAddress = {
+        let stride = std::mem::size_of::<T>() as BufferAddress;
+        assert!(
+            stride % wgpu::COPY_BUFFER_ALIGNMENT == 0,
+            "instance size must be a multiple of 4 bytes"
+        );
+        stride
+    };
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn instances(&self) -> &[T] {
+        &self.instances
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.instances.get(index)
+    }
+
+    /// Only marks the instance dirty if its value changed
+    pub fn set(&mut self, index: usize, instance: T) {
+        if bytemuck::bytes_of(&self.instances[index]) != bytemuck::bytes_of(&instance) {
+            self.instances[index] = instance;
+            self.mark_dirty(index..index + 1);
+        }
+    }
+
+    /// Returns the index of the new instance
+    pub fn push(&mut self, instance: T) -> usize {
+        let index = self.instances.len();
+        self.instances.push(instance);
+        self.mark_dirty(index..index + 1);
+        index
+    }
+
+    /// Shifts the following instances down, so everything after `index` is rewritten
+    pub fn remove(&mut self, index: usize) -> T {
+        let instance = self.instances.remove(index);
+        if index < self.instances.len() {
+            self.mark_dirty(index..self.instances.len());
+        }
+        instance
+    }
+
+    /// Replaces every instance, marking only the ones that changed as dirty
+    pub fn replace(&mut self, instances: &[T]) {
+        self.instances.truncate(instances.len());
+        for (index, instance) in instances.iter().enumerate() {
+            if index < self.instances.len() {
+                self.set(index, *instance);
+            } else {
+                self.push(*instance);
+            }
+        }
+    }
+
+    /// Writes the dirty range, or reallocates the buffer and writes everything once
+    /// the instances no longer fit
+    pub fn upload(&mut self, renderer: &mut Renderer) {
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Instance Buffer"),
+                size: self.capacity as BufferAddress * Self::STRIDE,
+                usage: self.usage,
+                mapped_at_creation: false,
+            });
+            self.dirty = Some(0..self.instances.len());
+        }
+        let Some(dirty) = self.dirty.take() else {
+            return;
+        };
+        let end = dirty.end.min(self.instances.len());
+        if dirty.start < end {
+            renderer.write_buffer(
+                &self.buffer,
+                dirty.start as BufferAddress * Self::STRIDE,
+                bytemuck::cast_slice(&self.instances[dirty.start..end]),
+            );
+        }
+    }
+
+    /// From the instance at `first` to the end of the buffer, for `set_vertex_buffer`
+    pub fn slice(&self, first: usize) -> BufferSlice<'_> {
+        self.buffer.slice(first as BufferAddress * Self::STRIDE..)
+    }
+
+    fn mark_dirty(&mut self, range: Range<usize>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(range.start)..dirty.end.max(range.end),
+            None => range,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RendererOptions, Viewport};
+
+    fn renderer() -> Renderer {
+        let viewport = Viewport {
+            width: 1,
+            height: 1,
+            ..Default::default()
+        };
+        Renderer::new_headless(&viewport, &RendererOptions::default())
+            .expect("a headless renderer, software adapters work")
+    }
+
+    #[test]
+    fn changes_merge_into_one_dirty_range() {
+        let mut renderer = renderer();
+        let mut instances = InstanceBuffer::new(&renderer.device, vec![0_u32; 16]);
+        assert_eq!(instances.dirty, None);
+
+        instances.set(3, 0);
+        assert_eq!(instances.dirty, None);
+        instances.set(5, 1);
+        assert_eq!(instances.dirty, Some(5..6));
+        instances.set(2, 1);
+        instances.set(4, 1);
+        assert_eq!(instances.dirty, Some(2..6));
+
+        instances.upload(&mut renderer);
+        assert_eq!(instances.dirty, None);
+
+        instances.remove(10);
+        assert_eq!(instances.dirty, Some(10..15));
+        instances.upload(&mut renderer);
+        instances.remove(14);
+        assert_eq!(instances.dirty, None);
+        assert_eq!(instances.push(7), 14);
+        assert_eq!(instances.dirty, Some(14..15));
+    }
+
+    #[test]
+    fn replace_marks_only_changed_instances() {
+        let mut renderer = renderer();
+        let mut instances = InstanceBuffer::new(&renderer.device, vec![1_u32, 2, 3, 4]);
+
+        instances.replace(&[1, 2, 9, 4, 5, 6]);
+        assert_eq!(instances.instances(), [1, 2, 9, 4, 5, 6]);
+        assert_eq!(instances.dirty, Some(2..6));
+
+        // Growing past the capacity reallocates and rewrites everything
+        instances.upload(&mut renderer);
+        assert_eq!(instances.capacity, 8);
+        assert_eq!(instances.dirty, None);
+
+        instances.replace(&[1, 2, 9]);
+        assert_eq!(instances.len(), 3);
+        assert_eq!(instances.dirty, None);
+    }
+}
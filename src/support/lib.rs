@@ -1,13 +1,76 @@
+pub mod adapter;
 pub mod app;
+pub mod asset_browser;
+pub mod atlas;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod benchmark;
+pub mod binding;
+pub mod bounds;
+pub mod bvh;
+pub mod cache;
 pub mod camera;
+pub mod capture;
+pub mod compute;
+pub mod config;
+pub mod console;
+pub mod depth_of_field;
+pub mod dock;
+pub mod download;
+pub mod frame_capture;
+pub mod frustum;
+pub mod fullscreen;
+pub mod fxaa;
 pub mod geometry;
+pub mod globals;
+#[cfg(feature = "golden")]
+pub mod golden;
+pub mod grid;
 pub mod gui;
 pub mod input;
+pub mod instance_buffer;
+pub mod lod;
+pub mod mesh_optimizer;
+pub mod motion_blur;
+pub mod noise;
+pub mod oit;
+pub mod palette;
+pub mod particles;
+pub mod per_draw;
+pub mod pipeline;
+pub mod primitives;
+pub mod quad;
+pub mod ray;
 pub mod render;
+pub mod render_target;
+pub mod scene_view;
+pub mod session;
+pub mod settings;
+pub mod shader;
+pub mod state;
+pub mod stats;
+pub mod streaming;
 pub mod system;
 pub mod texture;
+pub mod time_of_day;
 pub mod transform;
+pub mod tutorial;
+pub mod uniform;
+pub mod vertex;
 
 pub use self::{
-    app::*, geometry::*, gui::*, input::*, render::*, system::*, texture::*, transform::*,
+    adapter::*, app::*, asset_browser::*, atlas::*, benchmark::*, binding::*, bounds::*, bvh::*,
+    cache::*, capture::*, compute::*, config::*, console::*, depth_of_field::*, dock::*,
+    download::*, frame_capture::*, frustum::*, fullscreen::*, fxaa::*, geometry::*, globals::*,
+    grid::*, gui::*, input::*, instance_buffer::*, lod::*, mesh_optimizer::*, motion_blur::*,
+    noise::*, oit::*, palette::*, particles::*, per_draw::*, pipeline::*, primitives::*, quad::*,
+    ray::*, render::*, render_target::*, scene_view::*, session::*, settings::*, shader::*,
+    state::*, stats::*, streaming::*, system::*, texture::*, time_of_day::*, transform::*,
+    tutorial::*, uniform::*, vertex::*,
 };
+
+#[cfg(feature = "audio")]
+pub use self::audio::*;
+
+#[cfg(feature = "golden")]
+pub use self::golden::*;
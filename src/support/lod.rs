@@ -0,0 +1,75 @@
+use nalgebra_glm as glm;
+
+/// Chooses a level of detail per object from how much of the screen it covers
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodSelector {
+    /// Minimum screen coverage of each level after the first, in descending order
+    pub thresholds: Vec<f32>,
+    /// Forces every object to one level when set
+    pub forced_level: Option<usize>,
+    /// Tints objects by their selected level
+    pub visualize: bool,
+}
+
+impl Default for LodSelector {
+    fn default() -> Self {
+        Self {
+            thresholds: vec![0.15, 0.05],
+            forced_level: None,
+            visualize: false,
+        }
+    }
+}
+
+impl LodSelector {
+    pub fn level_count(&self) -> usize {
+        self.thresholds.len() + 1
+    }
+
+    pub fn select(&self, coverage: f32) -> usize {
+        if let Some(level) = self.forced_level {
+            return level.min(self.thresholds.len());
+        }
+        self.thresholds
+            .iter()
+            .take_while(|threshold| coverage < **threshold)
+            .count()
+    }
+
+    /// Draws the override and threshold controls, returning true if anything changed
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let previous = self.clone();
+        let level_text = |level: Option<usize>| {
+            level.map_or_else(|| "Automatic".to_string(), |level| format!("LOD {level}"))
+        };
+        egui::ComboBox::from_label("Level")
+            .selected_text(level_text(self.forced_level))
+            .show_ui(ui, |ui| {
+                let levels = std::iter::once(None).chain((0..self.level_count()).map(Some));
+                for level in levels {
+                    ui.selectable_value(&mut self.forced_level, level, level_text(level));
+                }
+            });
+        for (index, threshold) in self.thresholds.iter_mut().enumerate() {
+            ui.add(
+                egui::Slider::new(threshold, 0.0..=1.0).text(format!("LOD {} below", index + 1)),
+            );
+        }
+        ui.checkbox(&mut self.visualize, "Visualize levels");
+        *self != previous
+    }
+}
+
+/// Fraction of the viewport height covered by a bounding sphere
+pub fn screen_coverage(
+    center: &glm::Vec3,
+    radius: f32,
+    camera_position: &glm::Vec3,
+    y_fov_rad: f32,
+) -> f32 {
+    let distance = glm::distance(center, camera_position);
+    if distance <= radius {
+        return 1.0;
+    }
+    radius / (distance * (y_fov_rad * 0.5).tan())
+}
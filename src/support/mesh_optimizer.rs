@@ -0,0 +1,286 @@
+use crate::{MeshData, PrimitiveVertex};
+use std::collections::{HashMap, VecDeque};
+
+/// Vertices kept in the simulated post-transform cache while ordering triangles
+const ORDERING_CACHE_SIZE: usize = 32;
+
+/// Entries in the FIFO cache used to measure the miss ratio, a conservative hardware estimate
+const MEASURED_CACHE_SIZE: usize = 16;
+
+/// Counts describing how expensive a mesh is to fetch and transform
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct MeshReport {
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Vertex shader invocations per triangle, between 0.5 for an ideal grid and 3.0 for no reuse
+    pub cache_miss_ratio: f32,
+}
+
+impl MeshReport {
+    pub fn new(mesh: &MeshData) -> Self {
+        let mut cache = VecDeque::with_capacity(MEASURED_CACHE_SIZE);
+        let mut misses = 0;
+        for index in mesh.indices.iter() {
+            if cache.contains(index) {
+                continue;
+            }
+            misses += 1;
+            if cache.len() == MEASURED_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back(*index);
+        }
+        let triangles = mesh.indices.len() / 3;
+        Self {
+            vertices: mesh.vertices.len(),
+            triangles,
+            cache_miss_ratio: misses as f32 / triangles.max(1) as f32,
+        }
+    }
+}
+
+/// A mesh's counts before and after `MeshData::optimize`
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct MeshOptimization {
+    pub before: MeshReport,
+    pub after: MeshReport,
+}
+
+impl MeshOptimization {
+    pub fn show(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("mesh_optimization")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label("Before");
+                ui.label("After");
+                ui.end_row();
+                ui.label("Vertices");
+                ui.label(self.before.vertices.to_string());
+                ui.label(self.after.vertices.to_string());
+                ui.end_row();
+                ui.label("Triangles");
+                ui.label(self.before.triangles.to_string());
+                ui.label(self.after.triangles.to_string());
+                ui.end_row();
+                ui.label("Cache miss ratio");
+                ui.label(format!("{:.3}", self.before.cache_miss_ratio));
+                ui.label(format!("{:.3}", self.after.cache_miss_ratio));
+                ui.end_row();
+            });
+    }
+}
+
+impl MeshData {
+    /// Welds duplicate vertices, then reorders triangles for the vertex cache
+    /// and vertices for fetch locality
+    pub fn optimize(&mut self) -> MeshOptimization {
+        let before = MeshReport::new(self);
+        self.weld();
+        self.optimize_vertex_cache();
+        self.optimize_vertex_fetch();
+        MeshOptimization {
+            before,
+            after: MeshReport::new(self),
+        }
+    }
+
+    /// Merges vertices whose attributes are bit for bit identical
+    pub fn weld(&mut self) {
+        let mut unique = HashMap::new();
+        let mut vertices = Vec::new();
+        let remap = self
+            .vertices
+            .iter()
+            .map(|vertex| {
+                *unique
+                    .entry(bytemuck::cast::<PrimitiveVertex, [u32; 8]>(*vertex))
+                    .or_insert_with(|| {
+                        vertices.push(*vertex);
+                        vertices.len() as u32 - 1
+                    })
+            })
+            .collect::<Vec<_>>();
+        self.indices
+            .iter_mut()
+            .for_each(|index| *index = remap[*index as usize]);
+        self.vertices = vertices;
+    }
+
+    /// Reorders triangles so vertices are reused while they are still in the post-transform cache,
+    /// following Tom Forsyth's linear-speed vertex cache optimization
+    pub fn optimize_vertex_cache(&mut self) {
+        let triangle_count = self.indices.len() / 3;
+        let mut adjacency = vec![Vec::new(); self.vertices.len()];
+        for (triangle, corners) in self.indices.chunks_exact(3).enumerate() {
+            for &vertex in corners {
+                adjacency[vertex as usize].push(triangle);
+            }
+        }
+        let mut valence = adjacency
+            .iter()
+            .map(|triangles| triangles.len())
+            .collect::<Vec<_>>();
+        let mut scores = valence
+            .iter()
+            .map(|&valence| vertex_score(None, valence))
+            .collect::<Vec<_>>();
+
+        let mut emitted = vec![false; triangle_count];
+        let mut cache: Vec<u32> = Vec::with_capacity(ORDERING_CACHE_SIZE + 3);
+        let mut indices = Vec::with_capacity(self.indices.len());
+        // Where to look for the next triangle when nothing in the cache has any left
+        let mut cursor = 0;
+        loop {
+            let triangle_score = |triangle: usize| -> f32 {
+                self.indices[triangle * 3..triangle * 3 + 3]
+                    .iter()
+                    .map(|&vertex| scores[vertex as usize])
+                    .sum()
+            };
+            let best = cache
+                .iter()
+                .flat_map(|&vertex| adjacency[vertex as usize].iter().copied())
+                .filter(|&triangle| !emitted[triangle])
+                .map(|triangle| (triangle, triangle_score(triangle)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(triangle, _)| triangle);
+            let triangle = match best {
+                Some(triangle) => triangle,
+                None => {
+                    while cursor < triangle_count && emitted[cursor] {
+                        cursor += 1;
+                    }
+                    if cursor == triangle_count {
+                        break;
+                    }
+                    cursor
+                }
+            };
+
+            emitted[triangle] = true;
+            let corners = &self.indices[triangle * 3..triangle * 3 + 3];
+            indices.extend_from_slice(corners);
+            for &vertex in corners {
+                valence[vertex as usize] -= 1;
+            }
+
+            let mut next_cache = Vec::with_capacity(ORDERING_CACHE_SIZE + 3);
+            for &vertex in corners.iter().chain(cache.iter()) {
+                if !next_cache.contains(&vertex) {
+                    next_cache.push(vertex);
+                }
+            }
+            let evicted = next_cache.split_off(next_cache.len().min(ORDERING_CACHE_SIZE));
+            for (position, &vertex) in next_cache.iter().enumerate() {
+                scores[vertex as usize] = vertex_score(Some(position), valence[vertex as usize]);
+            }
+            for vertex in evicted {
+                scores[vertex as usize] = vertex_score(None, valence[vertex as usize]);
+            }
+            cache = next_cache;
+        }
+        self.indices = indices;
+    }
+
+    /// Reorders vertices into the order the indices first use them, dropping unreferenced ones
+    pub fn optimize_vertex_fetch(&mut self) {
+        let mut remap = vec![None; self.vertices.len()];
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        for index in self.indices.iter_mut() {
+            *index = *remap[*index as usize].get_or_insert_with(|| {
+                vertices.push(self.vertices[*index as usize]);
+                vertices.len() as u32 - 1
+            });
+        }
+        self.vertices = vertices;
+    }
+}
+
+/// Favors vertices already in the cache and vertices with few triangles left to draw,
+/// so nearly finished vertices are used up before they get evicted
+fn vertex_score(cache_position: Option<usize>, valence: usize) -> f32 {
+    if valence == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        // The last triangle's vertices score a little lower, so strips don't keep doubling back
+        Some(position) if position < 3 => 0.75,
+        Some(position) => {
+            let scale = 1.0 / (ORDERING_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(1.5)
+        }
+        None => 0.0,
+    };
+    cache_score + 2.0 * (valence as f32).powf(-0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each triangle's vertex attributes rotated to start at the smallest, keeping the winding
+    fn triangles(mesh: &MeshData) -> Vec<[[u32; 8]; 3]> {
+        let mut triangles = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|corners| {
+                let corners = [0, 1, 2].map(|corner| {
+                    bytemuck::cast::<PrimitiveVertex, [u32; 8]>(
+                        mesh.vertices[corners[corner] as usize],
+                    )
+                });
+                let first = (0..3).min_by_key(|corner| corners[*corner]).unwrap();
+                [0, 1, 2].map(|offset| corners[(first + offset) % 3])
+            })
+            .collect::<Vec<_>>();
+        triangles.sort_unstable();
+        triangles
+    }
+
+    /// Every triangle with its own three vertices, as a mesh loaded without an index buffer would be
+    fn unindexed(mesh: &MeshData) -> MeshData {
+        MeshData {
+            vertices: mesh
+                .indices
+                .iter()
+                .map(|index| mesh.vertices[*index as usize])
+                .collect(),
+            indices: (0..mesh.indices.len() as u32).collect(),
+        }
+    }
+
+    #[test]
+    fn weld_keeps_the_triangles_and_merges_duplicates() {
+        let sphere = MeshData::uv_sphere(1.0, 24, 12);
+        let mut mesh = unindexed(&sphere);
+        mesh.weld();
+        assert_eq!(triangles(&mesh), triangles(&sphere));
+        assert!(mesh.vertices.len() <= sphere.vertices.len());
+        let unique = mesh
+            .vertices
+            .iter()
+            .map(|vertex| bytemuck::cast::<PrimitiveVertex, [u32; 8]>(*vertex))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), mesh.vertices.len());
+    }
+
+    #[test]
+    fn optimize_draws_the_same_triangles() {
+        for original in [
+            MeshData::torus(0.8, 0.35, 48, 24),
+            MeshData::icosphere(1.0, 3),
+            MeshData::plane(4.0, 16),
+        ] {
+            let mut mesh = unindexed(&original);
+            let optimization = mesh.optimize();
+            assert_eq!(triangles(&mesh), triangles(&original));
+            assert!(mesh
+                .indices
+                .iter()
+                .all(|index| (*index as usize) < mesh.vertices.len()));
+            assert!(optimization.after.vertices < optimization.before.vertices);
+            assert!(optimization.after.cache_miss_ratio < optimization.before.cache_miss_ratio);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{BindingBuilder, BindingLayout, FullscreenPipeline, Renderer, Texture};
+use nalgebra_glm as glm;
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device, RenderPass, TextureFormat};
+
+const MOTION_BLUR_SOURCE: &str = "
+struct MotionBlur {
+    inverse_view_projection: mat4x4<f32>,
+    previous_view_projection: mat4x4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var depth_texture: texture_2d<f32>;
+@group(0) @binding(3)
+var<uniform> blur: MotionBlur;
+
+// Longest blur as a fraction of the screen, so camera cuts don't smear the whole frame
+const MAX_VELOCITY: f32 = 0.05;
+
+@fragment
+fn motion_blur_fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let coords = clamp(vec2<i32>(in.uv * vec2<f32>(size)), vec2<i32>(0), size - 1);
+    let depth = textureLoad(depth_texture, coords, 0).x;
+
+    // Points on the far plane of an infinite projection reconstruct to directions
+    let ndc = vec2<f32>(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+    var world = blur.inverse_view_projection * vec4<f32>(ndc, depth, 1.0);
+    if abs(world.w) > 1e-6 {
+        world = world / world.w;
+    } else {
+        world.w = 0.0;
+    }
+
+    let previous_clip = blur.previous_view_projection * world;
+    var velocity = vec2<f32>(0.0);
+    if previous_clip.w > 1e-6 {
+        let previous_ndc = previous_clip.xy / previous_clip.w;
+        let previous_uv = vec2<f32>(previous_ndc.x * 0.5 + 0.5, 0.5 - previous_ndc.y * 0.5);
+        velocity = (in.uv - previous_uv) * blur.params.x;
+        let speed = length(velocity);
+        if speed > MAX_VELOCITY {
+            velocity = velocity * (MAX_VELOCITY / speed);
+        }
+    }
+
+    let center = textureSampleLevel(source_texture, source_sampler, in.uv, 0.0);
+    let samples = max(i32(blur.params.y), 2);
+    var color = vec3<f32>(0.0);
+    for (var index = 0; index < samples; index++) {
+        let offset = f32(index) / f32(samples - 1) - 0.5;
+        color += textureSampleLevel(source_texture, source_sampler, in.uv + velocity * offset, 0.0).rgb;
+    }
+    return vec4<f32>(color / f32(samples), center.a);
+}
+";
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct MotionBlurUniform {
+    inverse_view_projection: glm::Mat4,
+    previous_view_projection: glm::Mat4,
+    /// Fraction of the frame's motion to blur over and the sample count
+    params: glm::Vec4,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MotionBlurSettings {
+    /// Degrees of the frame the shutter stays open, 360 blurs over the full motion since the last frame
+    pub shutter_angle: f32,
+    pub samples: u32,
+}
+
+impl Default for MotionBlurSettings {
+    fn default() -> Self {
+        Self {
+            shutter_angle: 180.0,
+            samples: 12,
+        }
+    }
+}
+
+impl MotionBlurSettings {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::Slider::new(&mut self.shutter_angle, 0.0..=360.0).text("Shutter angle"));
+        ui.add(egui::Slider::new(&mut self.samples, 2..=32).text("Samples"));
+    }
+}
+
+/// Blurs the scene's color along each pixel's screen space motion since the last frame.
+/// Motion comes from reprojecting depth with the previous frame's view projection,
+/// so it follows the camera; objects moving on their own aren't blurred.
+pub struct MotionBlur {
+    pipeline: FullscreenPipeline,
+    bind_group_layout: BindingLayout,
+    bind_group: BindGroup,
+    uniform: Buffer,
+    previous_view_projection: Option<glm::Mat4>,
+}
+
+impl MotionBlur {
+    /// The color needs a filtering sampler, the depth is the scene's depth attachment
+    pub fn new(device: &Device, format: TextureFormat, color: &Texture, depth: &Texture) -> Self {
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Motion Blur Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[MotionBlurUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = BindingBuilder::new()
+            .label("Motion Blur")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            .unfilterable_texture(2, wgpu::ShaderStages::FRAGMENT)
+            .uniform(3, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let pipeline = FullscreenPipeline::new(
+            device,
+            format,
+            MOTION_BLUR_SOURCE,
+            "motion_blur_fragment",
+            &[&bind_group_layout.layout],
+        );
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, color, depth, &uniform);
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            uniform,
+            previous_view_projection: None,
+        }
+    }
+
+    /// Call when the source textures are recreated, such as after their render target resizes
+    pub fn set_source(&mut self, device: &Device, color: &Texture, depth: &Texture) {
+        self.bind_group =
+            Self::create_bind_group(device, &self.bind_group_layout, color, depth, &self.uniform);
+    }
+
+    /// Call once per frame with the view projection the scene renders with.
+    /// It is kept for the next frame, the first frame has no motion.
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        settings: &MotionBlurSettings,
+        view_projection: glm::Mat4,
+    ) {
+        let previous_view_projection = self
+            .previous_view_projection
+            .replace(view_projection)
+            .unwrap_or(view_projection);
+        let uniform = MotionBlurUniform {
+            inverse_view_projection: glm::inverse(&view_projection),
+            previous_view_projection,
+            params: glm::vec4(
+                settings.shutter_angle / 360.0,
+                settings.samples as f32,
+                0.0,
+                0.0,
+            ),
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    /// Draws into a pass without a depth attachment, the scene's depth is being read
+    pub fn render<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.push_debug_group("Motion blur");
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        self.pipeline.draw(renderpass);
+        renderpass.pop_debug_group();
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindingLayout,
+        color: &Texture,
+        depth: &Texture,
+        uniform: &Buffer,
+    ) -> BindGroup {
+        layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&color.view),
+                wgpu::BindingResource::Sampler(&color.sampler),
+                wgpu::BindingResource::TextureView(&depth.view),
+                uniform.as_entire_binding(),
+            ],
+        )
+    }
+}
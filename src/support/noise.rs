@@ -0,0 +1,307 @@
+use crate::{BindingBuilder, ComputeKernel, Renderer, Texture};
+use wgpu::{util::DeviceExt, BindGroup, Buffer, Device};
+
+const NOISE_SOURCE: &str = "
+struct Noise {
+    size: u32,
+    kind: u32,
+    period: u32,
+    octaves: u32,
+    seed: u32,
+    persistence: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> noise: Noise;
+
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+fn hash(cell: vec2<i32>, octave: u32) -> u32 {
+    var state = (u32(cell.x) * 0x27d4eb2du) ^ (u32(cell.y) * 0x165667b1u) ^ ((noise.seed + octave) * 0x9e3779b9u);
+    state = (state ^ (state >> 15u)) * 0x2c1b3c6du;
+    state = (state ^ (state >> 12u)) * 0x297a2d39u;
+    return state ^ (state >> 15u);
+}
+
+fn random2(cell: vec2<i32>, octave: u32) -> vec2<f32> {
+    let value = hash(cell, octave);
+    return vec2<f32>(f32(value & 0xffffu), f32(value >> 16u)) / 65535.0;
+}
+
+fn gradient(cell: vec2<i32>, octave: u32) -> vec2<f32> {
+    let angle = random2(cell, octave).x * 6.28318530;
+    return vec2<f32>(cos(angle), sin(angle));
+}
+
+fn wrap(cell: vec2<i32>, period: i32) -> vec2<i32> {
+    return ((cell % period) + period) % period;
+}
+
+fn perlin(point: vec2<f32>, period: i32, octave: u32) -> f32 {
+    let cell = vec2<i32>(floor(point));
+    let local = fract(point);
+    let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+    let corner = vec2<i32>(1, 0);
+    let a = dot(gradient(wrap(cell, period), octave), local);
+    let b = dot(gradient(wrap(cell + corner.xy, period), octave), local - vec2<f32>(corner.xy));
+    let c = dot(gradient(wrap(cell + corner.yx, period), octave), local - vec2<f32>(corner.yx));
+    let d = dot(gradient(wrap(cell + corner.xx, period), octave), local - vec2<f32>(corner.xx));
+    return mix(mix(a, b, fade.x), mix(c, d, fade.x), fade.y) * 0.7 + 0.5;
+}
+
+// The skewed simplex lattice doesn't line up with the texture's edges, so this one doesn't tile
+fn simplex(point: vec2<f32>, period: i32, octave: u32) -> f32 {
+    let skew = (sqrt(3.0) - 1.0) * 0.5;
+    let unskew = (3.0 - sqrt(3.0)) / 6.0;
+    let cell = floor(point + (point.x + point.y) * skew);
+    let first = point - cell + (cell.x + cell.y) * unskew;
+    var step = vec2<f32>(0.0, 1.0);
+    if first.x > first.y {
+        step = vec2<f32>(1.0, 0.0);
+    }
+    let second = first - step + unskew;
+    let third = first - 1.0 + 2.0 * unskew;
+    let base = vec2<i32>(cell);
+    var total = 0.0;
+    var offsets = array<vec2<f32>, 3>(first, second, third);
+    var corners = array<vec2<i32>, 3>(base, base + vec2<i32>(step), base + vec2<i32>(1));
+    for (var index = 0; index < 3; index++) {
+        let offset = offsets[index];
+        let falloff = max(0.5 - dot(offset, offset), 0.0);
+        let falloff2 = falloff * falloff;
+        total += falloff2 * falloff2 * dot(gradient(wrap(corners[index], period), octave), offset);
+    }
+    return total * 35.0 + 0.5;
+}
+
+// Distance to the nearest of one random feature point per cell
+fn worley(point: vec2<f32>, period: i32, octave: u32) -> f32 {
+    let cell = vec2<i32>(floor(point));
+    let local = fract(point);
+    var nearest = 1.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let neighbor = vec2<i32>(x, y);
+            let feature = vec2<f32>(neighbor) + random2(wrap(cell + neighbor, period), octave);
+            nearest = min(nearest, distance(feature, local));
+        }
+    }
+    return nearest;
+}
+
+fn sample_noise(point: vec2<f32>, period: i32, octave: u32) -> f32 {
+    switch noise.kind {
+        case 1u: {
+            return simplex(point, period, octave);
+        }
+        case 2u: {
+            return worley(point, period, octave);
+        }
+        default: {
+            return perlin(point, period, octave);
+        }
+    }
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn generate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= noise.size * noise.size {
+        return;
+    }
+    let texel = vec2<u32>(index % noise.size, index / noise.size);
+    let uv = (vec2<f32>(texel) + 0.5) / f32(noise.size);
+
+    var value = 0.0;
+    var amplitude = 1.0;
+    var total = 0.0;
+    var period = i32(max(noise.period, 1u));
+    for (var octave = 0u; octave < max(noise.octaves, 1u); octave++) {
+        value += amplitude * sample_noise(uv * f32(period), period, octave);
+        total += amplitude;
+        amplitude *= noise.persistence;
+        period *= 2;
+    }
+    value = clamp(value / total, 0.0, 1.0);
+    textureStore(output, vec2<i32>(texel), vec4<f32>(vec3<f32>(value), 1.0));
+}
+";
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Smooth gradient noise
+    #[default]
+    Perlin,
+    /// Gradient noise on a triangular lattice, with fewer axis aligned artifacts than Perlin
+    Simplex,
+    /// Cellular noise, the distance to the nearest scattered feature point
+    Worley,
+}
+
+impl NoiseKind {
+    pub const ALL: [Self; 3] = [Self::Perlin, Self::Simplex, Self::Worley];
+}
+
+/// Fractal noise layered from octaves that double in frequency
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NoiseSettings {
+    pub kind: NoiseKind,
+    /// Lattice cells across the texture in the first octave, a whole number so Perlin and Worley tile
+    pub period: u32,
+    pub octaves: u32,
+    /// How much each octave's amplitude shrinks relative to the last
+    pub persistence: f32,
+    pub seed: u32,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            period: 8,
+            octaves: 4,
+            persistence: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+impl NoiseSettings {
+    /// Returns true if anything changed, so the texture can be regenerated
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let previous = *self;
+        egui::ComboBox::from_label("Noise")
+            .selected_text(format!("{:?}", self.kind))
+            .show_ui(ui, |ui| {
+                for kind in NoiseKind::ALL {
+                    ui.selectable_value(&mut self.kind, kind, format!("{kind:?}"));
+                }
+            });
+        ui.add(egui::Slider::new(&mut self.period, 1..=32).text("Period"));
+        ui.add(egui::Slider::new(&mut self.octaves, 1..=8).text("Octaves"));
+        ui.add(egui::Slider::new(&mut self.persistence, 0.0..=1.0).text("Persistence"));
+        ui.add(egui::DragValue::new(&mut self.seed).prefix("Seed "));
+        *self != previous
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct NoiseUniform {
+    size: u32,
+    kind: u32,
+    period: u32,
+    octaves: u32,
+    seed: u32,
+    persistence: f32,
+    _padding: [u32; 2],
+}
+
+/// A square grayscale noise texture filled by a compute kernel, sampled with repeating
+/// linear filtering so it can be tiled across surfaces. Regenerate it when the settings change.
+pub struct NoiseTexture {
+    pub texture: Texture,
+    pub size: u32,
+    kernel: ComputeKernel,
+    uniform: Buffer,
+    bind_group: BindGroup,
+}
+
+impl NoiseTexture {
+    pub fn new(device: &Device, size: u32, label: &str) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Noise Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[NoiseUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = BindingBuilder::new()
+            .label("Noise")
+            .uniform(0, wgpu::ShaderStages::COMPUTE)
+            .storage_texture(
+                1,
+                wgpu::ShaderStages::COMPUTE,
+                wgpu::TextureFormat::Rgba8Unorm,
+            )
+            .build(device);
+        let bind_group = bind_group_layout.bind_group(
+            device,
+            &[
+                uniform.as_entire_binding(),
+                wgpu::BindingResource::TextureView(&view),
+            ],
+        );
+        let kernel = ComputeKernel::new(
+            device,
+            NOISE_SOURCE,
+            "generate",
+            &[&bind_group_layout.layout],
+            64,
+        );
+
+        Self {
+            texture: Texture {
+                texture,
+                view,
+                sampler,
+            },
+            size,
+            kernel,
+            uniform,
+            bind_group,
+        }
+    }
+
+    /// Fills the texture in its own submission, so it is ready for the frame being recorded
+    pub fn generate(&self, renderer: &mut Renderer, settings: &NoiseSettings) {
+        let uniform = NoiseUniform {
+            size: self.size,
+            kind: settings.kind as u32,
+            period: settings.period.max(1),
+            octaves: settings.octaves.max(1),
+            seed: settings.seed,
+            persistence: settings.persistence,
+            _padding: [0; 2],
+        };
+        renderer.write_buffer(&self.uniform, 0, bytemuck::cast_slice(&[uniform]));
+
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Noise Encoder"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Noise Compute Pass"),
+            });
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            self.kernel.dispatch(&mut pass, self.size * self.size);
+        }
+        renderer.submit(encoder);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    render_target::create_color_texture, BindingBuilder, BindingLayout, FullscreenPipeline, Texture,
+};
+use wgpu::{
+    BindGroup, ColorTargetState, CommandEncoder, Device, RenderPass, TextureFormat, TextureView,
+};
+
+/// Prepended to shaders drawing into a `WeightedBlendedOit` pass. Fragment entry points
+/// return `oit_output` with their straight alpha color and view space distance.
+pub const OIT_SOURCE: &str = "
+struct OitOutput {
+    @location(0) accumulation: vec4<f32>,
+    @location(1) revealage: f32,
+};
+
+// Weights nearer and more opaque fragments higher, after McGuire and Bavoil's distance weight
+fn oit_output(color: vec4<f32>, view_distance: f32) -> OitOutput {
+    let falloff = 1e-5 + pow(view_distance / 5.0, 2.0) + pow(view_distance / 200.0, 6.0);
+    let weight = color.a * clamp(10.0 / falloff, 1e-2, 3e3);
+    var out: OitOutput;
+    out.accumulation = vec4<f32>(color.rgb * color.a, color.a) * weight;
+    out.revealage = -log(1.0 - min(color.a, 0.999));
+    return out;
+}
+";
+
+const COMPOSITE_SOURCE: &str = "
+@group(0) @binding(0)
+var accumulation_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var revealage_texture: texture_2d<f32>;
+
+@fragment
+fn oit_composite_fragment(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let coords = vec2<i32>(in.position.xy);
+    let revealage = exp(-textureLoad(revealage_texture, coords, 0).r);
+    if revealage >= 0.9999 {
+        discard;
+    }
+    let accumulation = textureLoad(accumulation_texture, coords, 0);
+    let average = accumulation.rgb / clamp(accumulation.a, 1e-4, 5e4);
+    return vec4<f32>(average, 1.0 - revealage);
+}
+";
+
+/// Weighted blended order independent transparency. Transparent surfaces are drawn in any
+/// order into an accumulation target, holding their weighted sum of premultiplied colors, and
+/// a revealage target, holding how much of the background still shows through. A composite
+/// pass then blends the weighted average over the opaque scene, so intersecting surfaces
+/// blend correctly where sorting by object can't order them. The result is an approximation
+/// that depends less on exact depth order the closer the surfaces' opacities are.
+///
+/// Revealage is kept as a sum of `-ln(1 - alpha)` so both targets share one additive blend
+/// state, which devices without independent blending can draw.
+pub struct WeightedBlendedOit {
+    pub accumulation: Texture,
+    pub revealage: Texture,
+    pub width: u32,
+    pub height: u32,
+    composite: FullscreenPipeline,
+    bind_group_layout: BindingLayout,
+    bind_group: BindGroup,
+}
+
+impl WeightedBlendedOit {
+    pub const ACCUMULATION_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+    pub const REVEALAGE_FORMAT: TextureFormat = TextureFormat::R16Float;
+
+    /// The format is the composite's target, the size must match the opaque scene's depth
+    pub fn new(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        let bind_group_layout = BindingBuilder::new()
+            .label("OIT Composite")
+            .unfilterable_texture(0, wgpu::ShaderStages::FRAGMENT)
+            .unfilterable_texture(1, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let composite = FullscreenPipeline::with_blend(
+            device,
+            format,
+            COMPOSITE_SOURCE,
+            "oit_composite_fragment",
+            &[&bind_group_layout.layout],
+            wgpu::BlendState::ALPHA_BLENDING,
+        );
+        let (width, height) = (width.max(1), height.max(1));
+        let (accumulation, revealage) = Self::create_targets(device, width, height);
+        let bind_group =
+            Self::create_bind_group(device, &bind_group_layout, &accumulation, &revealage);
+        Self {
+            accumulation,
+            revealage,
+            width,
+            height,
+            composite,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        (self.accumulation, self.revealage) = Self::create_targets(device, width, height);
+        self.bind_group = Self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.accumulation,
+            &self.revealage,
+        );
+        self.width = width;
+        self.height = height;
+    }
+
+    /// The color targets of pipelines drawing transparent surfaces into the pass,
+    /// which should test against the depth without writing to it
+    pub fn color_targets() -> [Option<ColorTargetState>; 2] {
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let target = |format| {
+            Some(ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: additive,
+                    alpha: additive,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })
+        };
+        [
+            target(Self::ACCUMULATION_FORMAT),
+            target(Self::REVEALAGE_FORMAT),
+        ]
+    }
+
+    /// Clears both targets and loads the opaque scene's depth, so opaque surfaces hide
+    /// the transparent ones behind them
+    pub fn begin_pass<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        depth: &'a TextureView,
+    ) -> RenderPass<'a> {
+        let attachment = |view, clear| {
+            Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: true,
+                },
+            })
+        };
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("OIT Pass"),
+            color_attachments: &[
+                attachment(&self.accumulation.view, wgpu::Color::TRANSPARENT),
+                attachment(&self.revealage.view, wgpu::Color::TRANSPARENT),
+            ],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        })
+    }
+
+    /// Blends the transparent surfaces over a pass holding the opaque scene
+    pub fn composite<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.push_debug_group("OIT composite");
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        self.composite.draw(renderpass);
+        renderpass.pop_debug_group();
+    }
+
+    fn create_targets(device: &Device, width: u32, height: u32) -> (Texture, Texture) {
+        (
+            create_color_texture(
+                device,
+                "OIT Accumulation",
+                Self::ACCUMULATION_FORMAT,
+                width,
+                height,
+            ),
+            create_color_texture(
+                device,
+                "OIT Revealage",
+                Self::REVEALAGE_FORMAT,
+                width,
+                height,
+            ),
+        )
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        layout: &BindingLayout,
+        accumulation: &Texture,
+        revealage: &Texture,
+    ) -> BindGroup {
+        layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&accumulation.view),
+                wgpu::BindingResource::TextureView(&revealage.view),
+            ],
+        )
+    }
+}
@@ -0,0 +1,92 @@
+use nalgebra_glm as glm;
+use serde::{Deserialize, Serialize};
+
+/// Categorical color sets for debug visualizations
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Classic,
+    /// Okabe and Ito's set, distinguishable with all common forms of color blindness
+    OkabeIto,
+    /// Paul Tol's bright qualitative scheme
+    TolBright,
+    /// IBM's color blind safe design palette
+    Ibm,
+}
+
+impl Palette {
+    pub const ALL: [Self; 4] = [Self::Classic, Self::OkabeIto, Self::TolBright, Self::Ibm];
+
+    /// Colors as 0xRRGGBB sRGB values
+    pub fn hex_colors(&self) -> &'static [u32] {
+        match self {
+            Self::Classic => &[0xff0000, 0x00ff00, 0x0000ff, 0xffff00, 0x00ffff, 0xff00ff],
+            Self::OkabeIto => &[
+                0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7, 0x000000,
+            ],
+            Self::TolBright => &[
+                0x4477aa, 0xee6677, 0x228833, 0xccbb44, 0x66ccee, 0xaa3377, 0xbbbbbb,
+            ],
+            Self::Ibm => &[0x648fff, 0x785ef0, 0xdc267f, 0xfe6100, 0xffb000],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hex_colors().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hex_colors().is_empty()
+    }
+
+    /// The sRGB color for a category, wrapping around when there are more categories than colors
+    pub fn color(&self, index: usize) -> glm::Vec3 {
+        let colors = self.hex_colors();
+        let hex = colors[index % colors.len()];
+        glm::vec3(
+            ((hex >> 16) & 0xff) as f32 / 255.0,
+            ((hex >> 8) & 0xff) as f32 / 255.0,
+            (hex & 0xff) as f32 / 255.0,
+        )
+    }
+
+    /// The color for a category converted to linear space, for writing to sRGB render targets
+    pub fn linear_color(&self, index: usize) -> glm::Vec3 {
+        self.color(index).map(srgb_to_linear)
+    }
+
+    pub fn egui_color(&self, index: usize) -> egui::Color32 {
+        let color = self.color(index) * 255.0;
+        egui::Color32::from_rgb(color.x as u8, color.y as u8, color.z as u8)
+    }
+
+    /// Draws a combo box for choosing a palette with a swatch preview of each option
+    pub fn show(&mut self, ui: &mut egui::Ui, label: &str) -> bool {
+        let previous = *self;
+        egui::ComboBox::from_label(label)
+            .selected_text(format!("{self:?}"))
+            .show_ui(ui, |ui| {
+                for palette in Self::ALL {
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(self, palette, format!("{palette:?}"));
+                        for index in 0..palette.len() {
+                            let (rect, _) = ui
+                                .allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::hover());
+                            ui.painter()
+                                .rect_filled(rect, 0.0, palette.egui_color(index));
+                        }
+                    });
+                }
+            });
+        *self != previous
+    }
+}
+
+/// Decodes one sRGB channel, such as a color picked in egui, for use in a shader
+pub fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,741 @@
+use crate::{BindingBuilder, BindingLayout, ComputeKernel, Renderer, Texture};
+use nalgebra_glm as glm;
+use std::{borrow::Cow, mem};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, RenderPass,
+    RenderPipeline, TextureFormat,
+};
+
+/// Spawn and appearance parameters for one source of particles
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    pub name: String,
+    pub enabled: bool,
+    pub position: [f32; 3],
+    pub direction: [f32; 3],
+    /// Half angle of the cone particles are launched in
+    pub spread_degrees: f32,
+    pub speed: f32,
+    /// Particles spawned per second
+    pub spawn_rate: f32,
+    pub lifetime: f32,
+    /// Fraction of the lifetime that is randomly shaved off each particle
+    pub lifetime_variance: f32,
+    pub gravity: [f32; 3],
+    pub drag: f32,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub start_color: [f32; 4],
+    pub end_color: [f32; 4],
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            name: "Emitter".to_string(),
+            enabled: true,
+            position: [0.0; 3],
+            direction: [0.0, 1.0, 0.0],
+            spread_degrees: 20.0,
+            speed: 3.0,
+            spawn_rate: 200.0,
+            lifetime: 2.0,
+            lifetime_variance: 0.3,
+            gravity: [0.0, -1.0, 0.0],
+            drag: 0.1,
+            start_size: 0.3,
+            end_size: 0.8,
+            start_color: [1.0, 1.0, 1.0, 1.0],
+            end_color: [1.0, 1.0, 1.0, 0.0],
+        }
+    }
+}
+
+impl ParticleEmitter {
+    /// Draws the emitter's parameters, returning true if anything changed
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let previous = self.clone();
+        ui.checkbox(&mut self.enabled, "Enabled");
+        ui.horizontal(|ui| {
+            ui.label("Position");
+            for value in self.position.iter_mut() {
+                ui.add(egui::DragValue::new(value).speed(0.05));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Direction");
+            for value in self.direction.iter_mut() {
+                ui.add(
+                    egui::DragValue::new(value)
+                        .speed(0.01)
+                        .clamp_range(-1.0..=1.0),
+                );
+            }
+        });
+        ui.add(egui::Slider::new(&mut self.spread_degrees, 0.0..=180.0).text("Spread"));
+        ui.add(egui::Slider::new(&mut self.speed, 0.0..=20.0).text("Speed"));
+        ui.add(egui::Slider::new(&mut self.spawn_rate, 0.0..=5000.0).text("Spawn rate"));
+        ui.add(egui::Slider::new(&mut self.lifetime, 0.1..=10.0).text("Lifetime"));
+        ui.add(egui::Slider::new(&mut self.lifetime_variance, 0.0..=1.0).text("Lifetime variance"));
+        ui.horizontal(|ui| {
+            ui.label("Gravity");
+            for value in self.gravity.iter_mut() {
+                ui.add(egui::DragValue::new(value).speed(0.05));
+            }
+        });
+        ui.add(egui::Slider::new(&mut self.drag, 0.0..=5.0).text("Drag"));
+        ui.add(egui::Slider::new(&mut self.start_size, 0.01..=5.0).text("Start size"));
+        ui.add(egui::Slider::new(&mut self.end_size, 0.01..=5.0).text("End size"));
+        ui.horizontal(|ui| {
+            ui.color_edit_button_rgba_unmultiplied(&mut self.start_color);
+            ui.label("Start color");
+            ui.color_edit_button_rgba_unmultiplied(&mut self.end_color);
+            ui.label("End color");
+        });
+        *self != previous
+    }
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+    position: [f32; 3],
+    age: f32,
+    velocity: [f32; 3],
+    lifetime: f32,
+    emitter: u32,
+    padding: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct EmitterUniform {
+    /// Position, with the cosine of the spread angle in w
+    position: glm::Vec4,
+    /// Direction, with the speed in w
+    velocity: glm::Vec4,
+    start_color: glm::Vec4,
+    end_color: glm::Vec4,
+    /// Gravity, with the drag in w
+    acceleration: glm::Vec4,
+    /// Start size, end size, lifetime and lifetime variance
+    shape: glm::Vec4,
+    /// First ring slot and number of particles to spawn this frame
+    spawn: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SimulationUniform {
+    camera_position: glm::Vec4,
+    camera_forward: glm::Vec4,
+    /// Delta time
+    params: glm::Vec4,
+    /// Capacity, emitter count and frame index
+    counts: [u32; 4],
+    emitters: [EmitterUniform; ParticleSystem::MAX_EMITTERS],
+}
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct RenderUniform {
+    view_projection: glm::Mat4,
+    inverse_projection: glm::Mat4,
+    camera_right: glm::Vec4,
+    camera_up: glm::Vec4,
+    /// Soft particle fade distance
+    params: glm::Vec4,
+}
+
+/// GPU simulated billboards, sorted back to front every frame so they alpha blend correctly
+/// and faded where they intersect the scene's depth buffer.
+///
+/// Emitters share one ring of particles, so the oldest particles are recycled early
+/// if the emitters spawn more than the capacity holds.
+pub struct ParticleSystem {
+    pub emitters: Vec<ParticleEmitter>,
+    /// View space distance over which particles fade out as they approach scene geometry
+    pub softness: f32,
+    pub sorting: bool,
+    capacity: u32,
+    cursor: u32,
+    frame: u32,
+    spawn_accumulators: [f32; Self::MAX_EMITTERS],
+    sort_steps: u32,
+    sort_stride: u32,
+    simulation_uniform: Buffer,
+    render_uniform: Buffer,
+    simulation_bind_group: BindGroup,
+    sort_bind_group: BindGroup,
+    render_bind_group: BindGroup,
+    depth_layout: BindingLayout,
+    depth_bind_group: Option<BindGroup>,
+    update_kernel: ComputeKernel,
+    sort_kernel: ComputeKernel,
+    render_pipeline: RenderPipeline,
+}
+
+impl ParticleSystem {
+    pub const MAX_EMITTERS: usize = 4;
+    const WORKGROUP_SIZE: u32 = 256;
+
+    /// The capacity is rounded up to a power of two for the bitonic sort
+    pub fn new(device: &Device, color_format: TextureFormat, capacity: u32) -> Self {
+        let capacity = capacity.max(Self::WORKGROUP_SIZE).next_power_of_two();
+
+        let particles = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: (capacity as usize * mem::size_of::<Particle>()) as _,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        // Pairs of view depth and particle index, sorted in place
+        let keys = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Sort Keys"),
+            size: capacity as u64 * 8,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let simulation_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Simulation Uniform"),
+            contents: bytemuck::cast_slice(&[SimulationUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let render_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Render Uniform"),
+            contents: bytemuck::cast_slice(&[RenderUniform::default()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Every (block size, comparison distance) pair of the bitonic network, one per aligned slot
+        let sort_stride = device.limits().min_uniform_buffer_offset_alignment.max(16);
+        let mut sort_params = Vec::new();
+        let mut block = 2;
+        while block <= capacity {
+            let mut distance = block / 2;
+            while distance > 0 {
+                let mut slot = vec![0_u32; sort_stride as usize / 4];
+                slot[0] = distance;
+                slot[1] = block;
+                sort_params.extend(slot);
+                distance /= 2;
+            }
+            block *= 2;
+        }
+        let sort_steps = (sort_params.len() * 4) as u32 / sort_stride;
+        let sort_uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Sort Steps"),
+            contents: bytemuck::cast_slice(&sort_params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let compute = wgpu::ShaderStages::COMPUTE;
+        let vertex = wgpu::ShaderStages::VERTEX;
+
+        let simulation_layout = BindingBuilder::new()
+            .label("Particle Simulation")
+            .uniform(0, compute)
+            .storage(1, compute, false)
+            .storage(2, compute, false)
+            .build(device);
+        let sort_layout = BindingBuilder::new()
+            .label("Particle Sort")
+            .dynamic_uniform(0, compute)
+            .storage(1, compute, false)
+            .build(device);
+        let render_layout = BindingBuilder::new()
+            .label("Particle Render")
+            .uniform(0, vertex | wgpu::ShaderStages::FRAGMENT)
+            .uniform(1, vertex)
+            .storage(2, vertex, true)
+            .storage(3, vertex, true)
+            .build(device);
+        // Loaded as a plain float so the depth can be read without a comparison sampler on every backend
+        let depth_layout = BindingBuilder::new()
+            .label("Particle Depth")
+            .unfilterable_texture(0, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+
+        let simulation_bind_group = simulation_layout.bind_group(
+            device,
+            &[
+                simulation_uniform.as_entire_binding(),
+                particles.as_entire_binding(),
+                keys.as_entire_binding(),
+            ],
+        );
+        let sort_bind_group = sort_layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &sort_uniform,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(16),
+                }),
+                keys.as_entire_binding(),
+            ],
+        );
+        let render_bind_group = render_layout.bind_group(
+            device,
+            &[
+                render_uniform.as_entire_binding(),
+                simulation_uniform.as_entire_binding(),
+                particles.as_entire_binding(),
+                keys.as_entire_binding(),
+            ],
+        );
+
+        let update_kernel = ComputeKernel::new(
+            device,
+            &format!("{COMMON_SOURCE}{UPDATE_SOURCE}"),
+            "update",
+            &[&simulation_layout.layout],
+            Self::WORKGROUP_SIZE,
+        );
+        let sort_kernel = ComputeKernel::new(
+            device,
+            &format!("{COMMON_SOURCE}{SORT_SOURCE}"),
+            "sort",
+            &[&sort_layout.layout],
+            Self::WORKGROUP_SIZE,
+        );
+        let render_pipeline = Self::create_render_pipeline(
+            device,
+            color_format,
+            &render_layout.layout,
+            &depth_layout.layout,
+        );
+
+        Self {
+            emitters: Vec::new(),
+            softness: 0.5,
+            sorting: true,
+            capacity,
+            cursor: 0,
+            frame: 0,
+            spawn_accumulators: [0.0; Self::MAX_EMITTERS],
+            sort_steps,
+            sort_stride,
+            simulation_uniform,
+            render_uniform,
+            simulation_bind_group,
+            sort_bind_group,
+            render_bind_group,
+            depth_layout,
+            depth_bind_group: None,
+            update_kernel,
+            sort_kernel,
+            render_pipeline,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Binds the scene depth the particles fade against, call again whenever it is recreated.
+    /// Particles are not drawn until a depth texture is set.
+    pub fn set_depth_texture(&mut self, device: &Device, depth: &Texture) {
+        self.depth_bind_group = Some(
+            self.depth_layout
+                .bind_group(device, &[wgpu::BindingResource::TextureView(&depth.view)]),
+        );
+    }
+
+    /// Works out this frame's spawns and uploads the emitters and camera
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        delta_time: f32,
+        view: &glm::Mat4,
+        projection: &glm::Mat4,
+    ) {
+        self.frame = self.frame.wrapping_add(1);
+        let camera = glm::inverse(view);
+        let camera_position = camera.column(3).xyz();
+        let camera_right = camera.column(0).xyz();
+        let camera_up = camera.column(1).xyz();
+        let camera_forward = -camera.column(2).xyz();
+
+        let mut simulation = SimulationUniform {
+            camera_position: camera_position.push(1.0),
+            camera_forward: camera_forward.push(0.0),
+            params: glm::vec4(delta_time, 0.0, 0.0, 0.0),
+            counts: [self.capacity, 0, self.frame, 0],
+            ..Default::default()
+        };
+
+        let mut budget = self.capacity;
+        for (index, emitter) in self.emitters.iter().take(Self::MAX_EMITTERS).enumerate() {
+            let accumulator = &mut self.spawn_accumulators[index];
+            *accumulator += emitter.spawn_rate * delta_time;
+            let count = if emitter.enabled {
+                (accumulator.floor() as u32).min(budget)
+            } else {
+                0
+            };
+            *accumulator = accumulator.fract();
+            budget -= count;
+
+            let direction = glm::Vec3::from(emitter.direction);
+            let direction = if direction.norm_squared() > 0.0 {
+                direction.normalize()
+            } else {
+                glm::Vec3::y()
+            };
+            simulation.emitters[index] = EmitterUniform {
+                position: glm::Vec3::from(emitter.position)
+                    .push(emitter.spread_degrees.to_radians().cos()),
+                velocity: direction.push(emitter.speed),
+                start_color: emitter.start_color.into(),
+                end_color: emitter.end_color.into(),
+                acceleration: glm::Vec3::from(emitter.gravity).push(emitter.drag),
+                shape: glm::vec4(
+                    emitter.start_size,
+                    emitter.end_size,
+                    emitter.lifetime,
+                    emitter.lifetime_variance,
+                ),
+                spawn: [self.cursor, count, 0, 0],
+            };
+            simulation.counts[1] = index as u32 + 1;
+            self.cursor = (self.cursor + count) % self.capacity;
+        }
+        renderer.write_buffer(
+            &self.simulation_uniform,
+            0,
+            bytemuck::cast_slice(&[simulation]),
+        );
+
+        let render = RenderUniform {
+            view_projection: projection * view,
+            inverse_projection: glm::inverse(projection),
+            camera_right: camera_right.push(0.0),
+            camera_up: camera_up.push(0.0),
+            params: glm::vec4(self.softness, 0.0, 0.0, 0.0),
+        };
+        renderer.write_buffer(&self.render_uniform, 0, bytemuck::cast_slice(&[render]));
+    }
+
+    /// Runs the simulation ahead so the emitters start out in their steady state
+    pub fn prewarm(
+        &mut self,
+        renderer: &mut Renderer,
+        duration: f32,
+        view: &glm::Mat4,
+        projection: &glm::Mat4,
+    ) {
+        const STEP: f32 = 1.0 / 30.0;
+        for _ in 0..(duration / STEP).ceil() as u32 {
+            self.update(renderer, STEP, view, projection);
+            let mut encoder =
+                renderer
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Particle Prewarm Encoder"),
+                    });
+            self.compute(&mut encoder);
+            renderer.submit(encoder);
+        }
+    }
+
+    /// Records the simulation step and the depth sort
+    pub fn compute(&self, encoder: &mut CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Compute Pass"),
+        });
+        pass.set_bind_group(0, &self.simulation_bind_group, &[]);
+        self.update_kernel.dispatch(&mut pass, self.capacity);
+
+        if self.sorting {
+            let workgroups = self.sort_kernel.workgroup_count(self.capacity);
+            pass.set_pipeline(&self.sort_kernel.pipeline);
+            for step in 0..self.sort_steps {
+                pass.set_bind_group(0, &self.sort_bind_group, &[step * self.sort_stride]);
+                pass.dispatch_workgroups(workgroups, 1, 1);
+            }
+        }
+    }
+
+    /// Draws the particles back to front. The pass must not have the
+    /// depth texture attached, since it is sampled for the soft fade instead.
+    pub fn render<'rpass>(&'rpass self, renderpass: &mut RenderPass<'rpass>) {
+        let Some(depth_bind_group) = self.depth_bind_group.as_ref() else {
+            return;
+        };
+        renderpass.set_pipeline(&self.render_pipeline);
+        renderpass.set_bind_group(0, &self.render_bind_group, &[]);
+        renderpass.set_bind_group(1, depth_bind_group, &[]);
+        renderpass.draw(0..6, 0..self.capacity);
+    }
+
+    /// Draws the sorting and softness toggles and a collapsible editor per emitter
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("Capacity: {}", self.capacity));
+        ui.checkbox(&mut self.sorting, "Depth sort");
+        ui.add(egui::Slider::new(&mut self.softness, 0.0..=5.0).text("Softness"));
+        for (index, emitter) in self.emitters.iter_mut().enumerate() {
+            egui::CollapsingHeader::new(&emitter.name)
+                .id_source(index)
+                .show(ui, |ui| {
+                    emitter.show(ui);
+                });
+        }
+    }
+
+    fn create_render_pipeline(
+        device: &Device,
+        color_format: TextureFormat,
+        render_layout: &BindGroupLayout,
+        depth_layout: &BindGroupLayout,
+    ) -> RenderPipeline {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Render Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{COMMON_SOURCE}{RENDER_SOURCE}"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Render Pipeline Layout"),
+            bind_group_layouts: &[render_layout, depth_layout],
+            push_constant_ranges: &[],
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Particle Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vertex_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        })
+    }
+}
+
+const COMMON_SOURCE: &str = "
+struct Particle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+    emitter: u32,
+    padding: array<u32, 3>,
+};
+
+struct SortKey {
+    depth: f32,
+    index: u32,
+};
+
+struct Emitter {
+    position: vec4<f32>,
+    velocity: vec4<f32>,
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    acceleration: vec4<f32>,
+    shape: vec4<f32>,
+    spawn: vec4<u32>,
+};
+
+struct Simulation {
+    camera_position: vec4<f32>,
+    camera_forward: vec4<f32>,
+    params: vec4<f32>,
+    counts: vec4<u32>,
+    emitters: array<Emitter, 4>,
+};
+";
+
+const UPDATE_SOURCE: &str = "
+@group(0) @binding(0)
+var<uniform> simulation: Simulation;
+
+@group(0) @binding(1)
+var<storage, read_write> particles: array<Particle>;
+
+@group(0) @binding(2)
+var<storage, read_write> keys: array<SortKey>;
+
+fn hash(value: u32) -> u32 {
+    var state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed) / 4294967295.0;
+}
+
+fn spawn(emitter: Emitter, emitter_index: u32, index: u32) -> Particle {
+    var seed = hash(index ^ hash(simulation.counts.z));
+
+    // A random direction inside the cone around the emitter direction
+    let cos_theta = mix(emitter.position.w, 1.0, random(&seed));
+    let sin_theta = sqrt(max(1.0 - cos_theta * cos_theta, 0.0));
+    let phi = random(&seed) * 6.2831853;
+    let axis = normalize(emitter.velocity.xyz);
+    var helper = vec3<f32>(1.0, 0.0, 0.0);
+    if abs(axis.x) > 0.9 {
+        helper = vec3<f32>(0.0, 1.0, 0.0);
+    }
+    let tangent = normalize(cross(axis, helper));
+    let bitangent = cross(axis, tangent);
+    let direction = axis * cos_theta + (tangent * cos(phi) + bitangent * sin(phi)) * sin_theta;
+
+    var particle: Particle;
+    particle.position = emitter.position.xyz;
+    particle.velocity = direction * emitter.velocity.w * mix(0.8, 1.0, random(&seed));
+    particle.age = 0.0;
+    particle.lifetime = emitter.shape.z * (1.0 - emitter.shape.w * random(&seed));
+    particle.emitter = emitter_index;
+    return particle;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn update(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    let capacity = simulation.counts.x;
+    if index >= capacity {
+        return;
+    }
+
+    var particle = particles[index];
+    for (var emitter_index = 0u; emitter_index < simulation.counts.y; emitter_index++) {
+        let emitter = simulation.emitters[emitter_index];
+        let slot = (index + capacity - emitter.spawn.x) % capacity;
+        if slot < emitter.spawn.y {
+            particle = spawn(emitter, emitter_index, index);
+        }
+    }
+
+    var key: SortKey;
+    key.index = index;
+    // Dead particles sort behind everything else
+    key.depth = -1.0e30;
+    if particle.age < particle.lifetime {
+        let delta_time = simulation.params.x;
+        let acceleration = simulation.emitters[particle.emitter].acceleration;
+        particle.velocity += acceleration.xyz * delta_time;
+        particle.velocity *= max(1.0 - acceleration.w * delta_time, 0.0);
+        particle.position += particle.velocity * delta_time;
+        particle.age += delta_time;
+        key.depth = dot(particle.position - simulation.camera_position.xyz, simulation.camera_forward.xyz);
+    }
+    particles[index] = particle;
+    keys[index] = key;
+}
+";
+
+const SORT_SOURCE: &str = "
+// Comparison distance and block size of the current bitonic merge step
+@group(0) @binding(0)
+var<uniform> sort_step: vec4<u32>;
+
+@group(0) @binding(1)
+var<storage, read_write> keys: array<SortKey>;
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn sort(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    let partner = index ^ sort_step.x;
+    if partner <= index || partner >= arrayLength(&keys) {
+        return;
+    }
+    let a = keys[index];
+    let b = keys[partner];
+    // Alternating block directions merge into one sequence ordered farthest first
+    let descending = (index & sort_step.y) == 0u;
+    if (a.depth < b.depth) == descending {
+        keys[index] = b;
+        keys[partner] = a;
+    }
+}
+";
+
+const RENDER_SOURCE: &str = "
+struct Render {
+    view_projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> render: Render;
+
+@group(0) @binding(1)
+var<uniform> simulation: Simulation;
+
+@group(0) @binding(2)
+var<storage, read> particles: array<Particle>;
+
+@group(0) @binding(3)
+var<storage, read> keys: array<SortKey>;
+
+@group(1) @binding(0)
+var scene_depth: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) view_depth: f32,
+};
+
+@vertex
+fn vertex_main(@builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance_index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let particle = particles[keys[instance_index].index];
+    if particle.age >= particle.lifetime {
+        // Outside the clip volume, so the quad is discarded
+        out.position = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+        return out;
+    }
+
+    var corners = array<vec2<f32>, 6>(
+        vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0),
+        vec2(-1.0, -1.0), vec2(1.0, 1.0), vec2(-1.0, 1.0),
+    );
+    let corner = corners[vertex_index];
+    let emitter = simulation.emitters[particle.emitter];
+    let t = clamp(particle.age / particle.lifetime, 0.0, 1.0);
+    let radius = mix(emitter.shape.x, emitter.shape.y, t) * 0.5;
+    let offset = (render.camera_right.xyz * corner.x + render.camera_up.xyz * corner.y) * radius;
+    out.position = render.view_projection * vec4(particle.position + offset, 1.0);
+    out.corner = corner;
+    out.color = mix(emitter.start_color, emitter.end_color, t);
+    out.view_depth = out.position.w;
+    return out;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - dot(in.corner, in.corner);
+    if falloff <= 0.0 {
+        discard;
+    }
+
+    // Fade out as the particle nears the geometry behind it, which also hides it behind that geometry
+    var fade = 1.0;
+    let depth = textureLoad(scene_depth, vec2<i32>(in.position.xy), 0).r;
+    if depth < 1.0 {
+        let scene = render.inverse_projection * vec4(0.0, 0.0, depth, 1.0);
+        let scene_depth = -scene.z / scene.w;
+        fade = clamp((scene_depth - in.view_depth) / max(render.params.x, 0.0001), 0.0, 1.0);
+    }
+    return vec4<f32>(in.color.rgb, in.color.a * falloff * fade);
+}
+";
@@ -0,0 +1,102 @@
+use crate::{Renderer, Uniform};
+use wgpu::{BindGroupLayout, Device, PushConstantRange, RenderPass, ShaderStages};
+
+/// How `PerDrawData` reaches the shader
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PerDrawPath {
+    /// Set before each draw, needs `Features::PUSH_CONSTANTS` and a large enough push constant limit
+    PushConstants,
+    /// Every draw's value written to one uniform buffer, bound at a dynamic offset per draw
+    DynamicUniform,
+}
+
+/// Small data that changes between draws, such as an object index or material id.
+/// Uses push constants when the device has them and falls back to a uniform buffer bound
+/// at a dynamic offset otherwise. The shader declares the data with `declaration`, whose
+/// WGSL differs between the two paths, and pipelines add `push_constant_ranges` and
+/// `bind_group_layout` to their layout.
+///
+/// `T` is laid out as the shader's struct, padded to a multiple of 16 bytes.
+pub struct PerDrawData<T> {
+    pub path: PerDrawPath,
+    visibility: ShaderStages,
+    values: Vec<T>,
+    uniform: Option<Uniform<T>>,
+}
+
+impl<T: bytemuck::Pod> PerDrawData<T> {
+    /// Enable `Features::PUSH_CONSTANTS` as an optional feature to use push constants where available.
+    /// GL takes the uniform path, since wgpu 0.17's GL backend misaligns emulated push constants
+    /// after debug markers. Drop the GL check once that is fixed.
+    pub fn new(renderer: &Renderer, visibility: ShaderStages) -> Self {
+        let device = &renderer.device;
+        let size = std::mem::size_of::<T>() as u32;
+        let path = if device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+            && device.limits().max_push_constant_size >= size
+            && renderer.adapter_info.backend != wgpu::Backend::Gl
+        {
+            PerDrawPath::PushConstants
+        } else {
+            PerDrawPath::DynamicUniform
+        };
+        Self::with_path(device, visibility, path)
+    }
+
+    /// Forces a path, the push constant path still needs the device to support it
+    pub fn with_path(device: &Device, visibility: ShaderStages, path: PerDrawPath) -> Self {
+        log::info!("Per draw data uses {path:?}");
+        let uniform =
+            (path == PerDrawPath::DynamicUniform).then(|| Uniform::array(device, visibility, 16));
+        Self {
+            path,
+            visibility,
+            values: Vec::new(),
+            uniform,
+        }
+    }
+
+    /// Declares the data as `draw` in WGSL, the uniform is bound to the given group
+    pub fn declaration(&self, type_name: &str, group: u32) -> String {
+        match self.path {
+            PerDrawPath::PushConstants => format!("var<push_constant> draw: {type_name};\n"),
+            PerDrawPath::DynamicUniform => {
+                format!("@group({group}) @binding(0)\nvar<uniform> draw: {type_name};\n")
+            }
+        }
+    }
+
+    /// Only the dynamic uniform path has a bind group
+    pub fn bind_group_layout(&self) -> Option<&BindGroupLayout> {
+        self.uniform.as_ref().map(Uniform::layout)
+    }
+
+    pub fn push_constant_ranges(&self) -> Vec<PushConstantRange> {
+        match self.path {
+            PerDrawPath::PushConstants => vec![PushConstantRange {
+                stages: self.visibility,
+                range: 0..std::mem::size_of::<T>() as u32,
+            }],
+            PerDrawPath::DynamicUniform => Vec::new(),
+        }
+    }
+
+    /// Sets the values for this frame's draws, indexed by `set`. The uniform buffer grows to fit.
+    pub fn write(&mut self, renderer: &mut Renderer, values: &[T]) {
+        self.values = values.to_vec();
+        if let Some(uniform) = self.uniform.as_mut() {
+            uniform.write_array(renderer, values);
+        }
+    }
+
+    /// Makes a value from the last `write` visible to the next draws.
+    /// The group is only bound on the dynamic uniform path.
+    pub fn set<'a>(&'a self, renderpass: &mut RenderPass<'a>, group: u32, index: usize) {
+        let Some(value) = self.values.get(index) else {
+            return;
+        };
+        match self.uniform.as_ref() {
+            Some(uniform) => uniform.set_at(renderpass, group, index),
+            None => renderpass.set_push_constants(self.visibility, 0, bytemuck::bytes_of(value)),
+        }
+    }
+}
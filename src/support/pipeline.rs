@@ -0,0 +1,257 @@
+use crate::{Renderer, ShaderCode};
+use anyhow::Result;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::Path,
+};
+use wgpu::{
+    BindGroupLayout, BufferAddress, ColorTargetState, DepthStencilState, Device, MultisampleState,
+    PipelineLayout, PrimitiveState, PushConstantRange, RenderPipeline, ShaderModule,
+    StencilFaceState, StencilState, VertexAttribute, VertexBufferLayout, VertexStepMode,
+};
+
+/// An owned vertex buffer layout that can be hashed as part of a `PipelineKey`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    pub array_stride: BufferAddress,
+    pub step_mode: VertexStepMode,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+impl VertexLayout {
+    pub fn new(
+        array_stride: BufferAddress,
+        step_mode: VertexStepMode,
+        attributes: &[VertexAttribute],
+    ) -> Self {
+        Self {
+            array_stride,
+            step_mode,
+            attributes: attributes.to_vec(),
+        }
+    }
+
+    pub fn buffer_layout(&self) -> VertexBufferLayout<'_> {
+        VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: self.step_mode,
+            attributes: &self.attributes,
+        }
+    }
+}
+
+/// How a pipeline uses the stencil of `Texture::DEPTH_STENCIL_FORMAT`, comparing against
+/// the reference value set with `RenderPass::set_stencil_reference`
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StencilMode {
+    #[default]
+    Disabled,
+    /// Writes the reference wherever the pipeline draws
+    Write,
+    /// Draws only where the stencil doesn't hold the reference, such as around a marked silhouette
+    NotEqual,
+}
+
+impl StencilMode {
+    pub fn state(self) -> StencilState {
+        let face = match self {
+            Self::Disabled => StencilFaceState::IGNORE,
+            Self::Write => StencilFaceState {
+                compare: wgpu::CompareFunction::Always,
+                fail_op: wgpu::StencilOperation::Keep,
+                depth_fail_op: wgpu::StencilOperation::Keep,
+                pass_op: wgpu::StencilOperation::Replace,
+            },
+            Self::NotEqual => StencilFaceState {
+                compare: wgpu::CompareFunction::NotEqual,
+                ..StencilFaceState::IGNORE
+            },
+        };
+        StencilState {
+            front: face,
+            back: face,
+            read_mask: !0,
+            write_mask: if self == Self::Write { !0 } else { 0 },
+        }
+    }
+}
+
+/// Everything that distinguishes one render pipeline variant from another
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    /// Returned by `PipelineCache::shader`
+    pub shader: u64,
+    /// Defaults to `shader`, for languages with one stage per file such as GLSL
+    pub fragment_shader: Option<u64>,
+    pub vertex_entry_point: &'static str,
+    pub fragment_entry_point: &'static str,
+    pub vertex_layouts: Vec<VertexLayout>,
+    /// One per color attachment, `None` leaves that attachment unwritten
+    pub color_targets: Vec<Option<ColorTargetState>>,
+    pub primitive: PrimitiveState,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub multisample: MultisampleState,
+}
+
+/// Builds render pipelines on first use and reuses them for every later request with the same key.
+/// All pipelines in a cache share one pipeline layout.
+/// Shaders and variants that fail to build are remembered, so each error is logged once.
+pub struct PipelineCache {
+    layout: PipelineLayout,
+    shaders: HashMap<u64, ShaderModule>,
+    pipelines: HashMap<PipelineKey, RenderPipeline>,
+    shader_errors: HashMap<u64, String>,
+    pipeline_errors: HashMap<PipelineKey, String>,
+}
+
+impl PipelineCache {
+    pub fn new(device: &Device, bind_group_layouts: &[&BindGroupLayout]) -> Self {
+        Self::with_push_constants(device, bind_group_layouts, &[])
+    }
+
+    /// For pipelines taking per draw data in push constants, such as from `PerDrawData`
+    pub fn with_push_constants(
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> Self {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pipeline Cache Layout"),
+            bind_group_layouts,
+            push_constant_ranges,
+        });
+        Self {
+            layout,
+            shaders: HashMap::new(),
+            pipelines: HashMap::new(),
+            shader_errors: HashMap::new(),
+            pipeline_errors: HashMap::new(),
+        }
+    }
+
+    /// Compiles a WGSL shader unless the same source was seen before, returning its id for a `PipelineKey`
+    pub fn shader(&mut self, device: &Device, source: &str) -> u64 {
+        self.shader_code(device, &ShaderCode::Wgsl(source.to_string()))
+    }
+
+    /// Like `shader`, for code in any language naga reads
+    pub fn shader_code(&mut self, device: &Device, code: &ShaderCode) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        code.hash(&mut hasher);
+        let id = hasher.finish();
+        if self.shaders.contains_key(&id) || self.shader_errors.contains_key(&id) {
+            return id;
+        }
+        let module = Renderer::validated(device, "Shader", || {
+            code.create_module(device, "Cached Shader")
+        })
+        .and_then(|module| module);
+        // A shader that fails to compile is left out, so pipelines using it report the error
+        match module {
+            Ok(module) => {
+                self.shaders.insert(id, module);
+            }
+            Err(error) => {
+                log::error!("{error:#}");
+                self.shader_errors.insert(id, format!("{error:#}"));
+            }
+        }
+        id
+    }
+
+    pub fn get(&self, key: &PipelineKey) -> Option<&RenderPipeline> {
+        self.pipelines.get(key)
+    }
+
+    /// Loads a shader file with the language picked by its extension, see `ShaderCode::load`
+    pub fn load_shader(&mut self, device: &Device, path: &Path) -> Result<u64> {
+        Ok(self.shader_code(device, &ShaderCode::load(path)?))
+    }
+
+    /// The shaders in the key must have been added with `shader` first.
+    /// A variant that fails validation is logged and returns the same error on later calls
+    /// without being rebuilt, until `clear`.
+    pub fn get_or_create(&mut self, device: &Device, key: &PipelineKey) -> Result<&RenderPipeline> {
+        if let Some(error) = self.pipeline_errors.get(key) {
+            anyhow::bail!("{error}");
+        }
+        if !self.pipelines.contains_key(key) {
+            let module = |id| match self.shaders.get(&id) {
+                Some(module) => Ok(module),
+                None => match self.shader_errors.get(&id) {
+                    Some(error) => Err(anyhow::anyhow!("The pipeline's shader failed: {error}")),
+                    None => Err(anyhow::anyhow!("The pipeline's shader was never added")),
+                },
+            };
+            let pipeline = module(key.shader).and_then(|vertex_module| {
+                let fragment_module = module(key.fragment_shader.unwrap_or(key.shader))?;
+                Renderer::validated(device, "Pipeline", || {
+                    self.create_pipeline(device, vertex_module, fragment_module, key)
+                })
+            });
+            match pipeline {
+                Ok(pipeline) => {
+                    self.pipelines.insert(key.clone(), pipeline);
+                }
+                Err(error) => {
+                    log::error!("{error:#}");
+                    self.pipeline_errors
+                        .insert(key.clone(), format!("{error:#}"));
+                    return Err(error);
+                }
+            }
+        }
+        Ok(&self.pipelines[key])
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Drops every pipeline and shader along with their remembered errors,
+    /// for example after shaders are reloaded
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+        self.shaders.clear();
+        self.shader_errors.clear();
+        self.pipeline_errors.clear();
+    }
+
+    fn create_pipeline(
+        &self,
+        device: &Device,
+        vertex_module: &ShaderModule,
+        fragment_module: &ShaderModule,
+        key: &PipelineKey,
+    ) -> RenderPipeline {
+        log::debug!("Creating pipeline variant {key:?}");
+        let buffers = key
+            .vertex_layouts
+            .iter()
+            .map(VertexLayout::buffer_layout)
+            .collect::<Vec<_>>();
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Cached Pipeline"),
+            layout: Some(&self.layout),
+            vertex: wgpu::VertexState {
+                module: vertex_module,
+                entry_point: key.vertex_entry_point,
+                buffers: &buffers,
+            },
+            primitive: key.primitive,
+            depth_stencil: key.depth_stencil.clone(),
+            multisample: key.multisample,
+            fragment: Some(wgpu::FragmentState {
+                module: fragment_module,
+                entry_point: key.fragment_entry_point,
+                targets: &key.color_targets,
+            }),
+            multiview: None,
+        })
+    }
+}
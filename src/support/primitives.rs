@@ -0,0 +1,313 @@
+use crate::{impl_vertex, Aabb, Geometry, Indices};
+use nalgebra_glm as glm;
+use std::{
+    collections::HashMap,
+    f32::consts::{PI, TAU},
+};
+
+#[repr(C)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PrimitiveVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl PrimitiveVertex {
+    pub fn new(position: glm::Vec3, normal: glm::Vec3, uv: glm::Vec2) -> Self {
+        Self {
+            position: position.into(),
+            normal: normal.into(),
+            uv: uv.into(),
+        }
+    }
+}
+
+impl_vertex!(PrimitiveVertex, [0 => Float32x3, 1 => Float32x3, 2 => Float32x2]);
+
+/// Procedurally generated geometry centered on the origin with counter-clockwise front faces
+#[derive(Default, Debug, Clone)]
+pub struct MeshData {
+    pub vertices: Vec<PrimitiveVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn cube(size: f32) -> Self {
+        let half = size * 0.5;
+        let faces = [
+            (glm::Vec3::x(), -glm::Vec3::z(), glm::Vec3::y()),
+            (-glm::Vec3::x(), glm::Vec3::z(), glm::Vec3::y()),
+            (glm::Vec3::y(), glm::Vec3::x(), -glm::Vec3::z()),
+            (-glm::Vec3::y(), glm::Vec3::x(), glm::Vec3::z()),
+            (glm::Vec3::z(), glm::Vec3::x(), glm::Vec3::y()),
+            (-glm::Vec3::z(), -glm::Vec3::x(), glm::Vec3::y()),
+        ];
+
+        let mut mesh = Self::default();
+        for (normal, u, v) in faces {
+            let base = mesh.vertices.len() as u32;
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let position = (normal + u * x + v * y) * half;
+                let uv = glm::vec2((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+                mesh.vertices
+                    .push(PrimitiveVertex::new(position, normal, uv));
+            }
+            mesh.indices
+                .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        mesh
+    }
+
+    /// A sphere made of latitude stacks and longitude sectors
+    pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> Self {
+        let (sectors, stacks) = (sectors.max(3), stacks.max(2));
+        let mut mesh = Self::default();
+        for stack in 0..=stacks {
+            let phi = PI * stack as f32 / stacks as f32;
+            for sector in 0..=sectors {
+                let theta = TAU * sector as f32 / sectors as f32;
+                let normal = glm::vec3(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos());
+                let uv = glm::vec2(sector as f32 / sectors as f32, stack as f32 / stacks as f32);
+                mesh.vertices
+                    .push(PrimitiveVertex::new(normal * radius, normal, uv));
+            }
+        }
+
+        for stack in 0..stacks {
+            for sector in 0..sectors {
+                let top = stack * (sectors + 1) + sector;
+                let bottom = top + sectors + 1;
+                // The first and last stacks meet at the poles and need only one triangle per sector
+                if stack != 0 {
+                    mesh.indices.extend([top, bottom, top + 1]);
+                }
+                if stack != stacks - 1 {
+                    mesh.indices.extend([top + 1, bottom, bottom + 1]);
+                }
+            }
+        }
+        mesh
+    }
+
+    /// A sphere made by subdividing an icosahedron, giving evenly sized triangles
+    pub fn icosphere(radius: f32, subdivisions: u32) -> Self {
+        let t = (1.0 + 5_f32.sqrt()) / 2.0;
+        let mut positions = [
+            (-1.0, t, 0.0),
+            (1.0, t, 0.0),
+            (-1.0, -t, 0.0),
+            (1.0, -t, 0.0),
+            (0.0, -1.0, t),
+            (0.0, 1.0, t),
+            (0.0, -1.0, -t),
+            (0.0, 1.0, -t),
+            (t, 0.0, -1.0),
+            (t, 0.0, 1.0),
+            (-t, 0.0, -1.0),
+            (-t, 0.0, 1.0),
+        ]
+        .map(|(x, y, z)| glm::vec3(x, y, z).normalize())
+        .to_vec();
+
+        #[rustfmt::skip]
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let position = (positions[a as usize] + positions[b as usize]).normalize();
+                    positions.push(position);
+                    positions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .iter()
+                .flat_map(|&[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        Self {
+            vertices: positions
+                .iter()
+                .map(|normal| {
+                    let uv = glm::vec2(
+                        0.5 + normal.x.atan2(normal.z) / TAU,
+                        normal.y.clamp(-1.0, 1.0).acos() / PI,
+                    );
+                    PrimitiveVertex::new(normal * radius, *normal, uv)
+                })
+                .collect(),
+            indices: triangles.into_iter().flatten().collect(),
+        }
+    }
+
+    /// A square in the XZ plane facing up, split into a grid of quads
+    pub fn plane(size: f32, subdivisions: u32) -> Self {
+        let cells = subdivisions + 1;
+        let mut mesh = Self::default();
+        for row in 0..=cells {
+            for column in 0..=cells {
+                let uv = glm::vec2(column as f32 / cells as f32, row as f32 / cells as f32);
+                let position = glm::vec3(uv.x - 0.5, 0.0, uv.y - 0.5) * size;
+                mesh.vertices
+                    .push(PrimitiveVertex::new(position, glm::Vec3::y(), uv));
+            }
+        }
+        for row in 0..cells {
+            for column in 0..cells {
+                let current = row * (cells + 1) + column;
+                let next_row = current + cells + 1;
+                mesh.indices.extend([
+                    current,
+                    next_row,
+                    current + 1,
+                    current + 1,
+                    next_row,
+                    next_row + 1,
+                ]);
+            }
+        }
+        mesh
+    }
+
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        Self::frustum(radius, radius, height, segments)
+    }
+
+    pub fn cone(radius: f32, height: f32, segments: u32) -> Self {
+        Self::frustum(radius, 0.0, height, segments)
+    }
+
+    /// A ring shaped surface of revolution around the Y axis
+    pub fn torus(
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Self {
+        let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+        let mut mesh = Self::default();
+        for major in 0..=major_segments {
+            let u = TAU * major as f32 / major_segments as f32;
+            for minor in 0..=minor_segments {
+                let v = TAU * minor as f32 / minor_segments as f32;
+                let normal = glm::vec3(v.cos() * u.sin(), v.sin(), v.cos() * u.cos());
+                let center = glm::vec3(u.sin(), 0.0, u.cos()) * major_radius;
+                let uv = glm::vec2(
+                    major as f32 / major_segments as f32,
+                    minor as f32 / minor_segments as f32,
+                );
+                mesh.vertices.push(PrimitiveVertex::new(
+                    center + normal * minor_radius,
+                    normal,
+                    uv,
+                ));
+            }
+        }
+        for major in 0..major_segments {
+            for minor in 0..minor_segments {
+                let current = major * (minor_segments + 1) + minor;
+                let next = current + minor_segments + 1;
+                mesh.indices
+                    .extend([current, next, current + 1, current + 1, next, next + 1]);
+            }
+        }
+        mesh
+    }
+
+    pub fn positions(&self) -> Vec<glm::Vec3> {
+        self.vertices
+            .iter()
+            .map(|vertex| glm::Vec3::from(vertex.position))
+            .collect()
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.positions())
+    }
+
+    /// Uploads the mesh, using 16 bit indices when the mesh is small enough
+    pub fn geometry(&self, device: &wgpu::Device) -> Geometry {
+        match Indices::narrow(&self.indices) {
+            Some(indices) => Geometry::new(device, &self.vertices, &indices),
+            None => Geometry::new(device, &self.vertices, &self.indices),
+        }
+    }
+
+    /// Shared by the cylinder and cone, capping each end that has a radius
+    fn frustum(bottom_radius: f32, top_radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let half_height = height * 0.5;
+        let slope = (bottom_radius - top_radius) / height;
+        let mut mesh = Self::default();
+
+        for (radius, y, v) in [
+            (bottom_radius, -half_height, 1.0),
+            (top_radius, half_height, 0.0),
+        ] {
+            for segment in 0..=segments {
+                let theta = TAU * segment as f32 / segments as f32;
+                let direction = glm::vec3(theta.sin(), 0.0, theta.cos());
+                let normal = glm::vec3(direction.x, slope, direction.z).normalize();
+                let uv = glm::vec2(segment as f32 / segments as f32, v);
+                mesh.vertices.push(PrimitiveVertex::new(
+                    direction * radius + glm::vec3(0.0, y, 0.0),
+                    normal,
+                    uv,
+                ));
+            }
+        }
+        for segment in 0..segments {
+            let bottom = segment;
+            let top = bottom + segments + 1;
+            mesh.indices.extend([bottom, bottom + 1, top + 1]);
+            if top_radius > 0.0 {
+                mesh.indices.extend([bottom, top + 1, top]);
+            }
+        }
+
+        for (radius, y, normal) in [
+            (bottom_radius, -half_height, -glm::Vec3::y()),
+            (top_radius, half_height, glm::Vec3::y()),
+        ] {
+            if radius <= 0.0 {
+                continue;
+            }
+            let center = mesh.vertices.len() as u32;
+            mesh.vertices.push(PrimitiveVertex::new(
+                glm::vec3(0.0, y, 0.0),
+                normal,
+                glm::vec2(0.5, 0.5),
+            ));
+            for segment in 0..=segments {
+                let theta = TAU * segment as f32 / segments as f32;
+                let direction = glm::vec3(theta.sin(), 0.0, theta.cos());
+                let uv = glm::vec2(direction.x, direction.z) * 0.5 + glm::vec2(0.5, 0.5);
+                mesh.vertices.push(PrimitiveVertex::new(
+                    direction * radius + glm::vec3(0.0, y, 0.0),
+                    normal,
+                    uv,
+                ));
+            }
+            for segment in 0..segments {
+                let (current, next) = (center + 1 + segment, center + 2 + segment);
+                if normal.y > 0.0 {
+                    mesh.indices.extend([center, current, next]);
+                } else {
+                    mesh.indices.extend([center, next, current]);
+                }
+            }
+        }
+        mesh
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    impl_vertex, BindingBuilder, BindingLayout, Geometry, ShaderReflection, Texture,
+    VertexDescription,
+};
+use anyhow::Result;
+use std::borrow::Cow;
+use wgpu::{BindGroup, Device, RenderPass, RenderPipeline, TextureFormat};
+
+/// Draws a texture on a screen aligned quad centered in the viewport
+pub struct TexturedQuad {
+    pub geometry: Geometry,
+    pub pipeline: RenderPipeline,
+    pub bind_group_layout: BindingLayout,
+}
+
+impl TexturedQuad {
+    const INDICES: [u32; 6] = [0, 1, 2, 1, 2, 3];
+
+    /// The quad spans `-half_extent..half_extent` in normalized device coordinates on both axes,
+    /// with texture coordinate (0, 0) at the bottom left corner
+    pub fn new(device: &Device, surface_format: TextureFormat, half_extent: f32) -> Result<Self> {
+        let vertices = [
+            QuadVertex {
+                position: [half_extent, -half_extent, 0.0, 1.0],
+                tex_coords: [1.0, 0.0],
+            },
+            QuadVertex {
+                position: [-half_extent, -half_extent, 0.0, 1.0],
+                tex_coords: [0.0, 0.0],
+            },
+            QuadVertex {
+                position: [half_extent, half_extent, 0.0, 1.0],
+                tex_coords: [1.0, 1.0],
+            },
+            QuadVertex {
+                position: [-half_extent, half_extent, 0.0, 1.0],
+                tex_coords: [0.0, 1.0],
+            },
+        ];
+        let geometry = Geometry::new(device, &vertices, &Self::INDICES);
+
+        let bind_group_layout = BindingBuilder::new()
+            .label("Textured Quad")
+            .texture(0, wgpu::ShaderStages::FRAGMENT)
+            .sampler(1, wgpu::ShaderStages::FRAGMENT)
+            .build(device);
+        let pipeline = Self::create_pipeline(device, surface_format, &bind_group_layout)?;
+
+        Ok(Self {
+            geometry,
+            pipeline,
+            bind_group_layout,
+        })
+    }
+
+    /// Binds the texture's view and sampler for drawing with this quad
+    pub fn create_bind_group(&self, device: &Device, texture: &Texture) -> BindGroup {
+        self.bind_group_layout.bind_group(
+            device,
+            &[
+                wgpu::BindingResource::TextureView(&texture.view),
+                wgpu::BindingResource::Sampler(&texture.sampler),
+            ],
+        )
+    }
+
+    pub fn render<'rpass>(
+        &'rpass self,
+        renderpass: &mut RenderPass<'rpass>,
+        bind_group: &'rpass BindGroup,
+    ) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, bind_group, &[]);
+
+        let (vertex_slice, index_slice, index_format) = self.geometry.slices();
+        renderpass.set_vertex_buffer(0, vertex_slice);
+        renderpass.set_index_buffer(index_slice, index_format);
+
+        renderpass.draw_indexed(0..(Self::INDICES.len() as _), 0, 0..1);
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        surface_format: TextureFormat,
+        bind_group_layout: &BindingLayout,
+    ) -> Result<RenderPipeline> {
+        let reflection = ShaderReflection::from_wgsl(SHADER_SOURCE)?;
+        reflection.validate_group(0, bind_group_layout.entries())?;
+        reflection.validate_vertex_input("vertex_main", &[QuadVertex::layout()])?;
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Textured Quad Shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER_SOURCE)),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Textured Quad Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout.layout],
+            push_constant_ranges: &[],
+        });
+
+        Ok(
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Textured Quad Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: "vertex_main",
+                    buffers: &[QuadVertex::layout().buffer_layout()],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                    unclipped_depth: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: "fragment_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+            }),
+        )
+    }
+}
+
+const SHADER_SOURCE: &str = "
+struct VertexInput {
+    @location(0) position: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@vertex
+fn vertex_main(vert: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.tex_coords = vert.tex_coords;
+    out.position = vert.position;
+    return out;
+};
+
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+
+@fragment
+fn fragment_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+}
+";
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct QuadVertex {
+    position: [f32; 4],
+    tex_coords: [f32; 2],
+}
+
+impl_vertex!(QuadVertex, [0 => Float32x4, 1 => Float32x2]);
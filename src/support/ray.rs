@@ -0,0 +1,123 @@
+use crate::Aabb;
+use nalgebra_glm as glm;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: glm::Vec3,
+    /// Normalized direction of the ray
+    pub direction: glm::Vec3,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: glm::Vec3,
+    /// Weights of the triangle's three corners at the hit point, for triangle hits
+    pub barycentric: Option<glm::Vec3>,
+    /// Index of the triangle that was hit, for mesh hits
+    pub triangle: Option<usize>,
+}
+
+impl Ray {
+    pub fn new(origin: glm::Vec3, direction: glm::Vec3) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> glm::Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Slab test returning the distance at which the ray enters the box, or zero if it starts inside
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        if aabb.is_empty() {
+            return None;
+        }
+        let inverse_direction = self.direction.map(|component| 1.0 / component);
+        let t0 = (aabb.min - self.origin).component_mul(&inverse_direction);
+        let t1 = (aabb.max - self.origin).component_mul(&inverse_direction);
+        let entry = glm::min2(&t0, &t1).max();
+        let exit = glm::max2(&t0, &t1).min();
+        if exit < entry.max(0.0) {
+            return None;
+        }
+        Some(entry.max(0.0))
+    }
+
+    pub fn intersect_plane(&self, point: &glm::Vec3, normal: &glm::Vec3) -> Option<RayHit> {
+        let denominator = normal.dot(&self.direction);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = normal.dot(&(point - self.origin)) / denominator;
+        (distance >= 0.0).then(|| RayHit {
+            distance,
+            point: self.at(distance),
+            barycentric: None,
+            triangle: None,
+        })
+    }
+
+    /// Moller-Trumbore intersection, hitting both sides of the triangle
+    pub fn intersect_triangle(&self, triangle: [&glm::Vec3; 3]) -> Option<RayHit> {
+        let [a, b, c] = triangle;
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(&edge2);
+        let determinant = edge1.dot(&p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = s.dot(&p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(&edge1);
+        let v = self.direction.dot(&q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = edge2.dot(&q) * inverse_determinant;
+        (distance >= 0.0).then(|| RayHit {
+            distance,
+            point: self.at(distance),
+            barycentric: Some(glm::vec3(1.0 - u - v, u, v)),
+            triangle: None,
+        })
+    }
+
+    /// Finds the nearest hit against an indexed triangle list
+    pub fn intersect_triangles(&self, positions: &[glm::Vec3], indices: &[u32]) -> Option<RayHit> {
+        indices
+            .chunks_exact(3)
+            .enumerate()
+            .filter_map(|(triangle, corners)| {
+                let corner = |index: u32| positions.get(index as usize);
+                let triangle_positions = [
+                    corner(corners[0])?,
+                    corner(corners[1])?,
+                    corner(corners[2])?,
+                ];
+                self.intersect_triangle(triangle_positions)
+                    .map(|hit| RayHit {
+                        triangle: Some(triangle),
+                        ..hit
+                    })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    /// The ray moved into another space by `matrix`, usually an inverse model matrix,
+    /// along with the factor that converts hit distances in that space back to this one.
+    /// Multiply local hit distances by it, or transform local hit points back, before
+    /// comparing them with hits found in this space.
+    pub fn transform(&self, matrix: &glm::Mat4) -> (Self, f32) {
+        let origin = (matrix * self.origin.push(1.0)).xyz();
+        let direction = (matrix * self.direction.push(0.0)).xyz();
+        (Self::new(origin, direction), 1.0 / direction.norm())
+    }
+}
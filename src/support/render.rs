@@ -1,11 +1,15 @@
-use crate::GuiRender;
+use crate::{select_adapter, AdapterPreference, FrameCapture, Globals, GuiRender, RenderSettings};
 use anyhow::{Context, Result};
 use egui::{ClippedPrimitive, TexturesDelta};
 use egui_wgpu::renderer::ScreenDescriptor;
-use std::cmp::max;
+use nalgebra_glm as glm;
+use std::{
+    cmp::max,
+    sync::mpsc::{Receiver, TryRecvError},
+};
 use wgpu::{
-    CommandEncoder, Device, Queue, Surface, SurfaceConfiguration, TextureView,
-    TextureViewDescriptor,
+    util::StagingBelt, CommandEncoder, Device, Queue, RenderPass, Surface, SurfaceConfiguration,
+    TextureView, TextureViewDescriptor,
 };
 
 #[derive(Default, Copy, Clone)]
@@ -18,24 +22,328 @@ pub struct Viewport {
 
 impl Viewport {
     pub fn aspect_ratio(&self) -> f32 {
-        self.width as f32 / max(self.height, 0) as f32
+        self.width as f32 / max(self.height, 1) as f32
+    }
+
+    /// True if a position in physical pixels, such as the mouse's, lies inside the region
+    pub fn contains(&self, position: glm::Vec2) -> bool {
+        position.x >= self.x as f32
+            && position.y >= self.y as f32
+            && position.x < (self.x + self.width) as f32
+            && position.y < (self.y + self.height) as f32
+    }
+
+    /// Divides the region into the layout's cells, in reading order. Odd sizes
+    /// give the remaining pixel to the right or bottom cells so the cells cover the region.
+    pub fn split(&self, layout: SplitLayout) -> Vec<Viewport> {
+        let (columns, rows) = layout.grid();
+        let edge = |start: u32, size: u32, cells: u32, index: u32| start + size * index / cells;
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let x = edge(self.x, self.width, columns, column);
+                let y = edge(self.y, self.height, rows, row);
+                Viewport {
+                    x,
+                    y,
+                    width: edge(self.x, self.width, columns, column + 1) - x,
+                    height: edge(self.y, self.height, rows, row + 1) - y,
+                }
+            })
+            .collect()
+    }
+}
+
+/// How `Renderer::split_viewports` divides the surface between cameras
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SplitLayout {
+    Single,
+    /// Two viewports next to each other
+    SideBySide,
+    /// Two viewports one above the other
+    Stacked,
+    #[default]
+    Quad,
+}
+
+impl SplitLayout {
+    pub const ALL: [Self; 4] = [Self::Single, Self::SideBySide, Self::Stacked, Self::Quad];
+
+    pub fn viewport_count(&self) -> usize {
+        let (columns, rows) = self.grid();
+        (columns * rows) as usize
+    }
+
+    /// Columns and rows
+    fn grid(&self) -> (u32, u32) {
+        match self {
+            Self::Single => (1, 1),
+            Self::SideBySide => (2, 1),
+            Self::Stacked => (1, 2),
+            Self::Quad => (2, 2),
+        }
+    }
+}
+
+/// One color attachment of a pass begun with `Renderer::begin_multi_target_pass`
+#[derive(Copy, Clone)]
+pub struct ColorAttachment<'a> {
+    pub view: &'a TextureView,
+    /// Loads the existing contents when absent
+    pub clear: Option<wgpu::Color>,
+}
+
+/// The depth attachment of a pass begun with `Renderer::begin_multi_target_pass`,
+/// cleared to 1.0 along with its stencil to 0
+#[derive(Copy, Clone)]
+pub struct DepthAttachment<'a> {
+    pub view: &'a TextureView,
+    /// Set for formats with a stencil aspect, such as `Texture::DEPTH_STENCIL_FORMAT`
+    pub stencil: bool,
+}
+
+/// Suballocates per frame buffer writes from a ring of mapped staging chunks,
+/// recording the copies into one encoder that is submitted ahead of the frame
+pub struct Uploader {
+    belt: StagingBelt,
+    encoder: Option<CommandEncoder>,
+}
+
+impl Default for Uploader {
+    fn default() -> Self {
+        Self {
+            belt: StagingBelt::new(Self::CHUNK_SIZE),
+            encoder: None,
+        }
+    }
+}
+
+impl Uploader {
+    /// Writes larger than this get a dedicated chunk
+    pub const CHUNK_SIZE: wgpu::BufferAddress = 1 << 16;
+
+    /// The target buffer needs `COPY_DST` usage, and the offset and data length must be multiples of four
+    pub fn write(
+        &mut self,
+        device: &Device,
+        buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+    ) {
+        let Some(size) = wgpu::BufferSize::new(data.len() as _) else {
+            return;
+        };
+        let encoder = self.encoder.get_or_insert_with(|| {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Upload Encoder"),
+            })
+        });
+        self.belt
+            .write_buffer(encoder, buffer, offset, size, device)
+            .copy_from_slice(data);
+    }
+
+    /// Unmaps the chunks written this frame and returns the copies to submit, if any
+    pub fn finish(&mut self) -> Option<wgpu::CommandBuffer> {
+        let encoder = self.encoder.take()?;
+        self.belt.finish();
+        Some(encoder.finish())
+    }
+
+    /// Reclaims chunks once the GPU is done with them, call after submitting
+    pub fn recall(&mut self) {
+        self.belt.recall();
+    }
+}
+
+/// A query set along with the buffers its results are resolved into and read back from
+pub struct QueryPool {
+    pub query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    count: u32,
+    /// Pipeline statistics queries produce one value per statistic, the rest produce one
+    values_per_query: u32,
+    /// Set between `map` and the `try_read` that finds the results on the CPU
+    mapping: Option<Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl QueryPool {
+    const VALUE_SIZE: wgpu::BufferAddress = std::mem::size_of::<u64>() as _;
+
+    /// Timestamp and pipeline statistics queries need their device features enabled
+    pub fn new(device: &Device, label: &str, ty: wgpu::QueryType, count: u32) -> Self {
+        let values_per_query = match ty {
+            wgpu::QueryType::PipelineStatistics(statistics) => statistics.bits().count_ones(),
+            wgpu::QueryType::Occlusion | wgpu::QueryType::Timestamp => 1,
+        };
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(label),
+            ty,
+            count,
+        });
+        let size = (count * values_per_query) as wgpu::BufferAddress * Self::VALUE_SIZE;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} Resolve Buffer")),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} Readback Buffer")),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            count,
+            values_per_query,
+            mapping: None,
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Resolves every query and copies the results for `read`, after the last query is written
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..self.count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            self.readback_buffer.size(),
+        );
+    }
+
+    /// Waits for the resolved results to reach the CPU, returning the values of every query in order
+    pub fn read(&mut self, device: &Device) -> Result<Vec<u64>> {
+        self.map();
+        device.poll(wgpu::Maintain::Wait);
+        self.try_read()?
+            .context("The query results were not mapped after waiting")
+    }
+
+    /// Starts copying the results to the CPU once the submitted resolve finishes, without waiting.
+    /// Collect them with `try_read`, the pool can't be resolved into again until then.
+    pub fn map(&mut self) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.mapping = Some(receiver);
+    }
+
+    /// True from `map` until `try_read` returns the results
+    pub fn is_mapping(&self) -> bool {
+        self.mapping.is_some()
+    }
+
+    /// The values of every query in order once the mapping started by `map` has finished,
+    /// which needs the device to have been polled since
+    pub fn try_read(&mut self) -> Result<Option<Vec<u64>>> {
+        let Some(receiver) = self.mapping.as_ref() else {
+            return Ok(None);
+        };
+        match receiver.try_recv() {
+            Ok(result) => result?,
+            Err(TryRecvError::Empty) => return Ok(None),
+            Err(TryRecvError::Disconnected) => anyhow::bail!("The query readback was dropped"),
+        }
+        self.mapping = None;
+        let values: Vec<u64> =
+            bytemuck::pod_collect_to_vec(&self.readback_buffer.slice(..).get_mapped_range());
+        self.readback_buffer.unmap();
+        debug_assert_eq!(values.len(), (self.count * self.values_per_query) as usize);
+        Ok(Some(values))
+    }
+}
+
+/// Choices made when the device is created, which can't change afterwards
+#[derive(Debug, Clone)]
+pub struct RendererOptions {
+    pub adapter_preference: AdapterPreference,
+    /// Device creation fails with a list of these that the adapter lacks
+    pub required_features: wgpu::Features,
+    /// Enabled when the adapter supports them, check `device.features()` before use
+    pub optional_features: wgpu::Features,
+    /// Raised to the adapter's texture resolution limits
+    pub required_limits: wgpu::Limits,
+    /// Used for the window surface when supported, such as `Rgba16Float` on HDR displays
+    pub surface_format: Option<wgpu::TextureFormat>,
+    /// Without a requested format, pick an sRGB one so shaders can output linear colors
+    pub prefer_srgb: bool,
+    /// Pick a surface alpha mode that lets the window's alpha show the desktop behind it
+    pub transparent: bool,
+}
+
+impl Default for RendererOptions {
+    fn default() -> Self {
+        Self {
+            adapter_preference: AdapterPreference::default(),
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            surface_format: None,
+            prefer_srgb: true,
+            transparent: false,
+        }
     }
 }
 
 pub struct Renderer {
-    pub surface: Surface,
+    /// Absent when rendering headlessly
+    pub surface: Option<Surface>,
+    pub instance: wgpu::Instance,
+    pub adapter_info: wgpu::AdapterInfo,
     pub device: Device,
     pub queue: Queue,
     pub config: SurfaceConfiguration,
     pub gui: GuiRender,
+    pub settings: RenderSettings,
+    pub uploader: Uploader,
+    /// Time, resolution and mouse position for every shader, updated before each frame's update
+    pub globals: Globals,
+    pub frame_capture: FrameCapture,
+    present_modes: Vec<wgpu::PresentMode>,
 }
 
 impl Renderer {
-    pub fn new<W>(window_handle: &W, viewport: &Viewport) -> Result<Self>
+    pub const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+    pub const DEFAULT_CLEAR_COLOR: wgpu::Color = wgpu::Color {
+        r: 0.1,
+        g: 0.2,
+        b: 0.3,
+        a: 1.0,
+    };
+
+    pub fn new<W>(window_handle: &W, viewport: &Viewport, options: &RendererOptions) -> Result<Self>
     where
         W: raw_window_handle::HasRawWindowHandle + raw_window_handle::HasRawDisplayHandle,
     {
-        pollster::block_on(Renderer::new_async(window_handle, viewport))
+        pollster::block_on(Renderer::new_async(window_handle, viewport, options))
+    }
+
+    /// Creates a renderer without a window, targeting offscreen textures of `HEADLESS_FORMAT`.
+    /// The surface format options don't apply.
+    pub fn new_headless(viewport: &Viewport, options: &RendererOptions) -> Result<Self> {
+        pollster::block_on(Renderer::new_headless_async(viewport, options))
+    }
+
+    /// Every adapter the enabled backends expose, whether or not it can present to the window
+    pub fn enumerate_adapters(&self) -> Vec<wgpu::AdapterInfo> {
+        self.instance
+            .enumerate_adapters(Self::backends())
+            .map(|adapter| adapter.get_info())
+            .collect()
     }
 
     pub fn resize(&mut self, dimensions: [u32; 2]) {
@@ -49,7 +357,37 @@ impl Renderer {
         }
         self.config.width = dimensions[0];
         self.config.height = dimensions[1];
-        self.surface.configure(&self.device, &self.config);
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// Stores new render settings and reconfigures the surface for any that apply to it
+    pub fn apply_settings(&mut self, settings: RenderSettings) {
+        self.settings = settings;
+
+        let present_mode = if settings.vsync {
+            wgpu::PresentMode::Fifo
+        } else {
+            [wgpu::PresentMode::Mailbox, wgpu::PresentMode::Immediate]
+                .into_iter()
+                .find(|mode| self.present_modes.contains(mode))
+                .unwrap_or(wgpu::PresentMode::Fifo)
+        };
+
+        if present_mode != self.config.present_mode {
+            log::info!("Switching present mode to {:?}", present_mode);
+            self.config.present_mode = present_mode;
+            if let Some(surface) = self.surface.as_ref() {
+                surface.configure(&self.device, &self.config);
+            }
+        }
+    }
+
+    /// The size of offscreen render targets after applying the resolution scale
+    pub fn render_resolution(&self) -> (u32, u32) {
+        self.settings
+            .render_resolution(self.config.width, self.config.height)
     }
 
     pub fn render_frame(
@@ -60,7 +398,25 @@ impl Renderer {
         screen_descriptor: &ScreenDescriptor,
         mut action: impl FnMut(&TextureView, &mut CommandEncoder, &mut GuiRender) -> Result<()>,
     ) -> Result<()> {
-        let surface_texture = self.surface.get_current_texture()?;
+        let surface = self
+            .surface
+            .as_ref()
+            .context("Cannot present a frame without a surface")?;
+        let surface_texture = match surface.get_current_texture() {
+            Ok(surface_texture) => surface_texture,
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                log::warn!("Surface lost or outdated, reconfiguring it");
+                surface.configure(&self.device, &self.config);
+                return Ok(());
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::warn!("Timed out waiting for the next surface texture, skipping the frame");
+                return Ok(());
+            }
+            Err(error @ wgpu::SurfaceError::OutOfMemory) => {
+                return Err(error).context("Failed to acquire the next surface texture")
+            }
+        };
 
         let view = surface_texture
             .texture
@@ -89,20 +445,198 @@ impl Renderer {
 
         action(&view, &mut encoder, &mut self.gui)?;
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.submit(encoder);
         surface_texture.present();
 
         Ok(())
     }
 
+    /// Advances the globals by `delta_time` seconds, with the cursor in window pixels
+    pub fn update_globals(&mut self, delta_time: f64, mouse: glm::Vec2) {
+        let resolution = glm::vec2(self.config.width as f32, self.config.height as f32);
+        self.globals.advance(
+            &self.device,
+            &mut self.uploader,
+            delta_time as f32,
+            resolution,
+            mouse,
+        );
+    }
+
+    /// Queues a buffer write through the uploader, flushed with the next submitted frame
+    pub fn write_buffer(
+        &mut self,
+        buffer: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+    ) {
+        self.uploader.write(&self.device, buffer, offset, data);
+    }
+
+    /// Submits the pending uploads followed by the encoder's commands
+    pub fn submit(&mut self, encoder: CommandEncoder) {
+        let uploads = self.uploader.finish();
+        self.queue
+            .submit(uploads.into_iter().chain(std::iter::once(encoder.finish())));
+        self.uploader.recall();
+    }
+
+    /// Begins a render pass targeting the given view, clearing it when a color is provided
+    /// and loading the existing contents otherwise. A depth attachment is cleared to 1.0.
+    pub fn begin_default_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        view: &'a TextureView,
+        clear: Option<wgpu::Color>,
+        depth: Option<&'a TextureView>,
+    ) -> RenderPass<'a> {
+        Self::begin_multi_target_pass(
+            encoder,
+            &[ColorAttachment { view, clear }],
+            depth.map(|view| DepthAttachment {
+                view,
+                stencil: false,
+            }),
+        )
+    }
+
+    /// Like `begin_default_pass`, but writing to several color attachments at once, such as a G-buffer.
+    /// Attachment `n` is the fragment shader's `@location(n)` output.
+    pub fn begin_multi_target_pass<'a>(
+        encoder: &'a mut CommandEncoder,
+        colors: &[ColorAttachment<'a>],
+        depth: Option<DepthAttachment<'a>>,
+    ) -> RenderPass<'a> {
+        encoder.insert_debug_marker("Render scene");
+
+        let color_attachments = colors
+            .iter()
+            .map(|attachment| {
+                let load = match attachment.clear {
+                    Some(color) => wgpu::LoadOp::Clear(color),
+                    None => wgpu::LoadOp::Load,
+                };
+                Some(wgpu::RenderPassColorAttachment {
+                    view: attachment.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations { load, store: true },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let depth_stencil_attachment = depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
+            view: depth.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: true,
+            }),
+            stencil_ops: depth.stencil.then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(0),
+                store: true,
+            }),
+        });
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &color_attachments,
+            depth_stencil_attachment,
+        })
+    }
+
+    /// True when the surface blends with the desktop behind the window
+    pub fn is_transparent(&self) -> bool {
+        !matches!(
+            self.config.alpha_mode,
+            wgpu::CompositeAlphaMode::Auto | wgpu::CompositeAlphaMode::Opaque
+        )
+    }
+
+    /// Converts a straight alpha color into what the surface expects,
+    /// premultiplying it for premultiplied surfaces and making it opaque for opaque ones
+    pub fn clear_color(&self, color: wgpu::Color) -> wgpu::Color {
+        match self.config.alpha_mode {
+            wgpu::CompositeAlphaMode::PreMultiplied => wgpu::Color {
+                r: color.r * color.a,
+                g: color.g * color.a,
+                b: color.b * color.a,
+                a: color.a,
+            },
+            wgpu::CompositeAlphaMode::PostMultiplied | wgpu::CompositeAlphaMode::Inherit => color,
+            wgpu::CompositeAlphaMode::Auto | wgpu::CompositeAlphaMode::Opaque => {
+                wgpu::Color { a: 1.0, ..color }
+            }
+        }
+    }
+
+    /// Blending that matches the surface's alpha mode. On premultiplied surfaces
+    /// fragment shaders must output colors already multiplied by their alpha.
+    pub fn blend_state(&self) -> wgpu::BlendState {
+        match self.config.alpha_mode {
+            wgpu::CompositeAlphaMode::PreMultiplied => {
+                wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING
+            }
+            _ => wgpu::BlendState::ALPHA_BLENDING,
+        }
+    }
+
     pub fn aspect_ratio(&self) -> f32 {
         self.config.width as f32 / std::cmp::max(1, self.config.height) as f32
     }
 
-    async fn new_async<W>(window_handle: &W, viewport: &Viewport) -> Result<Self>
+    /// The whole surface
+    pub fn viewport(&self) -> Viewport {
+        Viewport {
+            x: 0,
+            y: 0,
+            width: self.config.width,
+            height: self.config.height,
+        }
+    }
+
+    /// Regions of the surface for rendering several cameras in one frame, see `set_viewport`
+    pub fn split_viewports(&self, layout: SplitLayout) -> Vec<Viewport> {
+        self.viewport().split(layout)
+    }
+
+    /// Maps clip space onto the region and clips drawing to it, so later draws in the pass
+    /// only touch that part of the target. Set the whole target's viewport again before
+    /// drawing anything that spans it, such as a fullscreen pass.
+    pub fn set_viewport(renderpass: &mut RenderPass, viewport: &Viewport) {
+        renderpass.set_viewport(
+            viewport.x as f32,
+            viewport.y as f32,
+            viewport.width as f32,
+            viewport.height as f32,
+            0.0,
+            1.0,
+        );
+        renderpass.set_scissor_rect(viewport.x, viewport.y, viewport.width, viewport.height);
+    }
+
+    /// Runs `create` inside a validation error scope so a bad shader or pipeline
+    /// returns an error instead of reaching the uncaptured error handler
+    pub fn validated<T>(device: &Device, label: &str, create: impl FnOnce() -> T) -> Result<T> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let value = create();
+        match pollster::block_on(device.pop_error_scope()) {
+            Some(error) => Err(anyhow::anyhow!("{label} failed validation: {error}")),
+            None => Ok(value),
+        }
+    }
+
+    /// Asks RenderDoc to capture the next presented frame
+    pub fn trigger_capture(&mut self) {
+        self.frame_capture.trigger();
+    }
+
+    async fn new_async<W>(
+        window_handle: &W,
+        viewport: &Viewport,
+        options: &RendererOptions,
+    ) -> Result<Self>
     where
         W: raw_window_handle::HasRawWindowHandle + raw_window_handle::HasRawDisplayHandle,
     {
+        let frame_capture = FrameCapture::new();
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: Self::backends(),
             ..Default::default()
@@ -110,85 +644,231 @@ impl Renderer {
 
         let surface = unsafe { instance.create_surface(&window_handle) }.unwrap();
 
-        let adapter = Self::create_adapter(&instance, &surface).await.unwrap();
+        let adapter = select_adapter(
+            &instance,
+            Some(&surface),
+            Self::backends(),
+            &options.adapter_preference,
+        )
+        .await
+        .context("Failed to find an adapter that can present to the window!")?;
+        let adapter_info = adapter.get_info();
+        log::info!("Using adapter {adapter_info:?}");
 
-        let (device, queue) = Self::request_device(&adapter).await?;
+        let (device, queue) = Self::request_device(&adapter, options).await?;
+        let globals = Globals::new(&device);
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
-        // This assumes an sRGB surface texture
-        let surface_format = surface_capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_capabilities.formats[0]);
+        let surface_format = Self::surface_format(&surface_capabilities.formats, options);
+        log::info!("Using surface format {surface_format:?}");
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: viewport.width,
             height: viewport.height,
             present_mode: surface_capabilities.present_modes[0],
-            alpha_mode: surface_capabilities.alpha_modes[0],
+            alpha_mode: Self::alpha_mode(&surface_capabilities.alpha_modes, options),
             view_formats: vec![],
         };
         surface.configure(&device, &config);
 
         Ok(Self {
-            surface,
+            surface: Some(surface),
+            instance,
+            adapter_info,
             device,
             queue,
             config,
             gui: GuiRender::default(),
+            settings: RenderSettings::default(),
+            uploader: Uploader::default(),
+            globals,
+            frame_capture,
+            present_modes: surface_capabilities.present_modes,
         })
     }
 
-    fn backends() -> wgpu::Backends {
-        wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all)
+    async fn new_headless_async(viewport: &Viewport, options: &RendererOptions) -> Result<Self> {
+        let frame_capture = FrameCapture::new();
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: Self::backends(),
+            ..Default::default()
+        });
+
+        let adapter = select_adapter(
+            &instance,
+            None,
+            Self::backends(),
+            &options.adapter_preference,
+        )
+        .await
+        .context("Failed to find a headless adapter!")?;
+        let adapter_info = adapter.get_info();
+        log::info!("Using adapter {adapter_info:?}");
+
+        let (device, queue) = Self::request_device(&adapter, options).await?;
+        let globals = Globals::new(&device);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: Self::HEADLESS_FORMAT,
+            width: viewport.width,
+            height: viewport.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        Ok(Self {
+            surface: None,
+            instance,
+            adapter_info,
+            device,
+            queue,
+            config,
+            gui: GuiRender::default(),
+            settings: RenderSettings::default(),
+            uploader: Uploader::default(),
+            globals,
+            frame_capture,
+            present_modes: vec![wgpu::PresentMode::Fifo],
+        })
     }
 
-    fn required_limits(adapter: &wgpu::Adapter) -> wgpu::Limits {
-        wgpu::Limits::default()
-            // Use the texture resolution limits from the adapter
-            // to support images the size of the surface
-            .using_resolution(adapter.limits())
+    /// The requested format if the surface supports it, otherwise the first sRGB format
+    /// when preferred, falling back to the surface's first format
+    fn surface_format(
+        formats: &[wgpu::TextureFormat],
+        options: &RendererOptions,
+    ) -> wgpu::TextureFormat {
+        if let Some(requested) = options.surface_format {
+            if formats.contains(&requested) {
+                return requested;
+            }
+            log::warn!(
+                "The surface does not support {requested:?}, supported formats are {formats:?}"
+            );
+        }
+        formats
+            .iter()
+            .copied()
+            .find(|format| options.prefer_srgb && format.is_srgb())
+            .unwrap_or(formats[0])
     }
 
-    fn required_features() -> wgpu::Features {
-        wgpu::Features::empty()
+    /// Premultiplied alpha is the most widely supported transparent mode,
+    /// opaque windows skip blending with the desktop entirely
+    fn alpha_mode(
+        modes: &[wgpu::CompositeAlphaMode],
+        options: &RendererOptions,
+    ) -> wgpu::CompositeAlphaMode {
+        let preferred: &[wgpu::CompositeAlphaMode] = if options.transparent {
+            &[
+                wgpu::CompositeAlphaMode::PreMultiplied,
+                wgpu::CompositeAlphaMode::PostMultiplied,
+                wgpu::CompositeAlphaMode::Inherit,
+            ]
+        } else {
+            &[wgpu::CompositeAlphaMode::Opaque]
+        };
+        let alpha_mode = preferred
+            .iter()
+            .copied()
+            .find(|mode| modes.contains(mode))
+            .unwrap_or(modes[0]);
+        if options.transparent && !preferred.contains(&alpha_mode) {
+            log::warn!("The surface can't be transparent, supported alpha modes are {modes:?}");
+        }
+        log::info!("Using surface alpha mode {alpha_mode:?}");
+        alpha_mode
     }
 
-    fn optional_features() -> wgpu::Features {
-        wgpu::Features::empty()
+    fn backends() -> wgpu::Backends {
+        wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all)
     }
 
-    async fn create_adapter(
-        instance: &wgpu::Instance,
-        surface: &wgpu::Surface,
-    ) -> Option<wgpu::Adapter> {
-        instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(surface),
-                force_fallback_adapter: false,
-            })
-            .await
+    /// The requested limits raised to the adapter's texture resolution limits,
+    /// to support images the size of the surface, and to its push constant size
+    /// when push constants are enabled
+    fn required_limits(
+        adapter: &wgpu::Adapter,
+        options: &RendererOptions,
+        features: wgpu::Features,
+    ) -> Result<wgpu::Limits> {
+        let allowed = adapter.limits();
+        let mut exceeded = Vec::new();
+        options.required_limits.check_limits_with_fail_fn(
+            &allowed,
+            false,
+            |name, required, allowed| {
+                exceeded.push(format!(
+                    "{name} (requires {required}, adapter allows {allowed})"
+                ));
+            },
+        );
+        anyhow::ensure!(
+            exceeded.is_empty(),
+            "The adapter does not support the required limits: {}",
+            exceeded.join(", ")
+        );
+        let mut limits = options
+            .required_limits
+            .clone()
+            .using_resolution(allowed.clone());
+        if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            limits.max_push_constant_size = allowed.max_push_constant_size;
+        }
+        Ok(limits)
     }
 
-    async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue)> {
+    fn required_features(
+        adapter: &wgpu::Adapter,
+        options: &RendererOptions,
+    ) -> Result<wgpu::Features> {
+        let missing = options.required_features - adapter.features();
+        anyhow::ensure!(
+            missing.is_empty(),
+            "The adapter does not support the required features: {}",
+            missing
+                .iter_names()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        Ok(options.required_features)
+    }
+
+    async fn request_device(
+        adapter: &wgpu::Adapter,
+        options: &RendererOptions,
+    ) -> Result<(wgpu::Device, wgpu::Queue)> {
         log::info!("WGPU Adapter Features: {:#?}", adapter.features());
 
-        adapter
+        let features = (options.optional_features & adapter.features())
+            | Self::required_features(adapter, options)?;
+        let limits = Self::required_limits(adapter, options, features)?;
+        let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    features: (Self::optional_features() & adapter.features())
-                        | Self::required_features(),
-                    limits: Self::required_limits(adapter),
+                    features,
+                    limits,
                     label: Some("Render Device"),
                 },
                 None,
             )
             .await
-            .context("Failed to request a device!")
+            .context("Failed to request a device!")?;
+
+        // wgpu panics on errors outside of an error scope by default,
+        // logging them instead keeps the app running and shows them in the console
+        device.on_uncaptured_error(Box::new(|error| {
+            log::error!("Uncaptured wgpu error: {error}");
+        }));
+        // There is no device lost callback to recreate the device from, wgpu only adds
+        // `set_device_lost_callback` in 0.18. A lost device surfaces as an uncaptured error
+        // above, and recovering would also mean every example rebuilding its GPU resources.
+
+        Ok((device, queue))
     }
 }
@@ -0,0 +1,267 @@
+use crate::{ColorAttachment, DepthAttachment, Renderer, Texture};
+use anyhow::Result;
+use wgpu::{CommandEncoder, Device, RenderPass, TextureFormat};
+
+/// Color attachments a single pass can write to
+pub const MAX_COLOR_ATTACHMENTS: usize = 8;
+
+/// An offscreen color and depth attachment pair that can be rendered into and then sampled
+pub struct RenderTarget {
+    pub color: Texture,
+    pub depth: Texture,
+    pub format: TextureFormat,
+    pub depth_format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTarget {
+    pub fn new(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        Self::with_depth_format(device, format, Texture::DEPTH_FORMAT, width, height)
+    }
+
+    /// Pass `Texture::DEPTH_STENCIL_FORMAT` for a stencil, which each pass then clears
+    pub fn with_depth_format(
+        device: &Device,
+        format: TextureFormat,
+        depth_format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        Self {
+            color: create_color_texture(device, "Render Target", format, width, height),
+            depth: Texture::create_depth_texture_with_format(device, width, height, depth_format),
+            format,
+            depth_format,
+            width,
+            height,
+        }
+    }
+
+    /// Sized to the renderer's render resolution, keep it in step with `fit_render_resolution`
+    pub fn for_render_resolution(renderer: &Renderer, format: TextureFormat) -> Self {
+        let (width, height) = renderer.render_resolution();
+        Self::new(&renderer.device, format, width, height)
+    }
+
+    /// Recreates the attachments at a new size, returning true if anything
+    /// was recreated so bind groups referencing the old views can be rebuilt
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) -> bool {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return false;
+        }
+        *self = Self::with_depth_format(device, self.format, self.depth_format, width, height);
+        true
+    }
+
+    /// Call after the window resizes or the resolution scale changes
+    pub fn fit_render_resolution(&mut self, renderer: &Renderer) -> bool {
+        let (width, height) = renderer.render_resolution();
+        self.resize(&renderer.device, width, height)
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Begins a pass that clears both attachments
+    pub fn begin_pass<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        clear_color: wgpu::Color,
+    ) -> RenderPass<'a> {
+        Renderer::begin_multi_target_pass(
+            encoder,
+            &[ColorAttachment {
+                view: &self.color.view,
+                clear: Some(clear_color),
+            }],
+            Some(DepthAttachment {
+                view: &self.depth.view,
+                stencil: self.depth_format.has_stencil_aspect(),
+            }),
+        )
+    }
+}
+
+/// Describes one color attachment of a `MultiRenderTarget`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorTargetDescription {
+    pub label: &'static str,
+    pub format: TextureFormat,
+    /// Used by pipelines drawing into this attachment, `None` replaces the contents
+    pub blend: Option<wgpu::BlendState>,
+    pub clear: wgpu::Color,
+}
+
+impl ColorTargetDescription {
+    /// Replaces the contents and clears to transparent black
+    pub fn new(label: &'static str, format: TextureFormat) -> Self {
+        Self {
+            label,
+            format,
+            blend: None,
+            clear: wgpu::Color::TRANSPARENT,
+        }
+    }
+
+    pub fn color_target_state(&self) -> wgpu::ColorTargetState {
+        wgpu::ColorTargetState {
+            format: self.format,
+            blend: self.blend,
+            write_mask: wgpu::ColorWrites::ALL,
+        }
+    }
+}
+
+/// Several color attachments sharing one depth attachment, written together in a single pass,
+/// such as a G-buffer. Each attachment has its own format and blend state.
+pub struct MultiRenderTarget {
+    pub descriptions: Vec<ColorTargetDescription>,
+    /// Indexed the same as the descriptions
+    pub colors: Vec<Texture>,
+    pub depth: Texture,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl MultiRenderTarget {
+    pub fn new(
+        device: &Device,
+        descriptions: &[ColorTargetDescription],
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            !descriptions.is_empty() && descriptions.len() <= MAX_COLOR_ATTACHMENTS,
+            "A render target needs between 1 and {MAX_COLOR_ATTACHMENTS} color attachments, got {}",
+            descriptions.len()
+        );
+        let (width, height) = (width.max(1), height.max(1));
+        Ok(Self {
+            descriptions: descriptions.to_vec(),
+            colors: descriptions
+                .iter()
+                .map(|description| {
+                    create_color_texture(
+                        device,
+                        description.label,
+                        description.format,
+                        width,
+                        height,
+                    )
+                })
+                .collect(),
+            depth: Texture::create_depth_texture(device, width, height),
+            width,
+            height,
+        })
+    }
+
+    /// Sized to the renderer's render resolution, keep it in step with `fit_render_resolution`
+    pub fn for_render_resolution(
+        renderer: &Renderer,
+        descriptions: &[ColorTargetDescription],
+    ) -> Result<Self> {
+        let (width, height) = renderer.render_resolution();
+        Self::new(&renderer.device, descriptions, width, height)
+    }
+
+    /// Recreates the attachments at a new size, returning true if anything
+    /// was recreated so bind groups referencing the old views can be rebuilt
+    pub fn resize(&mut self, device: &Device, width: u32, height: u32) -> bool {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return false;
+        }
+        for (color, description) in self.colors.iter_mut().zip(self.descriptions.iter()) {
+            *color =
+                create_color_texture(device, description.label, description.format, width, height);
+        }
+        self.depth = Texture::create_depth_texture(device, width, height);
+        self.width = width;
+        self.height = height;
+        true
+    }
+
+    /// Call after the window resizes or the resolution scale changes
+    pub fn fit_render_resolution(&mut self, renderer: &Renderer) -> bool {
+        let (width, height) = renderer.render_resolution();
+        self.resize(&renderer.device, width, height)
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// The fragment targets for pipelines drawing into this target, in attachment order
+    pub fn color_target_states(&self) -> Vec<Option<wgpu::ColorTargetState>> {
+        self.descriptions
+            .iter()
+            .map(|description| Some(description.color_target_state()))
+            .collect()
+    }
+
+    /// Begins a pass that clears every attachment
+    pub fn begin_pass<'a>(&'a self, encoder: &'a mut CommandEncoder) -> RenderPass<'a> {
+        let colors = self
+            .colors
+            .iter()
+            .zip(self.descriptions.iter())
+            .map(|(color, description)| ColorAttachment {
+                view: &color.view,
+                clear: Some(description.clear),
+            })
+            .collect::<Vec<_>>();
+        Renderer::begin_multi_target_pass(
+            encoder,
+            &colors,
+            Some(DepthAttachment {
+                view: &self.depth.view,
+                stencil: false,
+            }),
+        )
+    }
+}
+
+pub(crate) fn create_color_texture(
+    device: &Device,
+    label: &str,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+) -> Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    });
+    Texture {
+        texture,
+        view,
+        sampler,
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{Input, RenderTarget, Renderer, Texture};
+use nalgebra_glm as glm;
+use wgpu::{CommandEncoder, RenderPass, TextureFormat};
+
+/// Pointer state over a scene view, captured while egui owns the mouse
+#[derive(Default, Debug, Copy, Clone)]
+struct ViewPointer {
+    hovered: bool,
+    primary_down: bool,
+    secondary_down: bool,
+    /// Position relative to the top left of the view in physical pixels
+    position: glm::Vec2,
+    /// Movement this frame in physical pixels
+    delta: glm::Vec2,
+    /// Scroll this frame in lines
+    wheel: glm::Vec2,
+}
+
+/// Renders a scene into an offscreen target that is displayed as an image inside an egui panel.
+/// The target follows the size of the space the panel gives it, scaled by the resolution scale.
+pub struct SceneView {
+    pub target: RenderTarget,
+    /// `None` until the GUI renderer exists, which happens with the first frame
+    pub texture_id: Option<egui::TextureId>,
+    pointer: ViewPointer,
+}
+
+impl SceneView {
+    /// egui reports a scroll of one line as this many points
+    const POINTS_PER_SCROLL_LINE: f32 = 50.0;
+
+    pub fn new(renderer: &Renderer, format: TextureFormat) -> Self {
+        Self::with_depth_format(renderer, format, Texture::DEPTH_FORMAT)
+    }
+
+    /// For scenes whose pipelines expect another depth format, such as one with a stencil
+    pub fn with_depth_format(
+        renderer: &Renderer,
+        format: TextureFormat,
+        depth_format: TextureFormat,
+    ) -> Self {
+        let (width, height) = renderer.render_resolution();
+        Self {
+            target: RenderTarget::with_depth_format(
+                &renderer.device,
+                format,
+                depth_format,
+                width,
+                height,
+            ),
+            texture_id: None,
+            pointer: ViewPointer::default(),
+        }
+    }
+
+    /// True once the view is shown in the GUI, after which the scene should be drawn into it
+    pub fn is_visible(&self) -> bool {
+        self.texture_id.is_some()
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.target.aspect_ratio()
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        self.pointer.hovered
+    }
+
+    /// Fills the remaining space of the ui with the scene, resizing the target to match.
+    /// Pointer positions stay in the panel's physical pixels whatever the resolution scale.
+    pub fn show(&mut self, ui: &mut egui::Ui, renderer: &mut Renderer) -> egui::Response {
+        let size = ui.available_size();
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let (width, height) = renderer.settings.render_resolution(
+            (size.x * pixels_per_point).round() as u32,
+            (size.y * pixels_per_point).round() as u32,
+        );
+
+        let resized = self.target.resize(&renderer.device, width, height);
+        match self.texture_id {
+            Some(id) if resized => renderer.gui.update_native_texture(
+                &renderer.device,
+                &self.target.color.view,
+                wgpu::FilterMode::Linear,
+                id,
+            ),
+            Some(_) => {}
+            None => {
+                self.texture_id = renderer.gui.register_native_texture(
+                    &renderer.device,
+                    &self.target.color.view,
+                    wgpu::FilterMode::Linear,
+                );
+            }
+        }
+
+        let Some(id) = self.texture_id else {
+            return ui.allocate_response(size, egui::Sense::hover());
+        };
+        let response = ui.add(egui::Image::new((id, size)).sense(egui::Sense::click_and_drag()));
+
+        let pointer = ui.input(|input| {
+            let to_pixels = |vector: egui::Vec2| glm::vec2(vector.x, vector.y) * pixels_per_point;
+            let hovered = response.hovered();
+            ViewPointer {
+                hovered,
+                primary_down: response.dragged_by(egui::PointerButton::Primary),
+                secondary_down: response.dragged_by(egui::PointerButton::Secondary),
+                position: input
+                    .pointer
+                    .hover_pos()
+                    .map(|position| to_pixels(position - response.rect.min))
+                    .unwrap_or_default(),
+                delta: if hovered || response.dragged() {
+                    to_pixels(input.pointer.delta())
+                } else {
+                    glm::Vec2::zeros()
+                },
+                wheel: if hovered {
+                    glm::vec2(input.scroll_delta.x, input.scroll_delta.y)
+                        / Self::POINTS_PER_SCROLL_LINE
+                } else {
+                    glm::Vec2::zeros()
+                },
+            }
+        });
+        self.pointer = pointer;
+        response
+    }
+
+    /// Copies the pointer state over the view into the input, so camera controllers that read
+    /// the mouse keep working while egui holds the pointer. Positions are relative to the view.
+    pub fn forward_input(&self, input: &mut Input) {
+        if !self.is_visible() {
+            return;
+        }
+        let mouse = &mut input.mouse;
+        mouse.is_left_clicked = self.pointer.primary_down;
+        mouse.is_right_clicked = self.pointer.secondary_down;
+        mouse.position = self.pointer.position;
+        mouse.position_delta = self.pointer.delta;
+        mouse.wheel_delta = self.pointer.wheel;
+    }
+
+    /// Begins a pass that clears the view's color and depth
+    pub fn begin_pass<'a>(
+        &'a self,
+        encoder: &'a mut CommandEncoder,
+        clear_color: wgpu::Color,
+    ) -> RenderPass<'a> {
+        self.target.begin_pass(encoder, clear_color)
+    }
+}
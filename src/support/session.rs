@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+
+/// Most recently opened assets, newest first. Entries may be file paths or URLs
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RecentFiles {
+    pub entries: Vec<String>,
+}
+
+impl RecentFiles {
+    pub const MAX_ENTRIES: usize = 10;
+
+    pub fn push(&mut self, entry: impl Into<String>) {
+        let entry = entry.into();
+        self.remove(&entry);
+        self.entries.insert(0, entry);
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+
+    pub fn remove(&mut self, entry: &str) {
+        self.entries.retain(|existing| existing != entry);
+    }
+
+    /// Draws a menu of recent entries, returning the one that was clicked
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut selected = None;
+        ui.menu_button("Open recent", |ui| {
+            if self.entries.is_empty() {
+                ui.label("No recent files");
+            }
+            for entry in self.entries.iter() {
+                if ui.button(entry).clicked() {
+                    selected = Some(entry.clone());
+                    ui.close_menu();
+                }
+            }
+            ui.separator();
+            if ui.button("Clear recent").clicked() {
+                self.entries.clear();
+                ui.close_menu();
+            }
+        });
+        selected
+    }
+}
+
+/// Recent files along with whatever an example needs to pick up where the user left off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session<T> {
+    pub recent_files: RecentFiles,
+    pub last_session: Option<T>,
+}
+
+impl<T> Default for Session<T> {
+    fn default() -> Self {
+        Self {
+            recent_files: RecentFiles::default(),
+            last_session: None,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Session<T> {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session from {}", path.display()))?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write session to {}", path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use crate::Palette;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AntiAliasing {
+    #[default]
+    None,
+    /// A post pass over the final image, applied by examples that render through a `Fxaa` pass
+    Fxaa,
+}
+
+impl AntiAliasing {
+    pub const ALL: [Self; 2] = [Self::None, Self::Fxaa];
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShadowQuality {
+    Off,
+    Low,
+    #[default]
+    High,
+}
+
+impl ShadowQuality {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Low, Self::High];
+
+    /// Steps taken by shadows traced through a distance field, zero leaves everything lit
+    pub fn trace_steps(&self) -> u32 {
+        match self {
+            Self::Off => 0,
+            Self::Low => 16,
+            Self::High => 64,
+        }
+    }
+}
+
+/// Post passes that change the look of a scene rather than its quality,
+/// so choosing a preset leaves them as they are
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostEffects {
+    pub depth_of_field: bool,
+    pub motion_blur: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderPreset {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
+impl RenderPreset {
+    pub const ALL: [Self; 4] = [Self::Low, Self::Medium, Self::High, Self::Ultra];
+
+    pub fn settings(&self) -> RenderSettings {
+        match self {
+            Self::Low => RenderSettings {
+                shadow_quality: ShadowQuality::Off,
+                resolution_scale: 0.5,
+                ..RenderSettings::default()
+            },
+            Self::Medium => RenderSettings {
+                shadow_quality: ShadowQuality::Low,
+                resolution_scale: 0.75,
+                ..RenderSettings::default()
+            },
+            Self::High => RenderSettings::default(),
+            Self::Ultra => RenderSettings {
+                anti_aliasing: AntiAliasing::Fxaa,
+                ..RenderSettings::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for RenderPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RenderSettings {
+    pub anti_aliasing: AntiAliasing,
+    #[serde(default)]
+    pub shadow_quality: ShadowQuality,
+    /// Scales the offscreen targets sized with `Renderer::render_resolution`
+    pub resolution_scale: f32,
+    pub vsync: bool,
+    #[serde(default)]
+    pub post_effects: PostEffects,
+    /// Colors used by debug visualizations, independent of the quality preset
+    #[serde(default)]
+    pub debug_palette: Palette,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            anti_aliasing: AntiAliasing::default(),
+            shadow_quality: ShadowQuality::default(),
+            resolution_scale: 1.0,
+            vsync: true,
+            post_effects: PostEffects::default(),
+            debug_palette: Palette::default(),
+        }
+    }
+}
+
+impl RenderSettings {
+    pub const MIN_RESOLUTION_SCALE: f32 = 0.25;
+    pub const MAX_RESOLUTION_SCALE: f32 = 2.0;
+
+    /// The preset these settings were created from, if they have not been customized
+    pub fn preset(&self) -> Option<RenderPreset> {
+        RenderPreset::ALL.into_iter().find(|preset| {
+            let settings = RenderSettings {
+                post_effects: self.post_effects,
+                debug_palette: self.debug_palette,
+                ..preset.settings()
+            };
+            settings == *self
+        })
+    }
+
+    /// Scales the surface dimensions by the resolution scale
+    pub fn render_resolution(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = self
+            .resolution_scale
+            .clamp(Self::MIN_RESOLUTION_SCALE, Self::MAX_RESOLUTION_SCALE);
+        (
+            ((width as f32 * scale) as u32).max(1),
+            ((height as f32 * scale) as u32).max(1),
+        )
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read render settings from {}", path.display()))?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write render settings to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Draws the settings editor, returning true if any setting changed
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let previous = *self;
+
+        let selected_preset = self
+            .preset()
+            .map_or_else(|| "Custom".to_string(), |preset| preset.to_string());
+        egui::ComboBox::from_label("Preset")
+            .selected_text(selected_preset)
+            .show_ui(ui, |ui| {
+                for preset in RenderPreset::ALL {
+                    if ui
+                        .selectable_label(self.preset() == Some(preset), preset.to_string())
+                        .clicked()
+                    {
+                        *self = RenderSettings {
+                            post_effects: self.post_effects,
+                            debug_palette: self.debug_palette,
+                            ..preset.settings()
+                        };
+                    }
+                }
+            });
+
+        egui::ComboBox::from_label("Anti-aliasing")
+            .selected_text(format!("{:?}", self.anti_aliasing))
+            .show_ui(ui, |ui| {
+                for anti_aliasing in AntiAliasing::ALL {
+                    ui.selectable_value(
+                        &mut self.anti_aliasing,
+                        anti_aliasing,
+                        format!("{anti_aliasing:?}"),
+                    );
+                }
+            });
+
+        egui::ComboBox::from_label("Shadows")
+            .selected_text(format!("{:?}", self.shadow_quality))
+            .show_ui(ui, |ui| {
+                for shadow_quality in ShadowQuality::ALL {
+                    ui.selectable_value(
+                        &mut self.shadow_quality,
+                        shadow_quality,
+                        format!("{shadow_quality:?}"),
+                    );
+                }
+            });
+
+        ui.add(
+            egui::Slider::new(
+                &mut self.resolution_scale,
+                Self::MIN_RESOLUTION_SCALE..=Self::MAX_RESOLUTION_SCALE,
+            )
+            .text("Resolution scale"),
+        );
+
+        ui.checkbox(&mut self.vsync, "VSync");
+        ui.checkbox(&mut self.post_effects.depth_of_field, "Depth of field");
+        ui.checkbox(&mut self.post_effects.motion_blur, "Motion blur");
+        self.debug_palette.show(ui, "Debug palette");
+
+        *self != previous
+    }
+}
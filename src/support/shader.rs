@@ -0,0 +1,940 @@
+use crate::{BindingBuilder, BindingLayout, Renderer, VertexLayout};
+use anyhow::{bail, Context, Result};
+use naga::{
+    valid::{Capabilities, ModuleInfo, ValidationFlags, Validator},
+    AddressSpace, ImageClass, ImageDimension, Module, ScalarKind, ShaderStage, StorageAccess,
+    StorageFormat, TypeInner,
+};
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU32, ops::RangeInclusive, path::Path};
+use wgpu::{
+    util::DeviceExt, BindGroup, BindGroupLayout, BindGroupLayoutEntry, BindingType, Buffer, Device,
+    RenderPass, ShaderStages,
+};
+
+/// Shader code in one of the languages naga reads, so existing shaders can be used without
+/// rewriting them in WGSL. `load` picks the language from a file's extension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ShaderCode {
+    Wgsl(String),
+    /// A single stage, whose entry point is `main`
+    Glsl {
+        source: String,
+        stage: ShaderStage,
+    },
+    /// A precompiled module
+    SpirV(Vec<u32>),
+}
+
+impl ShaderCode {
+    /// Reads `.wgsl`, GLSL's `.vert`, `.frag` and `.comp`, or SPIR-V's `.spv`
+    pub fn load(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        let read_source = || {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+        };
+        let glsl = |stage| -> Result<Self> {
+            Ok(Self::Glsl {
+                source: read_source()?,
+                stage,
+            })
+        };
+        match extension {
+            "wgsl" => Ok(Self::Wgsl(read_source()?)),
+            "vert" => glsl(ShaderStage::Vertex),
+            "frag" => glsl(ShaderStage::Fragment),
+            "comp" => glsl(ShaderStage::Compute),
+            "spv" => {
+                let bytes = std::fs::read(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Self::spirv(&bytes).with_context(|| format!("Failed to load {}", path.display()))
+            }
+            _ => bail!(
+                "Unknown shader extension for {}, expected wgsl, vert, frag, comp or spv",
+                path.display()
+            ),
+        }
+    }
+
+    /// Checks the bytes hold whole words starting with the SPIR-V magic number
+    pub fn spirv(bytes: &[u8]) -> Result<Self> {
+        const MAGIC_NUMBER: u32 = 0x0723_0203;
+        anyhow::ensure!(
+            bytes.len() % 4 == 0,
+            "SPIR-V is made of 4 byte words, but has {} bytes",
+            bytes.len()
+        );
+        let words: Vec<u32> = bytemuck::pod_collect_to_vec(bytes);
+        anyhow::ensure!(
+            words.first() == Some(&MAGIC_NUMBER),
+            "Missing the SPIR-V magic number"
+        );
+        Ok(Self::SpirV(words))
+    }
+
+    /// Parses the code into naga's IR, with errors pointing into the source
+    pub fn parse(&self) -> Result<Module> {
+        match self {
+            Self::Wgsl(source) => naga::front::wgsl::parse_str(source).map_err(|error| {
+                anyhow::anyhow!("Failed to parse WGSL:\n{}", error.emit_to_string(source))
+            }),
+            Self::Glsl { source, stage } => {
+                let options = naga::front::glsl::Options::from(*stage);
+                let mut module = naga::front::glsl::Frontend::default()
+                    .parse(&options, source)
+                    .map_err(|errors| {
+                        let errors = errors
+                            .iter()
+                            .map(|error| {
+                                let location = error.meta.location(source);
+                                format!(
+                                    "{}:{}: {}",
+                                    location.line_number, location.line_position, error.kind
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        anyhow::anyhow!("Failed to parse GLSL:\n{}", errors.join("\n"))
+                    })?;
+                apply_default_sampling(&mut module);
+                Ok(module)
+            }
+            Self::SpirV(words) => {
+                let options = naga::front::spv::Options::default();
+                naga::front::spv::Frontend::new(words.iter().copied(), &options)
+                    .parse()
+                    .context("Failed to parse SPIR-V")
+            }
+        }
+    }
+
+    /// WGSL is passed to wgpu as is, other languages go through naga first
+    pub fn create_module(&self, device: &Device, label: &str) -> Result<wgpu::ShaderModule> {
+        let source = match self {
+            Self::Wgsl(source) => wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+            Self::Glsl { .. } | Self::SpirV(_) => {
+                wgpu::ShaderSource::Naga(Cow::Owned(self.parse()?))
+            }
+        };
+        Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source,
+        }))
+    }
+}
+
+/// GLSL leaves the sampling of interpolated inputs and outputs unset where WGSL defaults it
+/// to center, and wgpu requires the stages of a pipeline to match. Setting it lets GLSL stages
+/// pair with WGSL ones.
+pub fn apply_default_sampling(module: &mut Module) {
+    fn apply(binding: &mut naga::Binding) {
+        if let naga::Binding::Location {
+            interpolation: Some(naga::Interpolation::Perspective | naga::Interpolation::Linear),
+            sampling: sampling @ None,
+            ..
+        } = binding
+        {
+            *sampling = Some(naga::Sampling::Center);
+        }
+    }
+
+    let mut struct_types = Vec::new();
+    for entry_point in module.entry_points.iter_mut() {
+        let function = &mut entry_point.function;
+        for argument in function.arguments.iter_mut() {
+            if let Some(binding) = argument.binding.as_mut() {
+                apply(binding);
+            }
+            struct_types.push(argument.ty);
+        }
+        if let Some(result) = function.result.as_mut() {
+            if let Some(binding) = result.binding.as_mut() {
+                apply(binding);
+            }
+            struct_types.push(result.ty);
+        }
+    }
+
+    struct_types.sort_by_key(|handle| handle.index());
+    struct_types.dedup();
+    for handle in struct_types {
+        let mut ty = module.types[handle].clone();
+        let TypeInner::Struct { members, .. } = &mut ty.inner else {
+            continue;
+        };
+        members
+            .iter_mut()
+            .filter_map(|member| member.binding.as_mut())
+            .for_each(apply);
+        // Replacing keeps the handle, but can't produce a type the arena already has
+        if ty != module.types[handle] && module.types.get(&ty).is_none() {
+            module.types.replace(handle, ty);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReflectedBinding {
+    pub name: String,
+    pub group: u32,
+    pub binding: u32,
+    pub visibility: ShaderStages,
+    pub ty: BindingType,
+    pub count: Option<NonZeroU32>,
+}
+
+impl ReflectedBinding {
+    pub fn layout_entry(&self) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding: self.binding,
+            visibility: self.visibility,
+            ty: self.ty,
+            count: self.count,
+        }
+    }
+}
+
+pub struct ShaderReflection {
+    pub module: Module,
+    pub info: ModuleInfo,
+    pub bindings: Vec<ReflectedBinding>,
+    names: HashMap<String, usize>,
+}
+
+impl ShaderReflection {
+    pub fn from_wgsl(source: &str) -> Result<Self> {
+        let module = naga::front::wgsl::parse_str(source).map_err(|error| {
+            anyhow::anyhow!("Failed to parse WGSL:\n{}", error.emit_to_string(source))
+        })?;
+        Self::from_module(module)
+    }
+
+    /// Reflects shaders in any language, see `ShaderCode`
+    pub fn from_code(code: &ShaderCode) -> Result<Self> {
+        Self::from_module(code.parse()?)
+    }
+
+    pub fn from_module(module: Module) -> Result<Self> {
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .context("Failed to validate shader module")?;
+
+        let mut bindings = Vec::new();
+        for (handle, variable) in module.global_variables.iter() {
+            let Some(resource_binding) = variable.binding.as_ref() else {
+                continue;
+            };
+
+            let visibility = module
+                .entry_points
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !info.get_entry_point(*index)[handle].is_empty())
+                .fold(ShaderStages::NONE, |stages, (_, entry_point)| {
+                    stages | shader_stage(entry_point.stage)
+                });
+
+            let name = variable.name.clone().unwrap_or_else(|| {
+                format!(
+                    "group{}_binding{}",
+                    resource_binding.group, resource_binding.binding
+                )
+            });
+
+            let (ty, count) = binding_type(&module, variable.space, variable.ty)
+                .with_context(|| format!("Unsupported type for shader binding '{name}'"))?;
+
+            bindings.push(ReflectedBinding {
+                name,
+                group: resource_binding.group,
+                binding: resource_binding.binding,
+                visibility,
+                ty,
+                count,
+            });
+        }
+        bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+        let names = bindings
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| (binding.name.clone(), index))
+            .collect();
+
+        Ok(Self {
+            module,
+            info,
+            bindings,
+            names,
+        })
+    }
+
+    pub fn binding(&self, name: &str) -> Option<&ReflectedBinding> {
+        self.names.get(name).map(|index| &self.bindings[*index])
+    }
+
+    pub fn group_count(&self) -> u32 {
+        self.bindings
+            .iter()
+            .map(|binding| binding.group + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn group_entries(&self, group: u32) -> Vec<BindGroupLayoutEntry> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.group == group)
+            .map(ReflectedBinding::layout_entry)
+            .collect()
+    }
+
+    /// Builds editable parameters from the uniform named `params`, if the shader declares one
+    pub fn params(&self, source: &str) -> Option<ShaderParams> {
+        ShaderParams::new(self, source)
+    }
+
+    /// Creates one bind group layout per group index declared in the shader
+    pub fn create_bind_group_layouts(&self, device: &Device) -> Vec<BindGroupLayout> {
+        (0..self.group_count())
+            .map(|group| {
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some(&format!("reflected_bind_group_layout_{group}")),
+                    entries: &self.group_entries(group),
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that layout entries built on the Rust side match what the shader declares
+    /// for a bind group, so mismatches are reported before pipeline creation
+    pub fn validate_group(&self, group: u32, entries: &[BindGroupLayoutEntry]) -> Result<()> {
+        for reflected in self
+            .bindings
+            .iter()
+            .filter(|binding| binding.group == group)
+        {
+            let Some(entry) = entries
+                .iter()
+                .find(|entry| entry.binding == reflected.binding)
+            else {
+                bail!(
+                    "Shader binding '{}' (group {}, binding {}) has no matching layout entry",
+                    reflected.name,
+                    group,
+                    reflected.binding
+                );
+            };
+
+            if !binding_types_match(&reflected.ty, &entry.ty) {
+                bail!(
+                    "Shader binding '{}' (group {}, binding {}) is declared as {:?} but the layout provides {:?}",
+                    reflected.name,
+                    group,
+                    reflected.binding,
+                    reflected.ty,
+                    entry.ty
+                );
+            }
+
+            if !entry.visibility.contains(reflected.visibility) {
+                bail!(
+                    "Shader binding '{}' (group {}, binding {}) is used in {:?} but the layout is only visible to {:?}",
+                    reflected.name,
+                    group,
+                    reflected.binding,
+                    reflected.visibility,
+                    entry.visibility
+                );
+            }
+
+            if entry.count != reflected.count {
+                bail!(
+                    "Shader binding '{}' (group {}, binding {}) expects an array count of {:?} but the layout provides {:?}",
+                    reflected.name,
+                    group,
+                    reflected.binding,
+                    reflected.count,
+                    entry.count
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every location a vertex entry point reads is provided by one of the
+    /// vertex buffer layouts with a format of the same scalar kind
+    pub fn validate_vertex_input(&self, entry_point: &str, layouts: &[VertexLayout]) -> Result<()> {
+        let Some(entry) = self
+            .module
+            .entry_points
+            .iter()
+            .find(|entry| entry.stage == ShaderStage::Vertex && entry.name == entry_point)
+        else {
+            bail!("Shader has no vertex entry point named '{entry_point}'");
+        };
+
+        let mut inputs = Vec::new();
+        for argument in entry.function.arguments.iter() {
+            match (&argument.binding, &self.module.types[argument.ty].inner) {
+                (Some(binding), _) => inputs.push((argument.name.clone(), binding, argument.ty)),
+                (None, TypeInner::Struct { members, .. }) => {
+                    inputs.extend(members.iter().filter_map(|member| {
+                        Some((member.name.clone(), member.binding.as_ref()?, member.ty))
+                    }))
+                }
+                (None, _) => {}
+            }
+        }
+
+        for (name, binding, ty) in inputs {
+            let naga::Binding::Location { location, .. } = binding else {
+                continue;
+            };
+            let name = name.unwrap_or_else(|| format!("location{location}"));
+            let Some(attribute) = layouts
+                .iter()
+                .flat_map(|layout| layout.attributes.iter())
+                .find(|attribute| attribute.shader_location == *location)
+            else {
+                bail!(
+                    "Vertex input '{name}' (location {location}) of '{entry_point}' has no matching vertex attribute"
+                );
+            };
+            let kind = match self.module.types[ty].inner {
+                TypeInner::Scalar { kind, .. } | TypeInner::Vector { kind, .. } => kind,
+                ref other => bail!("Vertex input '{name}' has unsupported type {other:?}"),
+            };
+            if kind != vertex_format_kind(attribute.format) {
+                bail!(
+                    "Vertex input '{name}' (location {location}) of '{entry_point}' is declared as {kind:?} but the attribute provides {:?}",
+                    attribute.format
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The kind of value a vertex format reads as in the shader, normalized formats read as floats
+fn vertex_format_kind(format: wgpu::VertexFormat) -> ScalarKind {
+    use wgpu::VertexFormat::*;
+    match format {
+        Uint8x2 | Uint8x4 | Uint16x2 | Uint16x4 | Uint32 | Uint32x2 | Uint32x3 | Uint32x4 => {
+            ScalarKind::Uint
+        }
+        Sint8x2 | Sint8x4 | Sint16x2 | Sint16x4 | Sint32 | Sint32x2 | Sint32x3 | Sint32x4 => {
+            ScalarKind::Sint
+        }
+        _ => ScalarKind::Float,
+    }
+}
+
+fn shader_stage(stage: ShaderStage) -> ShaderStages {
+    match stage {
+        ShaderStage::Vertex => ShaderStages::VERTEX,
+        ShaderStage::Fragment => ShaderStages::FRAGMENT,
+        ShaderStage::Compute => ShaderStages::COMPUTE,
+    }
+}
+
+fn binding_type(
+    module: &Module,
+    space: AddressSpace,
+    ty: naga::Handle<naga::Type>,
+) -> Result<(BindingType, Option<NonZeroU32>)> {
+    let inner = &module.types[ty].inner;
+
+    if let TypeInner::BindingArray { base, size } = *inner {
+        let count = match size {
+            naga::ArraySize::Constant(count) => Some(count),
+            naga::ArraySize::Dynamic => bail!("Runtime-sized binding arrays are not supported"),
+        };
+        let (ty, _) = binding_type(module, space, base)?;
+        return Ok((ty, count));
+    }
+
+    let ty = match space {
+        AddressSpace::Uniform => BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: wgpu::BufferSize::new(inner.size(module.to_ctx()) as _),
+        },
+        AddressSpace::Storage { access } => BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage {
+                read_only: !access.contains(StorageAccess::STORE),
+            },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        AddressSpace::Handle => match *inner {
+            TypeInner::Sampler { comparison } => BindingType::Sampler(if comparison {
+                wgpu::SamplerBindingType::Comparison
+            } else {
+                wgpu::SamplerBindingType::Filtering
+            }),
+            TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            } => {
+                let view_dimension = view_dimension(dim, arrayed);
+                match class {
+                    ImageClass::Sampled { kind, multi } => BindingType::Texture {
+                        sample_type: match kind {
+                            ScalarKind::Float => {
+                                wgpu::TextureSampleType::Float { filterable: true }
+                            }
+                            ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                            ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                            ScalarKind::Bool => bail!("Boolean textures are not supported"),
+                        },
+                        view_dimension,
+                        multisampled: multi,
+                    },
+                    ImageClass::Depth { multi } => BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension,
+                        multisampled: multi,
+                    },
+                    ImageClass::Storage { format, access } => BindingType::StorageTexture {
+                        access: if access.contains(StorageAccess::LOAD | StorageAccess::STORE) {
+                            wgpu::StorageTextureAccess::ReadWrite
+                        } else if access.contains(StorageAccess::STORE) {
+                            wgpu::StorageTextureAccess::WriteOnly
+                        } else {
+                            wgpu::StorageTextureAccess::ReadOnly
+                        },
+                        format: storage_format(format),
+                        view_dimension,
+                    },
+                }
+            }
+            _ => bail!("Unexpected handle type {:?}", inner),
+        },
+        space => bail!(
+            "Unexpected address space {:?} for a resource binding",
+            space
+        ),
+    };
+
+    Ok((ty, None))
+}
+
+fn view_dimension(dim: ImageDimension, arrayed: bool) -> wgpu::TextureViewDimension {
+    match (dim, arrayed) {
+        (ImageDimension::D1, _) => wgpu::TextureViewDimension::D1,
+        (ImageDimension::D2, false) => wgpu::TextureViewDimension::D2,
+        (ImageDimension::D2, true) => wgpu::TextureViewDimension::D2Array,
+        (ImageDimension::D3, _) => wgpu::TextureViewDimension::D3,
+        (ImageDimension::Cube, false) => wgpu::TextureViewDimension::Cube,
+        (ImageDimension::Cube, true) => wgpu::TextureViewDimension::CubeArray,
+    }
+}
+
+fn storage_format(format: StorageFormat) -> wgpu::TextureFormat {
+    use wgpu::TextureFormat as Tf;
+    match format {
+        StorageFormat::R8Unorm => Tf::R8Unorm,
+        StorageFormat::R8Snorm => Tf::R8Snorm,
+        StorageFormat::R8Uint => Tf::R8Uint,
+        StorageFormat::R8Sint => Tf::R8Sint,
+        StorageFormat::R16Uint => Tf::R16Uint,
+        StorageFormat::R16Sint => Tf::R16Sint,
+        StorageFormat::R16Float => Tf::R16Float,
+        StorageFormat::Rg8Unorm => Tf::Rg8Unorm,
+        StorageFormat::Rg8Snorm => Tf::Rg8Snorm,
+        StorageFormat::Rg8Uint => Tf::Rg8Uint,
+        StorageFormat::Rg8Sint => Tf::Rg8Sint,
+        StorageFormat::R32Uint => Tf::R32Uint,
+        StorageFormat::R32Sint => Tf::R32Sint,
+        StorageFormat::R32Float => Tf::R32Float,
+        StorageFormat::Rg16Uint => Tf::Rg16Uint,
+        StorageFormat::Rg16Sint => Tf::Rg16Sint,
+        StorageFormat::Rg16Float => Tf::Rg16Float,
+        StorageFormat::Rgba8Unorm => Tf::Rgba8Unorm,
+        StorageFormat::Rgba8Snorm => Tf::Rgba8Snorm,
+        StorageFormat::Rgba8Uint => Tf::Rgba8Uint,
+        StorageFormat::Rgba8Sint => Tf::Rgba8Sint,
+        StorageFormat::Rgb10a2Unorm => Tf::Rgb10a2Unorm,
+        StorageFormat::Rg11b10Float => Tf::Rg11b10Float,
+        StorageFormat::Rg32Uint => Tf::Rg32Uint,
+        StorageFormat::Rg32Sint => Tf::Rg32Sint,
+        StorageFormat::Rg32Float => Tf::Rg32Float,
+        StorageFormat::Rgba16Uint => Tf::Rgba16Uint,
+        StorageFormat::Rgba16Sint => Tf::Rgba16Sint,
+        StorageFormat::Rgba16Float => Tf::Rgba16Float,
+        StorageFormat::Rgba32Uint => Tf::Rgba32Uint,
+        StorageFormat::Rgba32Sint => Tf::Rgba32Sint,
+        StorageFormat::Rgba32Float => Tf::Rgba32Float,
+        StorageFormat::R16Unorm => Tf::R16Unorm,
+        StorageFormat::R16Snorm => Tf::R16Snorm,
+        StorageFormat::Rg16Unorm => Tf::Rg16Unorm,
+        StorageFormat::Rg16Snorm => Tf::Rg16Snorm,
+        StorageFormat::Rgba16Unorm => Tf::Rgba16Unorm,
+        StorageFormat::Rgba16Snorm => Tf::Rgba16Snorm,
+    }
+}
+
+/// Compares binding types while ignoring properties the shader cannot express,
+/// such as dynamic offsets, minimum binding sizes and sampler filtering
+fn binding_types_match(reflected: &BindingType, provided: &BindingType) -> bool {
+    match (reflected, provided) {
+        (BindingType::Buffer { ty: reflected, .. }, BindingType::Buffer { ty: provided, .. }) => {
+            reflected == provided
+        }
+        (BindingType::Sampler(reflected), BindingType::Sampler(provided)) => {
+            let reflected_comparison = *reflected == wgpu::SamplerBindingType::Comparison;
+            let provided_comparison = *provided == wgpu::SamplerBindingType::Comparison;
+            reflected_comparison == provided_comparison
+        }
+        (
+            BindingType::Texture {
+                sample_type: reflected_sample_type,
+                view_dimension: reflected_view_dimension,
+                multisampled: reflected_multisampled,
+            },
+            BindingType::Texture {
+                sample_type: provided_sample_type,
+                view_dimension: provided_view_dimension,
+                multisampled: provided_multisampled,
+            },
+        ) => {
+            let sample_types_match = match (reflected_sample_type, provided_sample_type) {
+                (wgpu::TextureSampleType::Float { .. }, wgpu::TextureSampleType::Float { .. }) => {
+                    true
+                }
+                (reflected, provided) => reflected == provided,
+            };
+            sample_types_match
+                && reflected_view_dimension == provided_view_dimension
+                && reflected_multisampled == provided_multisampled
+        }
+        (reflected, provided) => reflected == provided,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamScalar {
+    Float,
+    Sint,
+    Uint,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShaderParam {
+    pub name: String,
+    pub scalar: ParamScalar,
+    pub components: usize,
+    pub range: Option<RangeInclusive<f32>>,
+    pub color: bool,
+    offset: usize,
+}
+
+/// CPU-side copy of a shader's `params` uniform block with egui controls for each member.
+///
+/// Members are annotated in trailing WGSL comments, for example
+/// `speed: f32, // @range(0.0, 10.0) @default(1.0)` or `tint: vec3<f32>, // @color`.
+pub struct ShaderParams {
+    pub group: u32,
+    pub binding: u32,
+    pub fields: Vec<ShaderParam>,
+    words: Vec<u32>,
+}
+
+impl ShaderParams {
+    pub const NAME: &'static str = "params";
+
+    fn new(reflection: &ShaderReflection, source: &str) -> Option<Self> {
+        let module = &reflection.module;
+        let (_, variable) = module.global_variables.iter().find(|(_, variable)| {
+            variable.space == AddressSpace::Uniform && variable.name.as_deref() == Some(Self::NAME)
+        })?;
+        let resource_binding = variable.binding.as_ref()?;
+
+        let ty = &module.types[variable.ty];
+        let TypeInner::Struct { members, span } = &ty.inner else {
+            return None;
+        };
+        let annotations = ty
+            .name
+            .as_deref()
+            .map(|struct_name| member_annotations(source, struct_name))
+            .unwrap_or_default();
+
+        let mut params = Self {
+            group: resource_binding.group,
+            binding: resource_binding.binding,
+            fields: Vec::new(),
+            words: vec![0; (*span as usize + 3) / 4],
+        };
+
+        for member in members.iter() {
+            let Some(name) = member.name.clone() else {
+                continue;
+            };
+
+            let (kind, components) = match module.types[member.ty].inner {
+                TypeInner::Scalar { kind, width: 4 } => (kind, 1),
+                TypeInner::Vector {
+                    size,
+                    kind,
+                    width: 4,
+                } => (kind, size as usize),
+                _ => continue,
+            };
+            let scalar = match kind {
+                ScalarKind::Float => ParamScalar::Float,
+                ScalarKind::Sint => ParamScalar::Sint,
+                ScalarKind::Uint => ParamScalar::Uint,
+                ScalarKind::Bool => continue,
+            };
+
+            let annotation = annotations.get(&name).cloned().unwrap_or_default();
+            let field = ShaderParam {
+                name,
+                scalar,
+                components,
+                range: annotation.range,
+                color: annotation.color && scalar == ParamScalar::Float && components >= 3,
+                offset: member.offset as usize / 4,
+            };
+
+            let defaults = annotation.default.unwrap_or_else(|| {
+                let value = if field.color {
+                    1.0
+                } else {
+                    field.range.as_ref().map_or(0.0, |range| *range.start())
+                };
+                vec![value; components]
+            });
+            for (component, value) in defaults.into_iter().take(components).enumerate() {
+                params.words[field.offset + component] = match scalar {
+                    ParamScalar::Float => value.to_bits(),
+                    ParamScalar::Sint => (value as i32) as u32,
+                    ParamScalar::Uint => value as u32,
+                };
+            }
+
+            params.fields.push(field);
+        }
+
+        Some(params)
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.words)
+    }
+
+    pub fn size(&self) -> u64 {
+        (self.words.len() * 4) as u64
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        let field = self.fields.iter().find(|field| field.name == name)?;
+        (field.scalar == ParamScalar::Float).then(|| {
+            bytemuck::cast_slice(&self.words[field.offset..field.offset + field.components])
+        })
+    }
+
+    pub fn set(&mut self, name: &str, values: &[f32]) {
+        let Some(field) = self.fields.iter().find(|field| field.name == name) else {
+            return;
+        };
+        for (component, value) in values.iter().take(field.components).enumerate() {
+            self.words[field.offset + component] = match field.scalar {
+                ParamScalar::Float => value.to_bits(),
+                ParamScalar::Sint => (*value as i32) as u32,
+                ParamScalar::Uint => *value as u32,
+            };
+        }
+    }
+
+    /// Draws a control for each parameter, returning true if any value changed
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        let Self { fields, words, .. } = self;
+        egui::Grid::new("shader_params")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for field in fields.iter() {
+                    ui.label(&field.name);
+                    let values = &mut words[field.offset..field.offset + field.components];
+                    ui.horizontal(|ui| {
+                        changed |= Self::show_field(ui, field, values);
+                    });
+                    ui.end_row();
+                }
+            });
+        changed
+    }
+
+    fn show_field(ui: &mut egui::Ui, field: &ShaderParam, values: &mut [u32]) -> bool {
+        match field.scalar {
+            ParamScalar::Float if field.color => {
+                let values: &mut [f32] = bytemuck::cast_slice_mut(values);
+                if field.components == 3 {
+                    let color: &mut [f32; 3] = values.try_into().expect("vec3 has 3 components");
+                    egui::color_picker::color_edit_button_rgb(ui, color).changed()
+                } else {
+                    let mut color = egui::Rgba::from_rgba_unmultiplied(
+                        values[0], values[1], values[2], values[3],
+                    );
+                    let changed = egui::color_picker::color_edit_button_rgba(
+                        ui,
+                        &mut color,
+                        egui::color_picker::Alpha::OnlyBlend,
+                    )
+                    .changed();
+                    // Converting back loses rgb at zero alpha, so untouched values stay as they are
+                    if changed {
+                        values.copy_from_slice(&color.to_rgba_unmultiplied());
+                    }
+                    changed
+                }
+            }
+            ParamScalar::Float => {
+                let values: &mut [f32] = bytemuck::cast_slice_mut(values);
+                values.iter_mut().fold(false, |changed, value| {
+                    let response = match (&field.range, field.components) {
+                        (Some(range), 1) => ui.add(egui::Slider::new(value, range.clone())),
+                        (Some(range), _) => ui.add(
+                            egui::DragValue::new(value)
+                                .clamp_range(range.clone())
+                                .speed((range.end() - range.start()) / 100.0),
+                        ),
+                        (None, _) => ui.add(egui::DragValue::new(value).speed(0.01)),
+                    };
+                    changed | response.changed()
+                })
+            }
+            ParamScalar::Sint => {
+                let values: &mut [i32] = bytemuck::cast_slice_mut(values);
+                values.iter_mut().fold(false, |changed, value| {
+                    let mut drag = egui::DragValue::new(value);
+                    if let Some(range) = &field.range {
+                        drag = drag.clamp_range(range.clone());
+                    }
+                    changed | ui.add(drag).changed()
+                })
+            }
+            ParamScalar::Uint => values.iter_mut().fold(false, |changed, value| {
+                let mut drag = egui::DragValue::new(value);
+                if let Some(range) = &field.range {
+                    drag = drag.clamp_range(range.clone());
+                }
+                changed | ui.add(drag).changed()
+            }),
+        }
+    }
+}
+
+/// The buffer and bind group behind a shader's `params` uniform, which has to be the only binding
+/// in its group. Examples show the controls and bind it, and the values upload as they're edited.
+pub struct ShaderParamsBinding {
+    pub params: ShaderParams,
+    buffer: Buffer,
+    layout: BindingLayout,
+    bind_group: BindGroup,
+}
+
+impl ShaderParamsBinding {
+    pub fn new(device: &Device, params: ShaderParams) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shader Params Buffer"),
+            contents: params.bytes(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = BindingBuilder::new()
+            .label("Shader Params")
+            .uniform(params.binding, ShaderStages::VERTEX_FRAGMENT)
+            .build(device);
+        let bind_group = layout.bind_group(device, &[buffer.as_entire_binding()]);
+        Self {
+            params,
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+
+    /// For the pipeline layout, at the index of `params.group`
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout.layout
+    }
+
+    /// Draws the controls, uploading the values if any changed
+    pub fn show(&mut self, ui: &mut egui::Ui, renderer: &mut Renderer) -> bool {
+        let changed = self.params.show(ui);
+        if changed {
+            renderer.write_buffer(&self.buffer, 0, self.params.bytes());
+        }
+        changed
+    }
+
+    pub fn set<'a>(&'a self, renderpass: &mut RenderPass<'a>) {
+        renderpass.set_bind_group(self.params.group, &self.bind_group, &[]);
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct MemberAnnotation {
+    range: Option<RangeInclusive<f32>>,
+    default: Option<Vec<f32>>,
+    color: bool,
+}
+
+fn member_annotations(source: &str, struct_name: &str) -> HashMap<String, MemberAnnotation> {
+    let mut annotations = HashMap::new();
+
+    let Some(body) = source
+        .split("struct ")
+        .skip(1)
+        .find(|declaration| {
+            declaration
+                .split(|character: char| character.is_whitespace() || character == '{')
+                .next()
+                == Some(struct_name)
+        })
+        .and_then(|declaration| declaration.split_once('{'))
+        .map(|(_, body)| body.split('}').next().unwrap_or_default())
+    else {
+        return annotations;
+    };
+
+    for line in body.lines() {
+        let Some((declaration, comment)) = line.split_once("//") else {
+            continue;
+        };
+        let Some((name, _)) = declaration.split_once(':') else {
+            continue;
+        };
+
+        let mut annotation = MemberAnnotation {
+            color: comment.contains("@color"),
+            ..Default::default()
+        };
+        if let Some(values) = annotation_values(comment, "@range") {
+            if let [start, end] = values[..] {
+                annotation.range = Some(start..=end);
+            }
+        }
+        annotation.default = annotation_values(comment, "@default");
+
+        annotations.insert(name.trim().to_string(), annotation);
+    }
+
+    annotations
+}
+
+fn annotation_values(comment: &str, tag: &str) -> Option<Vec<f32>> {
+    let (_, arguments) = comment.split_once(&format!("{tag}("))?;
+    let (arguments, _) = arguments.split_once(')')?;
+    arguments
+        .split(',')
+        .map(|value| value.trim().parse::<f32>().ok())
+        .collect()
+}
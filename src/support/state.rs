@@ -0,0 +1,34 @@
+use crate::{camera::Orientation, RenderSettings};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A snapshot of what the user is looking at, so an inspection session can be resumed later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewerState {
+    pub camera: Orientation,
+    pub render_settings: RenderSettings,
+}
+
+impl ViewerState {
+    pub const QUICK_SAVE_PATH: &'static str = "viewer_state.json";
+
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("Failed to write viewer state to {}", path.display()))?;
+        log::info!("Saved viewer state to {}", path.display());
+        Ok(())
+    }
+
+    pub fn load_state(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read viewer state from {}", path.display()))?;
+        let state = serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse viewer state in {}", path.display()))?;
+        log::info!("Loaded viewer state from {}", path.display());
+        Ok(state)
+    }
+}
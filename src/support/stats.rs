@@ -0,0 +1,121 @@
+/// Per frame rendering counters and a panel for displaying them
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub instances: u64,
+    pub triangles: u64,
+    pub buffer_bytes: u64,
+    pub texture_bytes: u64,
+    /// Bytes written to buffers from the CPU this frame
+    pub uploaded_bytes: u64,
+    /// Seconds the frame took, for showing the upload bandwidth
+    pub frame_time: f64,
+    /// Seconds spent updating scene data on the CPU this frame
+    pub update_time: f64,
+}
+
+impl RenderStats {
+    /// Clears the counters at the start of a frame
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Records an indexed triangle list draw
+    pub fn record_draw(&mut self, index_count: u32, instance_count: u32) {
+        self.draw_calls += 1;
+        self.instances += u64::from(instance_count);
+        self.triangles += u64::from(index_count / 3) * u64::from(instance_count);
+    }
+
+    pub fn record_buffer(&mut self, buffer: &wgpu::Buffer) {
+        self.buffer_bytes += buffer.size();
+    }
+
+    pub fn record_geometry(&mut self, geometry: &crate::Geometry) {
+        self.buffer_bytes += geometry.allocated_bytes();
+    }
+
+    pub fn record_texture(&mut self, texture: &wgpu::Texture) {
+        self.texture_bytes += texture_size(texture);
+    }
+
+    pub fn record_upload(&mut self, bytes: u64) {
+        self.uploaded_bytes += bytes;
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("render_stats")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Draw calls");
+                ui.label(self.draw_calls.to_string());
+                ui.end_row();
+                ui.label("Instances");
+                ui.label(self.instances.to_string());
+                ui.end_row();
+                ui.label("Triangles");
+                ui.label(self.triangles.to_string());
+                ui.end_row();
+                ui.label("Buffer memory");
+                ui.label(format_bytes(self.buffer_bytes));
+                ui.end_row();
+                ui.label("Texture memory");
+                ui.label(format_bytes(self.texture_bytes));
+                ui.end_row();
+                if self.frame_time > 0.0 {
+                    ui.label("Frame time");
+                    ui.label(format!("{:.2} ms", self.frame_time * 1000.0));
+                    ui.end_row();
+                }
+                if self.update_time > 0.0 {
+                    ui.label("CPU update");
+                    ui.label(format!("{:.2} ms", self.update_time * 1000.0));
+                    ui.end_row();
+                }
+                if self.uploaded_bytes > 0 {
+                    ui.label("Uploaded");
+                    ui.label(format_bytes(self.uploaded_bytes));
+                    ui.end_row();
+                }
+                if self.uploaded_bytes > 0 && self.frame_time > 0.0 {
+                    ui.label("Upload bandwidth");
+                    let rate = self.uploaded_bytes as f64 / self.frame_time;
+                    ui.label(format!("{}/s", format_bytes(rate as u64)));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Estimates the memory used by a texture, including its mip chain and samples
+pub fn texture_size(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let block_size = u64::from(format.block_size(None).unwrap_or(4));
+    let (block_width, block_height) = format.block_dimensions();
+    let size = texture.size();
+    (0..texture.mip_level_count())
+        .map(|level| {
+            let blocks = |texels: u32, block: u32| u64::from((texels.max(1) + block - 1) / block);
+            let width = blocks(size.width >> level, block_width);
+            let height = blocks(size.height >> level, block_height);
+            width * height * block_size
+        })
+        .sum::<u64>()
+        * u64::from(size.depth_or_array_layers)
+        * u64::from(texture.sample_count())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{value:.2} {}", UNITS[unit])
+    }
+}
@@ -0,0 +1,429 @@
+use crate::format_bytes;
+use image::RgbaImage;
+
+/// Levels this many texels across or smaller are uploaded as soon as a texture is added
+/// and never evicted, so every texture can be sampled from its first frame
+const RESIDENT_FLOOR_SIZE: u32 = 64;
+
+const MEGABYTE: u64 = 1024 * 1024;
+
+/// A texture's full mip chain kept in system memory, from full resolution down to 1x1
+pub struct MipChain {
+    levels: Vec<RgbaImage>,
+}
+
+impl MipChain {
+    /// Builds each smaller level by averaging 2x2 blocks of the one above it
+    pub fn new(image: RgbaImage) -> Self {
+        let mut levels = vec![image];
+        while let Some(previous) = levels
+            .last()
+            .filter(|level| level.width().max(level.height()) > 1)
+        {
+            let (width, height) = (
+                (previous.width() / 2).max(1),
+                (previous.height() / 2).max(1),
+            );
+            let level = RgbaImage::from_fn(width, height, |x, y| {
+                let texel = |dx: u32, dy: u32| {
+                    let source_x = (x * 2 + dx).min(previous.width() - 1);
+                    let source_y = (y * 2 + dy).min(previous.height() - 1);
+                    previous.get_pixel(source_x, source_y).0
+                };
+                let corners = [texel(0, 0), texel(1, 0), texel(0, 1), texel(1, 1)];
+                image::Rgba(std::array::from_fn(|channel| {
+                    let sum = corners
+                        .iter()
+                        .map(|corner| corner[channel] as u32)
+                        .sum::<u32>();
+                    ((sum + 2) / 4) as u8
+                }))
+            });
+            levels.push(level);
+        }
+        Self { levels }
+    }
+
+    pub fn level_count(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    pub fn level(&self, level: u32) -> &RgbaImage {
+        &self.levels[level as usize]
+    }
+
+    /// GPU memory used when the given level and every smaller one are resident
+    pub fn resident_bytes(&self, level: u32) -> u64 {
+        self.levels[level as usize..]
+            .iter()
+            .map(|image| image.as_raw().len() as u64)
+            .sum()
+    }
+
+    /// The most detailed level that never needs to be evicted
+    pub fn floor_level(&self) -> u32 {
+        self.levels
+            .iter()
+            .position(|image| image.width().max(image.height()) <= RESIDENT_FLOOR_SIZE)
+            .unwrap_or(self.levels.len() - 1) as u32
+    }
+
+    /// The level whose texels are closest to one per pixel when drawn this many pixels across
+    pub fn level_for_pixels(&self, pixels: f32) -> u32 {
+        let texels = self.levels[0].width().max(self.levels[0].height()) as f32;
+        let level = (texels / pixels.max(1.0)).log2().floor().max(0.0) as u32;
+        level.min(self.level_count() - 1)
+    }
+}
+
+/// A texture whose most detailed levels are only on the GPU while they are needed
+pub struct StreamedTexture {
+    pub label: String,
+    pub mips: MipChain,
+    /// The most detailed level on the GPU, the view's first level
+    pub resident_level: u32,
+    /// The most detailed level its on-screen size asked for in the last update
+    pub wanted_level: u32,
+    /// Bumped each time the texture is reallocated, so bind groups holding the view can be rebuilt
+    pub generation: u64,
+    pub view: wgpu::TextureView,
+    requested: Option<u32>,
+}
+
+impl StreamedTexture {
+    pub fn resident_bytes(&self) -> u64 {
+        self.mips.resident_bytes(self.resident_level)
+    }
+
+    /// Reallocates the texture with only the levels from `level` down, returning the bytes uploaded
+    fn make_resident(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, level: u32) -> u64 {
+        self.view = upload_levels(device, queue, &self.label, &self.mips, level);
+        self.resident_level = level;
+        self.generation += 1;
+        self.resident_bytes()
+    }
+}
+
+/// Streams mip levels in as textures grow on screen and evicts them to stay under a memory budget
+pub struct TextureStreamer {
+    pub textures: Vec<StreamedTexture>,
+    /// GPU memory all resident levels may use together
+    pub budget: u64,
+    /// Upload bytes allowed per update, so detail arrives over several frames
+    pub upload_rate: u64,
+    /// Bytes uploaded by the last update
+    pub uploaded: u64,
+}
+
+impl Default for TextureStreamer {
+    fn default() -> Self {
+        Self {
+            textures: Vec::new(),
+            budget: 24 * MEGABYTE,
+            upload_rate: 8 * MEGABYTE,
+            uploaded: 0,
+        }
+    }
+}
+
+impl TextureStreamer {
+    /// Uploads only the small levels, returning the index used to request and look up the texture
+    pub fn add(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        label: &str,
+        image: RgbaImage,
+    ) -> usize {
+        let mips = MipChain::new(image);
+        let floor_level = mips.floor_level();
+        self.textures.push(StreamedTexture {
+            label: label.to_string(),
+            view: upload_levels(device, queue, label, &mips, floor_level),
+            mips,
+            resident_level: floor_level,
+            wanted_level: floor_level,
+            generation: 0,
+            requested: None,
+        });
+        self.textures.len() - 1
+    }
+
+    /// Records that a surface using the texture covers this many pixels across,
+    /// keeping the largest request until the next update
+    pub fn request(&mut self, index: usize, pixels: f32) {
+        let texture = &mut self.textures[index];
+        let level = texture.mips.level_for_pixels(pixels);
+        texture.requested = Some(texture.requested.map_or(level, |other| other.min(level)));
+    }
+
+    pub fn resident_bytes(&self) -> u64 {
+        self.textures
+            .iter()
+            .map(StreamedTexture::resident_bytes)
+            .sum()
+    }
+
+    /// Evicts levels over budget right away and streams wanted levels in one level at a time.
+    /// Textures nobody requested since the last update fall back to their floor level.
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mut targets = self
+            .textures
+            .iter_mut()
+            .map(|texture| {
+                let floor_level = texture.mips.floor_level();
+                texture.wanted_level = texture
+                    .requested
+                    .take()
+                    .map_or(floor_level, |level| level.min(floor_level));
+                texture.wanted_level
+            })
+            .collect::<Vec<_>>();
+
+        let mips = self
+            .textures
+            .iter()
+            .map(|texture| &texture.mips)
+            .collect::<Vec<_>>();
+        fit_budget(&mips, &mut targets, self.budget);
+
+        self.uploaded = 0;
+        for (texture, target) in self.textures.iter_mut().zip(targets.iter()) {
+            if *target > texture.resident_level {
+                self.uploaded += texture.make_resident(device, queue, *target);
+            }
+        }
+        // The textures missing the most detail stream first
+        let mut order = (0..self.textures.len())
+            .filter(|index| targets[*index] < self.textures[*index].resident_level)
+            .collect::<Vec<_>>();
+        order.sort_by_key(|index| {
+            std::cmp::Reverse(self.textures[*index].resident_level - targets[*index])
+        });
+        for index in order {
+            let texture = &mut self.textures[index];
+            let level = texture.resident_level - 1;
+            // Always allow one upload, so a level larger than the rate still arrives
+            if self.uploaded > 0
+                && self.uploaded + texture.mips.resident_bytes(level) > self.upload_rate
+            {
+                break;
+            }
+            self.uploaded += texture.make_resident(device, queue, level);
+        }
+    }
+
+    /// Draws the budget controls and a table of resident levels per texture
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let mut budget = self.budget / MEGABYTE;
+        ui.add(egui::Slider::new(&mut budget, 1..=128).text("Budget (MB)"));
+        self.budget = budget * MEGABYTE;
+        let mut upload_rate = self.upload_rate / MEGABYTE;
+        ui.add(egui::Slider::new(&mut upload_rate, 1..=64).text("Upload per frame (MB)"));
+        self.upload_rate = upload_rate * MEGABYTE;
+
+        let resident = self.resident_bytes();
+        ui.add(
+            egui::ProgressBar::new(resident as f32 / self.budget.max(1) as f32).text(format!(
+                "{} of {}",
+                format_bytes(resident),
+                format_bytes(self.budget)
+            )),
+        );
+        ui.label(format!(
+            "Uploaded this frame: {}",
+            format_bytes(self.uploaded)
+        ));
+        egui::Grid::new("texture_streaming")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Texture");
+                ui.label("Resident");
+                ui.label("Wanted");
+                ui.label("Memory");
+                ui.end_row();
+                for texture in self.textures.iter() {
+                    let level_text = |level: u32| {
+                        let image = texture.mips.level(level);
+                        format!("{level} ({}x{})", image.width(), image.height())
+                    };
+                    ui.label(&texture.label);
+                    ui.label(level_text(texture.resident_level));
+                    ui.label(level_text(texture.wanted_level));
+                    ui.label(format_bytes(texture.resident_bytes()));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Drops detail from whichever texture uses the most memory until everything fits the budget,
+/// stopping at each texture's floor level
+fn fit_budget(mips: &[&MipChain], targets: &mut [u32], budget: u64) {
+    let mut total = mips
+        .iter()
+        .zip(targets.iter())
+        .map(|(mips, level)| mips.resident_bytes(*level))
+        .sum::<u64>();
+    while total > budget {
+        let largest = mips
+            .iter()
+            .zip(targets.iter())
+            .enumerate()
+            .filter(|(_, (mips, level))| **level < mips.floor_level())
+            .max_by_key(|(_, (mips, level))| mips.resident_bytes(**level))
+            .map(|(index, _)| index);
+        let Some(index) = largest else {
+            break;
+        };
+        let mips = mips[index];
+        total -= mips.resident_bytes(targets[index]) - mips.resident_bytes(targets[index] + 1);
+        targets[index] += 1;
+    }
+}
+
+/// Creates a texture holding the levels from `level` down. Levels already on the GPU are uploaded
+/// again rather than copied over, which keeps streaming simple at the cost of upload bandwidth.
+fn upload_levels(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    mips: &MipChain,
+    level: u32,
+) -> wgpu::TextureView {
+    let top = mips.level(level);
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: top.width(),
+            height: top.height(),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: mips.level_count() - level,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    for (mip_level, image) in (level..mips.level_count())
+        .map(|source| mips.level(source))
+        .enumerate()
+    {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &texture,
+                mip_level: mip_level as u32,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            image.as_raw(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * image.width()),
+                rows_per_image: Some(image.height()),
+            },
+            wgpu::Extent3d {
+                width: image.width(),
+                height: image.height(),
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mips(width: u32, height: u32) -> MipChain {
+        MipChain::new(RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([x as u8, y as u8, 0, 255])
+        }))
+    }
+
+    #[test]
+    fn mip_chain_halves_down_to_one_texel() {
+        let chain = mips(300, 200);
+        let sizes = (0..chain.level_count())
+            .map(|level| chain.level(level).dimensions())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            [
+                (300, 200),
+                (150, 100),
+                (75, 50),
+                (37, 25),
+                (18, 12),
+                (9, 6),
+                (4, 3),
+                (2, 1),
+                (1, 1)
+            ]
+        );
+        assert_eq!(chain.floor_level(), 3);
+        assert_eq!(
+            chain.resident_bytes(7),
+            chain.level(7).as_raw().len() as u64 + 4
+        );
+    }
+
+    #[test]
+    fn mip_levels_average_their_parents() {
+        let image = RgbaImage::from_raw(
+            2,
+            2,
+            [
+                [0, 10, 255, 255],
+                [2, 20, 255, 255],
+                [4, 30, 0, 255],
+                [7, 40, 0, 255],
+            ]
+            .concat(),
+        )
+        .unwrap();
+        let chain = MipChain::new(image);
+        assert_eq!(chain.level_count(), 2);
+        assert_eq!(chain.level(1).get_pixel(0, 0).0, [3, 25, 128, 255]);
+    }
+
+    #[test]
+    fn level_for_pixels_matches_texels_to_pixels() {
+        let chain = mips(512, 512);
+        assert_eq!(chain.level_for_pixels(1024.0), 0);
+        assert_eq!(chain.level_for_pixels(512.0), 0);
+        assert_eq!(chain.level_for_pixels(300.0), 0);
+        assert_eq!(chain.level_for_pixels(256.0), 1);
+        assert_eq!(chain.level_for_pixels(0.0), chain.level_count() - 1);
+    }
+
+    #[test]
+    fn fit_budget_evicts_the_largest_texture_first() {
+        let (large, small) = (mips(512, 512), mips(256, 256));
+        let chains = [&large, &small];
+
+        let mut targets = [0, 0];
+        fit_budget(&chains, &mut targets, u64::MAX);
+        assert_eq!(targets, [0, 0]);
+
+        let mut targets = [0, 0];
+        let budget = large.resident_bytes(1) + small.resident_bytes(0);
+        fit_budget(&chains, &mut targets, budget);
+        assert_eq!(targets, [1, 0]);
+
+        let mut targets = [0, 0];
+        fit_budget(&chains, &mut targets, budget - 1);
+        assert_eq!(targets, [1, 1]);
+    }
+
+    #[test]
+    fn fit_budget_stops_at_the_floor_level() {
+        let (large, small) = (mips(512, 512), mips(32, 32));
+        let mut targets = [0, 0];
+        fit_budget(&[&large, &small], &mut targets, 0);
+        assert_eq!(targets, [large.floor_level(), 0]);
+        assert_eq!(small.floor_level(), 0);
+    }
+}
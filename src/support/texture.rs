@@ -1,5 +1,7 @@
-use anyhow::Result;
-use image::GenericImageView;
+use crate::AssetCache;
+use anyhow::{bail, Context, Result};
+use image::{AnimationDecoder, GenericImageView};
+use std::{io::Cursor, path::Path};
 use wgpu;
 
 pub struct Texture {
@@ -10,6 +12,8 @@ pub struct Texture {
 
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    /// For passes that mask drawing with the stencil, such as selection outlines
+    pub const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
 
     pub fn from_bytes(
         device: &wgpu::Device,
@@ -21,18 +25,80 @@ impl Texture {
         Self::from_image(device, queue, &img, Some(label))
     }
 
+    /// Decodes the image once and reuses the decoded pixels from the asset cache on later runs
+    pub fn from_bytes_cached(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cache: &AssetCache,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self> {
+        const HEADER_SIZE: usize = 8;
+        let decoded = cache.get_or_insert_with("rgba8", bytes, || {
+            let img = image::load_from_memory(bytes)?;
+            let (width, height) = img.dimensions();
+            let rgba = img.to_rgba8();
+            let mut decoded = Vec::with_capacity(HEADER_SIZE + rgba.len());
+            decoded.extend_from_slice(&width.to_le_bytes());
+            decoded.extend_from_slice(&height.to_le_bytes());
+            decoded.extend_from_slice(&rgba);
+            Ok(decoded)
+        })?;
+        anyhow::ensure!(decoded.len() >= HEADER_SIZE, "Cached texture is truncated");
+        let (header, rgba) = decoded.split_at(HEADER_SIZE);
+        let width = u32::from_le_bytes(header[0..4].try_into()?);
+        let height = u32::from_le_bytes(header[4..8].try_into()?);
+        // The header comes from disk, so the size is checked rather than trusted
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|texels| texels.checked_mul(4))
+            .with_context(|| format!("Cached texture size {width}x{height} overflows"))?;
+        anyhow::ensure!(rgba.len() == size, "Cached texture has the wrong size");
+        Ok(Self::from_rgba(
+            device,
+            queue,
+            rgba,
+            width,
+            height,
+            Some(label),
+        ))
+    }
+
+    pub fn from_path(device: &wgpu::Device, queue: &wgpu::Queue, path: &Path) -> Result<Self> {
+        let img = image::open(path)
+            .with_context(|| format!("Failed to load image from {}", path.display()))?;
+        let label = path.file_name().and_then(|name| name.to_str());
+        Self::from_image(device, queue, &img, label)
+    }
+
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
         label: Option<&str>,
     ) -> Result<Self> {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        let (width, height) = img.dimensions();
+        Ok(Self::from_rgba(
+            device,
+            queue,
+            &img.to_rgba8(),
+            width,
+            height,
+            label,
+        ))
+    }
 
+    pub fn from_rgba(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        label: Option<&str>,
+    ) -> Self {
         let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
+            width,
+            height,
             depth_or_array_layers: 1,
         };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -53,11 +119,11 @@ impl Texture {
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
             },
-            &rgba,
+            rgba,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
             },
             size,
         );
@@ -73,14 +139,94 @@ impl Texture {
             ..Default::default()
         });
 
-        Ok(Self {
+        Self {
             texture,
             view,
             sampler,
-        })
+        }
+    }
+
+    /// A texture compute shaders write with `textureStore` and render passes then sample,
+    /// such as a simulation or a procedural image. `filter` applies when it is sampled.
+    pub fn create_storage_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        filter: wgpu::FilterMode,
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Replaces the first mip level, with `pixels` tightly packed in the texture's format
+    pub fn write_pixels(&self, queue: &wgpu::Queue, pixels: &[u8]) {
+        let size = self.texture.size();
+        let block_size = self.texture.format().block_size(None).unwrap_or(4);
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(block_size * size.width),
+                rows_per_image: Some(size.height),
+            },
+            wgpu::Extent3d {
+                depth_or_array_layers: 1,
+                ..size
+            },
+        );
     }
 
     pub fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        Self::create_depth_texture_with_format(device, width, height, Self::DEPTH_FORMAT)
+    }
+
+    pub fn create_depth_stencil_texture(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        Self::create_depth_texture_with_format(device, width, height, Self::DEPTH_STENCIL_FORMAT)
+    }
+
+    pub fn create_depth_texture_with_format(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
         let size = wgpu::Extent3d {
             width,
             height,
@@ -93,7 +239,7 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::DEPTH_FORMAT,
+            format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         };
@@ -122,3 +268,188 @@ impl Texture {
         }
     }
 }
+
+/// One decoded frame of an animated texture and how long it stays on screen
+pub struct AnimationFrame {
+    pub rgba: Vec<u8>,
+    pub duration: f32,
+}
+
+/// A texture whose contents are replaced from a list of decoded frames as playback advances.
+/// Only the visible frame lives on the GPU, so bind groups made from `texture` stay valid.
+pub struct AnimatedTexture {
+    pub texture: Texture,
+    pub width: u32,
+    pub height: u32,
+    pub playing: bool,
+    /// Playback rate, where 1.0 uses the frame durations as given
+    pub speed: f32,
+    frames: Vec<AnimationFrame>,
+    current: usize,
+    elapsed: f32,
+}
+
+impl AnimatedTexture {
+    /// Browsers show GIF frames with very short or missing delays for this long instead
+    const DEFAULT_GIF_FRAME_DURATION: f32 = 0.1;
+
+    pub fn from_gif_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        label: &str,
+    ) -> Result<Self> {
+        let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(bytes))?;
+        let frames = decoder
+            .into_frames()
+            .collect_frames()
+            .with_context(|| format!("Failed to decode the frames of {label}"))?;
+        let Some(first) = frames.first() else {
+            bail!("{label} has no frames");
+        };
+        let (width, height) = first.buffer().dimensions();
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let (numerator, denominator) = frame.delay().numer_denom_ms();
+                let milliseconds = numerator as f32 / denominator.max(1) as f32;
+                let duration = if milliseconds <= 10.0 {
+                    Self::DEFAULT_GIF_FRAME_DURATION
+                } else {
+                    milliseconds / 1000.0
+                };
+                AnimationFrame {
+                    rgba: frame.into_buffer().into_raw(),
+                    duration,
+                }
+            })
+            .collect();
+        Self::from_frames(device, queue, width, height, frames, Some(label))
+    }
+
+    /// Loads every image in a folder in file name order, showing each for `frame_duration` seconds
+    pub fn from_image_sequence(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        directory: &Path,
+        frame_duration: f32,
+    ) -> Result<Self> {
+        let mut paths = std::fs::read_dir(directory)
+            .with_context(|| format!("Failed to read image sequence {}", directory.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.is_file() && image::ImageFormat::from_path(path).is_ok());
+        paths.sort();
+
+        let mut size = None;
+        let frames = paths
+            .iter()
+            .map(|path| {
+                let image = image::open(path)
+                    .with_context(|| format!("Failed to load image from {}", path.display()))?;
+                let dimensions = image.dimensions();
+                if *size.get_or_insert(dimensions) != dimensions {
+                    bail!(
+                        "{} is {}x{}, but the sequence started at a different size",
+                        path.display(),
+                        dimensions.0,
+                        dimensions.1
+                    );
+                }
+                Ok(AnimationFrame {
+                    rgba: image.to_rgba8().into_raw(),
+                    duration: frame_duration,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let Some((width, height)) = size else {
+            bail!("{} contains no images", directory.display());
+        };
+        let label = directory.file_name().and_then(|name| name.to_str());
+        Self::from_frames(device, queue, width, height, frames, label)
+    }
+
+    pub fn from_frames(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        frames: Vec<AnimationFrame>,
+        label: Option<&str>,
+    ) -> Result<Self> {
+        let Some(first) = frames.first() else {
+            bail!("An animated texture needs at least one frame");
+        };
+        let frame_size = (4 * width * height) as usize;
+        if let Some(index) = frames
+            .iter()
+            .position(|frame| frame.rgba.len() != frame_size)
+        {
+            bail!("Frame {index} doesn't match the {width}x{height} texture size");
+        }
+        let texture = Texture::from_rgba(device, queue, &first.rgba, width, height, label);
+        Ok(Self {
+            texture,
+            width,
+            height,
+            playing: true,
+            speed: 1.0,
+            frames,
+            current: 0,
+            elapsed: 0.0,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    /// Advances playback, uploading the new frame if it changed
+    pub fn update(&mut self, queue: &wgpu::Queue, delta_time: f32) {
+        if !self.playing || self.frames.len() < 2 {
+            return;
+        }
+        self.elapsed += delta_time * self.speed;
+        let mut frame = self.current;
+        // Skip frames whole when a long frame time spans several of them
+        while self.elapsed >= self.frames[frame].duration {
+            self.elapsed -= self.frames[frame].duration;
+            frame = (frame + 1) % self.frames.len();
+            if frame == self.current {
+                self.elapsed = 0.0;
+                break;
+            }
+        }
+        if frame != self.current {
+            self.set_frame(queue, frame);
+        }
+    }
+
+    pub fn set_frame(&mut self, queue: &wgpu::Queue, index: usize) {
+        let index = index % self.frames.len();
+        self.current = index;
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                aspect: wgpu::TextureAspect::All,
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            &self.frames[index].rgba,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * self.width),
+                rows_per_image: Some(self.height),
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
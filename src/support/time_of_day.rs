@@ -0,0 +1,141 @@
+use nalgebra_glm as glm;
+use std::f32::consts::TAU;
+
+pub struct TimeOfDay {
+    pub hours: f32,
+    pub hours_per_second: f32,
+    pub paused: bool,
+    pub sunrise: f32,
+    pub axis_tilt: f32,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hours: 9.0,
+            hours_per_second: 0.5,
+            paused: false,
+            sunrise: 6.0,
+            axis_tilt: 25_f32.to_radians(),
+        }
+    }
+}
+
+pub struct SkyLight {
+    pub direction: glm::Vec3,
+    pub color: glm::Vec3,
+    pub intensity: f32,
+}
+
+impl TimeOfDay {
+    pub const HOURS_PER_DAY: f32 = 24.0;
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.paused {
+            return;
+        }
+        self.hours =
+            (self.hours + self.hours_per_second * delta_time).rem_euclid(Self::HOURS_PER_DAY);
+    }
+
+    /// The direction pointing from the scene towards the sun
+    pub fn sun_direction(&self) -> glm::Vec3 {
+        let angle = ((self.hours - self.sunrise) / Self::HOURS_PER_DAY) * TAU;
+        let direction = glm::vec3(angle.cos(), angle.sin(), 0.0);
+        glm::rotate_x_vec3(&direction, self.axis_tilt).normalize()
+    }
+
+    pub fn moon_direction(&self) -> glm::Vec3 {
+        -self.sun_direction()
+    }
+
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_direction().y
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.sun_elevation() < 0.0
+    }
+
+    /// Color temperature of the sun in Kelvin, warm near the horizon and neutral at noon
+    pub fn color_temperature(&self) -> f32 {
+        let elevation = self.sun_elevation().clamp(0.0, 1.0);
+        glm::lerp_scalar(2000.0, 6500.0, elevation.sqrt())
+    }
+
+    pub fn sun_light(&self) -> SkyLight {
+        let elevation = self.sun_elevation();
+        SkyLight {
+            direction: self.sun_direction(),
+            color: kelvin_to_rgb(self.color_temperature()),
+            intensity: smoothstep(-0.05, 0.25, elevation),
+        }
+    }
+
+    pub fn moon_light(&self) -> SkyLight {
+        let elevation = -self.sun_elevation();
+        SkyLight {
+            direction: self.moon_direction(),
+            color: glm::vec3(0.6, 0.7, 1.0),
+            intensity: 0.15 * smoothstep(-0.05, 0.25, elevation),
+        }
+    }
+
+    /// The dominant light for the current time, switching to the moon at night
+    pub fn light(&self) -> SkyLight {
+        if self.is_night() {
+            self.moon_light()
+        } else {
+            self.sun_light()
+        }
+    }
+
+    /// Exposure multiplier that brightens the image as the light fades, mimicking eye adaptation
+    pub fn exposure(&self) -> f32 {
+        let luminance = self.sun_light().intensity + self.moon_light().intensity;
+        (0.5 / luminance.max(0.05)).clamp(1.0, 4.0)
+    }
+
+    pub fn sky_color(&self) -> glm::Vec3 {
+        let day = glm::vec3(0.35, 0.55, 0.9);
+        let sunset = glm::vec3(0.9, 0.45, 0.2);
+        let night = glm::vec3(0.01, 0.015, 0.04);
+        let elevation = self.sun_elevation();
+        let daylight = smoothstep(-0.1, 0.3, elevation);
+        let horizon_glow = 1.0 - (elevation.abs() * 4.0).min(1.0);
+        let color = glm::lerp(&night, &day, daylight);
+        glm::lerp(&color, &sunset, horizon_glow * daylight.max(0.25))
+    }
+}
+
+/// Approximates the RGB color of a black body radiator, valid from 1000K to 40000K
+pub fn kelvin_to_rgb(kelvin: f32) -> glm::Vec3 {
+    let temperature = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let red = if temperature <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (temperature - 60.0).powf(-0.133_204_76)
+    };
+
+    let green = if temperature <= 66.0 {
+        99.470_8 * temperature.ln() - 161.119_57
+    } else {
+        288.122_16 * (temperature - 60.0).powf(-0.075_514_85)
+    };
+
+    let blue = if temperature >= 66.0 {
+        255.0
+    } else if temperature <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (temperature - 10.0).ln() - 305.044_8
+    };
+
+    glm::vec3(red, green, blue).map(|channel| channel.clamp(0.0, 255.0) / 255.0)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
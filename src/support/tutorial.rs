@@ -0,0 +1,125 @@
+use anyhow::Result;
+use egui::{Align2, Color32, Context as GuiContext, Id, LayerId, Order, Rect, Stroke};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TutorialStep {
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub highlight: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Tutorial {
+    pub title: String,
+    pub steps: Vec<TutorialStep>,
+    #[serde(default)]
+    pub next_example: Option<String>,
+    #[serde(skip)]
+    pub current_step: usize,
+    #[serde(skip)]
+    pub visible: bool,
+}
+
+impl Tutorial {
+    pub fn from_ron(source: &str) -> Result<Self> {
+        let mut tutorial: Self = ron::from_str(source)?;
+        tutorial.visible = !tutorial.steps.is_empty();
+        Ok(tutorial)
+    }
+
+    pub fn step(&self) -> Option<&TutorialStep> {
+        self.steps.get(self.current_step)
+    }
+
+    pub fn next(&mut self) {
+        if self.current_step + 1 < self.steps.len() {
+            self.current_step += 1;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.current_step = self.current_step.saturating_sub(1);
+    }
+
+    pub fn is_last_step(&self) -> bool {
+        self.current_step + 1 >= self.steps.len()
+    }
+
+    pub fn show(&mut self, context: &GuiContext) {
+        if !self.visible {
+            return;
+        }
+
+        let Some(step) = self.step().cloned() else {
+            return;
+        };
+
+        let mut visible = self.visible;
+        egui::Window::new(format!("Tutorial: {}", self.title))
+            .open(&mut visible)
+            .resizable(false)
+            .collapsible(false)
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .show(context, |ui| {
+                ui.label(format!(
+                    "Step {} of {}",
+                    self.current_step + 1,
+                    self.steps.len()
+                ));
+                ui.heading(&step.title);
+                ui.label(&step.description);
+
+                if self.is_last_step() {
+                    if let Some(next_example) = self.next_example.as_ref() {
+                        ui.separator();
+                        ui.label("Continue the tutorial with:");
+                        ui.code(format!("cargo run -r --bin {next_example}"));
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.current_step > 0, egui::Button::new("Previous"))
+                        .clicked()
+                    {
+                        self.previous();
+                    }
+                    if ui
+                        .add_enabled(!self.is_last_step(), egui::Button::new("Next"))
+                        .clicked()
+                    {
+                        self.next();
+                    }
+                });
+            });
+        self.visible = visible;
+    }
+
+    /// Outlines a GUI element when the current step refers to it by name
+    pub fn highlight(&self, context: &GuiContext, name: &str, rect: Rect) {
+        if !self.visible {
+            return;
+        }
+
+        let highlighted = self
+            .step()
+            .and_then(|step| step.highlight.as_deref())
+            .is_some_and(|highlight| highlight == name);
+        if !highlighted {
+            return;
+        }
+
+        let painter = context.layer_painter(LayerId::new(
+            Order::Foreground,
+            Id::new("tutorial_highlight"),
+        ));
+        painter.rect_stroke(
+            rect.expand(4.0),
+            4.0,
+            Stroke::new(2.0, Color32::from_rgb(255, 200, 0)),
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{BindingBuilder, BindingLayout, Renderer};
+use std::marker::PhantomData;
+use wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferAddress, Device, DynamicOffset, RenderPass,
+    ShaderStages,
+};
+
+/// A uniform buffer holding a `T`, with the layout and bind group that expose it at binding 0.
+/// Arrays hold several values and bind one at a time with a dynamic offset.
+///
+/// `T` is laid out as the shader's struct, padded to a multiple of 16 bytes.
+pub struct Uniform<T> {
+    buffer: Buffer,
+    layout: BindingLayout,
+    bind_group: BindGroup,
+    /// Bytes between values, rounded up to the device's uniform offset alignment for arrays
+    stride: BufferAddress,
+    capacity: usize,
+    dynamic: bool,
+    _value: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> Uniform<T> {
+    /// A single zeroed value visible to the vertex and fragment stages
+    pub fn new(device: &Device) -> Self {
+        Self::with_visibility(device, ShaderStages::VERTEX_FRAGMENT)
+    }
+
+    /// A single zeroed value visible to the given stages, such as `COMPUTE`
+    pub fn with_visibility(device: &Device, visibility: ShaderStages) -> Self {
+        Self::create(device, visibility, 1, false)
+    }
+
+    /// Room for `capacity` zeroed values, one of which is bound at a time by index
+    pub fn array(device: &Device, visibility: ShaderStages, capacity: usize) -> Self {
+        Self::create(device, visibility, capacity.max(1), true)
+    }
+
+    fn create(device: &Device, visibility: ShaderStages, capacity: usize, dynamic: bool) -> Self {
+        let size = std::mem::size_of::<T>() as BufferAddress;
+        let stride = if dynamic {
+            let alignment = device.limits().min_uniform_buffer_offset_alignment as BufferAddress;
+            wgpu::util::align_to(size, alignment)
+        } else {
+            size
+        };
+        let layout = BindingBuilder::new()
+            .label("Uniform")
+            .entry(
+                0,
+                visibility,
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: dynamic,
+                    min_binding_size: wgpu::BufferSize::new(size),
+                },
+            )
+            .build(device);
+        let (buffer, bind_group) = Self::create_buffer(device, &layout, stride, capacity);
+        Self {
+            buffer,
+            layout,
+            bind_group,
+            stride,
+            capacity,
+            dynamic,
+            _value: PhantomData,
+        }
+    }
+
+    fn create_buffer(
+        device: &Device,
+        layout: &BindingLayout,
+        stride: BufferAddress,
+        capacity: usize,
+    ) -> (Buffer, BindGroup) {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Uniform Buffer"),
+            size: stride * capacity as BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = layout.bind_group(
+            device,
+            &[wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer,
+                offset: 0,
+                size: wgpu::BufferSize::new(std::mem::size_of::<T>() as BufferAddress),
+            })],
+        );
+        (buffer, bind_group)
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// For pipeline layouts. Layouts with the same entries are compatible,
+    /// so one uniform's layout also fits the bind groups of others holding the same type.
+    pub fn layout(&self) -> &BindGroupLayout {
+        &self.layout.layout
+    }
+
+    /// The layout along with its entries, such as for validating against a shader's reflection
+    pub fn binding_layout(&self) -> &BindingLayout {
+        &self.layout
+    }
+
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn write(&self, renderer: &mut Renderer, value: &T) {
+        self.write_at(renderer, 0, value);
+    }
+
+    pub fn write_at(&self, renderer: &mut Renderer, index: usize, value: &T) {
+        assert!(index < self.capacity, "uniform index {index} out of bounds");
+        renderer.write_buffer(
+            &self.buffer,
+            index as BufferAddress * self.stride,
+            bytemuck::bytes_of(value),
+        );
+    }
+
+    /// Writes the values from index 0, growing an array to fit. Growing replaces the buffer
+    /// and bind group but keeps the layout, so pipelines made with it stay valid.
+    pub fn write_array(&mut self, renderer: &mut Renderer, values: &[T]) {
+        assert!(
+            self.dynamic || values.len() <= 1,
+            "only uniform arrays hold several values"
+        );
+        if values.len() > self.capacity {
+            self.capacity = values.len().next_power_of_two();
+            (self.buffer, self.bind_group) =
+                Self::create_buffer(&renderer.device, &self.layout, self.stride, self.capacity);
+        }
+        let stride = self.stride as usize;
+        let mut contents = vec![0; values.len() * stride];
+        for (chunk, value) in contents.chunks_exact_mut(stride).zip(values) {
+            chunk[..std::mem::size_of::<T>()].copy_from_slice(bytemuck::bytes_of(value));
+        }
+        renderer.write_buffer(&self.buffer, 0, &contents);
+    }
+
+    /// The dynamic offset that selects a value in an array
+    pub fn offset(&self, index: usize) -> DynamicOffset {
+        (index as BufferAddress * self.stride) as DynamicOffset
+    }
+
+    pub fn set<'a>(&'a self, renderpass: &mut RenderPass<'a>, group: u32) {
+        self.set_at(renderpass, group, 0);
+    }
+
+    /// Binds the value at `index`, which must be 0 unless this is an array
+    pub fn set_at<'a>(&'a self, renderpass: &mut RenderPass<'a>, group: u32, index: usize) {
+        if self.dynamic {
+            renderpass.set_bind_group(group, &self.bind_group, &[self.offset(index)]);
+        } else {
+            debug_assert_eq!(index, 0, "only uniform arrays hold several values");
+            renderpass.set_bind_group(group, &self.bind_group, &[]);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::VertexLayout;
+use wgpu::{VertexAttribute, VertexStepMode};
+
+/// A vertex or instance type that describes its own buffer layout, so pipelines and geometry
+/// agree on it. Usually implemented with `impl_vertex!`.
+pub trait VertexDescription: bytemuck::Pod {
+    const STEP_MODE: VertexStepMode = VertexStepMode::Vertex;
+
+    fn attributes() -> Vec<VertexAttribute>;
+
+    /// Call `buffer_layout` on the result for a pipeline's vertex buffers
+    fn layout() -> VertexLayout {
+        VertexLayout::new(
+            std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            Self::STEP_MODE,
+            &Self::attributes(),
+        )
+    }
+}
+
+/// Implements `VertexDescription` from shader locations and formats in field order, e.g.
+/// `impl_vertex!(Vertex, [0 => Float32x4, 1 => Float32x2])`, or
+/// `impl_vertex!(Instance, Instance, [3 => Float32x4])` for per instance data
+#[macro_export]
+macro_rules! impl_vertex {
+    ($type:ty, [$($location:expr => $format:ident),* $(,)?]) => {
+        $crate::impl_vertex!($type, Vertex, [$($location => $format),*]);
+    };
+    ($type:ty, $step_mode:ident, [$($location:expr => $format:ident),* $(,)?]) => {
+        impl $crate::VertexDescription for $type {
+            const STEP_MODE: ::wgpu::VertexStepMode = ::wgpu::VertexStepMode::$step_mode;
+
+            fn attributes() -> Vec<::wgpu::VertexAttribute> {
+                ::wgpu::vertex_attr_array![$($location => $format),*].to_vec()
+            }
+        }
+    };
+}
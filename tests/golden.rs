@@ -0,0 +1,102 @@
+//! Renders each example headlessly and compares the last frame against a golden image
+//! in `tests/golden`. A missing golden image fails the test, and `UPDATE_GOLDEN=1` records
+//! them, for a new example or after an intended change to an example's output.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use support::{compare_images, CAPTURE_FRAME_FLAG, FRAMES_FLAG};
+
+/// Frames rendered before the capture, so simulations and animations have moved
+const FRAMES: usize = 5;
+
+/// Per pixel perceptual difference allowed before a pixel counts as changed,
+/// loose enough to absorb rounding differences between drivers
+const TOLERANCE: f32 = 0.1;
+
+/// Share of changed pixels that fails the test
+const THRESHOLD: f32 = 0.01;
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn check_example(name: &str, executable: &str) -> Result<()> {
+    let output_directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let actual_path = output_directory.join(format!("{name}.png"));
+    let status = Command::new(executable)
+        .args([CAPTURE_FRAME_FLAG, &actual_path.to_string_lossy()])
+        .args([FRAMES_FLAG, &FRAMES.to_string()])
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .status()
+        .with_context(|| format!("Failed to run {name}"))?;
+    anyhow::ensure!(status.success(), "{name} exited with {status}");
+    let actual = image::open(&actual_path)?.to_rgba8();
+
+    let golden_path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&golden_path)?;
+        eprintln!("Recorded golden image {}", golden_path.display());
+        return Ok(());
+    }
+    anyhow::ensure!(
+        golden_path.exists(),
+        "{name} has no golden image at {}. Run with UPDATE_GOLDEN=1 to record it from {}",
+        golden_path.display(),
+        actual_path.display()
+    );
+
+    let expected = image::open(&golden_path)?.to_rgba8();
+    let diff = compare_images(&actual, &expected, TOLERANCE)?;
+    if diff.fraction() > THRESHOLD {
+        let diff_path = output_directory.join(format!("{name}-diff.png"));
+        diff.image.save(&diff_path)?;
+        anyhow::bail!(
+            "{name} differs from its golden image in {:.2}% of pixels, up to {:.2}. \
+             See {} and {}",
+            diff.fraction() * 100.0,
+            diff.max_difference,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+    Ok(())
+}
+
+macro_rules! golden_tests {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() -> Result<()> {
+                check_example(stringify!($name), env!(concat!("CARGO_BIN_EXE_", stringify!($name))))
+            }
+        )*
+    };
+}
+
+golden_tests!(
+    atlas,
+    boids,
+    color,
+    deform,
+    fractal,
+    instancing,
+    life,
+    lights,
+    offscreen,
+    oit,
+    particles,
+    sdf,
+    shadertoy,
+    skinning,
+    split_screen,
+    streaming,
+    terrain,
+    texture,
+    transparent,
+    triangle,
+    uniforms,
+    water,
+);